@@ -0,0 +1,41 @@
+//! Compares solver configurations on hard mode. Run with `cargo bench`.
+
+use advent_wizard_rpg::{
+    solver::{solve_cheapest_win, Algorithm, SolveOptions},
+    Battle,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+fn solvers(c: &mut Criterion) {
+    let mut battle = Battle::new(true);
+    battle.wizard_turn_apply_effects();
+
+    let configurations = [
+        ("exhaustive", Algorithm::DepthFirst, false, 0),
+        ("prune", Algorithm::DepthFirst, true, 0),
+        ("memo", Algorithm::DepthFirst, false, 1_000_000),
+        ("prune + memo", Algorithm::DepthFirst, true, 1_000_000),
+        ("bfs", Algorithm::BreadthFirst, true, 1_000_000),
+        ("dijkstra", Algorithm::Dijkstra, true, 1_000_000),
+        ("astar", Algorithm::AStar, true, 1_000_000),
+    ];
+    let mut group = c.benchmark_group("hard mode");
+    // A search takes up to seconds, too long for criterion's default of 100 samples
+    group.sample_size(10);
+    for (name, algorithm, prune, memo_size) in configurations {
+        let options = SolveOptions {
+            algorithm,
+            max_depth: 12,
+            prune,
+            memo_size,
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| solve_cheapest_win(black_box(&battle), options))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, solvers);
+criterion_main!(benches);
@@ -0,0 +1,8 @@
+//! Arbitrary casts, castable or not, checking the engine's invariants after every round.
+
+#![no_main]
+
+use advent_wizard_rpg_fuzz::check_battle;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| check_battle(data));
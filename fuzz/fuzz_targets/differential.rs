@@ -0,0 +1,9 @@
+//! Castable spells, checking the engine against the reference implementation after every
+//! round.
+
+#![no_main]
+
+use advent_wizard_rpg_fuzz::check_differential;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| check_differential(data));
@@ -0,0 +1,164 @@
+//! Checks run by the fuzz targets, kept out of the targets so they can be run on a corpus
+//! by other means too.
+
+mod reference;
+pub use reference::*;
+
+use advent_wizard_rpg::{Battle, Boss, Spell};
+
+/// Battle described by the first bytes of `data`, at the start of the wizard's first turn,
+/// and the rest of `data`. The boss has 1 to 256 hitpoints and deals 0 to 19 damage.
+fn battle_from_bytes(data: &[u8]) -> Option<(bool, i32, i32, &[u8])> {
+    let [hard_mode, hitpoints, damage, rest @ ..] = data else {
+        return None;
+    };
+    Some((
+        hard_mode & 1 == 1,
+        *hitpoints as i32 + 1,
+        (*damage % 20) as i32,
+        rest,
+    ))
+}
+
+/// Cast a spell per byte of `data` after the first three, castable or not, asserting that
+/// casts that can't be made are rejected without changing the battle and that the
+/// battle's invariants hold after every round
+pub fn check_battle(data: &[u8]) {
+    let Some((hard_mode, hitpoints, damage, casts)) = battle_from_bytes(data) else {
+        return;
+    };
+    let mut battle = Battle::with_boss(hard_mode, Boss::new(hitpoints, damage));
+    battle.wizard_turn_apply_effects();
+    for byte in casts {
+        if battle.get_outcome().is_some() {
+            break;
+        }
+        let spell = Spell::ALL[*byte as usize % Spell::ALL.len()].clone();
+        let castable = battle.get_wizard().get_possible_spells().contains(&spell);
+        let before = battle.get_state();
+        let mana_used = battle.get_mana_used();
+        match battle.play_round(&spell) {
+            Err(_) => {
+                assert!(!castable, "{} was castable but rejected", spell);
+                assert_eq!(battle.get_state(), before, "rejected cast changed the battle");
+                assert_eq!(battle.get_mana_used(), mana_used);
+            }
+            Ok(outcome) => {
+                assert!(castable, "{} wasn't castable but was cast", spell);
+                assert_eq!(outcome, battle.get_outcome());
+                assert_eq!(
+                    battle.get_mana_used(),
+                    mana_used + battle.get_spellbook().get_mana(&spell)
+                );
+                check_invariants(&battle);
+            }
+        }
+    }
+}
+
+fn check_invariants(battle: &Battle) {
+    let wizard = battle.get_wizard();
+    let boss = battle.get_boss();
+    assert!(wizard.get_mana() >= 0, "mana went negative");
+    let spent: i32 = battle
+        .get_spells_used()
+        .iter()
+        .map(|spell| battle.get_spellbook().get_mana(spell))
+        .sum();
+    assert_eq!(battle.get_mana_used(), spent);
+    match battle.get_outcome() {
+        Some(true) => assert!(boss.get_hitpoints() <= 0, "won with the boss alive"),
+        Some(false) => assert!(wizard.get_hitpoints() <= 0, "lost with the wizard alive"),
+        None => {
+            assert!(wizard.get_hitpoints() > 0 && boss.get_hitpoints() > 0);
+            assert_eq!(wizard.get_armor() > 0, wizard.get_shielded().is_some());
+            for (timer, spell) in [
+                (wizard.get_shielded(), Spell::Shield),
+                (boss.get_poisoned(), Spell::Poison),
+                (wizard.get_recharging(), Spell::Recharge),
+            ] {
+                let duration = battle.get_spellbook().get(&spell).duration;
+                assert!(
+                    timer.is_none_or(|turns| turns > 0 && turns <= duration),
+                    "{} has {:?} turns left",
+                    spell,
+                    timer
+                );
+            }
+        }
+    }
+}
+
+/// Cast a spell the engine allows per byte of `data` after the first three, on both the
+/// engine and `ReferenceBattle`, asserting they agree after every round. The engine checks
+/// mana before Recharge's mana at the start of the turn, so it may allow fewer spells than
+/// the puzzle but never more.
+pub fn check_differential(data: &[u8]) {
+    let Some((hard_mode, hitpoints, damage, casts)) = battle_from_bytes(data) else {
+        return;
+    };
+    let mut battle = Battle::with_boss(hard_mode, Boss::new(hitpoints, damage));
+    battle.wizard_turn_apply_effects();
+    let mut reference = ReferenceBattle::new(hard_mode, hitpoints, damage);
+    assert_agree(&battle, &reference);
+    for byte in casts {
+        if battle.get_outcome().is_some() {
+            break;
+        }
+        let castable: Vec<usize> = (0..Spell::ALL.len())
+            .filter(|i| {
+                battle
+                    .get_wizard()
+                    .get_possible_spells()
+                    .contains(&Spell::ALL[*i])
+            })
+            .collect();
+        for i in &castable {
+            assert!(
+                reference.can_cast(*i),
+                "{} is castable in the engine only",
+                Spell::ALL[*i]
+            );
+        }
+        // The puzzle's wizard loses when they can't afford any spell
+        let Some(&i) = castable.get(*byte as usize % castable.len().max(1)) else {
+            break;
+        };
+        battle.play_round(&Spell::ALL[i]).unwrap();
+        assert!(reference.play_round(i));
+        assert_agree(&battle, &reference);
+    }
+}
+
+fn assert_agree(battle: &Battle, reference: &ReferenceBattle) {
+    let wizard = battle.get_wizard();
+    let boss = battle.get_boss();
+    let engine = (
+        battle.get_outcome(),
+        wizard.get_hitpoints(),
+        wizard.get_mana(),
+        wizard.get_armor(),
+        boss.get_hitpoints(),
+        [
+            wizard.get_shielded().unwrap_or(0),
+            boss.get_poisoned().unwrap_or(0),
+            wizard.get_recharging().unwrap_or(0),
+        ],
+        battle.get_mana_used(),
+    );
+    let expected = (
+        reference.outcome,
+        reference.wizard_hitpoints,
+        reference.wizard_mana,
+        reference.get_armor(),
+        reference.boss_hitpoints,
+        reference.timers,
+        reference.mana_spent,
+    );
+    assert_eq!(
+        engine,
+        expected,
+        "engine and reference disagree after {:?}",
+        battle.get_spells_used()
+    );
+}
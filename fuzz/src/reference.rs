@@ -0,0 +1,132 @@
+//! The rules of Advent of Code 2015 day 22, written from the puzzle text rather than from the
+//! engine, so the two can be played side by side and compared.
+
+/// Mana each spell costs, in the order of `Spell::ALL`
+pub const SPELL_COSTS: [i32; 5] = [53, 73, 113, 173, 229];
+/// Turns Shield, Poison and Recharge last once cast
+pub const EFFECT_TURNS: [i32; 3] = [6, 6, 5];
+
+const SHIELD: usize = 0;
+const POISON: usize = 1;
+const RECHARGE: usize = 2;
+
+/// A battle against a boss with the puzzle's wizard and spells, at the start of one of the
+/// wizard's turns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReferenceBattle {
+    pub hard_mode: bool,
+    pub wizard_hitpoints: i32,
+    pub wizard_mana: i32,
+    pub boss_hitpoints: i32,
+    pub boss_damage: i32,
+    /// Turns left of Shield, Poison and Recharge, 0 if not active
+    pub timers: [i32; 3],
+    pub mana_spent: i32,
+    /// Whether the wizard won, None while the battle goes on
+    pub outcome: Option<bool>,
+}
+
+impl ReferenceBattle {
+    /// The battle at the start of the wizard's first turn
+    pub fn new(hard_mode: bool, boss_hitpoints: i32, boss_damage: i32) -> Self {
+        let mut battle = Self {
+            hard_mode,
+            wizard_hitpoints: 50,
+            wizard_mana: 500,
+            boss_hitpoints,
+            boss_damage,
+            timers: [0; 3],
+            mana_spent: 0,
+            outcome: None,
+        };
+        battle.start_wizard_turn();
+        battle
+    }
+
+    /// Armor the wizard has while Shield is active
+    pub fn get_armor(&self) -> i32 {
+        if self.timers[SHIELD] > 0 {
+            7
+        } else {
+            0
+        }
+    }
+
+    /// Whether the wizard can afford `spell`, an index into `Spell::ALL`, and its effect
+    /// isn't active. Effects ending this turn have already ended.
+    pub fn can_cast(&self, spell: usize) -> bool {
+        self.outcome.is_none()
+            && self.wizard_mana >= SPELL_COSTS[spell]
+            && (spell < 2 || self.timers[spell - 2] == 0)
+    }
+
+    /// Cast `spell`, then play the boss's turn and the start of the wizard's next turn.
+    /// Returns false without changing the battle if the spell can't be cast.
+    pub fn play_round(&mut self, spell: usize) -> bool {
+        if !self.can_cast(spell) {
+            return false;
+        }
+        self.wizard_mana -= SPELL_COSTS[spell];
+        self.mana_spent += SPELL_COSTS[spell];
+        match spell {
+            // Magic Missile
+            0 => self.boss_hitpoints -= 4,
+            // Drain
+            1 => {
+                self.boss_hitpoints -= 2;
+                self.wizard_hitpoints += 2;
+            }
+            effect => self.timers[effect - 2] = EFFECT_TURNS[effect - 2],
+        }
+        if self.check_boss_dead() {
+            return true;
+        }
+
+        self.apply_effects();
+        if self.check_boss_dead() {
+            return true;
+        }
+        self.wizard_hitpoints -= (self.boss_damage - self.get_armor()).max(1);
+        if self.wizard_hitpoints <= 0 {
+            self.outcome = Some(false);
+            return true;
+        }
+
+        self.start_wizard_turn();
+        true
+    }
+
+    /// Hard mode's hitpoint, then the effects
+    fn start_wizard_turn(&mut self) {
+        if self.hard_mode {
+            self.wizard_hitpoints -= 1;
+            if self.wizard_hitpoints <= 0 {
+                self.outcome = Some(false);
+                return;
+            }
+        }
+        self.apply_effects();
+        self.check_boss_dead();
+    }
+
+    /// Effects happen at the start of both the wizard's and the boss's turns, and end once
+    /// their timer reaches 0
+    fn apply_effects(&mut self) {
+        if self.timers[POISON] > 0 {
+            self.boss_hitpoints -= 3;
+        }
+        if self.timers[RECHARGE] > 0 {
+            self.wizard_mana += 101;
+        }
+        for timer in &mut self.timers {
+            *timer = (*timer - 1).max(0);
+        }
+    }
+
+    fn check_boss_dead(&mut self) -> bool {
+        if self.boss_hitpoints <= 0 {
+            self.outcome = Some(true);
+        }
+        self.outcome.is_some()
+    }
+}
@@ -0,0 +1,170 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Attacks between uses of Silence, counting the one it is used with
+pub const SILENCE_INTERVAL: u8 = 3;
+/// Wizard turns a silence lasts
+pub const SILENCE_DURATION: i32 = 2;
+/// Attacks between uses of Burn, counting the one it is used with
+pub const BURN_INTERVAL: u8 = 1;
+/// Turns, the boss's and the wizard's alike, a stack of burn lasts. Longer than two
+/// rounds, so stacks from three attacks in a row overlap.
+pub const BURN_DURATION: u8 = 5;
+/// Damage each stack of burn deals at the start of every turn
+pub const BURN_DAMAGE: i32 = 1;
+/// Most stacks of burn the wizard can have at once
+pub const MAX_BURN_STACKS: usize = 3;
+/// Attacks between uses of Dispel, counting the one it is used with
+pub const DISPEL_INTERVAL: u8 = 4;
+/// Attacks between uses of Heal, counting the one it is used with. Once it's ready, the
+/// boss holds it until it falls below `HEAL_THRESHOLD_PERCENT` of its hitpoints.
+pub const HEAL_INTERVAL: u8 = 3;
+/// Hitpoints Heal restores, up to the boss's starting hitpoints
+pub const HEAL_AMOUNT: i32 = 12;
+/// Share of its starting hitpoints, in percent, the boss has to fall below to heal
+pub const HEAL_THRESHOLD_PERCENT: i32 = 30;
+/// Attacks between uses of Lifesteal, counting the one it is used with
+pub const LIFESTEAL_INTERVAL: u8 = 2;
+/// Hitpoints Lifesteal takes from the wizard and gives the boss, up to its starting
+/// hitpoints
+pub const LIFESTEAL_AMOUNT: i32 = 3;
+
+/// Something a boss does besides attacking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum BossAbility {
+    /// Every `SILENCE_INTERVAL`th attack keeps the wizard from casting effect spells for
+    /// `SILENCE_DURATION` turns
+    Silence,
+    /// Every `BURN_INTERVAL`th attack adds a stack of burn to the wizard. Unlike Poison,
+    /// stacks add up, to `MAX_BURN_STACKS`, each with its own timer.
+    Burn,
+    /// Every `DISPEL_INTERVAL`th attack ends the wizard's Shield and Recharge early
+    Dispel,
+    /// Heals the boss by `HEAL_AMOUNT` once it's below `HEAL_THRESHOLD_PERCENT` of its
+    /// hitpoints, at most every `HEAL_INTERVAL`th attack
+    Heal,
+    /// Every `LIFESTEAL_INTERVAL`th attack takes `LIFESTEAL_AMOUNT` hitpoints from the
+    /// wizard, whatever their armor, and heals the boss by as much
+    Lifesteal,
+}
+
+impl BossAbility {
+    pub const ALL: [BossAbility; 5] = [
+        BossAbility::Silence,
+        BossAbility::Burn,
+        BossAbility::Dispel,
+        BossAbility::Heal,
+        BossAbility::Lifesteal,
+    ];
+
+    /// Key of the ability in bestiary and challenge files
+    pub fn get_key(&self) -> &'static str {
+        match self {
+            BossAbility::Silence => "silence",
+            BossAbility::Burn => "burn",
+            BossAbility::Dispel => "dispel",
+            BossAbility::Heal => "heal",
+            BossAbility::Lifesteal => "lifesteal",
+        }
+    }
+
+    /// Attacks between uses of the ability, counting the one it is used with
+    pub fn get_interval(&self) -> u8 {
+        match self {
+            BossAbility::Silence => SILENCE_INTERVAL,
+            BossAbility::Burn => BURN_INTERVAL,
+            BossAbility::Dispel => DISPEL_INTERVAL,
+            BossAbility::Heal => HEAL_INTERVAL,
+            BossAbility::Lifesteal => LIFESTEAL_INTERVAL,
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|ability| ability.get_key().eq_ignore_ascii_case(key))
+    }
+
+    pub fn get_display_name(&self) -> &'static str {
+        match self {
+            BossAbility::Silence => "Silence",
+            BossAbility::Burn => "Burn",
+            BossAbility::Dispel => "Dispel",
+            BossAbility::Heal => "Heal",
+            BossAbility::Lifesteal => "Lifesteal",
+        }
+    }
+
+    pub fn get_description(&self) -> String {
+        match self {
+            BossAbility::Silence => format!(
+                "every {} attacks, no effect spells for {} turns",
+                SILENCE_INTERVAL, SILENCE_DURATION
+            ),
+            BossAbility::Burn => format!(
+                "each attack adds a stack of {} damage a turn for {} turns, up to {} stacks",
+                BURN_DAMAGE, BURN_DURATION, MAX_BURN_STACKS
+            ),
+            BossAbility::Dispel => format!(
+                "every {} attacks, ends Shield and Recharge early",
+                DISPEL_INTERVAL
+            ),
+            BossAbility::Heal => format!(
+                "below {}% hitpoints, heals {} at most every {} attacks",
+                HEAL_THRESHOLD_PERCENT, HEAL_AMOUNT, HEAL_INTERVAL
+            ),
+            BossAbility::Lifesteal => format!(
+                "every {} attacks, steals {} hitpoints from the wizard",
+                LIFESTEAL_INTERVAL, LIFESTEAL_AMOUNT
+            ),
+        }
+    }
+}
+
+impl fmt::Display for BossAbility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.get_display_name())
+    }
+}
+
+/// Stacks of burn on the wizard, each counting down its own turns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BurnStacks {
+    /// Turns left of each stack, 0 for a stack not burning
+    timers: [u8; MAX_BURN_STACKS],
+}
+
+impl BurnStacks {
+    /// Add a stack lasting `BURN_DURATION` turns. With every stack burning, the one closest
+    /// to running out is replaced.
+    pub fn add(&mut self) {
+        if let Some(timer) = self.timers.iter_mut().min() {
+            *timer = BURN_DURATION;
+        }
+    }
+
+    /// Count a turn off every stack. Returns the damage the stacks dealt.
+    pub fn tick(&mut self) -> i32 {
+        let stacks = self.get_stacks();
+        for timer in self.timers.iter_mut().filter(|timer| **timer > 0) {
+            *timer -= 1;
+        }
+        stacks as i32 * BURN_DAMAGE
+    }
+
+    pub fn get_stacks(&self) -> usize {
+        self.timers.iter().filter(|timer| **timer > 0).count()
+    }
+
+    /// Turns left of each stack, longest first and 0 for the stacks not burning, so
+    /// the same burns compare equal whichever order they were added in
+    pub fn get_timers(&self) -> [u8; MAX_BURN_STACKS] {
+        let mut timers = self.timers;
+        timers.sort_unstable_by(|a, b| b.cmp(a));
+        timers
+    }
+}
@@ -0,0 +1,179 @@
+//! What the player can do in a battle, independent of the input device that does it.
+
+use ratatui::crossterm::event::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ScrollEventsUp,
+    ScrollEventsDown,
+    /// Scroll the events to the start of the previous round
+    PreviousRound,
+    /// Scroll the events to the start of the next round
+    NextRound,
+    SelectUp,
+    SelectLeft,
+    SelectDown,
+    SelectRight,
+    /// Cast the selected spell, or confirm a wasteful cast
+    Cast,
+    /// Confirm a wasteful cast
+    Confirm,
+    /// Start or stop planning, where casting adds spells to a plan that is cast one per
+    /// round once planning stops
+    TogglePlanning,
+    /// Remove the last spell of the plan while planning
+    Unplan,
+    /// Cast the spell cast last round again
+    RepeatSpell,
+    /// End the turn with action points left, under the action point economy
+    EndTurn,
+    /// Select the spell of the button at this index, in reading order, and cast it
+    CastSpell(usize),
+    ToggleForecast,
+    /// Show the first spell of the cheapest win from the position
+    Hint,
+    /// Run the phases of a round faster
+    SpeedUp,
+    SlowDown,
+    /// Pause or resume the phases of a round
+    TogglePause,
+    /// Save the current frame as text and ANSI art
+    Screenshot,
+    /// Save the battle to a named slot, to resume later
+    SaveToSlot,
+    /// Copy the share string of the finished battle
+    CopyResult,
+    /// Copy the Markdown report of the finished battle
+    CopyReport,
+    /// Open the sandbox console, if the sandbox is enabled
+    OpenConsole,
+    /// Show or hide the debug overlay, if debugging is enabled
+    ToggleDebug,
+    /// Run the next phase of the round now, if debugging is enabled
+    StepPhase,
+    /// Open or close the menu of actions, for controllers that lack the keys
+    ToggleMenu,
+}
+
+/// Keys casting the spells of the buttons, in reading order
+pub const DEFAULT_SPELL_KEYS: &str = "12345678";
+/// Buttons of the spells of the original puzzle, which need a key each
+const CLASSIC_SPELL_BUTTONS: usize = 5;
+
+impl Action {
+    /// Action bound to a key, if any. `spell_keys` cast the spells of the buttons, in
+    /// reading order, and are none of the other bindings' keys.
+    pub fn from_key(key: KeyCode, spell_keys: &[char]) -> Option<Self> {
+        if let KeyCode::Char(c) = key {
+            if let Some(index) = spell_keys.iter().position(|spell_key| *spell_key == c) {
+                return Some(Action::CastSpell(index));
+            }
+        }
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+            KeyCode::Char('j') | KeyCode::Down => Some(Action::ScrollEventsDown),
+            KeyCode::Char('k') | KeyCode::Up => Some(Action::ScrollEventsUp),
+            KeyCode::Char('[') => Some(Action::PreviousRound),
+            KeyCode::Char(']') => Some(Action::NextRound),
+            KeyCode::Char('w') => Some(Action::SelectUp),
+            KeyCode::Char('a') => Some(Action::SelectLeft),
+            KeyCode::Char('s') => Some(Action::SelectDown),
+            KeyCode::Char('d') => Some(Action::SelectRight),
+            KeyCode::Enter => Some(Action::Cast),
+            KeyCode::Char('y') => Some(Action::Confirm),
+            KeyCode::Char('.') => Some(Action::RepeatSpell),
+            KeyCode::Char('e') => Some(Action::EndTurn),
+            KeyCode::Char('l') => Some(Action::TogglePlanning),
+            KeyCode::Backspace => Some(Action::Unplan),
+            KeyCode::Char('f') => Some(Action::ToggleForecast),
+            KeyCode::Char('h') => Some(Action::Hint),
+            KeyCode::Char('+') | KeyCode::Char('=') => Some(Action::SpeedUp),
+            KeyCode::Char('-') => Some(Action::SlowDown),
+            KeyCode::Char('p') => Some(Action::TogglePause),
+            KeyCode::Char('c') => Some(Action::Screenshot),
+            KeyCode::Char('S') => Some(Action::SaveToSlot),
+            KeyCode::Char('x') => Some(Action::CopyResult),
+            KeyCode::Char('X') => Some(Action::CopyReport),
+            KeyCode::Char(':') => Some(Action::OpenConsole),
+            KeyCode::F(12) => Some(Action::ToggleDebug),
+            KeyCode::Char('n') => Some(Action::StepPhase),
+            KeyCode::Char('m') => Some(Action::ToggleMenu),
+            _ => None,
+        }
+    }
+
+    /// Action bound to a game controller button, if any
+    #[cfg(feature = "gamepad")]
+    pub fn from_button(button: gilrs::Button) -> Option<Self> {
+        use gilrs::Button;
+        match button {
+            Button::DPadUp => Some(Action::SelectUp),
+            Button::DPadLeft => Some(Action::SelectLeft),
+            Button::DPadDown => Some(Action::SelectDown),
+            Button::DPadRight => Some(Action::SelectRight),
+            Button::South => Some(Action::Cast),
+            Button::East => Some(Action::ToggleMenu),
+            _ => None,
+        }
+    }
+}
+
+impl Action {
+    /// Whether the action helps the player beyond what the battle shows, looking ahead or
+    /// changing the battle, which ironman runs don't allow
+    pub fn is_assist(self) -> bool {
+        matches!(
+            self,
+            Action::ToggleForecast | Action::Hint | Action::OpenConsole
+        )
+    }
+
+    /// Whether the action plays or changes the battle, which watching a battle played
+    /// elsewhere doesn't allow
+    pub fn changes_battle(self) -> bool {
+        matches!(
+            self,
+            Action::SelectUp
+                | Action::SelectLeft
+                | Action::SelectDown
+                | Action::SelectRight
+                | Action::Cast
+                | Action::Confirm
+                | Action::TogglePlanning
+                | Action::Unplan
+                | Action::RepeatSpell
+                | Action::EndTurn
+                | Action::CastSpell(_)
+                | Action::OpenConsole
+                | Action::StepPhase
+        )
+    }
+}
+
+/// Parse `--spell-keys`: one distinct key per spell button, which isn't bound to another
+/// action. The buttons of the extra spells may be left without keys.
+pub fn parse_spell_keys(keys: &str) -> Result<Vec<char>, String> {
+    let keys: Vec<char> = keys.chars().collect();
+    if !(CLASSIC_SPELL_BUTTONS..=DEFAULT_SPELL_KEYS.len()).contains(&keys.len()) {
+        return Err(format!(
+            "expected {} to {} keys, one per spell",
+            CLASSIC_SPELL_BUTTONS,
+            DEFAULT_SPELL_KEYS.len()
+        ));
+    }
+    if keys
+        .iter()
+        .enumerate()
+        .any(|(i, key)| keys[..i].contains(key))
+    {
+        return Err("each spell needs a different key".to_string());
+    }
+    if let Some(key) = keys
+        .iter()
+        .find(|key| Action::from_key(KeyCode::Char(**key), &[]).is_some())
+    {
+        return Err(format!("`{}` is already bound to another action", key));
+    }
+    Ok(keys)
+}
@@ -0,0 +1,89 @@
+//! Per-boss ambiance: music or sounds the bestiary gives a boss, played by an external
+//! program from the start of a battle against it to the end. The game supervises the one
+//! child process playing, killing it when the battle ends, the game exits or it panics.
+
+use advent_wizard_rpg::bestiary::BestiaryEntry;
+use clap::{arg, Arg, ArgMatches};
+use std::{
+    process::{Child, Command, Stdio},
+    sync::Mutex,
+};
+
+/// Process playing the current battle's ambiance, if any
+static PLAYING: Mutex<Option<Child>> = Mutex::new(None);
+
+pub fn args() -> [Arg; 2] {
+    [
+        arg!(--ambiance "Play each boss's ambiance from the bestiary during battles against it"),
+        arg!(--"ambiance-player" <COMMAND> "Program playing the ambiance, given the boss's ambiance as a track path, such as \"mpv --no-video --loop\"; without it the ambiance is run as a shell command")
+            .requires("ambiance"),
+    ]
+}
+
+/// What plays during a battle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ambiance {
+    /// Program playing `track`, None to run `track` as a shell command itself
+    player: Option<String>,
+    track: String,
+}
+
+impl Ambiance {
+    /// The ambiance of `boss`, None if it has none or ambiance isn't turned on by `args`
+    pub fn from_matches(matches: &ArgMatches, boss: &BestiaryEntry) -> Option<Self> {
+        if !matches.get_flag("ambiance") {
+            return None;
+        }
+        Some(Self {
+            player: matches.get_one::<String>("ambiance-player").cloned(),
+            track: boss.ambiance.clone()?,
+        })
+    }
+
+    /// Start playing in the background, in place of whatever was playing. The player's
+    /// output is discarded, and failing to start it is only logged.
+    pub fn start(&self) {
+        stop();
+        // `exec` has the shell replaced by the player, so killing the child stops the sound
+        let mut command = Command::new("sh");
+        match &self.player {
+            Some(player) => command
+                .arg("-c")
+                .arg(format!("exec {} \"$1\"", player))
+                .arg("sh")
+                .arg(&self.track),
+            None => command.arg("-c").arg(format!("exec {}", self.track)),
+        };
+        let spawned = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(child) => {
+                if let Ok(mut playing) = PLAYING.lock() {
+                    *playing = Some(child);
+                }
+            }
+            Err(_err) => {
+                #[cfg(feature = "logging")]
+                tracing::warn!("could not play the ambiance: {}", _err);
+            }
+        }
+    }
+}
+
+/// Stop the ambiance playing, if any
+pub fn stop() {
+    // A panic while the lock was held leaves it poisoned, but the child is still there to
+    // kill
+    let mut playing = match PLAYING.lock() {
+        Ok(playing) => playing,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if let Some(mut child) = playing.take() {
+        // The player may have exited already, such as at the end of its track
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
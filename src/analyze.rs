@@ -0,0 +1,79 @@
+//! `analyze` subcommand: explore the game tree and report on the winning lines.
+
+use crate::{
+    cli::{battle_args, battle_from_matches, display_line},
+    report::{self, OutputFormat},
+};
+use advent_wizard_rpg::solver::{analyze_tree, LineStats};
+use clap::{arg, value_parser, ArgMatches, Command};
+
+pub fn command() -> Command {
+    Command::new("analyze")
+        .about("Explore the game tree and report winning lines")
+        .args(battle_args())
+        .arg(
+            arg!(--depth <ROUNDS> "Rounds to explore")
+                .value_parser(value_parser!(usize))
+                .default_value("10"),
+        )
+        .arg(report::format_arg(&[]))
+}
+
+pub fn run(matches: &ArgMatches) {
+    let battle = battle_from_matches(matches);
+    let boss_hitpoints = battle.get_boss().get_hitpoints();
+    let boss_damage = battle.get_boss().get_damage();
+    let hard_mode = battle.get_hard_mode();
+    let depth = *matches.get_one::<usize>("depth").unwrap();
+
+    let analysis = analyze_tree(&battle, depth);
+
+    let format = report::format_from_matches(matches);
+    if format != OutputFormat::Human {
+        report::analysis_report(&battle, depth, &analysis).print(format);
+        return;
+    }
+
+    println!(
+        "Boss: {} hitpoints, {} damage{}",
+        boss_hitpoints,
+        boss_damage,
+        if hard_mode { " (hard)" } else { "" }
+    );
+    println!("Depth: {} rounds", depth);
+    println!();
+    println!(
+        "{:<15}{:<12}{:>15}{:>15}{:>15}",
+        "First move", "Evaluation", "Winning lines", "Shortest win", "Cheapest win"
+    );
+    for (spell, stats) in &analysis.first_moves {
+        print_table_row(spell.get_display_name(), stats);
+    }
+    print_table_row("Total", &analysis.total);
+    println!();
+    match &analysis.total.shortest_win {
+        Some(line) => println!("Shortest win: {}", display_line(line)),
+        None => println!("Shortest win: none found"),
+    }
+    match &analysis.total.cheapest_win {
+        Some((mana, line)) => println!("Cheapest win ({} mana): {}", mana, display_line(line)),
+        None => println!("Cheapest win: none found"),
+    }
+}
+
+fn print_table_row(name: &str, stats: &LineStats) {
+    println!(
+        "{:<15}{:<12}{:>15}{:>15}{:>15}",
+        name,
+        report::evaluation_name(stats.get_evaluation()),
+        stats.winning_lines,
+        stats
+            .shortest_win
+            .as_ref()
+            .map_or("-".to_string(), |line| format!("{} rounds", line.len())),
+        stats
+            .cheapest_win
+            .as_ref()
+            .map_or("-".to_string(), |(mana, _)| format!("{} mana", mana)),
+    );
+}
@@ -0,0 +1,113 @@
+//! Playing against the boss of the player's own Advent of Code puzzle input.
+
+use crate::data_dir;
+use advent_wizard_rpg::{bestiary::BestiaryEntry, challenge::Challenge, file_format::write_atomic};
+use clap::{arg, Arg, ArgMatches};
+use std::{env, fs, io, path::PathBuf, process, time::Duration};
+use ureq::Agent;
+
+const INPUT_URL: &str = "https://adventofcode.com/2015/day/22/input";
+/// Identifies the game to the Advent of Code servers, as they ask of automated requests
+const USER_AGENT: &str = concat!(
+    "advent-wizard-rpg/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/binotation/advent-wizard-rpg)"
+);
+/// Longest the download of the input may take
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+/// Environment variable holding the session cookie when `--aoc-session` isn't given
+const SESSION_ENV_VAR: &str = "AOC_SESSION";
+/// Name of the challenge against the puzzle input's boss
+const BOSS_NAME: &str = "Puzzle Input Boss";
+
+pub fn arg() -> Arg {
+    arg!(--"aoc-session" <TOKEN> "Advent of Code session cookie, to fight the boss of your 2015 day 22 input [env: AOC_SESSION]")
+}
+
+/// Challenge against the boss of the player's puzzle input, if a session was given by
+/// `arg` or the environment. Exits if the input can't be fetched.
+pub fn challenge_from_matches(matches: &ArgMatches) -> Option<Challenge> {
+    let session = matches
+        .get_one::<String>("aoc-session")
+        .cloned()
+        .or_else(|| env::var(SESSION_ENV_VAR).ok())
+        .filter(|session| !session.is_empty())?;
+    match load_boss(&session) {
+        Ok(boss) => Some(Challenge {
+            hard_mode: matches.get_flag("hard"),
+            ..Challenge::new(boss)
+        }),
+        Err(err) => {
+            eprintln!("Could not get the Advent of Code puzzle input: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Where the input of a session is cached, so it's only downloaded once
+fn cache_path(session: &str) -> PathBuf {
+    // Named by a hash of the session, so changing accounts fetches the other input
+    let hash = session.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    data_dir::local_dir()
+        .join("cache")
+        .join(format!("aoc-2015-22-{:016x}.txt", hash))
+}
+
+/// Boss of the session's puzzle input, from the cache if it was downloaded before
+fn load_boss(session: &str) -> io::Result<BestiaryEntry> {
+    let path = cache_path(session);
+    if let Ok(input) = fs::read_to_string(&path) {
+        return parse_input(&input);
+    }
+    let input = download_input(session)?;
+    let boss = parse_input(&input)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomic(&path, &input)?;
+    Ok(boss)
+}
+
+/// Download the puzzle input, sending the session as the cookie the site expects
+fn download_input(session: &str) -> io::Result<String> {
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(Some(DOWNLOAD_TIMEOUT))
+        .build()
+        .into();
+    agent
+        .get(INPUT_URL)
+        .header("User-Agent", USER_AGENT)
+        .header("Cookie", format!("session={}", session))
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .map_err(|err| io::Error::other(format!("download failed: {}", err)))
+}
+
+/// Boss of a puzzle input, given as `Hit Points: <HP>` and `Damage: <DAMAGE>` lines
+fn parse_input(input: &str) -> io::Result<BestiaryEntry> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut hitpoints = None;
+    let mut damage = None;
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid(format!("unexpected line `{}`", line)))?;
+        let value = value
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| invalid(format!("`{}` is not a number", value.trim())))?;
+        match key.trim() {
+            "Hit Points" => hitpoints = Some(value),
+            "Damage" => damage = Some(value),
+            key => return Err(invalid(format!("unknown stat `{}`", key))),
+        }
+    }
+    match (hitpoints, damage) {
+        (Some(hitpoints), Some(damage)) => Ok(BestiaryEntry::new(BOSS_NAME, hitpoints, damage)),
+        _ => Err(invalid(
+            "input is missing `Hit Points` or `Damage`".to_string(),
+        )),
+    }
+}
@@ -0,0 +1,127 @@
+use std::io;
+
+/// Size of a block of a tar archive: each header, and each file padded to a whole number
+/// of them
+const BLOCK_SIZE: usize = 512;
+
+/// A file in an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path within the archive, with directories separated by `/`
+    pub path: String,
+    /// When the file was last modified, in seconds since the Unix epoch
+    pub modified: u64,
+    pub contents: Vec<u8>,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Write `value` in octal to `field`, padded with zeros and ending in a NUL
+fn write_octal(field: &mut [u8], value: u64) -> io::Result<()> {
+    let text = format!("{:0width$o}\0", value, width = field.len() - 1);
+    if text.len() > field.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is too large for a tar header", value),
+        ));
+    }
+    field.copy_from_slice(text.as_bytes());
+    Ok(())
+}
+
+/// Octal number of a header field, which ends at a NUL or space
+fn read_octal(field: &[u8]) -> io::Result<u64> {
+    let text = String::from_utf8_lossy(field);
+    let digits = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    u64::from_str_radix(digits, 8)
+        .map_err(|_| invalid_data(format!("invalid number `{}` in a tar header", digits)))
+}
+
+/// Text of a header field, up to the first NUL
+fn read_text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).to_string()
+}
+
+/// Sum of the bytes of `header`, counting its checksum field as spaces
+fn get_checksum(header: &[u8]) -> u64 {
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+        .sum()
+}
+
+/// A ustar archive of `entries`, readable by `tar` and other archivers. Errors if a path
+/// is longer than the 100 bytes a header holds.
+pub fn write_tar(entries: &[ArchiveEntry]) -> io::Result<Vec<u8>> {
+    let mut archive = Vec::new();
+    for entry in entries {
+        let mut header = [0; BLOCK_SIZE];
+        if entry.path.len() > 100 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is too long a path for a tar archive", entry.path),
+            ));
+        }
+        header[..entry.path.len()].copy_from_slice(entry.path.as_bytes());
+        write_octal(&mut header[100..108], 0o644)?;
+        write_octal(&mut header[108..116], 0)?;
+        write_octal(&mut header[116..124], 0)?;
+        write_octal(&mut header[124..136], entry.contents.len() as u64)?;
+        write_octal(&mut header[136..148], entry.modified)?;
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        let checksum = format!("{:06o}\0 ", get_checksum(&header));
+        header[148..156].copy_from_slice(checksum.as_bytes());
+
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(&entry.contents);
+        let padding = (BLOCK_SIZE - entry.contents.len() % BLOCK_SIZE) % BLOCK_SIZE;
+        archive.resize(archive.len() + padding, 0);
+    }
+    // The archive ends with two empty blocks
+    archive.resize(archive.len() + 2 * BLOCK_SIZE, 0);
+    Ok(archive)
+}
+
+/// The files of a tar archive, in the order they were archived. Directories, links and
+/// other kinds of entry are left out.
+pub fn read_tar(archive: &[u8]) -> io::Result<Vec<ArchiveEntry>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while let Some(header) = archive.get(offset..offset + BLOCK_SIZE) {
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if read_octal(&header[148..156])? != get_checksum(header) {
+            return Err(invalid_data(format!(
+                "header at byte {} of the tar archive is corrupt",
+                offset
+            )));
+        }
+        let size = read_octal(&header[124..136])? as usize;
+        let start = offset + BLOCK_SIZE;
+        let contents = archive
+            .get(start..start + size)
+            .ok_or_else(|| invalid_data("tar archive ends part way through a file".to_string()))?;
+        if matches!(header[156], b'0' | 0) {
+            let mut path = read_text(&header[..100]);
+            // ustar headers continue long paths in a prefix
+            let prefix = read_text(&header[345..500]);
+            if header[257..262] == *b"ustar" && !prefix.is_empty() {
+                path = format!("{}/{}", prefix, path);
+            }
+            entries.push(ArchiveEntry {
+                path,
+                modified: read_octal(&header[136..148])?,
+                contents: contents.to_vec(),
+            });
+        }
+        offset = start + size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+    }
+    Ok(entries)
+}
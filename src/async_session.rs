@@ -0,0 +1,120 @@
+//! `tokio` feature: `BattleSession` for async frontends, such as a game server, that play
+//! and follow a battle from tasks instead of threads.
+
+use crate::{
+    session::{SessionAction, SessionUpdate},
+    Battle, BattleState, EffectOngoingError,
+};
+use std::sync::{Arc, Mutex};
+use tokio::{
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+
+/// Updates a subscriber can fall behind by before it misses some
+const UPDATE_CAPACITY: usize = 256;
+
+/// A battle owned by its own task, played by actions sent from any task, that broadcasts
+/// its events and state to every subscriber. The async counterpart of `BattleSession`,
+/// which must be created within a tokio runtime.
+#[derive(Debug)]
+pub struct AsyncBattleSession {
+    actions: mpsc::UnboundedSender<SessionAction>,
+    updates: broadcast::Sender<SessionUpdate>,
+    /// Latest state, for new subscribers
+    state: Arc<Mutex<(BattleState, Option<bool>)>>,
+    task: Option<JoinHandle<Battle>>,
+}
+
+impl AsyncBattleSession {
+    /// Start a session of `battle`, which should be at the start of the wizard's turn
+    pub fn spawn(mut battle: Battle) -> Self {
+        let (actions, receiver) = mpsc::unbounded_channel();
+        let (updates, _) = broadcast::channel(UPDATE_CAPACITY);
+        let state = Arc::new(Mutex::new((battle.get_state(), battle.get_outcome())));
+
+        let event_updates = updates.clone();
+        battle.subscribe(Box::new(move |event| {
+            // Fails only while nobody is subscribed
+            let _ = event_updates.send(SessionUpdate::Event(event.clone()));
+        }));
+        let task_updates = updates.clone();
+        let task_state = state.clone();
+        let task = tokio::spawn(async move {
+            play(&mut battle, receiver, &task_updates, &task_state).await;
+            battle
+        });
+        Self {
+            actions,
+            updates,
+            state,
+            task: Some(task),
+        }
+    }
+
+    /// Sender of actions to the session, which can be cloned to other tasks
+    pub fn get_sender(&self) -> mpsc::UnboundedSender<SessionAction> {
+        self.actions.clone()
+    }
+
+    /// The current state, and a receiver of the updates of the session from then on.
+    /// A subscriber more than 256 updates behind misses the oldest, as its receiver
+    /// reports. Dropping the receiver unsubscribes.
+    pub fn subscribe(&self) -> (SessionUpdate, broadcast::Receiver<SessionUpdate>) {
+        // Hold the state while subscribing so no round is missed in between
+        let current = self.state.lock().unwrap();
+        let (state, outcome) = *current;
+        (
+            SessionUpdate::State { state, outcome },
+            self.updates.subscribe(),
+        )
+    }
+
+    /// Stop the session once the actions already sent are played, returning the battle
+    pub async fn finish(mut self) -> Battle {
+        let _ = self.actions.send(SessionAction::Quit);
+        self.task
+            .take()
+            .unwrap()
+            .await
+            .expect("battle session panicked")
+    }
+}
+
+impl Drop for AsyncBattleSession {
+    fn drop(&mut self) {
+        // The task stops by itself once it gets to the quit
+        if self.task.is_some() {
+            let _ = self.actions.send(SessionAction::Quit);
+        }
+    }
+}
+
+/// Play actions until told to quit or every sender is dropped
+async fn play(
+    battle: &mut Battle,
+    mut actions: mpsc::UnboundedReceiver<SessionAction>,
+    updates: &broadcast::Sender<SessionUpdate>,
+    state: &Mutex<(BattleState, Option<bool>)>,
+) {
+    while let Some(action) = actions.recv().await {
+        let spell = match action {
+            SessionAction::Cast(spell) => spell,
+            SessionAction::Quit => return,
+        };
+        let played = match battle.get_outcome() {
+            Some(_) => Err(EffectOngoingError()),
+            None => battle.play_round(&spell),
+        };
+        if played.is_err() {
+            let _ = updates.send(SessionUpdate::Rejected(spell));
+            continue;
+        }
+        let mut current = state.lock().unwrap();
+        *current = (battle.get_state(), battle.get_outcome());
+        let _ = updates.send(SessionUpdate::State {
+            state: current.0,
+            outcome: current.1,
+        });
+    }
+}
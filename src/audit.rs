@@ -0,0 +1,107 @@
+//! `audit` subcommand: report degenerate battles in a spellbook and bestiary.
+
+use crate::cli::display_line;
+use advent_wizard_rpg::{
+    balance::{audit_battle, find_dominated_spells},
+    bestiary::Bestiary,
+    challenge::Challenge,
+    Spellbook,
+};
+use clap::{arg, value_parser, ArgMatches, Command};
+use std::{io, path::PathBuf};
+
+pub fn command() -> Command {
+    Command::new("audit")
+        .about("Check a spellbook against every boss of a bestiary for degenerate battles")
+        .arg(
+            arg!(--spellbook <FILE> "Spellbook file to check [default: the built-in spells]")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--bestiary <FILE> "Bestiary file to check [default: the built-in bosses]")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(arg!(--hard "Set difficulty to hard"))
+        .arg(
+            arg!(--depth <ROUNDS> "Rounds to search for a win")
+                .value_parser(value_parser!(usize))
+                .default_value("20"),
+        )
+        .arg(
+            arg!(--playouts <COUNT> "Random battles to play against each boss")
+                .value_parser(value_parser!(u64))
+                .default_value("100000"),
+        )
+        .arg(
+            arg!(--seed <SEED> "Seed for the random playouts")
+                .value_parser(value_parser!(u64))
+                .default_value("0"),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> io::Result<()> {
+    let spellbook = match matches.get_one::<PathBuf>("spellbook") {
+        Some(path) => Spellbook::load(path)?,
+        None => Spellbook::default(),
+    };
+    let bestiary = match matches.get_one::<PathBuf>("bestiary") {
+        Some(path) => Bestiary::load(path)?,
+        None => Bestiary::builtin(),
+    };
+    let depth = *matches.get_one::<usize>("depth").unwrap();
+    let playouts = *matches.get_one::<u64>("playouts").unwrap();
+    let seed = *matches.get_one::<u64>("seed").unwrap();
+
+    let mut reports = Vec::new();
+    let mut degenerate = 0;
+    for boss in bestiary.get_entries() {
+        let challenge = Challenge {
+            spellbook: spellbook.clone(),
+            hard_mode: matches.get_flag("hard"),
+            ..Challenge::new(boss.clone())
+        };
+        let mut battle = challenge.create_battle();
+        battle.wizard_turn_apply_effects();
+        let report = audit_battle(&battle, depth, playouts, seed);
+
+        println!(
+            "{} ({} hitpoints, {} damage)",
+            boss.name, boss.hitpoints, boss.damage
+        );
+        match &report.cheapest_win {
+            Some(solution) => println!(
+                "  Cheapest win: {} mana in {} rounds: {}",
+                solution.mana,
+                solution.spells.len(),
+                display_line(&solution.spells)
+            ),
+            None => println!("  Cheapest win: none within {} rounds", depth),
+        }
+        println!(
+            "  Random spells win {:.2}% of {} playouts",
+            report.random_playouts.get_win_probability() * 100.0,
+            report.random_playouts.playouts
+        );
+        for warning in &report.warnings {
+            println!("  Warning: {}", warning);
+        }
+        if !report.warnings.is_empty() {
+            degenerate += 1;
+        }
+        reports.push(report);
+    }
+
+    println!();
+    let dominated = find_dominated_spells(&reports, &spellbook);
+    if dominated.is_empty() {
+        println!("Every spell is cast in some cheapest win");
+    } else {
+        println!("Never cast in a cheapest win: {}", display_line(&dominated));
+    }
+    println!(
+        "{} of {} bosses have warnings",
+        degenerate,
+        bestiary.get_entries().len()
+    );
+    Ok(())
+}
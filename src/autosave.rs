@@ -0,0 +1,104 @@
+//! Rolling autosaves written after every round, and recovery after a crash.
+
+use crate::data_dir;
+use advent_wizard_rpg::{
+    challenge::Challenge,
+    file_format::write_atomic,
+    save::{SaveGame, SAVE_FILE_SUFFIX},
+    Battle,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Name of the file recording when the game last exited cleanly
+const CLEAN_EXIT_FILE: &str = "clean-exit";
+
+/// Autosave directory used when none is given
+pub fn default_dir() -> PathBuf {
+    data_dir::local_dir().join("autosaves")
+}
+
+#[derive(Debug)]
+pub struct Autosaver {
+    dir: PathBuf,
+    /// Number of autosaves kept, older ones are removed
+    keep: usize,
+    /// Rules of the battle being saved
+    challenge: Challenge,
+}
+
+impl Autosaver {
+    pub fn new(dir: PathBuf, keep: usize, challenge: Challenge) -> Self {
+        Self {
+            dir,
+            keep,
+            challenge,
+        }
+    }
+
+    /// Write the battle to a new slot, then remove the oldest slots beyond `keep`
+    pub fn save(&self, battle: &Battle) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self
+            .dir
+            .join(format!("autosave-{:013}{}", now_millis(), SAVE_FILE_SUFFIX));
+        SaveGame::new(self.challenge.clone(), battle).save(&path)?;
+
+        let slots = list_slots(&self.dir)?;
+        for (_, old) in slots.iter().take(slots.len().saturating_sub(self.keep)) {
+            fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+
+    /// Record that the game exited cleanly, so its autosaves aren't offered for recovery
+    pub fn mark_clean_exit(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        write_atomic(&self.dir.join(CLEAN_EXIT_FILE), now_millis().to_string())
+    }
+}
+
+/// The newest autosave in `dir` if it was written after the last clean exit, meaning the
+/// game didn't get to exit cleanly
+pub fn find_recoverable(dir: &Path) -> Option<PathBuf> {
+    let (saved_at, path) = list_slots(dir).ok()?.pop()?;
+    let clean_exit_at = fs::read_to_string(dir.join(CLEAN_EXIT_FILE))
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or(0);
+    (saved_at > clean_exit_at).then_some(path)
+}
+
+/// The newest autosave in `dir`, if any
+pub fn find_latest(dir: &Path) -> Option<PathBuf> {
+    list_slots(dir).ok()?.pop().map(|(_, path)| path)
+}
+
+/// Autosaves in `dir` and when they were written, oldest first
+fn list_slots(dir: &Path) -> io::Result<Vec<(u128, PathBuf)>> {
+    let mut slots = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let saved_at = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("autosave-"))
+            .and_then(|name| name.strip_suffix(SAVE_FILE_SUFFIX))
+            .and_then(|millis| millis.parse().ok());
+        if let Some(saved_at) = saved_at {
+            slots.push((saved_at, path));
+        }
+    }
+    slots.sort();
+    Ok(slots)
+}
+
+fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
@@ -0,0 +1,120 @@
+use crate::{
+    playout::{estimate_win_probability, PlayoutStats},
+    solver::{
+        analyze_tree, evaluate_position, solve_cheapest_win, Algorithm, Evaluation, SearchBudget,
+        Solution, SolveOptions,
+    },
+    Battle, Spell, Spellbook,
+};
+use std::fmt;
+
+/// Rounds within which a win is considered trivial
+pub const TRIVIAL_WIN_ROUNDS: usize = 2;
+
+/// Rounds explored when looking for a losing line. Every line is explored, so this is
+/// kept small.
+const LOSING_LINE_DEPTH: usize = 8;
+
+/// Something that makes a battle degenerate
+#[derive(Debug, Eq, PartialEq, Clone)]
+#[non_exhaustive]
+pub enum BalanceWarning {
+    /// No line of spells wins within the rounds searched
+    Unwinnable { max_depth: usize },
+    /// Some line of spells wins within `TRIVIAL_WIN_ROUNDS` rounds
+    TriviallyWinnable,
+    /// No line of spells loses within the rounds explored
+    Unloseable { max_depth: usize },
+}
+
+impl fmt::Display for BalanceWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalanceWarning::Unwinnable { max_depth } => {
+                write!(f, "unwinnable: no win within {} rounds", max_depth)
+            }
+            BalanceWarning::TriviallyWinnable => write!(
+                f,
+                "trivially winnable: the boss can be beaten within {} rounds",
+                TRIVIAL_WIN_ROUNDS
+            ),
+            BalanceWarning::Unloseable { max_depth } => {
+                write!(f, "unloseable: no line loses within {} rounds", max_depth)
+            }
+        }
+    }
+}
+
+/// Balance of a battle, from the solver and random playouts
+#[derive(Debug, Clone)]
+pub struct BalanceReport {
+    pub warnings: Vec<BalanceWarning>,
+    /// Cheapest win within the rounds searched
+    pub cheapest_win: Option<Solution>,
+    /// Battles where the wizard casts random possible spells
+    pub random_playouts: PlayoutStats,
+}
+
+/// Quick solver check of a battle, from the start of the wizard's turn, for outcomes that
+/// don't depend on how the wizard plays
+pub fn check_balance(battle: &Battle, max_depth: usize) -> Vec<BalanceWarning> {
+    solve_balance(battle, max_depth).0
+}
+
+/// `check_balance` along with the cheapest win and `playouts` random playouts
+pub fn audit_battle(battle: &Battle, max_depth: usize, playouts: u64, seed: u64) -> BalanceReport {
+    let (warnings, cheapest_win) = solve_balance(battle, max_depth);
+    BalanceReport {
+        warnings,
+        cheapest_win,
+        random_playouts: estimate_win_probability(battle, playouts, seed),
+    }
+}
+
+/// Spells of `spellbook` that aren't sealed and are cast in none of the cheapest wins of
+/// `reports`. Only one cheapest win is found per battle, so a spell tied with another one
+/// may be reported too.
+pub fn find_dominated_spells(reports: &[BalanceReport], spellbook: &Spellbook) -> Vec<Spell> {
+    Spell::ALL
+        .iter()
+        .filter(|spell| {
+            !spellbook.is_sealed(spell)
+                && !reports
+                    .iter()
+                    .filter_map(|report| report.cheapest_win.as_ref())
+                    .any(|solution| solution.spells.contains(spell))
+        })
+        .cloned()
+        .collect()
+}
+
+fn solve_balance(battle: &Battle, max_depth: usize) -> (Vec<BalanceWarning>, Option<Solution>) {
+    let mut warnings = Vec::new();
+
+    let result = solve_cheapest_win(
+        battle,
+        SolveOptions {
+            algorithm: Algorithm::AStar,
+            max_depth,
+            prune: true,
+            memo_size: 1_000_000,
+        },
+    );
+    if result.solution.is_none() {
+        warnings.push(BalanceWarning::Unwinnable { max_depth });
+    }
+
+    let budget = SearchBudget {
+        max_depth: TRIVIAL_WIN_ROUNDS,
+        time_limit: None,
+    };
+    if evaluate_position(battle, budget) == Evaluation::Won {
+        warnings.push(BalanceWarning::TriviallyWinnable);
+    }
+
+    let depth = max_depth.min(LOSING_LINE_DEPTH);
+    if analyze_tree(battle, depth).total.losing_lines == 0 {
+        warnings.push(BalanceWarning::Unloseable { max_depth: depth });
+    }
+    (warnings, result.solution)
+}
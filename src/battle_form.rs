@@ -0,0 +1,532 @@
+//! `new` subcommand: describe a custom battle with flags, or fill it in a form with
+//! `--interactive`, then fight it or save it as a challenge file.
+
+use crate::{
+    presets,
+    ui::{theme, tui, TextInput},
+};
+use advent_wizard_rpg::{
+    bestiary::BestiaryEntry, challenge::Challenge, encounter::EncounterMutator, item::Equipment,
+    preset::BattlePreset, RulesVersion, Spellbook,
+};
+use clap::{arg, value_parser, ArgAction, ArgMatches, Command};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Alignment, Constraint, Layout},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Paragraph},
+    Frame,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Mutators offered besides the difficulty, which covers Hard Mode
+const RULE_MUTATORS: [EncounterMutator; 4] = [
+    EncounterMutator::CostlyShield,
+    EncounterMutator::WeakPoison,
+    EncounterMutator::ShortRecharge,
+    EncounterMutator::DullMissile,
+];
+
+pub fn command() -> Command {
+    Command::new("new")
+        .about("Fight a custom boss, or save it as a challenge file")
+        .arg(arg!(-i --interactive "Fill in the battle in a form instead of with flags"))
+        .arg(arg!(--name <NAME> "Name of the boss").default_value("Custom Boss"))
+        .arg(
+            arg!(--"boss-hp" <HP> "Boss hitpoints")
+                .value_parser(value_parser!(i32))
+                .default_value("55"),
+        )
+        .arg(
+            arg!(--"boss-damage" <DAMAGE> "Boss damage")
+                .value_parser(value_parser!(i32))
+                .default_value("8"),
+        )
+        .arg(arg!(--hard "Set difficulty to hard"))
+        .arg(arg!(--classic "Show the boss's hitpoints and damage from the start, instead of scouting them"))
+        .arg(
+            arg!(--mutator <MUTATOR> "Change the rules of a spell, can be given more than once")
+                .value_parser(RULE_MUTATORS.map(EncounterMutator::get_key))
+                .action(ArgAction::Append),
+        )
+        .arg(
+            arg!(--spellbook <FILE> "Spellbook file to fight with [default: the standard spellbook]")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(arg!(--items <ITEMS> "Items the wizard wears, separated by commas"))
+        .arg(
+            arg!(--save <FILE> "Write a challenge file instead of fighting")
+                .value_parser(value_parser!(PathBuf)),
+        )
+}
+
+/// The challenge to fight, None if it was saved instead or the form was left
+pub fn run(matches: &ArgMatches) -> io::Result<Option<Challenge>> {
+    let settings = BattleSettings::from_matches(matches);
+    let save_path = matches.get_one::<PathBuf>("save");
+    if matches.get_flag("interactive") {
+        let presets = presets::load_registry().get_presets().clone();
+        let mut terminal = tui::init()?;
+        let result = BattleForm::new(&settings, save_path, presets).run(&mut terminal);
+        tui::restore()?;
+        return result;
+    }
+
+    let challenge = match settings.create_challenge() {
+        Ok(challenge) => challenge,
+        Err(message) => {
+            eprintln!("Invalid battle: {}", message);
+            process::exit(1);
+        }
+    };
+    match save_path {
+        Some(path) => {
+            challenge.save(path)?;
+            println!("Saved challenge to {}", path.display());
+            Ok(None)
+        }
+        None => Ok(Some(challenge)),
+    }
+}
+
+/// Everything the flags and the form describe about a custom battle
+#[derive(Debug, Clone)]
+struct BattleSettings {
+    name: String,
+    hitpoints: i32,
+    damage: i32,
+    hard_mode: bool,
+    scouting: bool,
+    rules_version: RulesVersion,
+    mutators: Vec<EncounterMutator>,
+    /// Spellbook file, the default spellbook if None
+    spellbook: Option<PathBuf>,
+    items: String,
+}
+
+impl BattleSettings {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        Self {
+            name: matches.get_one::<String>("name").unwrap().clone(),
+            hitpoints: *matches.get_one::<i32>("boss-hp").unwrap(),
+            damage: *matches.get_one::<i32>("boss-damage").unwrap(),
+            hard_mode: matches.get_flag("hard"),
+            scouting: !matches.get_flag("classic"),
+            rules_version: RulesVersion::LATEST,
+            mutators: matches
+                .get_many::<String>("mutator")
+                .unwrap_or_default()
+                .filter_map(|key| EncounterMutator::from_key(key))
+                .collect(),
+            spellbook: matches.get_one::<PathBuf>("spellbook").cloned(),
+            items: matches
+                .get_one::<String>("items")
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The challenge of the battle, or why it can't be fought. The name lists the
+    /// mutators, so they show in the boss panel.
+    fn create_challenge(&self) -> Result<Challenge, String> {
+        let boss = BestiaryEntry::new(self.name.trim(), self.hitpoints, self.damage);
+        boss.validate()?;
+        let mut challenge = Challenge::new(boss);
+        if let Some(path) = &self.spellbook {
+            challenge.spellbook = Spellbook::load(path)
+                .map_err(|err| format!("could not load {}: {}", path.display(), err))?;
+        }
+        challenge.hard_mode = self.hard_mode;
+        challenge.scouting = self.scouting;
+        challenge.rules_version = self.rules_version;
+        challenge.equipment = Equipment::parse(&self.items)?;
+        for mutator in &self.mutators {
+            mutator.apply(&mut challenge);
+        }
+        challenge.spellbook.validate()?;
+        if !self.mutators.is_empty() {
+            let mutators: Vec<String> = self.mutators.iter().map(ToString::to_string).collect();
+            challenge.name = format!("{} ({})", challenge.name, mutators.join(", "));
+        }
+        Ok(challenge)
+    }
+}
+
+/// Row of the form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Preset,
+    Name,
+    Hitpoints,
+    Damage,
+    Difficulty,
+    Scouting,
+    /// Index into `RULE_MUTATORS`
+    Mutator(usize),
+    Spellbook,
+    Items,
+    SavePath,
+    Fight,
+    Save,
+}
+
+impl Field {
+    fn get_label(self) -> String {
+        match self {
+            Self::Preset => "Preset".to_string(),
+            Self::Name => "Name".to_string(),
+            Self::Hitpoints => "Hitpoints".to_string(),
+            Self::Damage => "Damage".to_string(),
+            Self::Difficulty => "Difficulty".to_string(),
+            Self::Scouting => "Scouting".to_string(),
+            Self::Mutator(i) => RULE_MUTATORS[i].to_string(),
+            Self::Spellbook => "Spellbook".to_string(),
+            Self::Items => "Items".to_string(),
+            Self::SavePath => "Save to".to_string(),
+            Self::Fight | Self::Save => String::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BattleForm {
+    exit: bool,
+    fields: Vec<Field>,
+    field_selected: usize,
+    name: TextInput,
+    hitpoints: TextInput,
+    damage: TextInput,
+    hard_mode: bool,
+    scouting: bool,
+    /// Rules of the preset picked last, the latest rules if none was
+    rules_version: RulesVersion,
+    /// Presets that fill in the form
+    presets: Vec<BattlePreset>,
+    /// Index into `presets` of the preset picked last
+    preset_selected: Option<usize>,
+    /// Whether each of `RULE_MUTATORS` is on
+    mutators: [bool; RULE_MUTATORS.len()],
+    /// Spellbook file, left empty for the default spellbook
+    spellbook: TextInput,
+    items: TextInput,
+    /// Challenge file to save to, left empty for a name made from the boss's
+    save_path: TextInput,
+    /// Challenge to fight once the form is left
+    fight: Option<Challenge>,
+    /// Result of the last action
+    status: String,
+}
+
+impl BattleForm {
+    fn new(
+        settings: &BattleSettings,
+        save_path: Option<&PathBuf>,
+        presets: Vec<BattlePreset>,
+    ) -> Self {
+        let mut fields = vec![
+            Field::Preset,
+            Field::Name,
+            Field::Hitpoints,
+            Field::Damage,
+            Field::Difficulty,
+            Field::Scouting,
+        ];
+        fields.extend((0..RULE_MUTATORS.len()).map(Field::Mutator));
+        fields.extend([
+            Field::Spellbook,
+            Field::Items,
+            Field::SavePath,
+            Field::Fight,
+            Field::Save,
+        ]);
+        Self {
+            exit: false,
+            fields,
+            field_selected: 0,
+            name: TextInput::new(settings.name.clone()),
+            hitpoints: TextInput::new(settings.hitpoints.to_string()),
+            damage: TextInput::new(settings.damage.to_string()),
+            hard_mode: settings.hard_mode,
+            scouting: settings.scouting,
+            rules_version: settings.rules_version,
+            presets,
+            preset_selected: None,
+            mutators: RULE_MUTATORS.map(|mutator| settings.mutators.contains(&mutator)),
+            spellbook: TextInput::new(
+                settings
+                    .spellbook
+                    .as_ref()
+                    .map_or(String::new(), |path| path.display().to_string()),
+            ),
+            items: TextInput::new(settings.items.clone()),
+            save_path: TextInput::new(
+                save_path.map_or(String::new(), |path| path.display().to_string()),
+            ),
+            fight: None,
+            status: String::new(),
+        }
+    }
+
+    fn run(mut self, terminal: &mut tui::Tui) -> io::Result<Option<Challenge>> {
+        while !self.exit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                self.handle_key_event(key.code);
+            }
+        }
+        Ok(self.fight)
+    }
+
+    fn handle_key_event(&mut self, key: KeyCode) {
+        let field = self.fields[self.field_selected];
+        match key {
+            KeyCode::Esc => self.exit = true,
+            KeyCode::Tab | KeyCode::Down => self.select_next(),
+            KeyCode::BackTab | KeyCode::Up => {
+                self.field_selected =
+                    (self.field_selected + self.fields.len() - 1) % self.fields.len();
+            }
+            KeyCode::Enter => match field {
+                Field::Preset => self.cycle_preset(true),
+                Field::Fight => self.fight(),
+                Field::Save => self.save(),
+                Field::Difficulty | Field::Scouting | Field::Mutator(_) => self.toggle(field),
+                _ => self.select_next(),
+            },
+            KeyCode::Char(' ') | KeyCode::Right if field == Field::Preset => {
+                self.cycle_preset(true)
+            }
+            KeyCode::Left if field == Field::Preset => self.cycle_preset(false),
+            KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right
+                if matches!(
+                    field,
+                    Field::Difficulty | Field::Scouting | Field::Mutator(_)
+                ) =>
+            {
+                self.toggle(field)
+            }
+            key => {
+                if let Some(input) = self.get_input_mut(field) {
+                    input.handle_key_event(key);
+                }
+            }
+        }
+    }
+
+    fn select_next(&mut self) {
+        self.field_selected = (self.field_selected + 1) % self.fields.len();
+    }
+
+    fn toggle(&mut self, field: Field) {
+        match field {
+            Field::Difficulty => self.hard_mode = !self.hard_mode,
+            Field::Scouting => self.scouting = !self.scouting,
+            Field::Mutator(i) => self.mutators[i] = !self.mutators[i],
+            _ => (),
+        }
+    }
+
+    /// Pick the next or previous preset, going through none between the last and the
+    /// first, and fill in the form with it
+    fn cycle_preset(&mut self, forward: bool) {
+        let count = self.presets.len() + 1;
+        let position = self.preset_selected.map_or(0, |i| i + 1);
+        let position = if forward {
+            (position + 1) % count
+        } else {
+            (position + count - 1) % count
+        };
+        self.preset_selected = position.checked_sub(1);
+        let Some(preset) = self.preset_selected.map(|i| &self.presets[i]) else {
+            self.rules_version = RulesVersion::LATEST;
+            return;
+        };
+        self.name = TextInput::new(preset.boss.clone());
+        self.hitpoints = TextInput::new(preset.hitpoints.to_string());
+        self.damage = TextInput::new(preset.damage.to_string());
+        self.hard_mode = preset.hard_mode || preset.mutators.contains(&EncounterMutator::HardMode);
+        self.mutators = RULE_MUTATORS.map(|mutator| preset.mutators.contains(&mutator));
+        self.rules_version = preset.rules_version;
+    }
+
+    fn get_input(&self, field: Field) -> Option<&TextInput> {
+        match field {
+            Field::Name => Some(&self.name),
+            Field::Hitpoints => Some(&self.hitpoints),
+            Field::Damage => Some(&self.damage),
+            Field::Spellbook => Some(&self.spellbook),
+            Field::Items => Some(&self.items),
+            Field::SavePath => Some(&self.save_path),
+            _ => None,
+        }
+    }
+
+    fn get_input_mut(&mut self, field: Field) -> Option<&mut TextInput> {
+        match field {
+            Field::Name => Some(&mut self.name),
+            Field::Hitpoints => Some(&mut self.hitpoints),
+            Field::Damage => Some(&mut self.damage),
+            Field::Spellbook => Some(&mut self.spellbook),
+            Field::Items => Some(&mut self.items),
+            Field::SavePath => Some(&mut self.save_path),
+            _ => None,
+        }
+    }
+
+    /// The challenge filled in, or None with the reason in the status
+    fn create_challenge(&mut self) -> Option<Challenge> {
+        let (Ok(hitpoints), Ok(damage)) = (
+            self.hitpoints.get_value().trim().parse(),
+            self.damage.get_value().trim().parse(),
+        ) else {
+            self.status = "Hitpoints and damage must be whole numbers".to_string();
+            return None;
+        };
+        let spellbook = self.spellbook.get_value().trim();
+        let settings = BattleSettings {
+            name: self.name.get_value().to_string(),
+            hitpoints,
+            damage,
+            hard_mode: self.hard_mode,
+            scouting: self.scouting,
+            rules_version: self.rules_version,
+            mutators: RULE_MUTATORS
+                .into_iter()
+                .zip(self.mutators)
+                .filter_map(|(mutator, on)| on.then_some(mutator))
+                .collect(),
+            spellbook: (!spellbook.is_empty()).then(|| PathBuf::from(spellbook)),
+            items: self.items.get_value().to_string(),
+        };
+        match settings.create_challenge() {
+            Ok(challenge) => Some(challenge),
+            Err(message) => {
+                self.status = format!("Invalid battle: {}", message);
+                None
+            }
+        }
+    }
+
+    fn fight(&mut self) {
+        if let Some(challenge) = self.create_challenge() {
+            self.fight = Some(challenge);
+            self.exit = true;
+        }
+    }
+
+    fn save(&mut self) {
+        let Some(challenge) = self.create_challenge() else {
+            return;
+        };
+        let path = match self.save_path.get_value().trim() {
+            "" => challenge.get_file_name(),
+            path => path.to_string(),
+        };
+        let path = Path::new(&path);
+        self.status = match challenge.save(path) {
+            Ok(()) => format!("Saved challenge to {}", path.display()),
+            Err(err) => format!("Could not save to {}: {}", path.display(), err),
+        };
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let chunks = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(frame.area());
+
+        let title = Block::new()
+            .title_alignment(Alignment::Center)
+            .title("New Battle".bold());
+        frame.render_widget(title, chunks[0]);
+
+        let form_block = Block::bordered()
+            .border_style(Style::default().fg(theme::get().accent))
+            .title("Custom Boss".bold().gray())
+            .title_alignment(Alignment::Center);
+        let form_area = form_block.inner(chunks[1]);
+        frame.render_widget(form_block, chunks[1]);
+
+        let rows =
+            Layout::vertical(vec![Constraint::Length(1); self.fields.len()]).split(form_area);
+        for (i, (row, field)) in rows.iter().zip(self.fields.clone()).enumerate() {
+            let selected = i == self.field_selected;
+            let row = Layout::horizontal([Constraint::Length(18), Constraint::Min(0)]).split(*row);
+            let label_style = if selected {
+                Style::default().fg(theme::get().accent).bold()
+            } else {
+                Style::default().gray()
+            };
+            let label = match field {
+                Field::Fight | Field::Save => String::new(),
+                field => format!("{}:", field.get_label()),
+            };
+            frame.render_widget(
+                Paragraph::new(format!("{}{}", if selected { "> " } else { "  " }, label))
+                    .style(label_style),
+                row[0],
+            );
+
+            if let Some(input) = self.get_input_mut(field) {
+                input.set_focused(selected);
+            }
+            if let Some(input) = self.get_input(field) {
+                frame.render_widget(input, row[1]);
+                continue;
+            }
+            if field == Field::Preset {
+                let value = match self.preset_selected {
+                    Some(i) => format!(
+                        "< {} >  {}",
+                        self.presets[i].name,
+                        presets::describe_preset(&self.presets[i])
+                    ),
+                    None => "< None >".to_string(),
+                };
+                frame.render_widget(Paragraph::new(value).style(label_style), row[1]);
+                continue;
+            }
+            let value = match field {
+                Field::Difficulty => {
+                    if self.hard_mode {
+                        "Hard"
+                    } else {
+                        "Normal"
+                    }
+                }
+                Field::Scouting => toggle_box(self.scouting),
+                Field::Mutator(i) => toggle_box(self.mutators[i]),
+                Field::Fight => "[ Fight ]",
+                Field::Save => "[ Save challenge ]",
+                _ => "",
+            };
+            frame.render_widget(Paragraph::new(value).style(label_style), row[1]);
+        }
+
+        frame.render_widget(
+            Paragraph::new(self.status.as_str()).fg(theme::get().accent),
+            chunks[2],
+        );
+        frame.render_widget(
+            Line::from("Tab/Up/Down: field  Space: toggle  Left/Right: preset  Enter: next/press  Esc: quit")
+                .dark_gray(),
+            chunks[3],
+        );
+    }
+}
+
+fn toggle_box(on: bool) -> &'static str {
+    if on {
+        "[x]"
+    } else {
+        "[ ]"
+    }
+}
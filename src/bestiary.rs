@@ -0,0 +1,231 @@
+use crate::{
+    ability::BossAbility,
+    boss_ai::BossAiMode,
+    file_format::write_atomic,
+    taunt::Taunts,
+    toml::{self, ParseError, Table, Value},
+    Boss,
+};
+use std::{fs, io, path::Path};
+
+/// A boss that can be fought, as described in a bestiary file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestiaryEntry {
+    pub name: String,
+    pub hitpoints: i32,
+    pub damage: i32,
+    /// What the boss says during a battle
+    pub taunts: Taunts,
+    /// Lines of text or art shown when a battle against the boss starts, the name if empty
+    pub banner: Vec<String>,
+    /// What the boss does besides attacking
+    pub ability: Option<BossAbility>,
+    /// Damage the wizard takes from the boss's spiky skin for each spell that hits it, 0
+    /// for none
+    pub spiky_skin: i32,
+    /// How the boss decides whether to use its ability when it comes up
+    pub ai: BossAiMode,
+    /// Track or command played while the boss is fought, if ambiance is turned on
+    pub ambiance: Option<String>,
+}
+
+impl BestiaryEntry {
+    pub fn new(name: &str, hitpoints: i32, damage: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            hitpoints,
+            damage,
+            taunts: Taunts::default(),
+            banner: Vec::new(),
+            ability: None,
+            spiky_skin: 0,
+            ai: BossAiMode::default(),
+            ambiance: None,
+        }
+    }
+
+    /// Lines shown when a battle against the boss starts
+    pub fn get_banner(&self) -> Vec<String> {
+        if self.banner.is_empty() {
+            vec![self.name.to_uppercase()]
+        } else {
+            self.banner.clone()
+        }
+    }
+
+    pub fn create_boss(&self) -> Boss {
+        let boss = Boss::new(self.hitpoints, self.damage)
+            .with_spiky_skin(self.spiky_skin)
+            .with_ai(self.ai);
+        match self.ability {
+            Some(ability) => boss.with_ability(ability),
+            None => boss,
+        }
+    }
+
+    /// Describe what is wrong with the entry, if anything
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            Err("name must not be empty".to_string())
+        } else if self.hitpoints <= 0 {
+            Err("hitpoints must be positive".to_string())
+        } else if self.damage < 0 {
+            Err("damage must not be negative".to_string())
+        } else if self.spiky_skin < 0 {
+            Err("spiky skin must not be negative".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn from_table(table: &Table) -> Result<Self, ParseError> {
+        let name = table
+            .get_string("name")?
+            .ok_or_else(|| table.error("boss is missing `name`".to_string()))?;
+        let hitpoints = table
+            .get_integer("hitpoints")?
+            .ok_or_else(|| table.error("boss is missing `hitpoints`".to_string()))?;
+        let hitpoints = i32::try_from(hitpoints)
+            .map_err(|_| table.error("`hitpoints` is out of range".to_string()))?;
+        let damage = table
+            .get_integer("damage")?
+            .ok_or_else(|| table.error("boss is missing `damage`".to_string()))?;
+        let damage = i32::try_from(damage)
+            .map_err(|_| table.error("`damage` is out of range".to_string()))?;
+        let mut entry = Self::new(&name, hitpoints, damage);
+        entry.taunts = Taunts::from_table(table)?;
+        // Leading spaces are kept, to line up art
+        if let Some(banner) = table.get_string("banner")? {
+            entry.banner = banner
+                .lines()
+                .map(|line| line.trim_end().to_string())
+                .collect();
+        }
+        if let Some(key) = table.get_string("ability")? {
+            entry.ability = Some(
+                BossAbility::from_key(&key)
+                    .ok_or_else(|| table.error(format!("unknown ability `{}`", key)))?,
+            );
+        }
+        if let Some(spiky_skin) = table.get_integer("spiky_skin")? {
+            entry.spiky_skin = i32::try_from(spiky_skin)
+                .map_err(|_| table.error("`spiky_skin` is out of range".to_string()))?;
+        }
+        if let Some(key) = table.get_string("ai")? {
+            entry.ai = BossAiMode::from_key(&key)
+                .ok_or_else(|| table.error(format!("unknown ai `{}`", key)))?;
+        }
+        entry.ambiance = table
+            .get_string("ambiance")?
+            .filter(|ambiance| !ambiance.trim().is_empty());
+        entry.validate().map_err(|message| table.error(message))?;
+        Ok(entry)
+    }
+
+    pub(crate) fn to_table(&self) -> Table {
+        let mut table = Table::new("boss");
+        table.push("name", Value::String(self.name.clone()));
+        table.push("hitpoints", Value::Integer(self.hitpoints as i64));
+        table.push("damage", Value::Integer(self.damage as i64));
+        self.taunts.push_to_table(&mut table);
+        if !self.banner.is_empty() {
+            table.push("banner", Value::String(self.banner.join("\n")));
+        }
+        if let Some(ability) = self.ability {
+            table.push("ability", Value::String(ability.get_key().to_string()));
+        }
+        if self.spiky_skin > 0 {
+            table.push("spiky_skin", Value::Integer(self.spiky_skin as i64));
+        }
+        if self.ai != BossAiMode::default() {
+            table.push("ai", Value::String(self.ai.get_key().to_string()));
+        }
+        if let Some(ambiance) = &self.ambiance {
+            table.push("ambiance", Value::String(ambiance.clone()));
+        }
+        table
+    }
+}
+
+/// Collection of bosses, stored as `[[boss]]` tables in a TOML file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bestiary {
+    entries: Vec<BestiaryEntry>,
+}
+
+impl Bestiary {
+    /// Bosses that ship with the game
+    pub fn builtin() -> Self {
+        Self {
+            entries: vec![
+                BestiaryEntry::new("Advent Boss", 55, 8),
+                BestiaryEntry::new("Glass Golem", 30, 12),
+                BestiaryEntry::new("Iron Tortoise", 80, 5),
+                BestiaryEntry {
+                    ability: Some(BossAbility::Silence),
+                    ..BestiaryEntry::new("Shadow Drake", 71, 10)
+                },
+                BestiaryEntry {
+                    ability: Some(BossAbility::Burn),
+                    ..BestiaryEntry::new("Ember Wyrm", 60, 4)
+                },
+                BestiaryEntry {
+                    ability: Some(BossAbility::Dispel),
+                    ..BestiaryEntry::new("Hollow Magus", 58, 9)
+                },
+                BestiaryEntry {
+                    ability: Some(BossAbility::Heal),
+                    ..BestiaryEntry::new("Moss Troll", 62, 8)
+                },
+                BestiaryEntry {
+                    ability: Some(BossAbility::Lifesteal),
+                    ..BestiaryEntry::new("Vampire Count", 50, 7)
+                },
+                BestiaryEntry {
+                    spiky_skin: 2,
+                    ..BestiaryEntry::new("Bramble Colossus", 64, 7)
+                },
+            ],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut entries = Vec::new();
+        for table in toml::parse(text)? {
+            if table.name != "boss" {
+                return Err(table.error(format!("unknown table `[[{}]]`", table.name)));
+            }
+            entries.push(BestiaryEntry::from_table(&table)?);
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn to_toml(&self) -> String {
+        let tables: Vec<Table> = self.entries.iter().map(BestiaryEntry::to_table).collect();
+        toml::write(&tables)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, self.to_toml())
+    }
+
+    /// Find an entry by name, ignoring case
+    pub fn get_entry(&self, name: &str) -> Option<&BestiaryEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_entries(&self) -> &Vec<BestiaryEntry> {
+        &self.entries
+    }
+
+    pub fn get_entries_mut(&mut self) -> &mut Vec<BestiaryEntry> {
+        &mut self.entries
+    }
+}
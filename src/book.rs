@@ -0,0 +1,193 @@
+use crate::{
+    challenge::Challenge,
+    file_format::{write_atomic, OPENING_BOOK_FORMAT},
+    item::Equipment,
+    parallel::{run_jobs, Progress},
+    solver::{solve_cheapest_win, Algorithm, SolveOptions},
+    toml::{self, ParseError, Table, Value},
+    Battle, Boss, RulesVersion, Spell, Spellbook,
+};
+use rustc_hash::FxHashMap;
+use std::{fs, io, path::Path};
+
+/// Search for each line of the book
+pub const BOOK_SOLVE_OPTIONS: SolveOptions = SolveOptions {
+    algorithm: Algorithm::AStar,
+    max_depth: 20,
+    prune: true,
+    memo_size: 1_000_000,
+};
+
+/// Boss a line of the book is played against, by the default wizard with the default
+/// spellbook and rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BookKey {
+    pub hard_mode: bool,
+    pub hitpoints: i32,
+    pub damage: i32,
+}
+
+impl BookKey {
+    /// Key of `challenge`, None if the book can't cover it because it changes the wizard,
+    /// spells or rules
+    pub fn from_challenge(challenge: &Challenge) -> Option<Self> {
+        let covered = challenge.spellbook == Spellbook::default()
+            && challenge.loadout.is_none()
+            && challenge.equipment == Equipment::default()
+            && challenge.rules_version == RulesVersion::LATEST
+            && !challenge.action_points
+            && !challenge.blood_magic
+            && challenge.boss.ability.is_none()
+            && challenge.boss.spiky_skin == 0;
+        covered.then_some(Self {
+            hard_mode: challenge.hard_mode,
+            hitpoints: challenge.boss.hitpoints,
+            damage: challenge.boss.damage,
+        })
+    }
+
+    /// The battle at the start of the wizard's first turn
+    pub fn create_battle(self) -> Battle {
+        let mut battle = Battle::with_boss(self.hard_mode, Boss::new(self.hitpoints, self.damage));
+        battle.wizard_turn_apply_effects();
+        battle
+    }
+}
+
+/// What the book says about the next cast of a battle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookMove {
+    /// The book has no line against the boss
+    Unknown,
+    /// The spells cast so far left the book's line
+    OutOfBook,
+    /// There is no win within the book's search
+    NoWin,
+    /// Next spell of the cheapest win
+    Best(Spell),
+}
+
+/// Cheapest wins from the start of battles against bosses of known stats, so the best
+/// move along them is known without searching.
+///
+/// The file holds a `[[line]]` table per boss with `hard_mode`, `hitpoints`, `damage` and
+/// the `spells` of the cheapest win separated by commas, left out if there is no win.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpeningBook {
+    lines: FxHashMap<BookKey, Option<Vec<Spell>>>,
+}
+
+impl OpeningBook {
+    /// Book of the cheapest win against each of `keys`, searched with `BOOK_SOLVE_OPTIONS`
+    /// over `threads` threads. Bosses not searched before `progress` was cancelled are
+    /// left out.
+    pub fn build(keys: Vec<BookKey>, threads: usize, progress: &Progress) -> Self {
+        let lines = run_jobs(keys, threads, progress, |key| {
+            let line = solve_line(key);
+            progress.advance(1);
+            (key, line)
+        });
+        Self {
+            lines: lines.into_iter().flatten().collect(),
+        }
+    }
+
+    /// Search the cheapest win against `key` and add it to the book
+    pub fn add_line(&mut self, key: BookKey) {
+        self.lines.insert(key, solve_line(key));
+    }
+
+    /// Add the lines of `other` against bosses this book has no line against
+    pub fn merge(&mut self, other: Self) {
+        for (key, line) in other.lines {
+            self.lines.entry(key).or_insert(line);
+        }
+    }
+
+    pub fn contains(&self, key: &BookKey) -> bool {
+        self.lines.contains_key(key)
+    }
+
+    pub fn get_line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Best move against `key` after `spells_cast`, as long as they kept to the book's line
+    pub fn get_move(&self, key: &BookKey, spells_cast: &[Spell]) -> BookMove {
+        match self.lines.get(key) {
+            None => BookMove::Unknown,
+            Some(None) => BookMove::NoWin,
+            Some(Some(spells)) => match spells.strip_prefix(spells_cast) {
+                Some([next, ..]) => BookMove::Best(next.clone()),
+                _ => BookMove::OutOfBook,
+            },
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        OPENING_BOOK_FORMAT.read_version(text)?;
+        let mut lines = FxHashMap::default();
+        for table in toml::parse(text)? {
+            if table.name != "line" {
+                return Err(table.error(format!("unknown table `{}`", table.name)));
+            }
+            let get_integer = |key: &str| {
+                table
+                    .get_integer(key)?
+                    .ok_or_else(|| table.error(format!("line is missing `{}`", key)))
+            };
+            let key = BookKey {
+                hard_mode: table.get_boolean("hard_mode")?.unwrap_or(false),
+                hitpoints: get_integer("hitpoints")? as i32,
+                damage: get_integer("damage")? as i32,
+            };
+            let spells = match table.get_string("spells")? {
+                Some(spells) => Some(
+                    spells
+                        .split(',')
+                        .map(|name| name.trim().parse::<Spell>())
+                        .collect::<Result<Vec<Spell>, _>>()
+                        .map_err(|err| table.error(err.to_string()))?,
+                ),
+                None => None,
+            };
+            lines.insert(key, spells);
+        }
+        Ok(Self { lines })
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut keys: Vec<&BookKey> = self.lines.keys().collect();
+        keys.sort_by_key(|key| (key.hard_mode, key.hitpoints, key.damage));
+        let tables: Vec<Table> = keys
+            .into_iter()
+            .map(|key| {
+                let mut table = Table::new("line");
+                table.push("hard_mode", Value::Boolean(key.hard_mode));
+                table.push("hitpoints", Value::Integer(key.hitpoints as i64));
+                table.push("damage", Value::Integer(key.damage as i64));
+                if let Some(spells) = &self.lines[key] {
+                    let names: Vec<&str> = spells.iter().map(Spell::get_display_name).collect();
+                    table.push("spells", Value::String(names.join(", ")));
+                }
+                table
+            })
+            .collect();
+        OPENING_BOOK_FORMAT.with_header(&toml::write(&tables))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, self.to_toml())
+    }
+}
+
+fn solve_line(key: BookKey) -> Option<Vec<Spell>> {
+    solve_cheapest_win(&key.create_battle(), BOOK_SOLVE_OPTIONS)
+        .solution
+        .map(|solution| solution.spells)
+}
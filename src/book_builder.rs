@@ -0,0 +1,114 @@
+//! `book build` subcommand: regenerate the opening book hints consult before searching.
+
+use crate::{
+    cli::{threads_arg, threads_from_matches},
+    data_dir, ui,
+};
+use advent_wizard_rpg::{
+    book::{BookKey, OpeningBook},
+    parallel::Progress,
+};
+use clap::{arg, value_parser, ArgMatches, Command};
+use std::{fs, io, path::PathBuf};
+
+/// Where the opening book is cached between runs
+pub fn default_path() -> PathBuf {
+    data_dir::profile_dir().join("opening-book.toml")
+}
+
+pub fn command() -> Command {
+    Command::new("book")
+        .about("Manage the opening book of cheapest wins that hints consult")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("build")
+                .about("Search the cheapest win against a range of bosses, in normal and hard mode, replacing the book")
+                .arg(
+                    arg!(--"boss-hp" <HP> "Boss hitpoints, or ranges of them such as 50-75, separated by commas")
+                        .value_parser(parse_stat_range)
+                        .value_delimiter(',')
+                        .default_value("50-75"),
+                )
+                .arg(
+                    arg!(--"boss-damage" <DAMAGE> "Boss damage, or ranges of it, separated by commas")
+                        .value_parser(parse_stat_range)
+                        .value_delimiter(',')
+                        .default_value("7-10"),
+                )
+                .arg(
+                    arg!(--output <FILE> "File to write the book to [default: ~/.local/state/advent-wizard-rpg/profile/opening-book.toml]")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(threads_arg()),
+        )
+}
+
+/// Parse a stat of `book build`: a number or an inclusive range of them like `50-75`
+fn parse_stat_range(range: &str) -> Result<Vec<i32>, String> {
+    let parse = |number: &str| {
+        number
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| format!("`{}` is not a number", number.trim()))
+    };
+    let (min, max) = match range.split_once('-') {
+        Some((min, max)) => (parse(min)?, parse(max)?),
+        None => (parse(range)?, parse(range)?),
+    };
+    if min < 0 || min > max {
+        return Err(format!("`{}` is not a range of stats", range));
+    }
+    Ok((min..=max).collect())
+}
+
+pub fn run(sub_matches: &ArgMatches) -> io::Result<()> {
+    let Some(("build", matches)) = sub_matches.subcommand() else {
+        return Ok(());
+    };
+    let mut keys = Vec::new();
+    for hard_mode in [false, true] {
+        for hitpoints in matches.get_many::<Vec<i32>>("boss-hp").unwrap().flatten() {
+            for damage in matches
+                .get_many::<Vec<i32>>("boss-damage")
+                .unwrap()
+                .flatten()
+            {
+                keys.push(BookKey {
+                    hard_mode,
+                    hitpoints: *hitpoints,
+                    damage: *damage,
+                });
+            }
+        }
+    }
+    keys.retain(|key| key.hitpoints > 0);
+    keys.sort_by_key(|key| (key.hard_mode, key.hitpoints, key.damage));
+    keys.dedup();
+
+    let progress = Progress::new(keys.len() as u64);
+    let threads = threads_from_matches(matches);
+    let book = ui::with_progress("bosses", &progress, || {
+        OpeningBook::build(keys, threads, &progress)
+    });
+    if progress.is_cancelled() {
+        eprintln!(
+            "Stopped after {} of {} bosses",
+            progress.get_done(),
+            progress.get_total()
+        );
+    }
+    let path = matches
+        .get_one::<PathBuf>("output")
+        .cloned()
+        .unwrap_or_else(default_path);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    book.save(&path)?;
+    println!(
+        "Wrote {} lines to {}",
+        book.get_line_count(),
+        path.display()
+    );
+    Ok(())
+}
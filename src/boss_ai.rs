@@ -0,0 +1,154 @@
+use crate::{ability::BossAbility, rng::Rng, Battle, Spell};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Seed of the coin the random boss AI flips, drawn from a stream per round so battles
+/// replay the same from their spells
+const RANDOM_BOSS_AI_SEED: u64 = 0x5eed_b055;
+
+/// A policy deciding whether the boss uses its ability when it comes up, or holds it for
+/// a later attack. The boss attacks either way.
+pub trait BossAi {
+    fn get_name(&self) -> &'static str;
+
+    /// Whether the boss uses its ready ability now. `battle` is just after the boss's
+    /// attack, with the boss still alive.
+    fn use_ability(&self, battle: &Battle) -> bool;
+}
+
+/// Uses a ready ability half of the time, whatever it does
+#[derive(Debug, Default)]
+pub struct RandomBossAi;
+
+impl BossAi for RandomBossAi {
+    fn get_name(&self) -> &'static str {
+        "random"
+    }
+
+    fn use_ability(&self, battle: &Battle) -> bool {
+        let round = battle.get_spells_used().len() as u64;
+        Rng::from_stream(RANDOM_BOSS_AI_SEED, round).below(2) == 0
+    }
+}
+
+/// Uses a ready ability right away, except Heal, which it holds until the boss is badly
+/// wounded. How bosses have always fought.
+#[derive(Debug, Default)]
+pub struct GreedyBossAi;
+
+impl BossAi for GreedyBossAi {
+    fn get_name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn use_ability(&self, battle: &Battle) -> bool {
+        let boss = battle.get_boss();
+        boss.get_ability() != Some(BossAbility::Heal) || boss.is_badly_wounded()
+    }
+}
+
+/// Plays both choices out on clones of the battle, answers each with every spell the
+/// wizard can cast next, and picks the choice whose best answer leaves the boss better
+/// off. Ties go the greedy way. The clones fight greedily, so the search stays one ply.
+#[derive(Debug, Default)]
+pub struct LookaheadBossAi;
+
+impl LookaheadBossAi {
+    /// How good `battle` is for the boss: a win is best, a loss worst, and otherwise the
+    /// lead in hitpoints
+    fn score(battle: &Battle) -> i32 {
+        match battle.get_outcome() {
+            Some(true) => i32::MIN,
+            Some(false) => i32::MAX,
+            None => battle.get_boss().get_hitpoints() - battle.get_wizard().get_hitpoints(),
+        }
+    }
+
+    /// Score of the boss's choice after the wizard's best answer to it
+    fn score_choice(battle: &Battle, use_ability: bool) -> i32 {
+        let mut next = battle.for_search();
+        next.set_boss_ai(BossAiMode::Greedy);
+        if next
+            .boss_turn_ability(use_ability)
+            .or_else(|| next.wizard_turn_apply_effects())
+            .is_some()
+        {
+            return Self::score(&next);
+        }
+        Spell::ALL
+            .iter()
+            .filter(|spell| next.get_wizard().get_possible_spells().contains(spell))
+            .filter_map(|spell| {
+                let mut answer = next.clone();
+                answer.play_round(spell).ok()?;
+                Some(Self::score(&answer))
+            })
+            .min()
+            // Without a spell to cast the wizard loses
+            .unwrap_or(i32::MAX)
+    }
+}
+
+impl BossAi for LookaheadBossAi {
+    fn get_name(&self) -> &'static str {
+        "lookahead"
+    }
+
+    fn use_ability(&self, battle: &Battle) -> bool {
+        let used = Self::score_choice(battle, true);
+        let held = Self::score_choice(battle, false);
+        if used == held {
+            GreedyBossAi.use_ability(battle)
+        } else {
+            used > held
+        }
+    }
+}
+
+/// Which boss AI a boss fights with, as chosen in bestiary and challenge files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BossAiMode {
+    Random,
+    #[default]
+    Greedy,
+    Lookahead,
+    /// Smarter on harder battles: greedy normally and looking ahead on hard mode
+    Adaptive,
+}
+
+impl BossAiMode {
+    pub const ALL: [BossAiMode; 4] = [
+        BossAiMode::Random,
+        BossAiMode::Greedy,
+        BossAiMode::Lookahead,
+        BossAiMode::Adaptive,
+    ];
+
+    /// Key of the mode in bestiary and challenge files
+    pub fn get_key(&self) -> &'static str {
+        match self {
+            BossAiMode::Random => "random",
+            BossAiMode::Greedy => "greedy",
+            BossAiMode::Lookahead => "lookahead",
+            BossAiMode::Adaptive => "adaptive",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.get_key().eq_ignore_ascii_case(key))
+    }
+
+    /// The AI the boss fights with in a battle on hard mode or not
+    pub fn get_ai(&self, hard_mode: bool) -> &'static dyn BossAi {
+        match self {
+            BossAiMode::Random => &RandomBossAi,
+            BossAiMode::Greedy => &GreedyBossAi,
+            BossAiMode::Lookahead => &LookaheadBossAi,
+            BossAiMode::Adaptive if hard_mode => &LookaheadBossAi,
+            BossAiMode::Adaptive => &GreedyBossAi,
+        }
+    }
+}
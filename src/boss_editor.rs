@@ -0,0 +1,321 @@
+//! `edit-bestiary` subcommand: create and modify bestiary entries in a form-style editor.
+
+use crate::ui::{theme, tui, TextInput};
+use advent_wizard_rpg::{
+    bestiary::{Bestiary, BestiaryEntry},
+    challenge::Challenge,
+};
+use clap::{arg, value_parser, ArgMatches, Command};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Alignment, Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListState, Paragraph},
+    Frame,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Labels of the form fields, in order
+const FIELDS: [&str; 3] = ["Name", "Hitpoints", "Damage"];
+
+pub fn command() -> Command {
+    Command::new("edit-bestiary")
+        .about("Create and modify bosses in a bestiary file")
+        .arg(
+            arg!(--file <FILE> "Bestiary file to edit, created on save if missing")
+                .value_parser(value_parser!(PathBuf))
+                .default_value("bestiary.toml"),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> io::Result<()> {
+    let path = matches.get_one::<PathBuf>("file").unwrap().clone();
+    let bestiary = if path.exists() {
+        Bestiary::load(&path)?
+    } else {
+        Bestiary::builtin()
+    };
+
+    let mut terminal = tui::init()?;
+    let result = BossEditor::new(path, bestiary).run(&mut terminal);
+    tui::restore()?;
+    result
+}
+
+#[derive(Debug)]
+struct BossEditor {
+    exit: bool,
+    path: PathBuf,
+    bestiary: Bestiary,
+    list_state: ListState,
+    /// Fields of the entry being edited, None when browsing the list
+    form: Option<[TextInput; 3]>,
+    /// Whether the entry being edited was just added with `n`, so cancelling removes it
+    adding: bool,
+    field_selected: usize,
+    /// Result of the last action
+    status: String,
+}
+
+impl BossEditor {
+    fn new(path: PathBuf, bestiary: Bestiary) -> Self {
+        Self {
+            exit: false,
+            path,
+            bestiary,
+            list_state: ListState::default().with_selected(Some(0)),
+            form: None,
+            adding: false,
+            field_selected: 0,
+            status: String::new(),
+        }
+    }
+
+    fn run(&mut self, terminal: &mut tui::Tui) -> io::Result<()> {
+        while !self.exit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if self.form.is_some() {
+                    self.handle_form_key_event(key.code);
+                } else {
+                    self.handle_list_key_event(key.code);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_list_key_event(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => self.exit = true,
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char('n') => {
+                let entries = self.bestiary.get_entries_mut();
+                entries.push(BestiaryEntry::new("New Boss", 50, 8));
+                let last = entries.len() - 1;
+                self.list_state.select(Some(last));
+                self.open_form();
+                self.adding = true;
+            }
+            KeyCode::Char('e') | KeyCode::Enter => self.open_form(),
+            KeyCode::Char('x') | KeyCode::Delete => {
+                if let Some(selected) = self.selected_index() {
+                    let entry = self.bestiary.get_entries_mut().remove(selected);
+                    self.status = format!("Deleted {}", entry.name);
+                }
+            }
+            KeyCode::Char('s') => self.save(),
+            KeyCode::Char('c') => self.export_challenge(),
+            _ => (),
+        }
+    }
+
+    fn handle_form_key_event(&mut self, key: KeyCode) {
+        let Some(form) = self.form.as_mut() else {
+            return;
+        };
+        match key {
+            KeyCode::Esc => {
+                self.form = None;
+                if self.adding {
+                    if let Some(selected) = self.selected_index() {
+                        self.bestiary.get_entries_mut().remove(selected);
+                    }
+                    self.list_state.select_previous();
+                }
+                self.status = "Edit cancelled".to_string();
+            }
+            KeyCode::Enter => self.apply_form(),
+            KeyCode::Tab | KeyCode::Down => {
+                self.field_selected = (self.field_selected + 1) % FIELDS.len();
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                self.field_selected = (self.field_selected + FIELDS.len() - 1) % FIELDS.len();
+            }
+            key => {
+                form[self.field_selected].handle_key_event(key);
+            }
+        }
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        let len = self.bestiary.get_entries().len();
+        self.list_state
+            .selected()
+            .filter(|_| len > 0)
+            .map(|i| i.min(len - 1))
+    }
+
+    fn open_form(&mut self) {
+        let Some(selected) = self.selected_index() else {
+            return;
+        };
+        let entry = &self.bestiary.get_entries()[selected];
+        self.adding = false;
+        self.form = Some([
+            TextInput::new(entry.name.clone()),
+            TextInput::new(entry.hitpoints.to_string()),
+            TextInput::new(entry.damage.to_string()),
+        ]);
+        self.field_selected = 0;
+        self.status.clear();
+    }
+
+    /// Validate the form and write it to the selected entry
+    fn apply_form(&mut self) {
+        let (Some(form), Some(selected)) = (&self.form, self.selected_index()) else {
+            return;
+        };
+        let (Ok(hitpoints), Ok(damage)) = (
+            form[1].get_value().trim().parse(),
+            form[2].get_value().trim().parse(),
+        ) else {
+            self.status = "Hitpoints and damage must be whole numbers".to_string();
+            return;
+        };
+        let mut entry = BestiaryEntry::new(form[0].get_value().trim(), hitpoints, damage);
+        // Taunts, banners, abilities, spiky skin, AI and ambiance aren't on the form
+        entry.taunts = self.bestiary.get_entries()[selected].taunts.clone();
+        entry.banner = self.bestiary.get_entries()[selected].banner.clone();
+        entry.ability = self.bestiary.get_entries()[selected].ability;
+        entry.spiky_skin = self.bestiary.get_entries()[selected].spiky_skin;
+        entry.ai = self.bestiary.get_entries()[selected].ai;
+        entry.ambiance = self.bestiary.get_entries()[selected].ambiance.clone();
+        if let Err(message) = entry.validate() {
+            self.status = format!("Invalid boss: {}", message);
+            return;
+        }
+        let duplicate = self
+            .bestiary
+            .get_entries()
+            .iter()
+            .enumerate()
+            .any(|(i, other)| i != selected && other.name.eq_ignore_ascii_case(&entry.name));
+        if duplicate {
+            self.status = format!("Invalid boss: {} already exists", entry.name);
+            return;
+        }
+
+        self.status = if self.adding {
+            format!("Added {} (unsaved)", entry.name)
+        } else {
+            format!("Updated {} (unsaved)", entry.name)
+        };
+        self.bestiary.get_entries_mut()[selected] = entry;
+        self.form = None;
+        self.adding = false;
+    }
+
+    fn save(&mut self) {
+        self.status = match self.bestiary.save(&self.path) {
+            Ok(()) => format!("Saved to {}", self.path.display()),
+            Err(err) => format!("Could not save to {}: {}", self.path.display(), err),
+        };
+    }
+
+    /// Write a challenge against the selected boss next to the bestiary
+    fn export_challenge(&mut self) {
+        let Some(selected) = self.selected_index() else {
+            return;
+        };
+        let challenge = Challenge::new(self.bestiary.get_entries()[selected].clone());
+        let path = self
+            .path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(challenge.get_file_name());
+        self.status = match challenge.save(&path) {
+            Ok(()) => format!("Exported challenge to {}", path.display()),
+            Err(err) => format!("Could not export to {}: {}", path.display(), err),
+        };
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let chunks = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(frame.area());
+
+        let title = Block::new()
+            .title_alignment(Alignment::Center)
+            .title(format!("Bestiary Editor: {}", self.path.display()).bold());
+        frame.render_widget(title, chunks[0]);
+
+        let columns = Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+            .split(chunks[1]);
+
+        // Left: list of bosses
+        let names: Vec<String> = self
+            .bestiary
+            .get_entries()
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect();
+        let list = List::new(names)
+            .gray()
+            .highlight_style(Style::default().fg(theme::get().accent).bold())
+            .highlight_symbol("> ")
+            .block(
+                Block::bordered()
+                    .border_style(Style::default().fg(theme::get().boss))
+                    .title("Bosses".bold().gray())
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_stateful_widget(list, columns[0], &mut self.list_state);
+
+        // Right: form for the selected boss
+        let form_block = Block::bordered()
+            .border_style(Style::default().fg(if self.form.is_some() {
+                theme::get().accent
+            } else {
+                Color::Gray
+            }))
+            .title("Boss".bold().gray())
+            .title_alignment(Alignment::Center);
+        let form_area = form_block.inner(columns[1]);
+        frame.render_widget(form_block, columns[1]);
+
+        let rows = Layout::vertical([Constraint::Length(2); FIELDS.len()]).split(form_area);
+        let selected_entry = self
+            .selected_index()
+            .map(|selected| self.bestiary.get_entries()[selected].clone());
+        for (i, (row, label)) in rows.iter().zip(FIELDS).enumerate() {
+            let row = Layout::horizontal([Constraint::Length(12), Constraint::Min(0)]).split(*row);
+            frame.render_widget(Paragraph::new(format!("{}:", label)).gray(), row[0]);
+            match (self.form.as_mut(), &selected_entry) {
+                (Some(form), _) => {
+                    form[i].set_focused(i == self.field_selected);
+                    frame.render_widget(&form[i], row[1]);
+                }
+                (None, Some(entry)) => {
+                    let value = match i {
+                        0 => entry.name.clone(),
+                        1 => entry.hitpoints.to_string(),
+                        _ => entry.damage.to_string(),
+                    };
+                    frame.render_widget(Paragraph::new(value).gray(), row[1]);
+                }
+                (None, None) => (),
+            }
+        }
+
+        frame.render_widget(
+            Paragraph::new(self.status.as_str()).fg(theme::get().accent),
+            chunks[2],
+        );
+        let help = if self.form.is_some() {
+            "Tab/Up/Down: field  Enter: apply  Esc: cancel"
+        } else {
+            "j/k: select  n: new  e: edit  x: delete  s: save  c: export challenge  q: quit"
+        };
+        frame.render_widget(Line::from(help).dark_gray(), chunks[3]);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{bestiary::BestiaryEntry, challenge::Challenge, Battle};
+use std::{fmt::Write, time::Duration};
+
+/// How the wizard stood after defeating a boss of a boss rush
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BossRushSplit {
+    pub boss: String,
+    /// Time since the start of the rush
+    pub time: Duration,
+    /// Hitpoints and mana the wizard has left
+    pub hitpoints: i32,
+    pub mana: i32,
+    /// Time the player spent deciding spells against the boss
+    pub think_time: Duration,
+}
+
+/// Splits of a boss rush, one for each boss defeated so far
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BossRushRun {
+    pub splits: Vec<BossRushSplit>,
+}
+
+impl BossRushRun {
+    pub fn push_split(
+        &mut self,
+        boss: &str,
+        time: Duration,
+        battle: &Battle,
+        think_time: Duration,
+    ) {
+        let wizard = battle.get_wizard();
+        self.splits.push(BossRushSplit {
+            boss: boss.to_string(),
+            time,
+            hitpoints: wizard.get_hitpoints(),
+            mana: wizard.get_mana(),
+            think_time,
+        });
+    }
+
+    /// Time of the last split, the final time if every boss was defeated
+    pub fn get_total_time(&self) -> Duration {
+        self.splits
+            .last()
+            .map_or(Duration::ZERO, |split| split.time)
+    }
+
+    /// Time spent on the boss of split `i` alone
+    pub fn get_segment_time(&self, i: usize) -> Duration {
+        let start = if i == 0 {
+            Duration::ZERO
+        } else {
+            self.splits[i - 1].time
+        };
+        self.splits[i].time - start
+    }
+
+    /// Markdown table of the splits, ending with the final time
+    pub fn to_markdown(&self, completed: bool) -> String {
+        let mut markdown = String::new();
+        markdown.push_str("| Boss | Segment | Split | HP | Mana | Think |\n");
+        markdown.push_str("| --- | ---: | ---: | ---: | ---: | ---: |\n");
+        for (i, split) in self.splits.iter().enumerate() {
+            writeln!(
+                markdown,
+                "| {} | {} | {} | {} | {} | {} |",
+                split.boss,
+                format_run_time(self.get_segment_time(i)),
+                format_run_time(split.time),
+                split.hitpoints,
+                split.mana,
+                format_run_time(split.think_time)
+            )
+            .unwrap();
+        }
+        writeln!(
+            markdown,
+            "\n**{}:** {}",
+            if completed {
+                "Final time"
+            } else {
+                "Time of the last split"
+            },
+            format_run_time(self.get_total_time())
+        )
+        .unwrap();
+        markdown
+    }
+}
+
+/// Time as minutes, seconds and tenths, like 1:23.4
+pub fn format_run_time(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+/// Battle against `boss`, the wizard keeping the hitpoints and mana they had at the end
+/// of `previous` without healing. Effects don't carry over.
+pub fn next_boss_rush_battle(previous: &Battle, boss: &BestiaryEntry) -> Battle {
+    let battle = Challenge {
+        hard_mode: previous.get_hard_mode(),
+        ..Challenge::new(boss.clone())
+    }
+    .create_battle();
+    let wizard = previous.get_wizard();
+    let hitpoints = wizard.get_hitpoints() - battle.get_wizard().get_hitpoints();
+    let mana = wizard.get_mana() - battle.get_wizard().get_mana();
+    battle.with_wizard_bonus(hitpoints, mana)
+}
@@ -0,0 +1,184 @@
+use crate::{
+    bestiary::{Bestiary, BestiaryEntry},
+    item::Item,
+    toml::{self, ParseError, Table},
+};
+use std::{fs, io, path::Path};
+
+/// What the wizard does before a battle, giving them extra hitpoints and mana to start it
+/// with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoryChoice {
+    pub label: String,
+    pub hitpoints: i32,
+    pub mana: i32,
+}
+
+impl StoryChoice {
+    pub fn new(label: &str, hitpoints: i32, mana: i32) -> Self {
+        Self {
+            label: label.to_string(),
+            hitpoints,
+            mana,
+        }
+    }
+
+    /// Choices of chapters that don't list their own: fight on, rest to heal 10
+    /// hitpoints, or scavenge for a potion worth 100 mana
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("Fight the next boss", 0, 0),
+            Self::new("Rest to heal 10 HP", 10, 0),
+            Self::new("Scavenge for a mana potion", 0, 100),
+        ]
+    }
+
+    fn from_table(table: &Table) -> Result<Self, ParseError> {
+        let label = table
+            .get_string("label")?
+            .ok_or_else(|| table.error("choice is missing `label`".to_string()))?;
+        Ok(Self::new(
+            &label,
+            table.get_integer("hitpoints")?.unwrap_or(0) as i32,
+            table.get_integer("mana")?.unwrap_or(0) as i32,
+        ))
+    }
+}
+
+/// Story told before a battle, and the choices the wizard has before fighting its boss
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub title: String,
+    pub text: String,
+    pub boss: BestiaryEntry,
+    pub choices: Vec<StoryChoice>,
+    /// Item the wizard wears from the next battle on, once the boss is defeated
+    pub reward: Option<Item>,
+}
+
+/// Bosses fought one after another, with story between the battles.
+///
+/// The file holds a `[[campaign]]` table with the name and the ending shown after the
+/// last battle is won, then a `[[chapter]]` table with `title`, `text` and an optional
+/// `reward` item for each battle. A chapter is followed by the `[[boss]]` table of its boss, like the ones in a
+/// bestiary, and `[[choice]]` tables with a `label` and the `hitpoints` and `mana` the
+/// choice adds. Chapters without choices get [`StoryChoice::defaults`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Campaign {
+    pub name: String,
+    pub chapters: Vec<Chapter>,
+    pub ending: String,
+}
+
+impl Campaign {
+    /// Campaign through the built-in bosses
+    pub fn builtin() -> Self {
+        let texts = [
+            (
+                "The Gate",
+                "A boss blocks the road to the tower, as it has every December.",
+            ),
+            (
+                "The Glass Hall",
+                "Beyond the gate, something glitters between the pillars. It hits hard, but it looks brittle.",
+            ),
+            (
+                "The Moat",
+                "An armored shell rises from the water. This will be a long fight.",
+            ),
+            (
+                "The Tower",
+                "At the top of the tower, wings unfold in the dark.",
+            ),
+        ];
+        let rewards = [
+            Some(Item::Robe),
+            Some(Item::RingOfMana),
+            Some(Item::Amulet),
+            None,
+        ];
+        let chapters = Bestiary::builtin()
+            .get_entries()
+            .iter()
+            .zip(texts)
+            .zip(rewards)
+            .map(|((boss, (title, text)), reward)| Chapter {
+                title: title.to_string(),
+                text: text.to_string(),
+                boss: boss.clone(),
+                choices: StoryChoice::defaults(),
+                reward,
+            })
+            .collect();
+        Self {
+            name: "The Wizard's Tower".to_string(),
+            chapters,
+            ending: "The tower is quiet. Until next December.".to_string(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut header = None;
+        let mut chapters: Vec<(Table, Option<BestiaryEntry>, Vec<StoryChoice>)> = Vec::new();
+        for table in toml::parse(text)? {
+            match (table.name.as_str(), chapters.last_mut()) {
+                ("campaign", _) if header.is_none() => header = Some(table),
+                ("chapter", _) => chapters.push((table, None, Vec::new())),
+                ("boss", Some((_, boss @ None, _))) => {
+                    *boss = Some(BestiaryEntry::from_table(&table)?)
+                }
+                ("choice", Some((_, _, choices))) => choices.push(StoryChoice::from_table(&table)?),
+                ("boss" | "choice", None) => {
+                    return Err(table.error(format!("`[[{}]]` before any chapter", table.name)))
+                }
+                ("campaign" | "boss", _) => {
+                    return Err(table.error(format!("more than one `[[{}]]`", table.name)))
+                }
+                (name, _) => return Err(table.error(format!("unknown table `[[{}]]`", name))),
+            }
+        }
+        let header = header.ok_or_else(|| ParseError {
+            line: 0,
+            message: "campaign is missing `[[campaign]]`".to_string(),
+        })?;
+        if chapters.is_empty() {
+            return Err(header.error("campaign has no `[[chapter]]`".to_string()));
+        }
+
+        let chapters = chapters
+            .into_iter()
+            .map(|(table, boss, choices)| {
+                let reward = match table.get_string("reward")? {
+                    Some(key) => Some(
+                        Item::from_key(&key)
+                            .ok_or_else(|| table.error(format!("unknown item `{}`", key)))?,
+                    ),
+                    None => None,
+                };
+                Ok(Chapter {
+                    title: table.get_string("title")?.unwrap_or_default(),
+                    text: table.get_string("text")?.unwrap_or_default(),
+                    boss: boss.ok_or_else(|| {
+                        table.error("chapter is missing its `[[boss]]`".to_string())
+                    })?,
+                    choices: if choices.is_empty() {
+                        StoryChoice::defaults()
+                    } else {
+                        choices
+                    },
+                    reward,
+                })
+            })
+            .collect::<Result<_, ParseError>>()?;
+        Ok(Self {
+            name: header.get_string("name")?.unwrap_or_default(),
+            chapters,
+            ending: header.get_string("ending")?.unwrap_or_default(),
+        })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
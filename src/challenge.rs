@@ -0,0 +1,272 @@
+use crate::{
+    bestiary::BestiaryEntry,
+    file_format::write_atomic,
+    item::Equipment,
+    knowledge::KnowledgeMask,
+    loadout::Loadout,
+    toml::{self, ParseError, Table, Value},
+    Battle, RulesVersion, Spell, Spellbook,
+};
+use std::{fs, io, path::Path};
+
+/// Ending of challenge file names
+pub const CHALLENGE_FILE_SUFFIX: &str = ".challenge.toml";
+
+/// A boss, spellbook and ruleset bundled into one file so custom content can be shared.
+///
+/// The file holds a `[[challenge]]` table with the name, ruleset, whether the boss is
+/// scouted, rules version, seed, whether turns use action points and the `items` the
+/// wizard wears,
+/// a `[[boss]]` table like the ones in a bestiary, a `[[spell]]` table for each spell
+/// that differs from the default spellbook, and the `[[loadout]]` table if the wizard
+/// picked one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub name: String,
+    pub boss: BestiaryEntry,
+    pub spellbook: Spellbook,
+    pub hard_mode: bool,
+    /// Whether the boss's stats are hidden until the wizard learns them. Classic play shows
+    /// them from the start.
+    pub scouting: bool,
+    /// Rules the challenge is played under, the version of the game that made it
+    pub rules_version: RulesVersion,
+    /// Seed for anything random about the challenge, such as simulated playouts
+    pub seed: u64,
+    /// Spells and trait the wizard picked, all spells and no trait if None
+    pub loadout: Option<Loadout>,
+    pub equipment: Equipment,
+    /// Whether each turn gives action points to spend on casts, instead of one cast a turn
+    pub action_points: bool,
+    /// Whether the wizard can cast spells they are short of mana for, paying hitpoints
+    pub blood_magic: bool,
+    /// Most mana the wizard can hold, None if there is no limit
+    pub max_mana: Option<i32>,
+}
+
+impl Challenge {
+    /// A challenge against `boss` with the default spellbook and rules
+    pub fn new(boss: BestiaryEntry) -> Self {
+        Self {
+            name: boss.name.clone(),
+            boss,
+            spellbook: Spellbook::default(),
+            hard_mode: false,
+            scouting: true,
+            rules_version: RulesVersion::LATEST,
+            seed: 0,
+            loadout: None,
+            equipment: Equipment::default(),
+            action_points: false,
+            blood_magic: false,
+            max_mana: None,
+        }
+    }
+
+    /// The battle of the challenge, before the effects of the wizard's first turn
+    pub fn create_battle(&self) -> Battle {
+        let mut spellbook = self.spellbook.clone();
+        if let Some(loadout) = &self.loadout {
+            loadout.apply_to_spellbook(&mut spellbook);
+        }
+        let battle = Battle::with_spellbook(self.hard_mode, self.boss.create_boss(), spellbook)
+            .with_rules_version(self.rules_version)
+            .with_equipment(self.equipment)
+            .with_knowledge(if self.scouting {
+                KnowledgeMask::NONE
+            } else {
+                KnowledgeMask::FULL
+            });
+        let battle = if self.action_points {
+            battle.with_action_points()
+        } else {
+            battle
+        };
+        let battle = if self.blood_magic {
+            battle.with_blood_magic()
+        } else {
+            battle
+        };
+        let battle = match self.max_mana {
+            Some(max_mana) => battle.with_max_mana(max_mana),
+            None => battle,
+        };
+        match &self.loadout {
+            Some(loadout) => battle.with_wizard_bonus(loadout.get_hitpoint_bonus(), 0),
+            None => battle,
+        }
+    }
+
+    /// Identifies the rules of the challenge, so results are only compared between
+    /// challenges that play the same. Names, descriptions and the seed don't change the
+    /// rules.
+    pub fn get_bucket(&self) -> String {
+        let mut rules = self.to_tables();
+        rules[0] = Table::new("challenge");
+        rules[0].push("hard_mode", Value::Boolean(self.hard_mode));
+        // Left out for the first version, so buckets of older challenges don't change
+        if self.rules_version != RulesVersion::V1 {
+            rules[0].push("rules", Value::Integer(self.rules_version.get_number()));
+        }
+        if !self.equipment.is_empty() {
+            rules[0].push("items", Value::String(self.equipment.to_keys()));
+        }
+        if self.action_points {
+            rules[0].push("action_points", Value::Boolean(true));
+        }
+        if self.blood_magic {
+            rules[0].push("blood_magic", Value::Boolean(true));
+        }
+        if let Some(max_mana) = self.max_mana {
+            rules[0].push("max_mana", Value::Integer(max_mana as i64));
+        }
+        for table in &mut rules[1..] {
+            // Spells are identified by name, the boss only by its stats
+            let keep_name = table.name == "spell";
+            table
+                .entries
+                .retain(|(key, _)| (key != "name" || keep_name) && key != "description");
+        }
+        // 64-bit FNV-1a, which stays the same across builds
+        let hash = toml::write(&rules)
+            .bytes()
+            .fold(0xcbf29ce484222325_u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+        format!("{:016x}", hash)
+    }
+
+    /// File name to export the challenge to, made from its name
+    pub fn get_file_name(&self) -> String {
+        let slug: String = self
+            .name
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        format!("{}{}", slug, CHALLENGE_FILE_SUFFIX)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        Self::from_tables(toml::parse(text)?)
+    }
+
+    pub(crate) fn from_tables(tables: Vec<Table>) -> Result<Self, ParseError> {
+        let mut header = None;
+        let mut boss = None;
+        let mut spellbook = Spellbook::default();
+        let mut loadout = None;
+        for table in tables {
+            match table.name.as_str() {
+                "challenge" if header.is_none() => header = Some(table),
+                "boss" if boss.is_none() => boss = Some(BestiaryEntry::from_table(&table)?),
+                "spell" => {
+                    spellbook.apply_table(&table)?;
+                    spellbook
+                        .validate()
+                        .map_err(|message| table.error(message))?;
+                }
+                "loadout" if loadout.is_none() => loadout = Some(Loadout::from_table(&table)?),
+                "challenge" | "boss" | "loadout" => {
+                    return Err(table.error(format!("more than one `[[{}]]`", table.name)))
+                }
+                name => return Err(table.error(format!("unknown table `[[{}]]`", name))),
+            }
+        }
+        let missing = |name: &str| ParseError {
+            line: 0,
+            message: format!("challenge is missing `[[{}]]`", name),
+        };
+        let header = header.ok_or_else(|| missing("challenge"))?;
+        let boss = boss.ok_or_else(|| missing("boss"))?;
+        let max_mana = match header.get_integer("max_mana")? {
+            Some(max_mana) => Some(
+                i32::try_from(max_mana)
+                    .ok()
+                    .filter(|max_mana| *max_mana > 0)
+                    .ok_or_else(|| header.error("`max_mana` is out of range".to_string()))?,
+            ),
+            None => None,
+        };
+        // Files from before rules were versioned are played under the first version
+        let rules_version = match header.get_integer("rules")? {
+            Some(number) => RulesVersion::from_number(number).ok_or_else(|| {
+                header.error(format!(
+                    "rules version {} is newer than this game, which supports up to {}",
+                    number,
+                    RulesVersion::LATEST.get_number()
+                ))
+            })?,
+            None => RulesVersion::V1,
+        };
+
+        Ok(Self {
+            name: header
+                .get_string("name")?
+                .unwrap_or_else(|| boss.name.clone()),
+            boss,
+            spellbook,
+            hard_mode: header.get_boolean("hard_mode")?.unwrap_or(false),
+            scouting: header.get_boolean("scouting")?.unwrap_or(true),
+            rules_version,
+            // Seeds are stored as signed integers and may wrap around
+            seed: header.get_integer("seed")?.unwrap_or(0) as u64,
+            loadout,
+            equipment: Equipment::parse(&header.get_string("items")?.unwrap_or_default())
+                .map_err(|message| header.error(message))?,
+            action_points: header.get_boolean("action_points")?.unwrap_or(false),
+            blood_magic: header.get_boolean("blood_magic")?.unwrap_or(false),
+            max_mana,
+        })
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::write(&self.to_tables())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, self.to_toml())
+    }
+
+    /// The tables of the challenge file: header, boss, changed spells, then the loadout
+    pub(crate) fn to_tables(&self) -> Vec<Table> {
+        let mut header = Table::new("challenge");
+        header.push("name", Value::String(self.name.clone()));
+        header.push("hard_mode", Value::Boolean(self.hard_mode));
+        header.push("scouting", Value::Boolean(self.scouting));
+        header.push("rules", Value::Integer(self.rules_version.get_number()));
+        header.push("seed", Value::Integer(self.seed as i64));
+        if !self.equipment.is_empty() {
+            header.push("items", Value::String(self.equipment.to_keys()));
+        }
+        if self.action_points {
+            header.push("action_points", Value::Boolean(true));
+        }
+        if self.blood_magic {
+            header.push("blood_magic", Value::Boolean(true));
+        }
+        if let Some(max_mana) = self.max_mana {
+            header.push("max_mana", Value::Integer(max_mana as i64));
+        }
+
+        let default = Spellbook::default();
+        let spells = Spell::ALL
+            .iter()
+            .zip(self.spellbook.to_tables())
+            .filter(|(spell, _)| {
+                self.spellbook.get(spell) != default.get(spell)
+                    || self.spellbook.is_sealed(spell) != default.is_sealed(spell)
+            })
+            .map(|(_, table)| table);
+        [header, self.boss.to_table()]
+            .into_iter()
+            .chain(spells)
+            .chain(self.loadout.as_ref().map(Loadout::to_table))
+            .collect()
+    }
+}
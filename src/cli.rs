@@ -0,0 +1,81 @@
+//! Arguments shared by the headless subcommands.
+
+use advent_wizard_rpg::{challenge::Challenge, parallel::default_threads, Battle, Boss, Spell};
+use clap::{arg, value_parser, Arg, ArgMatches};
+use std::{path::PathBuf, process};
+
+/// Arguments describing the battle to play
+pub fn battle_args() -> [Arg; 4] {
+    [
+        arg!(--"boss-hp" <HP> "Boss hitpoints")
+            .value_parser(value_parser!(i32))
+            .default_value("55"),
+        arg!(--"boss-damage" <DAMAGE> "Boss damage")
+            .value_parser(value_parser!(i32))
+            .default_value("8"),
+        arg!(--hard "Set difficulty to hard"),
+        challenge_arg().conflicts_with_all(["boss-hp", "boss-damage", "hard"]),
+    ]
+}
+
+/// Argument loading a challenge file instead of describing the battle
+pub fn challenge_arg() -> Arg {
+    arg!(--challenge <FILE> "Challenge file with the boss, spellbook and rules to play")
+        .value_parser(value_parser!(PathBuf))
+}
+
+/// Challenge given by `challenge_arg`, if any. Exits if it can't be loaded.
+pub fn challenge_from_matches(matches: &ArgMatches) -> Option<Challenge> {
+    let path = matches.get_one::<PathBuf>("challenge")?;
+    match Challenge::load(path) {
+        Ok(challenge) => Some(challenge),
+        Err(err) => {
+            eprintln!("Could not load challenge {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Battle described by `battle_args`, at the start of the wizard's first turn
+pub fn battle_from_matches(matches: &ArgMatches) -> Battle {
+    if let Some(challenge) = challenge_from_matches(matches) {
+        return battle_from_challenge(&challenge);
+    }
+    let mut battle = Battle::with_boss(
+        matches.get_flag("hard"),
+        Boss::new(
+            *matches.get_one::<i32>("boss-hp").unwrap(),
+            *matches.get_one::<i32>("boss-damage").unwrap(),
+        ),
+    );
+    battle.wizard_turn_apply_effects();
+    battle
+}
+
+/// Battle of a challenge, at the start of the wizard's first turn
+pub fn battle_from_challenge(challenge: &Challenge) -> Battle {
+    let mut battle = challenge.create_battle();
+    battle.wizard_turn_apply_effects();
+    battle
+}
+
+/// Argument setting how many threads a subcommand runs on
+pub fn threads_arg() -> Arg {
+    arg!(--threads <COUNT> "Threads to run on [default: one per core]")
+        .value_parser(value_parser!(u64).range(1..))
+}
+
+/// Threads given by `threads_arg`, or one per core
+pub fn threads_from_matches(matches: &ArgMatches) -> usize {
+    matches
+        .get_one::<u64>("threads")
+        .map_or_else(default_threads, |&threads| threads as usize)
+}
+
+/// Spells of a line, separated by commas
+pub fn display_line(line: &[Spell]) -> String {
+    line.iter()
+        .map(|spell| spell.get_display_name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
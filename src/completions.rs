@@ -0,0 +1,17 @@
+//! `completions` subcommand: print a script completing the command line in a shell.
+
+use clap::{arg, value_parser, ArgMatches, Command};
+use clap_complete::Shell;
+use std::io;
+
+pub fn command() -> Command {
+    Command::new("completions")
+        .about("Print a shell completion script, including the names of palettes, strategies and other values")
+        .arg(arg!(<SHELL> "Shell to complete in").value_parser(value_parser!(Shell)))
+}
+
+pub fn run(matches: &ArgMatches, mut command: Command) {
+    let shell = *matches.get_one::<Shell>("SHELL").unwrap();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+}
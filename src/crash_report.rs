@@ -0,0 +1,139 @@
+//! Diagnostic bundle written when the game panics or fails, for attaching to bug reports.
+
+use crate::ui::tui;
+use advent_wizard_rpg::{
+    challenge::Challenge,
+    save::{SaveGame, SAVE_FILE_SUFFIX},
+    Battle,
+};
+use std::{
+    collections::VecDeque,
+    env, fs, io, panic,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Lines of the event log kept for the report
+const RECENT_EVENTS_KEPT: usize = 50;
+
+/// What the game is doing, kept up to date so a crash report can describe it
+struct CrashContext {
+    /// Rules of the battle being played
+    challenge: Option<Challenge>,
+    battle: Option<Battle>,
+    recent_events: VecDeque<String>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    challenge: None,
+    battle: None,
+    recent_events: VecDeque::new(),
+});
+
+/// On panic, restore the terminal, then write a crash report and print its path after
+/// the panic message
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        tui::restore().ok();
+        crate::ambiance::stop();
+        let report = write_report(&info.to_string());
+        default_hook(info);
+        print_report_path(report);
+    }));
+}
+
+/// Set the rules of the battle being played
+pub fn set_challenge(challenge: &Challenge) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.challenge = Some(challenge.clone());
+    }
+}
+
+/// Record the battle as it is after a phase
+pub fn record_battle(battle: &Battle) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.battle = Some(battle.clone());
+    }
+}
+
+/// Record a line of the event log
+pub fn record_event(line: &str) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        if context.recent_events.len() == RECENT_EVENTS_KEPT {
+            context.recent_events.pop_front();
+        }
+        context.recent_events.push_back(line.to_string());
+    }
+}
+
+/// Write a crash report for `reason` to the temp directory. The report is a save file
+/// that replays the battle up to the crash, headed by comments with the version, command
+/// line, battle state and recent events.
+pub fn write_report(reason: &str) -> io::Result<PathBuf> {
+    // A panic while the context was locked leaves it poisoned but still readable
+    let context = CONTEXT.lock().unwrap_or_else(|err| err.into_inner());
+    let mut header = vec![
+        format!(
+            "Crash report for advent-wizard-rpg {}",
+            env!("CARGO_PKG_VERSION")
+        ),
+        format!("Reason: {}", reason),
+        format!(
+            "Command line: {}",
+            env::args().collect::<Vec<_>>().join(" ")
+        ),
+    ];
+    if let Some(battle) = &context.battle {
+        header.push(format!("State: {:?}", battle.get_state()));
+        header.push(format!("Outcome: {:?}", battle.get_outcome()));
+    }
+    header.push("Recent events:".to_string());
+    header.extend(
+        context
+            .recent_events
+            .iter()
+            .map(|line| format!("  {}", line)),
+    );
+
+    let mut report: String = header
+        .iter()
+        .flat_map(|line| line.lines())
+        .map(|line| format!("# {}\n", line))
+        .collect();
+    if let Some(challenge) = &context.challenge {
+        let spells = context
+            .battle
+            .as_ref()
+            .map_or(Vec::new(), |battle| battle.get_spells_used().clone());
+        let save = SaveGame {
+            challenge: challenge.clone(),
+            spells,
+        };
+        report.push('\n');
+        report.push_str(&save.to_toml());
+    }
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = env::temp_dir().join(format!(
+        "advent-wizard-rpg-crash-{}{}",
+        millis, SAVE_FILE_SUFFIX
+    ));
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+/// Tell the user where the crash report went, once the terminal is restored
+pub fn print_report_path(report: io::Result<PathBuf>) {
+    match report {
+        Ok(path) => eprintln!(
+            "A crash report was written to {}. Please attach it when reporting the issue.",
+            path.display()
+        ),
+        Err(err) => eprintln!("Could not write a crash report: {}", err),
+    }
+}
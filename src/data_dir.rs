@@ -0,0 +1,234 @@
+//! Layout of the data directory the game keeps its files in, laid out so it can be synced
+//! between devices with Dropbox or Syncthing:
+//!
+//! - `layout`: the version of the layout
+//! - `profile/`: the player's own files, such as presets and the opening book
+//! - `saves/`: save slots and their previews
+//! - `local/`: files only for this device, not worth syncing, such as autosaves, the
+//!   cache of puzzle inputs and the log
+//!
+//! Files are written atomically, so a sync never picks up half a file, and the files the
+//! game reads back have a version header (see `FileFormat`). `migrate` moves the files of
+//! older layouts into place.
+
+use advent_wizard_rpg::{
+    book::OpeningBook,
+    file_format::{write_atomic, FileFormat, OPENING_BOOK_FORMAT, SAVE_FORMAT},
+    save::{SaveGame, SAVE_FILE_SUFFIX},
+};
+use clap::Command;
+use std::{
+    env,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Environment variable moving the data directory, such as into a synced folder
+const DATA_DIR_VAR: &str = "WIZARD_RPG_DATA_DIR";
+/// Name of the file holding the version of the layout
+pub const LAYOUT_FILE: &str = "layout";
+/// Version 1 was every file at the top of the directory, with no layout file
+pub const LAYOUT_FORMAT: FileFormat = FileFormat {
+    kind: "layout",
+    version: 2,
+};
+
+/// Format of the survival high score
+pub const HIGH_SCORE_FORMAT: FileFormat = FileFormat {
+    kind: "survival-high-score",
+    version: 1,
+};
+
+/// The data directory: `WIZARD_RPG_DATA_DIR` if set, otherwise
+/// `~/.local/state/advent-wizard-rpg`
+pub fn root() -> PathBuf {
+    if let Some(dir) = env::var_os(DATA_DIR_VAR).filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    match env::var_os("HOME") {
+        Some(home) => Path::new(&home).join(".local/state/advent-wizard-rpg"),
+        None => PathBuf::new(),
+    }
+}
+
+/// The player's own files, synced between devices
+pub fn profile_dir() -> PathBuf {
+    root().join("profile")
+}
+
+/// Save slots, synced between devices
+pub fn saves_dir() -> PathBuf {
+    root().join("saves")
+}
+
+/// Files only for this device
+pub fn local_dir() -> PathBuf {
+    root().join("local")
+}
+
+pub fn command() -> Command {
+    Command::new("migrate").about(
+        "Move the files of older versions of the game into the current layout of the data directory",
+    )
+}
+
+/// Run the `migrate` subcommand: migrate the data directory, printing each change
+pub fn run() -> io::Result<()> {
+    let root = root();
+    let changes = migrate(&root)?;
+    if changes.is_empty() {
+        println!("{} is up to date", root.display());
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+    Ok(())
+}
+
+/// Migrate the data directory if it's in an older layout, as the game starts
+pub fn migrate_if_outdated() -> io::Result<()> {
+    let root = root();
+    if root.exists() && read_layout_version(&root)? < LAYOUT_FORMAT.version {
+        migrate(&root)?;
+    }
+    Ok(())
+}
+
+/// Version of the layout of the data directory at `root`
+fn read_layout_version(root: &Path) -> io::Result<u32> {
+    match fs::read_to_string(root.join(LAYOUT_FILE)) {
+        Ok(text) => LAYOUT_FORMAT
+            .read_version(&text)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(1),
+        Err(err) => Err(err),
+    }
+}
+
+/// Where a file or directory at the top of a version 1 data directory goes, relative to
+/// the directory, None if it stays put
+fn get_new_place(name: &str) -> Option<&'static str> {
+    match name {
+        "presets.toml" | "opening-book.toml" | "survival-high-score.txt" => Some("profile"),
+        "autosaves" => Some("local"),
+        _ if name.starts_with("advent-wizard-rpg.log") => Some("local"),
+        _ if name.starts_with("aoc-2015-22-") => Some("local/cache"),
+        _ => None,
+    }
+}
+
+/// Bring the data directory at `root` up to the current layout: move the files of older
+/// layouts into place, then add version headers to the files written before them. Files
+/// whose new place is taken, such as by a sync from another device, are left where they
+/// are. Returns a line describing each change.
+pub fn migrate(root: &Path) -> io::Result<Vec<String>> {
+    // Erroring on a newer layout, rather than moving its files back
+    read_layout_version(root)?;
+    let mut changes = Vec::new();
+    let mut names: Vec<String> = match fs::read_dir(root) {
+        Ok(entries) => entries
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string()))
+            .collect::<io::Result<_>>()?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(changes),
+        Err(err) => return Err(err),
+    };
+    names.sort();
+    for name in names {
+        let Some(place) = get_new_place(&name) else {
+            continue;
+        };
+        let from = root.join(&name);
+        let to = root.join(place).join(&name);
+        if to.exists() {
+            changes.push(format!(
+                "Left {}, as {} already exists",
+                from.display(),
+                to.display()
+            ));
+            continue;
+        }
+        fs::create_dir_all(root.join(place))?;
+        fs::rename(&from, &to)?;
+        changes.push(format!("Moved {} to {}", from.display(), to.display()));
+    }
+
+    let profile = root.join("profile");
+    upgrade(
+        &profile.join("opening-book.toml"),
+        OPENING_BOOK_FORMAT,
+        &mut changes,
+        |text| {
+            OpeningBook::parse(text)
+                .map(|book| book.to_toml())
+                .map_err(|err| err.to_string())
+        },
+    )?;
+    upgrade(
+        &profile.join("survival-high-score.txt"),
+        HIGH_SCORE_FORMAT,
+        &mut changes,
+        |text| Ok(HIGH_SCORE_FORMAT.with_header(text)),
+    )?;
+    for dir in [root.join("saves"), root.join("local/autosaves")] {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut saves = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.to_string_lossy().ends_with(SAVE_FILE_SUFFIX) {
+                saves.push(path);
+            }
+        }
+        saves.sort();
+        for path in saves {
+            upgrade(&path, SAVE_FORMAT, &mut changes, |text| {
+                SaveGame::parse(text)
+                    .map(|save| save.to_toml())
+                    .map_err(|err| err.to_string())
+            })?;
+        }
+    }
+
+    write_atomic(&root.join(LAYOUT_FILE), LAYOUT_FORMAT.with_header(""))?;
+    Ok(changes)
+}
+
+/// Rewrite the file at `path` in `format` with `rewrite` if it was written before version
+/// headers, keeping when it was modified, such as when a slot was saved. A file that
+/// doesn't exist is left out, and one that can't be read is left as it is.
+fn upgrade(
+    path: &Path,
+    format: FileFormat,
+    changes: &mut Vec<String>,
+    rewrite: impl Fn(&str) -> Result<String, String>,
+) -> io::Result<()> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    if !matches!(format.read_version(&text), Ok(0)) {
+        return Ok(());
+    }
+    let rewritten = match rewrite(&text) {
+        Ok(rewritten) => rewritten,
+        Err(err) => {
+            changes.push(format!(
+                "Left {}, as it can't be read: {}",
+                path.display(),
+                err
+            ));
+            return Ok(());
+        }
+    };
+    let modified = fs::metadata(path)?.modified()?;
+    write_atomic(path, &rewritten)?;
+    File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(modified)?;
+    changes.push(format!("Added a version header to {}", path.display()));
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use crate::{
+    strategy::{Observation, Strategy},
+    EffectOngoingError, Spell, Spellbook, Wizard,
+};
+
+/// Mirror match between two wizards who take turns casting spells at each other.
+///
+/// At the start of every turn the effects on both wizards apply. Magic Missile and Drain
+/// are reduced by the target's armor (to a minimum of 1), while Poison ignores it.
+#[derive(Debug, Clone)]
+pub struct Duel {
+    wizards: [Wizard; 2],
+    /// Spells both wizards cast
+    spellbook: Spellbook,
+    /// Index of the wizard whose turn it is
+    turn: usize,
+    /// Index of the wizard who won
+    winner: Option<usize>,
+}
+
+impl Default for Duel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Duel {
+    pub fn new() -> Self {
+        Self {
+            wizards: [Wizard::default(), Wizard::default()],
+            spellbook: Spellbook::default(),
+            turn: 0,
+            winner: None,
+        }
+    }
+
+    /// Apply the effects on both wizards at the start of a turn.
+    /// Returns the index of the winner if a wizard died to poison.
+    pub fn apply_effects(&mut self) -> Option<usize> {
+        for wizard in self.wizards.iter_mut() {
+            wizard.apply_effect(&self.spellbook);
+        }
+        // The wizard whose turn it is dies first
+        for side in [self.turn, 1 - self.turn] {
+            if self.wizards[side].get_hitpoints() <= 0 {
+                self.winner = Some(1 - side);
+                return self.winner;
+            }
+        }
+        None
+    }
+
+    /// Spells the wizard on `side` can cast now
+    pub fn get_possible_spells(&self, side: usize) -> Vec<Spell> {
+        let wizard = &self.wizards[side];
+        let enemy = &self.wizards[1 - side];
+        Spell::ALL
+            .iter()
+            .filter(|spell| {
+                !self.spellbook.is_sealed(spell)
+                    && wizard.get_mana() >= self.spellbook.get_mana(spell)
+                    && match spell {
+                        Spell::Shield => wizard.get_shielded().is_none(),
+                        Spell::Poison => enemy.get_poisoned().is_none(),
+                        Spell::Recharge => wizard.get_recharging().is_none(),
+                        Spell::Cleanse => wizard.get_poisoned().is_some(),
+                        // Wizards don't attack each other, so there is nothing to reflect
+                        Spell::Thorns => false,
+                        // Familiars only fight bosses
+                        Spell::Summon => false,
+                        _ => true,
+                    }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The wizard whose turn it is casts `spell`, then the turn passes to the other wizard.
+    /// Returns the index of the winner if the duel is over.
+    pub fn cast_spell(&mut self, spell: &Spell) -> Result<Option<usize>, EffectOngoingError> {
+        if !self.get_possible_spells(self.turn).contains(spell) {
+            return Err(EffectOngoingError());
+        }
+
+        let [first, second] = &mut self.wizards;
+        let (wizard, enemy) = if self.turn == 0 {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        wizard.cast_at_wizard(spell, enemy, &self.spellbook);
+        if enemy.get_hitpoints() <= 0 {
+            self.winner = Some(self.turn);
+        }
+        self.turn = 1 - self.turn;
+        Ok(self.winner)
+    }
+
+    /// Play a whole turn: apply effects, then the wizard whose turn it is casts the spell
+    /// chosen by `strategy`, forfeiting if it chooses none.
+    /// Returns the spell cast, and the index of the winner if the duel is over.
+    pub fn play_turn(&mut self, strategy: &mut dyn Strategy) -> (Option<Spell>, Option<usize>) {
+        if let Some(winner) = self.apply_effects() {
+            return (None, Some(winner));
+        }
+        let observation = Observation::from_duel(self, self.turn);
+        match strategy.choose_spell(&observation) {
+            Some(spell) => match self.cast_spell(&spell) {
+                Ok(winner) => (Some(spell), winner),
+                Err(_) => (None, Some(self.forfeit())),
+            },
+            None => (None, Some(self.forfeit())),
+        }
+    }
+
+    /// The wizard whose turn it is gives up, having no spell to cast
+    pub fn forfeit(&mut self) -> usize {
+        self.winner = Some(1 - self.turn);
+        1 - self.turn
+    }
+
+    /// Damage the poison on the wizard on `side` deals over the next `turns` turns, until
+    /// it wears off. It ticks at the start of every turn, either wizard's.
+    pub fn projected_poison_damage(&self, side: usize, turns: i32) -> i32 {
+        let ticks = self.wizards[side]
+            .get_poisoned()
+            .unwrap_or(0)
+            .min(turns)
+            .max(0);
+        ticks * self.spellbook.get(&Spell::Poison).damage
+    }
+
+    /// Mana Recharge gives the wizard on `side` over the next `turns` turns, until it
+    /// wears off, ticking like `projected_poison_damage`
+    pub fn projected_recharge_mana(&self, side: usize, turns: i32) -> i32 {
+        let ticks = self.wizards[side]
+            .get_recharging()
+            .unwrap_or(0)
+            .min(turns)
+            .max(0);
+        ticks * self.spellbook.get(&Spell::Recharge).mana_gain
+    }
+
+    pub fn get_wizard(&self, side: usize) -> &Wizard {
+        &self.wizards[side]
+    }
+
+    pub fn get_turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn get_winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    pub fn get_spellbook(&self) -> &Spellbook {
+        &self.spellbook
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    balance::check_balance, bestiary::BestiaryEntry, challenge::Challenge, rng::Rng, Spell,
+};
+use std::fmt;
+
+/// Rounds searched when checking that a generated encounter is balanced
+const ENCOUNTER_CHECK_DEPTH: usize = 20;
+/// Encounters generated before settling for one that isn't balanced
+const MAX_ENCOUNTER_ATTEMPTS: usize = 16;
+/// Budget points a point of boss damage costs, one point being a boss hitpoint
+const DAMAGE_COST: i32 = 5;
+/// Lowest and highest damage of a generated boss
+const DAMAGE_RANGE: (i32, i32) = (5, 12);
+/// Lowest hitpoints of a generated boss
+const MIN_HITPOINTS: i32 = 10;
+
+const NAME_STARTS: [&str; 8] = [
+    "Grim", "Ash", "Frost", "Bone", "Mire", "Thorn", "Gloom", "Rust",
+];
+const NAME_ENDS: [&str; 8] = [
+    "fang", "maw", "wyrm", "golem", "shade", "claw", "hound", "drake",
+];
+
+/// How hard a generated encounter is, as a budget of points spent on the boss's
+/// hitpoints, damage and mutators. The Advent of Code boss, 55 hitpoints and 8 damage, is
+/// worth 95 points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DifficultyTier {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl DifficultyTier {
+    pub const ALL: [DifficultyTier; 3] = [Self::Easy, Self::Normal, Self::Hard];
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Self::Easy => "easy",
+            Self::Normal => "normal",
+            Self::Hard => "hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tier| tier.get_name() == name)
+    }
+
+    /// Lowest and highest budget of the tier
+    pub fn get_budget(self) -> (i32, i32) {
+        match self {
+            Self::Easy => (65, 85),
+            Self::Normal => (85, 105),
+            Self::Hard => (105, 125),
+        }
+    }
+}
+
+/// A change to the rules of a generated encounter, paid for from its budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncounterMutator {
+    /// The wizard loses a hitpoint at the start of each of their turns
+    HardMode,
+    /// Shield costs 40 more mana
+    CostlyShield,
+    /// Poison deals a point less damage each turn
+    WeakPoison,
+    /// Recharge lasts a turn less
+    ShortRecharge,
+    /// Magic Missile deals a point less damage
+    DullMissile,
+}
+
+impl EncounterMutator {
+    pub const ALL: [EncounterMutator; 5] = [
+        Self::HardMode,
+        Self::CostlyShield,
+        Self::WeakPoison,
+        Self::ShortRecharge,
+        Self::DullMissile,
+    ];
+
+    /// Budget points the mutator costs
+    pub fn get_cost(self) -> i32 {
+        match self {
+            Self::HardMode => 20,
+            _ => 10,
+        }
+    }
+
+    /// Name of the mutator on the command line
+    pub fn get_key(self) -> &'static str {
+        match self {
+            Self::HardMode => "hard-mode",
+            Self::CostlyShield => "costly-shield",
+            Self::WeakPoison => "weak-poison",
+            Self::ShortRecharge => "short-recharge",
+            Self::DullMissile => "dull-missile",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mutator| mutator.get_key() == key)
+    }
+
+    /// Change the rules of `challenge`
+    pub fn apply(self, challenge: &mut Challenge) {
+        let spellbook = &mut challenge.spellbook;
+        match self {
+            Self::HardMode => challenge.hard_mode = true,
+            Self::CostlyShield => spellbook.get_mut(&Spell::Shield).mana += 40,
+            Self::WeakPoison => spellbook.get_mut(&Spell::Poison).damage -= 1,
+            Self::ShortRecharge => spellbook.get_mut(&Spell::Recharge).duration -= 1,
+            Self::DullMissile => spellbook.get_mut(&Spell::MagicMissile).damage -= 1,
+        }
+    }
+}
+
+impl fmt::Display for EncounterMutator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::HardMode => "Hard Mode",
+            Self::CostlyShield => "Costly Shield",
+            Self::WeakPoison => "Weak Poison",
+            Self::ShortRecharge => "Short Recharge",
+            Self::DullMissile => "Dull Missile",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A generated boss and the rules it is fought under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encounter {
+    /// The boss, spellbook and difficulty, with the mutators applied
+    pub challenge: Challenge,
+    pub mutators: Vec<EncounterMutator>,
+}
+
+/// Generate an encounter from `seed` within the budget of `tier`: a boss with a random
+/// name, up to two mutators, and the rest of the budget split between the boss's damage
+/// and hitpoints. Encounters the solver finds unwinnable, trivially winnable or
+/// unloseable are generated again, up to a limit. The same tier and seed always give the
+/// same encounter.
+pub fn generate_encounter(tier: DifficultyTier, seed: u64) -> Encounter {
+    let mut rng = Rng::new(seed);
+    let mut encounter = generate_candidate(tier, seed, &mut rng);
+    for _ in 1..MAX_ENCOUNTER_ATTEMPTS {
+        let mut battle = encounter.challenge.create_battle();
+        battle.wizard_turn_apply_effects();
+        if check_balance(&battle, ENCOUNTER_CHECK_DEPTH).is_empty() {
+            break;
+        }
+        encounter = generate_candidate(tier, seed, &mut rng);
+    }
+    encounter
+}
+
+fn generate_candidate(tier: DifficultyTier, seed: u64, rng: &mut Rng) -> Encounter {
+    let (low, high) = tier.get_budget();
+    let mut budget = low + rng.below((high - low + 1) as u32) as i32;
+
+    let mut mutators = Vec::new();
+    for _ in 0..rng.below(3) {
+        let mutator = EncounterMutator::ALL[rng.below(EncounterMutator::ALL.len() as u32) as usize];
+        if !mutators.contains(&mutator) {
+            mutators.push(mutator);
+            budget -= mutator.get_cost();
+        }
+    }
+
+    // Leave at least the lowest hitpoints after paying for the damage
+    let highest_damage = DAMAGE_RANGE
+        .1
+        .min((budget - MIN_HITPOINTS) / DAMAGE_COST)
+        .max(DAMAGE_RANGE.0);
+    let damage = DAMAGE_RANGE.0 + rng.below((highest_damage - DAMAGE_RANGE.0 + 1) as u32) as i32;
+    let hitpoints = (budget - damage * DAMAGE_COST).max(MIN_HITPOINTS);
+
+    let name = format!(
+        "{}{}",
+        NAME_STARTS[rng.below(NAME_STARTS.len() as u32) as usize],
+        NAME_ENDS[rng.below(NAME_ENDS.len() as u32) as usize]
+    );
+    let mut challenge = Challenge {
+        seed,
+        ..Challenge::new(BestiaryEntry::new(&name, hitpoints, damage))
+    };
+    for mutator in &mutators {
+        mutator.apply(&mut challenge);
+    }
+    Encounter {
+        challenge,
+        mutators,
+    }
+}
@@ -0,0 +1,106 @@
+//! Survival: bosses that get stronger, one after another, until the wizard falls.
+
+use crate::{
+    crash_report, create_app,
+    data_dir::{self, HIGH_SCORE_FORMAT},
+    ui,
+    ui::tui,
+};
+use advent_wizard_rpg::{
+    challenge::Challenge,
+    file_format::write_atomic,
+    solver::SearchBudget,
+    survival::{get_survival_boss, next_survival_battle, SURVIVAL_HEAL},
+};
+use clap::{arg, Arg, ArgMatches};
+use std::{fs, io, path::PathBuf};
+
+pub fn arg() -> Arg {
+    arg!(--survival "Fight stronger and stronger bosses, healing a little between them, until the wizard falls")
+}
+
+/// File keeping the most bosses defeated in one survival run
+fn high_score_path() -> PathBuf {
+    data_dir::profile_dir().join("survival-high-score.txt")
+}
+
+/// Most bosses defeated in one survival run, 0 if there was no run yet or it was kept by
+/// a newer game
+fn load_high_score() -> u32 {
+    fs::read_to_string(high_score_path())
+        .ok()
+        .and_then(|text| parse_high_score(&text))
+        .unwrap_or(0)
+}
+
+/// High score held by the text of a high score file, None if it isn't one this game reads
+pub fn parse_high_score(text: &str) -> Option<u32> {
+    HIGH_SCORE_FORMAT.read_version(text).ok()?;
+    text.lines()
+        .find(|line| !line.starts_with('#'))
+        .and_then(|line| line.trim().parse().ok())
+}
+
+fn save_high_score(score: u32) -> io::Result<()> {
+    fs::create_dir_all(data_dir::profile_dir())?;
+    write_atomic(
+        &high_score_path(),
+        HIGH_SCORE_FORMAT.with_header(&format!("{}\n", score)),
+    )
+}
+
+/// Fight survival bosses until the wizard loses or the player quits, then show the score.
+/// A victory moves straight on to the next boss. Survival battles aren't autosaved,
+/// since a save can't hold the run.
+pub fn play(
+    terminal: &mut tui::Tui,
+    matches: &ArgMatches,
+    blunder_check: Option<SearchBudget>,
+) -> io::Result<()> {
+    let first = Challenge {
+        hard_mode: matches.get_flag("hard"),
+        ..Challenge::new(get_survival_boss(0))
+    };
+    let mut battle = first.create_battle();
+    let mut defeated = 0;
+    loop {
+        let boss = get_survival_boss(defeated as usize);
+        let challenge = Challenge {
+            hard_mode: first.hard_mode,
+            ..Challenge::new(boss.clone())
+        };
+        crash_report::set_challenge(&challenge);
+        let mut app = create_app(
+            matches,
+            battle,
+            Some(boss.name),
+            &challenge,
+            blunder_check,
+            None,
+        );
+        app.exit_on_win = true;
+        app.run(terminal, &[])?;
+        if app.game.get_outcome() != Some(true) {
+            break;
+        }
+        defeated += 1;
+        battle = next_survival_battle(&app.game, defeated as usize);
+    }
+
+    let high_score = load_high_score();
+    let record = if defeated > high_score {
+        save_high_score(defeated)?;
+        "A new best!".to_string()
+    } else {
+        format!("Best: {}", high_score)
+    };
+    let text = format!(
+        "The run ended at wave {}.\n\nBosses defeated: {}\n{}\n\nThe wizard healed {} hitpoints after each boss.",
+        defeated + 1,
+        defeated,
+        record,
+        SURVIVAL_HEAL
+    );
+    ui::tell_story(terminal, "Survival Over", &text, &[])?;
+    Ok(())
+}
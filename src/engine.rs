@@ -0,0 +1,15 @@
+//! The battle engine: combatants, spells and the rules of a battle.
+//!
+//! This is the stable part of the API. Additions to it are minor releases, and enums
+//! that are likely to grow are `#[non_exhaustive]`, so match them with a wildcard arm.
+//! The rest of the crate builds tools such as solvers and file formats on top of it.
+
+pub use crate::rpg::{
+    Battle, BattleEvent, BattleState, Boss, DamageSource, EffectOngoingError, EventObserver,
+    ParseSpellError, RulesVersion, Spell, SpellAvailability, StateDiff, WastedCast, Wizard,
+};
+pub use crate::spellbook::{
+    SpellStat, SpellStats, Spellbook, ACTION_POINTS_PER_TURN, BLOOD_MAGIC_MANA_PER_HITPOINT,
+    CHEAP_SPELL_MANA,
+};
+pub use crate::toml::ParseError;
@@ -0,0 +1,144 @@
+//! Lines of the event window, with the round and phase each one happened in.
+
+use crate::ui::theme;
+use ratatui::{style::Stylize, text::Line};
+use std::fmt;
+
+/// Whose turn an event happened in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPhase {
+    Wizard,
+    Boss,
+}
+
+impl fmt::Display for EventPhase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Wizard => write!(f, "Wizard"),
+            Self::Boss => write!(f, "Boss"),
+        }
+    }
+}
+
+/// One event of the battle, or a notice from the game when `phase` is None
+#[derive(Debug, Clone)]
+pub struct EventEntry {
+    pub round: usize,
+    pub phase: Option<EventPhase>,
+    /// Said by the boss, drawn apart from the events
+    pub taunt: bool,
+    pub text: String,
+}
+
+/// Events in the order they happened, and the lines drawing them
+#[derive(Debug, Default)]
+pub struct EventLog {
+    entries: Vec<EventEntry>,
+    /// Lines of the entries, with a rule starting each round after the first
+    lines: Vec<Line<'static>>,
+    /// Index of the first line of each round, in order
+    round_starts: Vec<usize>,
+    /// Whether lines are prefixed with their round and phase
+    round_markers: bool,
+    /// Round and phase of the events being pushed
+    round: usize,
+    phase: Option<EventPhase>,
+}
+
+impl EventLog {
+    pub fn new(round_markers: bool) -> Self {
+        Self {
+            round_markers,
+            ..Self::default()
+        }
+    }
+
+    /// Attach the events pushed from now on to `phase` of `round`
+    pub fn begin_phase(&mut self, round: usize, phase: EventPhase) {
+        self.round = round;
+        self.phase = Some(phase);
+    }
+
+    /// Add an event of the current round and phase
+    pub fn push(&mut self, text: String) {
+        self.push_entry(EventEntry {
+            round: self.round,
+            phase: self.phase,
+            taunt: false,
+            text,
+        });
+    }
+
+    /// Add a line said by the boss in the current round and phase
+    pub fn push_taunt(&mut self, text: String) {
+        self.push_entry(EventEntry {
+            round: self.round,
+            phase: self.phase,
+            taunt: true,
+            text,
+        });
+    }
+
+    /// Add a notice from the game, which belongs to no phase
+    pub fn push_notice(&mut self, text: String) {
+        self.push_entry(EventEntry {
+            round: self.round,
+            phase: None,
+            taunt: false,
+            text,
+        });
+    }
+
+    fn push_entry(&mut self, entry: EventEntry) {
+        let new_round = self
+            .entries
+            .last()
+            .is_none_or(|last| last.round < entry.round);
+        if new_round {
+            self.round_starts.push(self.lines.len());
+        }
+        if new_round && !self.entries.is_empty() {
+            self.lines
+                .push(Line::from(format!("──── Round {} ────", entry.round)).dark_gray());
+        }
+        self.lines.push(self.entry_line(&entry));
+        self.entries.push(entry);
+    }
+
+    fn entry_line(&self, entry: &EventEntry) -> Line<'static> {
+        let text = if entry.taunt {
+            entry.text.clone().italic().fg(theme::get().boss)
+        } else {
+            entry.text.clone().into()
+        };
+        if !self.round_markers || entry.text.is_empty() {
+            return Line::from(text);
+        }
+        let marker = match entry.phase {
+            Some(phase) => format!("R{} • {} ", entry.round, phase),
+            None => format!("R{} ", entry.round),
+        };
+        Line::from(vec![marker.dark_gray(), text])
+    }
+
+    pub fn get_lines(&self) -> &[Line<'static>] {
+        &self.lines
+    }
+
+    /// Index of the first line of the last round starting before line `index`
+    pub fn get_previous_round_start(&self, index: usize) -> Option<usize> {
+        self.round_starts
+            .iter()
+            .rev()
+            .find(|start| **start < index)
+            .copied()
+    }
+
+    /// Index of the first line of the first round starting after line `index`
+    pub fn get_next_round_start(&self, index: usize) -> Option<usize> {
+        self.round_starts
+            .iter()
+            .find(|start| **start > index)
+            .copied()
+    }
+}
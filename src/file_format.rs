@@ -0,0 +1,105 @@
+use crate::toml::ParseError;
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    process,
+};
+
+/// Start of the comment naming the format of a file
+const HEADER_PREFIX: &str = "# advent-wizard-rpg format: ";
+
+/// A kind of file the game keeps, and the version of its format this game writes. Files
+/// start with a header comment naming both, such as `# advent-wizard-rpg format: save 1`.
+/// Being a comment, games from before the header skip it, and files without one are
+/// version 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileFormat {
+    pub kind: &'static str,
+    pub version: u32,
+}
+
+/// Format of save files
+pub const SAVE_FORMAT: FileFormat = FileFormat {
+    kind: "save",
+    version: 1,
+};
+
+/// Format of the opening book
+pub const OPENING_BOOK_FORMAT: FileFormat = FileFormat {
+    kind: "opening-book",
+    version: 1,
+};
+
+impl FileFormat {
+    /// Header line of a file of this format, without the newline
+    pub fn get_header(&self) -> String {
+        format!("{}{} {}", HEADER_PREFIX, self.kind, self.version)
+    }
+
+    /// `contents` following the header
+    pub fn with_header(&self, contents: &str) -> String {
+        format!("{}\n{}", self.get_header(), contents)
+    }
+
+    /// Version of the format `text` was written in, from the header among its leading
+    /// comments, or 0 if it has none. Errors if the header names another kind of file, or
+    /// a version newer than this game reads.
+    pub fn read_version(&self, text: &str) -> Result<u32, ParseError> {
+        let leading = text
+            .lines()
+            .enumerate()
+            .take_while(|(_, line)| line.trim().is_empty() || line.starts_with('#'));
+        for (i, line) in leading {
+            let Some(header) = line.strip_prefix(HEADER_PREFIX) else {
+                continue;
+            };
+            let error = |message: String| ParseError {
+                line: i + 1,
+                message,
+            };
+            let (kind, version) = header
+                .trim()
+                .rsplit_once(' ')
+                .ok_or_else(|| error("expected the kind of file and its version".to_string()))?;
+            if kind != self.kind {
+                return Err(error(format!(
+                    "expected a {} file, not {}",
+                    self.kind, kind
+                )));
+            }
+            let version: u32 = version
+                .parse()
+                .map_err(|_| error(format!("invalid version `{}`", version)))?;
+            if version > self.version {
+                return Err(error(format!(
+                    "{} format {} is newer than this game, which reads up to {}",
+                    self.kind, version, self.version
+                )));
+            }
+            return Ok(version);
+        }
+        Ok(0)
+    }
+}
+
+/// Write `contents` to `path` so that it always holds either the old or the new contents,
+/// even if the game crashes or a file sync picks it up part way through. The contents go
+/// to a hidden temporary file next to `path`, which is flushed to disk, then moved into
+/// place.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    // Named by the process, so two games writing at once don't share a temporary file
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp_path = path.with_file_name(temp_name);
+    let written = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    });
+    if let Err(err) = written.and_then(|_| fs::rename(&temp_path, path)) {
+        fs::remove_file(&temp_path).ok();
+        return Err(err);
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::{Battle, BattleState};
+
+/// A round projected by `forecast_battle`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForecastRound {
+    /// Rounds from now, starting at 1
+    pub round: usize,
+    /// State at the start of the wizard's turn after the round, or when the battle ended
+    pub state: BattleState,
+    /// Some(true) if the wizard won during the round, Some(false) if the boss won
+    pub outcome: Option<bool>,
+}
+
+/// Project up to `rounds` rounds of `battle`, from the start of the wizard's turn, if the
+/// wizard casts nothing more. Shows when effects expire and whether poison alone finishes
+/// the boss. Stops early when the battle ends. Empty while any of the boss's stats are
+/// hidden, as the rounds would give them away.
+pub fn forecast_battle(battle: &Battle, rounds: usize) -> Vec<ForecastRound> {
+    let mut battle = battle.for_search();
+    let mut forecast = Vec::new();
+    if battle.get_outcome().is_some() || !battle.get_knowledge().is_full() {
+        return forecast;
+    }
+    for round in 1..=rounds {
+        let outcome = battle
+            .boss_turn_apply_effects()
+            .or_else(|| battle.boss_turn_attack())
+            .or_else(|| battle.wizard_turn_apply_effects());
+        forecast.push(ForecastRound {
+            round,
+            state: battle.get_state(),
+            outcome,
+        });
+        if outcome.is_some() {
+            break;
+        }
+    }
+    forecast
+}
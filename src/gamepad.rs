@@ -0,0 +1,50 @@
+//! `gamepad` feature: play the battle with a game controller, whose buttons are mapped to
+//! the same actions as keys.
+
+use crate::action::Action;
+use gilrs::{EventType, Gilrs};
+use std::{fmt, time::Duration};
+
+/// Longest the main loop waits for a key before polling the controllers again
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+/// Longest it waits while no controller is connected, only to notice one being plugged in
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The controllers connected, including ones plugged in while playing
+pub struct Gamepad {
+    gilrs: Gilrs,
+}
+
+impl fmt::Debug for Gamepad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Gamepad({} connected)", self.gilrs.gamepads().count())
+    }
+}
+
+impl Gamepad {
+    /// Start listening to controllers, None if the platform doesn't support them
+    pub fn open() -> Option<Self> {
+        Gilrs::new().ok().map(|gilrs| Self { gilrs })
+    }
+
+    /// How long the main loop may wait before polling again, short only while a
+    /// controller is connected so an idle game doesn't wake up every frame
+    pub fn get_poll_interval(&self) -> Duration {
+        if self.gilrs.gamepads().next().is_some() {
+            POLL_INTERVAL
+        } else {
+            IDLE_POLL_INTERVAL
+        }
+    }
+
+    /// Actions of the buttons pressed on any controller since the last poll, in order
+    pub fn poll_actions(&mut self) -> Vec<Action> {
+        let mut actions = Vec::new();
+        while let Some(event) = self.gilrs.next_event() {
+            if let EventType::ButtonPressed(button, _) = event.event {
+                actions.extend(Action::from_button(button));
+            }
+        }
+        actions
+    }
+}
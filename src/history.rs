@@ -0,0 +1,129 @@
+use crate::BattleState;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Snapshots of a battle at the start of each of the wizard's turns, keeping only the
+/// latest rounds once full. Undoing, reviewing and stepping back through a replay all read
+/// the battle from here instead of replaying it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BattleHistory {
+    capacity: usize,
+    /// Round of the oldest snapshot kept, from 1
+    first_round: usize,
+    states: VecDeque<BattleState>,
+}
+
+impl BattleHistory {
+    /// History keeping the last `capacity` rounds, at least one
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            first_round: 1,
+            states: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record the state at the start of the next round, dropping the oldest round if full
+    pub(crate) fn push(&mut self, state: BattleState) {
+        if self.states.len() == self.capacity {
+            self.states.pop_front();
+            self.first_round += 1;
+        }
+        self.states.push_back(state);
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Rounds recorded so far, including the ones dropped
+    pub fn get_rounds(&self) -> usize {
+        self.first_round + self.states.len() - 1
+    }
+
+    /// Round of the oldest snapshot kept, None before the first is recorded
+    pub fn get_first_round(&self) -> Option<usize> {
+        (!self.states.is_empty()).then_some(self.first_round)
+    }
+
+    /// The state at the start of round `round`, from 1. None if the round hasn't started
+    /// yet or was dropped.
+    pub fn state_at_round(&self, round: usize) -> Option<BattleState> {
+        round
+            .checked_sub(self.first_round)
+            .and_then(|i| self.states.get(i))
+            .copied()
+    }
+
+    /// Rounds kept and the state at the start of each, oldest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (usize, BattleState)> + '_ {
+        self.states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (self.first_round + i, *state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Battle, Boss, Spell};
+
+    /// States at the start of the first `rounds` rounds of a battle
+    fn play_states(rounds: usize) -> Vec<BattleState> {
+        let mut battle = Battle::with_boss(false, Boss::new(100, 1));
+        (0..rounds)
+            .map(|_| {
+                let state = battle.get_state();
+                battle.play_round(&Spell::MagicMissile).unwrap();
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_latest_rounds_once_full() {
+        let states = play_states(5);
+        let mut history = BattleHistory::new(3);
+        for state in &states {
+            history.push(*state);
+        }
+        assert_eq!(history.get_rounds(), 5);
+        assert_eq!(history.get_first_round(), Some(3));
+        assert_eq!(history.state_at_round(2), None);
+        assert_eq!(history.state_at_round(3), Some(states[2]));
+        assert_eq!(history.state_at_round(5), Some(states[4]));
+        assert_eq!(history.state_at_round(6), None);
+        let rounds: Vec<usize> = history.iter().map(|(round, _)| round).collect();
+        assert_eq!(rounds, [3, 4, 5]);
+    }
+
+    #[test]
+    fn keeps_at_least_one_round() {
+        let mut history = BattleHistory::new(0);
+        assert_eq!(history.get_capacity(), 1);
+        assert_eq!(history.get_first_round(), None);
+        for state in play_states(2) {
+            history.push(state);
+        }
+        assert_eq!(history.get_first_round(), Some(2));
+    }
+
+    #[test]
+    fn battle_records_the_start_of_each_round() {
+        let mut battle = Battle::with_boss(false, Boss::new(100, 1)).with_history(10);
+        battle.wizard_turn_apply_effects();
+        let mut states = Vec::new();
+        for _ in 0..3 {
+            states.push(battle.get_state());
+            battle.play_round(&Spell::MagicMissile).unwrap();
+        }
+        for (round, state) in states.iter().enumerate() {
+            assert_eq!(battle.state_at_round(round + 1), Some(*state));
+        }
+        assert!(battle.for_search().history().is_none());
+    }
+}
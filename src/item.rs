@@ -0,0 +1,188 @@
+use crate::Spell;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Where an item is worn. The wizard wears one item per slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemSlot {
+    Ring,
+    Body,
+    Neck,
+}
+
+impl ItemSlot {
+    pub const ALL: [ItemSlot; 3] = [Self::Ring, Self::Body, Self::Neck];
+
+    pub fn get_display_name(self) -> &'static str {
+        match self {
+            Self::Ring => "Ring",
+            Self::Body => "Body",
+            Self::Neck => "Neck",
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::Ring => 0,
+            Self::Body => 1,
+            Self::Neck => 2,
+        }
+    }
+}
+
+/// Changes to the wizard's stats, applied by the engine wherever the stat is used. Every
+/// hook leaves the stat as it is by default, so a modifier only overrides what it changes,
+/// and modifiers compose by passing a stat through each in turn.
+pub trait StatModifier {
+    /// Mana the wizard gains at the start of each turn, on top of Recharge
+    fn modify_turn_mana(&self, mana: i32) -> i32 {
+        mana
+    }
+
+    fn modify_armor(&self, armor: i32) -> i32 {
+        armor
+    }
+
+    /// Turns the effect of `spell` lasts when cast
+    fn modify_duration(&self, _spell: &Spell, duration: i32) -> i32 {
+        duration
+    }
+}
+
+/// Passive item the wizard wears into battle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Item {
+    /// 1 mana at the start of each turn
+    RingOfMana,
+    /// 1 armor at all times
+    Robe,
+    /// Poison lasts 7 turns
+    Amulet,
+}
+
+impl Item {
+    pub const ALL: [Item; 3] = [Self::RingOfMana, Self::Robe, Self::Amulet];
+
+    pub fn get_slot(self) -> ItemSlot {
+        match self {
+            Self::RingOfMana => ItemSlot::Ring,
+            Self::Robe => ItemSlot::Body,
+            Self::Amulet => ItemSlot::Neck,
+        }
+    }
+
+    /// Name of the item in challenge and campaign files
+    pub fn get_key(self) -> &'static str {
+        match self {
+            Self::RingOfMana => "ring_of_mana",
+            Self::Robe => "robe",
+            Self::Amulet => "amulet",
+        }
+    }
+
+    pub fn get_display_name(self) -> &'static str {
+        match self {
+            Self::RingOfMana => "Ring of Mana",
+            Self::Robe => "Robe",
+            Self::Amulet => "Amulet",
+        }
+    }
+
+    pub fn get_description(self) -> &'static str {
+        match self {
+            Self::RingOfMana => "+1 mana each turn",
+            Self::Robe => "+1 armor",
+            Self::Amulet => "Poison lasts 7 turns",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|item| item.get_key() == key)
+    }
+}
+
+impl StatModifier for Item {
+    fn modify_turn_mana(&self, mana: i32) -> i32 {
+        match self {
+            Self::RingOfMana => mana + 1,
+            _ => mana,
+        }
+    }
+
+    fn modify_armor(&self, armor: i32) -> i32 {
+        match self {
+            Self::Robe => armor + 1,
+            _ => armor,
+        }
+    }
+
+    fn modify_duration(&self, spell: &Spell, duration: i32) -> i32 {
+        match (self, spell) {
+            (Self::Amulet, Spell::Poison) => 7,
+            _ => duration,
+        }
+    }
+}
+
+/// Items the wizard wears, one per slot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Equipment {
+    /// Item in each slot, in the order of `ItemSlot::ALL`
+    slots: [Option<Item>; 3],
+}
+
+impl Equipment {
+    /// Wear `item`, returning the item it replaces in its slot
+    pub fn equip(&mut self, item: Item) -> Option<Item> {
+        self.slots[item.get_slot().index()].replace(item)
+    }
+
+    pub fn get(&self, slot: ItemSlot) -> Option<Item> {
+        self.slots[slot.index()]
+    }
+
+    pub fn get_items(&self) -> impl Iterator<Item = Item> + '_ {
+        self.slots.iter().flatten().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.get_items().next().is_none()
+    }
+
+    /// Parse item names separated by commas, later items replacing earlier ones in the
+    /// same slot
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut equipment = Self::default();
+        for key in text.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+            let item = Item::from_key(key).ok_or_else(|| format!("unknown item `{}`", key))?;
+            equipment.equip(item);
+        }
+        Ok(equipment)
+    }
+
+    /// Item names separated by commas, as read by `parse`
+    pub fn to_keys(&self) -> String {
+        let keys: Vec<&str> = self.get_items().map(Item::get_key).collect();
+        keys.join(", ")
+    }
+}
+
+impl StatModifier for Equipment {
+    fn modify_turn_mana(&self, mana: i32) -> i32 {
+        self.get_items()
+            .fold(mana, |mana, item| item.modify_turn_mana(mana))
+    }
+
+    fn modify_armor(&self, armor: i32) -> i32 {
+        self.get_items()
+            .fold(armor, |armor, item| item.modify_armor(armor))
+    }
+
+    fn modify_duration(&self, spell: &Spell, duration: i32) -> i32 {
+        self.get_items().fold(duration, |duration, item| {
+            item.modify_duration(spell, duration)
+        })
+    }
+}
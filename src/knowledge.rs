@@ -0,0 +1,105 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Width of the ranges the boss's hitpoints are shown in while they aren't known
+pub const HITPOINT_RANGE: i32 = 10;
+
+/// Which of the boss's stats the wizard knows exactly. The engine plays by the real stats
+/// either way; the mask only limits what UIs and bots are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KnowledgeMask {
+    pub hitpoints: bool,
+    pub damage: bool,
+}
+
+impl KnowledgeMask {
+    /// Everything is known, as in classic play
+    pub const FULL: Self = Self {
+        hitpoints: true,
+        damage: true,
+    };
+    /// Nothing is known, as at the start of a battle with scouting
+    pub const NONE: Self = Self {
+        hitpoints: false,
+        damage: false,
+    };
+
+    /// Whether every stat is known, which anything playing the battle out ahead needs
+    pub fn is_full(self) -> bool {
+        self == Self::FULL
+    }
+
+    /// What is known from either mask
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            hitpoints: self.hitpoints || other.hitpoints,
+            damage: self.damage || other.damage,
+        }
+    }
+}
+
+/// A stat of the boss as far as the wizard knows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Known {
+    Exact(i32),
+    /// Somewhere between the two, inclusive
+    Range(i32, i32),
+    Unknown,
+}
+
+impl Known {
+    /// Hitpoints as the wizard sees them: exact if known, otherwise the range of
+    /// `HITPOINT_RANGE` they fall in
+    pub fn hitpoints(hitpoints: i32, known: bool) -> Self {
+        if known || hitpoints <= 0 {
+            return Self::Exact(hitpoints);
+        }
+        let low = (hitpoints - 1) / HITPOINT_RANGE * HITPOINT_RANGE + 1;
+        Self::Range(low, low + HITPOINT_RANGE - 1)
+    }
+
+    pub fn exact_or_unknown(value: i32, known: bool) -> Self {
+        if known {
+            Self::Exact(value)
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// The exact value, if known
+    pub fn get_exact(self) -> Option<i32> {
+        match self {
+            Self::Exact(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Highest the value can be, if anything is known
+    pub fn get_upper_bound(self) -> Option<i32> {
+        match self {
+            Self::Exact(value) | Self::Range(_, value) => Some(value),
+            Self::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for Known {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exact(value) => write!(f, "{}", value),
+            Self::Range(low, high) => write!(f, "{}-{}", low, high),
+            Self::Unknown => write!(f, "???"),
+        }
+    }
+}
+
+/// The boss as the wizard sees it, see `Battle::get_boss_view`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BossView {
+    pub hitpoints: Known,
+    pub damage: Known,
+    /// Turns of poison left, which the wizard always knows
+    pub poisoned: Option<i32>,
+}
@@ -1,2 +1,44 @@
+pub mod ability;
+pub mod archive;
+#[cfg(feature = "tokio")]
+pub mod async_session;
+pub mod balance;
+pub mod bestiary;
+pub mod book;
+pub mod boss_ai;
+pub mod boss_rush;
+pub mod campaign;
+pub mod challenge;
+pub mod duel;
+pub mod encounter;
+pub mod engine;
+pub use engine::*;
+pub mod file_format;
+pub mod forecast;
+pub mod history;
+pub mod item;
+pub mod knowledge;
+pub mod loadout;
+pub mod notation;
+pub mod parallel;
+pub mod playout;
+pub mod preset;
+pub mod rating;
+#[cfg(feature = "results-db")]
+pub mod results;
+pub mod review;
+pub mod rng;
 mod rpg;
-pub use rpg::*;
+pub mod save;
+pub mod session;
+pub mod share;
+pub mod shop;
+pub mod solver;
+mod spellbook;
+pub mod stats;
+pub mod strategy;
+pub mod survival;
+pub mod taunt;
+mod toml;
+pub mod tournament;
+mod trace;
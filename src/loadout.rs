@@ -0,0 +1,117 @@
+use crate::{
+    toml::{ParseError, Table, Value},
+    Spell, Spellbook,
+};
+
+/// Spells the wizard takes into a battle with a loadout
+pub const LOADOUT_SPELLS: usize = 4;
+
+/// Bonus the wizard picks as part of a loadout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassiveTrait {
+    /// 5 more hitpoints at the start of the battle
+    Vigor,
+    /// Every spell costs 10% less mana, rounded down
+    Thrift,
+}
+
+impl PassiveTrait {
+    pub const ALL: [PassiveTrait; 2] = [Self::Vigor, Self::Thrift];
+
+    /// Name of the trait in a `[[loadout]]` table
+    pub fn get_key(self) -> &'static str {
+        match self {
+            Self::Vigor => "vigor",
+            Self::Thrift => "thrift",
+        }
+    }
+
+    pub fn get_display_name(self) -> &'static str {
+        match self {
+            Self::Vigor => "Vigor",
+            Self::Thrift => "Thrift",
+        }
+    }
+
+    pub fn get_description(self) -> &'static str {
+        match self {
+            Self::Vigor => "+5 HP",
+            Self::Thrift => "-10% mana costs",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|passive| passive.get_key() == key)
+    }
+}
+
+/// Spells and trait the wizard picked before a battle, stored in challenge and save files
+/// as a `[[loadout]]` table with the spell names separated by commas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loadout {
+    /// Spells that can be cast, the rest are sealed
+    pub spells: Vec<Spell>,
+    pub passive: PassiveTrait,
+}
+
+impl Loadout {
+    /// Seal the spells left out and apply the trait's costs to `spellbook`
+    pub fn apply_to_spellbook(&self, spellbook: &mut Spellbook) {
+        for spell in Spell::ALL.iter() {
+            if !self.spells.contains(spell) {
+                spellbook.seal(spell);
+            }
+            if self.passive == PassiveTrait::Thrift {
+                let stats = spellbook.get_mut(spell);
+                stats.mana = (stats.mana * 9 / 10).max(1);
+            }
+        }
+    }
+
+    /// Extra hitpoints the wizard starts with
+    pub fn get_hitpoint_bonus(&self) -> i32 {
+        match self.passive {
+            PassiveTrait::Vigor => 5,
+            PassiveTrait::Thrift => 0,
+        }
+    }
+
+    pub(crate) fn from_table(table: &Table) -> Result<Self, ParseError> {
+        let spells = table
+            .get_string("spells")?
+            .ok_or_else(|| table.error("loadout is missing `spells`".to_string()))?
+            .split(',')
+            .map(|name| {
+                name.trim()
+                    .parse::<Spell>()
+                    .map_err(|err| table.error(err.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if spells.is_empty() || spells.len() > LOADOUT_SPELLS {
+            return Err(table.error(format!(
+                "loadout must have between 1 and {} spells",
+                LOADOUT_SPELLS
+            )));
+        }
+        let key = table
+            .get_string("trait")?
+            .ok_or_else(|| table.error("loadout is missing `trait`".to_string()))?;
+        let passive = PassiveTrait::from_key(&key)
+            .ok_or_else(|| table.error(format!("unknown trait `{}`", key)))?;
+        Ok(Self { spells, passive })
+    }
+
+    pub(crate) fn to_table(&self) -> Table {
+        let names: Vec<&str> = self
+            .spells
+            .iter()
+            .map(|spell| spell.get_display_name())
+            .collect();
+        let mut table = Table::new("loadout");
+        table.push("spells", Value::String(names.join(", ")));
+        table.push("trait", Value::String(self.passive.get_key().to_string()));
+        table
+    }
+}
@@ -0,0 +1,74 @@
+//! `--log-level` flag: write log records, in the spans of the round and phase they happen
+//! in, to a file that is rotated daily.
+
+use crate::data_dir;
+use clap::{arg, value_parser, Arg, ArgMatches};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+
+/// Log files kept, the current day's and the previous ones
+const LOG_FILES_KEPT: usize = 4;
+
+/// Arguments choosing what to log and where
+pub fn args() -> [Arg; 2] {
+    [
+        arg!(--"log-level" <LEVEL> "Log records at this level and above")
+            .value_parser(["off", "error", "warn", "info", "debug", "trace"])
+            .default_value("off")
+            .global(true),
+        arg!(--"log-file" <FILE> "File to log to, with the day added to its name [default: ~/.local/state/advent-wizard-rpg/local/advent-wizard-rpg.log]")
+            .value_parser(value_parser!(PathBuf))
+            .global(true),
+    ]
+}
+
+/// Start logging as given by `args`. Exits if the log file can't be opened.
+pub fn init_from_matches(matches: &ArgMatches) {
+    let level: LevelFilter = matches
+        .get_one::<String>("log-level")
+        .unwrap()
+        .parse()
+        .unwrap();
+    if level == LevelFilter::OFF {
+        return;
+    }
+    let path = matches
+        .get_one::<PathBuf>("log-file")
+        .cloned()
+        .unwrap_or_else(|| data_dir::local_dir().join("advent-wizard-rpg.log"));
+    if let Err(err) = init(&path, level) {
+        eprintln!("Could not log to {}: {}", path.display(), err);
+        process::exit(1);
+    }
+}
+
+/// Log records at `level` and above to `path`, with the day added to the file's name,
+/// such as `advent-wizard-rpg.2024-12-22.log`
+pub fn init(path: &Path, level: LevelFilter) -> io::Result<()> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let mut builder = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .max_log_files(LOG_FILES_KEPT);
+    if let Some(prefix) = path.file_stem() {
+        builder = builder.filename_prefix(prefix.to_string_lossy());
+    }
+    if let Some(suffix) = path.extension() {
+        builder = builder.filename_suffix(suffix.to_string_lossy());
+    }
+    let appender = builder.build(dir).map_err(io::Error::other)?;
+    tracing_subscriber::fmt()
+        .with_writer(appender)
+        .with_ansi(false)
+        .with_max_level(level)
+        .try_init()
+        .map_err(io::Error::other)
+}
@@ -1,211 +1,1051 @@
+mod action;
+mod ambiance;
+mod analyze;
+mod aoc;
+mod audit;
+mod autosave;
+mod battle_form;
+mod book_builder;
+mod boss_editor;
+mod cli;
+mod completions;
+mod crash_report;
+mod data_dir;
+mod endless;
+mod event_log;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+#[cfg(feature = "logging")]
+mod logger;
+mod manpage;
+mod mirror;
+mod presets;
+mod profile;
+mod ranking;
+mod report;
+mod rush;
+#[cfg(feature = "sandbox")]
+mod sandbox;
+mod save_slots;
+mod scheduler;
+mod session_stats;
+mod simulate;
+mod solve;
+mod spectate;
+mod spellbook_editor;
+mod stopwatch;
+#[cfg(feature = "results-db")]
+mod tally;
 mod ui;
-
-use advent_wizard_rpg::{Battle, Spell};
-use clap::{arg, command};
+mod webhook;
+
+use action::Action;
+use advent_wizard_rpg::{
+    bestiary::Bestiary,
+    book::{BookKey, BookMove, OpeningBook, BOOK_SOLVE_OPTIONS},
+    boss_ai::BossAiMode,
+    boss_rush::format_run_time,
+    campaign::Campaign,
+    challenge::{Challenge, CHALLENGE_FILE_SUFFIX},
+    encounter::{generate_encounter, DifficultyTier},
+    forecast::forecast_battle,
+    knowledge::{KnowledgeMask, Known},
+    notation::{format_line, format_spell, parse_line},
+    save::SaveGame,
+    share::BattleSummary,
+    shop::{describe_intel, get_victory_gold, CampaignProgress, ShopOffer},
+    solver::{evaluate_position, solve_cheapest_win, Evaluation, SearchBudget},
+    stats::{BattleStats, DamageBreakdown, RoundSample},
+    taunt::{TauntTrigger, Taunts},
+    Battle, BattleEvent, BattleState, DamageSource, Spell, SpellAvailability, Spellbook,
+    WastedCast, ACTION_POINTS_PER_TURN, BLOOD_MAGIC_MANA_PER_HITPOINT,
+};
+use autosave::Autosaver;
+use clap::{arg, command, value_parser, ArgMatches, Command};
+use event_log::{EventLog, EventPhase};
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
-    layout::Alignment,
-    prelude::{Constraint, Layout, Margin},
+    crossterm::event::{self, Event},
+    layout::{Alignment, Flex, Rect},
+    prelude::{Constraint, Direction, Layout, Margin},
     style::{Color, Style, Stylize},
     symbols::scrollbar,
     text::Line,
-    widgets::{Block, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+    widgets::{
+        BarChart, Block, Cell, Clear, Paragraph, Row, Scrollbar, ScrollbarOrientation, Sparkline,
+        Table, Wrap,
+    },
     Frame,
 };
+use scheduler::{Phase, PhaseScheduler};
 use std::{
-    io,
-    time::{Duration, Instant},
+    collections::VecDeque,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use ui::{tui, CenterPosition};
+use stopwatch::Stopwatch;
+use ui::{theme, tui, Badge, CenterPosition, Meter};
+
+/// Animation speed of text in the event window
+const EVENT_WINDOW_TEXT_CHARS_PER_SECOND: f64 = 40.0;
+/// How long the blunder check may search after each round
+const BLUNDER_CHECK_TIME_LIMIT: Duration = Duration::from_millis(200);
+/// How long to wait for input when nothing is animating
+const IDLE_POLL_TIMEOUT: Duration = Duration::from_secs(1);
+/// Time between redraws of the run timer
+const RUN_TIMER_REFRESH: Duration = Duration::from_millis(100);
+/// A boss attack taking at least 1 in this many of the wizard's hitpoints is a big hit
+const BIG_HIT_SHARE: i32 = 4;
+/// Rounds projected by the battle forecast
+const FORECAST_ROUNDS: usize = 6;
+/// Entries of the menu, with the actions they take
+const MENU: [(&str, Action); 7] = [
+    ("Forecast", Action::ToggleForecast),
+    ("Hint", Action::Hint),
+    ("Pause", Action::TogglePause),
+    ("Save", Action::SaveToSlot),
+    ("Screenshot", Action::Screenshot),
+    ("Copy result", Action::CopyResult),
+    ("Quit", Action::Quit),
+];
+/// Inner height a side panel needs to fit a sprite below its text
+const SPRITE_MIN_PANEL_HEIGHT: u16 = 24;
+/// Spells with a button, in reading order, the extra spells last
+const SPELL_BUTTONS: [Spell; 8] = [
+    Spell::MagicMissile,
+    Spell::Drain,
+    Spell::Poison,
+    Spell::Shield,
+    Spell::Recharge,
+    Spell::Cleanse,
+    Spell::Thorns,
+    Spell::Summon,
+];
+
+/// Why the player is asked to confirm a cast
+#[derive(Debug)]
+enum CastWarning {
+    Wasted(WastedCast),
+    /// The wizard doesn't survive the boss's turn
+    Fatal,
+    /// The wizard is short of mana and pays the hitpoints for the rest under blood magic
+    BloodPrice(i32),
+}
 
 #[derive(Debug)]
-struct App<'a> {
+struct App {
     exit: bool,
     game: Battle,
+    /// Name of the boss, shown when playing a challenge
+    boss_name: Option<String>,
+    /// Writes the battle after every round, if autosaving is enabled
+    autosaver: Option<Autosaver>,
+    /// Reads the rounds of a battle played elsewhere, when watching it instead of playing
+    follower: Option<spectate::SaveFollower>,
+    /// Per-round samples for the stats pane
+    stats: BattleStats,
+    /// Time the player has spent deciding a spell this turn, None while a round is being
+    /// played
+    turn_timer: Option<Stopwatch>,
+    /// Time the battle has been played, for the session's stats
+    battle_timer: Stopwatch,
+    /// Whether the terminal has lost focus, pausing the timers, animations and phases
+    unfocused: bool,
+    /// Whether losing focus paused the phases, so they are resumed when it's regained
+    unfocused_paused_phases: bool,
+    /// Runs the phases of each round, with a delay between phases
+    scheduler: PhaseScheduler,
+    /// Wasteful cast waiting for the player to confirm
+    pending_confirmation: Option<(Spell, CastWarning)>,
+    /// Whether to ask before a cast that leaves the wizard dead to the boss's turn
+    insurance: bool,
+    /// Search limits for checking whether the position is lost after each round,
+    /// None if the check is disabled
+    blunder_check: Option<SearchBudget>,
+    /// Result of the last blunder check
+    position_evaluation: Evaluation,
+    /// Whether the battle forecast is shown
+    show_forecast: bool,
+    /// Index of the selected entry of the open menu, None if it's closed
+    menu: Option<usize>,
+    /// Boss of the battle in the opening book, None if the book can't cover the battle
+    book_key: Option<BookKey>,
+    /// Opening book hints consult, loaded on the first hint
+    book: Option<OpeningBook>,
+    /// Webhook the result is posted to once the battle is over, None if it isn't posted
+    webhook: Option<String>,
+    /// Played from the start of the battle to its end, None if there is none
+    ambiance: Option<ambiance::Ambiance>,
+    /// Whether the battle has been added to the session's stats, or is left out of them
+    session_recorded: bool,
+    /// Game controllers the battle can be played with, None if they aren't supported
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<gamepad::Gamepad>,
+    /// Whether the sandbox console may be opened
+    #[cfg(feature = "sandbox")]
+    sandbox: bool,
+    /// Sandbox command being typed, if the console is open
+    #[cfg(feature = "sandbox")]
+    console: Option<ui::TextInput>,
+    /// Spells to cast in the next rounds, first next
+    plan: VecDeque<Spell>,
+    /// Whether casting adds to the plan instead, which also stops the plan being cast
+    planning: bool,
+    /// Whether to save the next frame drawn as a screenshot
+    screenshot_requested: bool,
+    /// Saves the battle to a slot the player picks, None if the battle can't be saved
+    slot_saver: Option<save_slots::SlotSaver>,
+    /// Whether to save the battle to a slot after the next frame, which previews it
+    slot_save_requested: bool,
+    /// Whether the debug overlay is shown, None if debugging isn't enabled
+    debug_overlay: Option<bool>,
+    /// Where the wizard and boss sprites were last drawn, None if they weren't
+    sprite_areas: [Option<Rect>; 2],
+    /// Index of the selected button, in reading order
     spell_selected: usize,
-    event_window_scroll_state: ScrollbarState,
-    event_window_scroll: usize,
-    /// Track event window height so when event text is larger than this, scroll down
-    event_window_height: u16,
-    /// Event window text
-    event_window_text: Vec<Line<'a>>,
-    /// Which line should be animated next
-    event_window_text_index: Option<usize>,
-    /// Which char of the line should be animated next
-    event_window_text_char_index: usize,
+    /// How the full layout arranges the spell buttons
+    spell_arrangement: ui::SpellArrangement,
+    /// How the selection moves between the buttons, as last drawn
+    selection_geometry: ui::SelectionGeometry,
+    /// Keys casting the spells of the buttons, in reading order
+    spell_keys: Vec<char>,
+    /// Lines of the event window in view, scrolled down as new lines are typed out
+    event_window_scroll: ui::ScrollView,
+    /// Events shown in the event window
+    event_log: EventLog,
+    /// What the boss says, None if taunts are turned off
+    taunts: Option<Taunts>,
+    /// Boss hitpoints at the start of the battle
+    boss_max_hitpoints: i32,
+    /// Whether the boss has fallen below half of its hitpoints
+    boss_wounded: bool,
+    /// Whether to leave as soon as the wizard wins, to go on to the next battle
+    exit_on_win: bool,
+    /// Whether assists are refused, see `set_ironman`
+    ironman: bool,
+    /// Time of the timed run the battle is part of, shown in the title
+    run_timer: Option<Stopwatch>,
+    /// Time of the run when the battle ended
+    run_time: Option<Duration>,
+    /// How far the event lines have been typed out
+    event_window_typewriter: ui::Typewriter,
+    /// Whether the line being typed out is highlighted and kept in the middle of the event
+    /// window, with the lines before it dimmed
+    focus_follow: bool,
+    /// Time between rendered frames
+    frame_duration: Duration,
+    /// Animation of the spell cast this round, None once it has played
+    cast_animation: Option<ui::CastAnimation>,
+    /// Whether casts and idle sprites are animated
+    animate: bool,
+    /// Tick of the idle animation of the sprites
+    idle_tick: u64,
+    /// Time spent idle since the last idle tick
+    idle_elapsed: Duration,
+    /// Banner of the intro played when a new battle starts, None if it isn't played
+    intro_banner: Option<Vec<String>>,
+    /// Intro being played, None once it has finished or was skipped
+    intro: Option<ui::BattleIntro>,
+    /// Ending being played once the battle is over, None before and after
+    ending: Option<ui::EndingCinematic>,
+    /// Whether the ending has started, so it's only played once
+    ending_started: bool,
+    /// Screen shake and flashes being played
+    effects: ui::TransientEffects,
+    /// Recent changes of the stats in the side panels, fading out
+    stat_diffs: ui::StatDiffs,
+    /// State of the battle the last changes were found from
+    last_state: BattleState,
+    /// Whether the screen needs to be redrawn
+    dirty: bool,
 }
 
-impl<'a> App<'a> {
-    fn new(hard_mode: bool) -> Self {
+impl App {
+    fn new(
+        game: Battle,
+        boss_name: Option<String>,
+        fps: u32,
+        phase_delay: Duration,
+        blunder_check: Option<SearchBudget>,
+        autosaver: Option<Autosaver>,
+        debug: bool,
+    ) -> Self {
+        let boss_max_hitpoints = game.get_boss().get_max_hitpoints();
+        let last_state = game.get_state();
         Self {
             exit: false,
-            game: Battle::new(hard_mode),
+            game,
+            boss_name,
+            autosaver,
+            follower: None,
+            stats: BattleStats::default(),
+            turn_timer: None,
+            battle_timer: Stopwatch::start(),
+            unfocused: false,
+            unfocused_paused_phases: false,
+            scheduler: PhaseScheduler::new(phase_delay),
+            pending_confirmation: None,
+            insurance: true,
+            blunder_check,
+            position_evaluation: Evaluation::Unknown,
+            show_forecast: false,
+            menu: None,
+            book_key: None,
+            book: None,
+            webhook: None,
+            ambiance: None,
+            session_recorded: false,
+            plan: VecDeque::new(),
+            planning: false,
+            screenshot_requested: false,
+            slot_saver: None,
+            slot_save_requested: false,
+            debug_overlay: debug.then_some(false),
+            #[cfg(feature = "gamepad")]
+            gamepad: gamepad::Gamepad::open(),
+            #[cfg(feature = "sandbox")]
+            sandbox: false,
+            #[cfg(feature = "sandbox")]
+            console: None,
+            sprite_areas: [None; 2],
             spell_selected: 0,
-            event_window_scroll_state: ScrollbarState::default(),
-            event_window_scroll: usize::default(),
-            event_window_height: 2, // 2 lines are printed initially on hard mode
-            event_window_text: Vec::default(),
-            event_window_text_index: None,
-            event_window_text_char_index: 0,
+            spell_arrangement: ui::SpellArrangement::default(),
+            selection_geometry: ui::SelectionGeometry::bar(0),
+            spell_keys: action::DEFAULT_SPELL_KEYS.chars().collect(),
+            event_window_scroll: ui::ScrollView::new(0),
+            event_log: EventLog::default(),
+            taunts: None,
+            boss_max_hitpoints,
+            boss_wounded: false,
+            exit_on_win: false,
+            ironman: false,
+            run_timer: None,
+            run_time: None,
+            event_window_typewriter: ui::Typewriter::default(),
+            focus_follow: false,
+            frame_duration: Duration::from_secs(1) / fps,
+            cast_animation: None,
+            animate: true,
+            idle_tick: 0,
+            idle_elapsed: Duration::ZERO,
+            intro_banner: None,
+            intro: None,
+            ending: None,
+            ending_started: false,
+            effects: ui::TransientEffects::default(),
+            stat_diffs: ui::StatDiffs::default(),
+            last_state,
+            dirty: true,
         }
     }
 
-    /// runs the application's main loop until the user quits
-    fn run(&mut self, terminal: &mut tui::Tui) -> io::Result<()> {
-        let tick_rate = Duration::from_millis(50);
-        let mut last_tick = Instant::now();
+    /// runs the application's main loop until the user quits, after replaying the rounds
+    /// of `spells`
+    fn run(&mut self, terminal: &mut tui::Tui, spells: &[Spell]) -> io::Result<()> {
+        let mut last_frame = Instant::now();
+        self.battle_timer = Stopwatch::start();
 
         self.wizard_turn_apply_effects();
+        self.stats.record_round(&self.game);
+        crash_report::record_battle(&self.game);
+        for spell in spells {
+            for phase in PhaseScheduler::round_phases(spell.clone()) {
+                self.run_phase(phase, true);
+            }
+        }
+        // The replayed rounds and the first turn's effects aren't news to the player
+        self.last_state = self.game.get_state();
+        if spells.is_empty() {
+            self.taunt(TauntTrigger::BattleStart);
+            self.intro = self
+                .intro_banner
+                .take()
+                .map(|banner| ui::BattleIntro::new(banner, self.get_intro_stats()));
+        }
+        if let Some(ambiance) = &self.ambiance {
+            ambiance.start();
+        }
+        self.start_turn_timer();
+
+        // Sprites are shown as images where the terminal supports it
+        #[cfg(feature = "sprites")]
+        let mut sprite_images = tui::detect_graphics_protocol().map(|protocol| {
+            ui::SpriteImages::new(protocol, &[&ui::WIZARD_SPRITE, &ui::BOSS_SPRITE])
+        });
 
         while !self.exit {
-            terminal.draw(|frame| self.draw(frame))?;
+            // Only redraw when something visible has changed
+            if self.dirty {
+                let frame = terminal.draw(|frame| self.draw(frame))?;
+                let slot_preview = self
+                    .slot_save_requested
+                    .then(|| ui::buffer_to_text(frame.buffer));
+                if self.screenshot_requested {
+                    self.screenshot_requested = false;
+                    let line = match ui::save_screenshot(frame.buffer, Path::new(".")) {
+                        Ok(path) => format!("Screenshot saved to {}", path.display()),
+                        Err(err) => format!("Screenshot failed: {}", err),
+                    };
+                    self.output_notice(line);
+                    self.dirty = true;
+                } else {
+                    self.dirty = false;
+                }
+                #[cfg(feature = "sprites")]
+                if let Some(sprite_images) = &mut sprite_images {
+                    self.dirty = sprite_images.update(terminal, &self.sprite_areas)?;
+                }
+                if let Some(preview) = slot_preview {
+                    // The slot screen covers the sprites and every cell of the battle
+                    #[cfg(feature = "sprites")]
+                    if let Some(sprite_images) = &mut sprite_images {
+                        sprite_images.clear(terminal)?;
+                    }
+                    self.save_to_slot(terminal, &preview)?;
+                    terminal.clear()?;
+                    self.dirty = true;
+                }
+            }
 
-            // Poll for remaining time until next tick
-            let timeout = tick_rate.saturating_sub(last_tick.elapsed());
+            // Poll for remaining time until next frame, or wait for input when idle
+            let was_animating = self.is_animating();
+            let timeout = if was_animating {
+                self.frame_duration.saturating_sub(last_frame.elapsed())
+            } else {
+                IDLE_POLL_TIMEOUT
+            };
+            // Keep the run timer ticking
+            let timeout = if self.is_run_timer_ticking() && !self.unfocused {
+                self.dirty = true;
+                timeout.min(RUN_TIMER_REFRESH)
+            } else {
+                timeout
+            };
+            // Keep drawing the intro, cast animation and effects
+            let timeout = if self.intro.is_some()
+                || self.ending.is_some()
+                || self.cast_animation.is_some()
+                || !self.effects.is_empty()
+            {
+                timeout.min(self.frame_duration.saturating_sub(last_frame.elapsed()))
+            } else {
+                timeout
+            };
+            // Wake up in time for the next idle frame of the sprites
+            let idle = self.is_idle(was_animating);
+            let timeout = if idle {
+                timeout.min(ui::IDLE_TICK.saturating_sub(self.idle_elapsed))
+            } else {
+                timeout
+            };
+            // Nothing moves while the terminal is unfocused
+            let timeout = if self.unfocused {
+                IDLE_POLL_TIMEOUT
+            } else {
+                timeout
+            };
+            // Wake up in time for the next phase of the round
+            let timeout = self
+                .scheduler
+                .time_until_next_phase()
+                .map_or(timeout, |until_phase| timeout.min(until_phase));
+            // Wake up in time to read the rounds of a watched battle
+            let timeout = self.follower.as_ref().map_or(timeout, |follower| {
+                timeout.min(follower.time_until_next_read())
+            });
+            // Wake up in time to poll the game controllers
+            #[cfg(feature = "gamepad")]
+            let timeout = self
+                .gamepad
+                .as_ref()
+                .map_or(timeout, |gamepad| timeout.min(gamepad.get_poll_interval()));
             if event::poll(timeout)? {
-                if let Event::Key(key) = event::read()? {
-                    self.handle_key_event(key.code);
+                // Any input (including resizes) may change what is drawn
+                let event = event::read()?;
+                match event {
+                    Event::FocusLost => self.set_focused(false),
+                    Event::FocusGained => self.set_focused(true),
+                    _ => (),
                 }
+                if let Event::Key(key) = event {
+                    // The console takes all keys while it's open, once the intro and
+                    // ending are over
+                    #[cfg(feature = "sandbox")]
+                    let handled = self.intro.is_none()
+                        && self.ending.is_none()
+                        && self.handle_console_key(key.code);
+                    #[cfg(not(feature = "sandbox"))]
+                    let handled = false;
+                    if !handled {
+                        self.handle_input(Action::from_key(key.code, &self.spell_keys));
+                    }
+                }
+                self.dirty = true;
             }
-            // Update last tick
-            if last_tick.elapsed() >= tick_rate {
-                last_tick = Instant::now();
+            #[cfg(feature = "gamepad")]
+            if let Some(gamepad) = self.gamepad.as_mut() {
+                for action in gamepad.poll_actions() {
+                    // Buttons don't type in the console
+                    #[cfg(feature = "sandbox")]
+                    if self.console.is_some() {
+                        continue;
+                    }
+                    self.handle_input(Some(action));
+                    self.dirty = true;
+                }
+            }
+
+            // Animation progresses by elapsed time, independent of frame rate. Time spent
+            // idle doesn't count towards animating newly added text, and time spent unfocused
+            // doesn't count at all.
+            let now = Instant::now();
+            let frame_elapsed = if self.unfocused {
+                Duration::ZERO
+            } else {
+                now - last_frame
+            };
+            let elapsed = if was_animating {
+                frame_elapsed
+            } else {
+                Duration::ZERO
+            };
+            // The events are held back until the intro is over
+            if self.intro.is_none() && self.animate_event_window(elapsed) {
+                self.dirty = true;
+            }
+            if self.start_ending() {
+                self.dirty = true;
+            }
+            if let Some(ending) = &mut self.ending {
+                ending.advance(frame_elapsed);
+                if ending.is_finished() {
+                    self.ending = None;
+                }
+                self.dirty = true;
+            }
+            // The player's turn starts once the intro is over
+            if let Some(intro) = &mut self.intro {
+                intro.advance(frame_elapsed);
+                if intro.is_finished() {
+                    self.intro = None;
+                    self.start_turn_timer();
+                }
+                self.dirty = true;
+            }
+            // The cast animation holds still while the phases are paused
+            if let Some(animation) = &mut self.cast_animation {
+                if !self.scheduler.is_paused() {
+                    animation.advance(frame_elapsed);
+                }
+                if animation.is_finished() {
+                    self.cast_animation = None;
+                }
+                self.dirty = true;
+            }
+            if !self.effects.is_empty() {
+                self.effects.advance(frame_elapsed);
+                self.dirty = true;
+            }
+            let state = self.game.get_state();
+            let diff = self.last_state.diff(&state);
+            self.last_state = state;
+            if !diff.is_empty() {
+                self.stat_diffs.record(&diff);
+            }
+            if !self.stat_diffs.is_empty() {
+                self.stat_diffs.advance(frame_elapsed);
+                self.dirty = true;
+            }
+            // The sprites only idle while nothing else moves on the screen
+            if idle {
+                self.idle_elapsed += frame_elapsed;
+                while self.idle_elapsed >= ui::IDLE_TICK {
+                    self.idle_elapsed -= ui::IDLE_TICK;
+                    self.idle_tick += 1;
+                    self.dirty = true;
+                }
+            } else if self.idle_tick != 0 || !self.idle_elapsed.is_zero() {
+                self.idle_tick = 0;
+                self.idle_elapsed = Duration::ZERO;
+                self.dirty = true;
+            }
+            last_frame = now;
+
+            if self.run_due_phases() {
+                self.dirty = true;
+            }
+            if self.advance_plan() {
+                self.dirty = true;
+            }
+            if self.follow_rounds() {
+                self.dirty = true;
+            }
+            if self.is_run_timer_ticking() && self.game.get_outcome().is_some() {
+                self.run_time = self.get_run_time();
+            }
+            if self.exit_on_win && self.game.get_outcome() == Some(true) && !self.is_animating() {
+                self.exit = true;
+            }
+            if let Some(summary) = self.get_summary() {
+                if let Some(url) = self.webhook.take() {
+                    webhook::post_result(&url, &summary);
+                }
+                if !self.session_recorded {
+                    session_stats::record_battle(
+                        summary.won,
+                        summary.mana,
+                        self.battle_timer.elapsed(),
+                    );
+                    self.session_recorded = true;
+                }
             }
         }
+        #[cfg(feature = "sprites")]
+        if let Some(sprite_images) = &mut sprite_images {
+            sprite_images.clear(terminal)?;
+        }
+        ambiance::stop();
+        if let Some(autosaver) = &self.autosaver {
+            autosaver.mark_clean_exit()?;
+        }
         Ok(())
     }
 
-    fn handle_key_event(&mut self, key: KeyCode) {
+    /// Handle the action of a key or button: any input skips the intro or the ending while
+    /// one plays, otherwise the action is taken
+    fn handle_input(&mut self, action: Option<Action>) {
+        if self.intro.take().is_some() {
+            self.start_turn_timer();
+        } else if self.ending.take().is_none() {
+            self.handle_action(action);
+        }
+    }
+
+    fn handle_action(&mut self, action: Option<Action>) {
+        if let Some((spell, _reason)) = self.pending_confirmation.take() {
+            // Confirm or cancel a wasteful cast
+            if let Some(Action::Confirm | Action::Cast) = action {
+                self.schedule_cast(spell);
+            }
+            return;
+        }
+
+        if let Some(selected) = self.menu {
+            self.handle_menu_action(selected, action);
+            return;
+        }
+
+        if self.follower.is_some() && action.is_some_and(Action::changes_battle) {
+            self.output_notice("Watching: the battle is played elsewhere".to_string());
+            return;
+        }
+
+        if self.ironman && action.is_some_and(Action::is_assist) {
+            self.output_notice("Ironman: assists are disabled".to_string());
+            return;
+        }
+
+        match action {
+            Some(Action::Quit) => self.exit = true,
+            Some(Action::ScrollEventsDown) => self.event_window_scroll.scroll_down(),
+            Some(Action::ScrollEventsUp) => self.event_window_scroll.scroll_up(),
+            Some(Action::PreviousRound) => {
+                if let Some(start) = self
+                    .event_log
+                    .get_previous_round_start(self.event_window_scroll.get_offset())
+                {
+                    self.event_window_scroll.scroll_to(start);
+                }
+            }
+            Some(Action::NextRound) => {
+                // Only rounds whose text has been animated
+                let start = self
+                    .event_log
+                    .get_next_round_start(self.event_window_scroll.get_offset())
+                    .filter(|start| *start < self.event_window_typewriter.get_line_index());
+                if let Some(start) = start {
+                    self.event_window_scroll.scroll_to(start);
+                }
+            }
+            Some(Action::SelectUp) => self.select_spell(ui::SelectionStep::Up),
+            Some(Action::SelectLeft) => self.select_spell(ui::SelectionStep::Left),
+            Some(Action::SelectDown) => self.select_spell(ui::SelectionStep::Down),
+            Some(Action::SelectRight) => self.select_spell(ui::SelectionStep::Right),
+            Some(Action::Cast) => self.cast_selected_spell(),
+            Some(Action::RepeatSpell) => {
+                let button = self
+                    .game
+                    .get_last_spell_cast()
+                    .and_then(Self::get_spell_button);
+                if let Some(index) = button {
+                    self.spell_selected = index;
+                    self.cast_selected_spell();
+                }
+            }
+            Some(Action::CastSpell(index)) => {
+                if index < self.get_spells_shown().len() {
+                    self.spell_selected = index;
+                    self.cast_selected_spell();
+                }
+            }
+            Some(Action::EndTurn) => self.end_turn(),
+            Some(Action::TogglePlanning) => self.planning = !self.planning,
+            Some(Action::Unplan) => {
+                if self.planning {
+                    self.plan.pop_back();
+                }
+            }
+            Some(Action::ToggleForecast) => self.show_forecast = !self.show_forecast,
+            Some(Action::Hint) => self.show_hint(),
+            Some(Action::SpeedUp) => self.scheduler.speed_up(),
+            Some(Action::SlowDown) => self.scheduler.slow_down(),
+            Some(Action::TogglePause) => self.scheduler.toggle_pause(),
+            Some(Action::Screenshot) => self.screenshot_requested = true,
+            Some(Action::SaveToSlot) => self.request_slot_save(),
+            Some(Action::CopyResult) => self.copy_summary(BattleSummary::to_share_string),
+            Some(Action::CopyReport) => self.copy_summary(BattleSummary::to_markdown),
+            Some(Action::OpenConsole) => self.open_console(),
+            Some(Action::ToggleDebug) => {
+                if let Some(shown) = &mut self.debug_overlay {
+                    *shown = !*shown;
+                }
+            }
+            Some(Action::StepPhase) => {
+                if self.debug_overlay.is_some() {
+                    if let Some(phase) = self.scheduler.step() {
+                        self.run_phase(phase, false);
+                    }
+                }
+            }
+            Some(Action::ToggleMenu) => self.menu = Some(0),
+            Some(Action::Confirm) | None => (),
+        }
+    }
+
+    /// Move through the open menu with the selection actions, taking the selected entry's
+    /// action on Cast. Any other action closes the menu.
+    fn handle_menu_action(&mut self, selected: usize, action: Option<Action>) {
+        match action {
+            Some(Action::SelectUp) => self.menu = Some(selected.saturating_sub(1)),
+            Some(Action::SelectDown) => self.menu = Some((selected + 1).min(MENU.len() - 1)),
+            Some(Action::Cast | Action::Confirm) => {
+                self.menu = None;
+                self.handle_action(Some(MENU[selected].1));
+            }
+            Some(Action::SelectLeft | Action::SelectRight) | None => (),
+            Some(_) => self.menu = None,
+        }
+    }
+
+    /// Output the first spell of the cheapest win from the position as a notice. While the
+    /// battle keeps to a line of the opening book, the book gives it without searching.
+    /// A boss missing from the book has its line searched and cached on the first hint.
+    /// There are no hints while any of the boss's stats are hidden.
+    fn show_hint(&mut self) {
+        if self.game.get_outcome().is_some() {
+            return;
+        }
+        if !self.game.get_knowledge().is_full() {
+            self.output_notice("Hint: scout the boss first".to_string());
+            return;
+        }
+        let book_move = match self.book_key {
+            Some(key) => {
+                let path = book_builder::default_path();
+                let book = self
+                    .book
+                    .get_or_insert_with(|| OpeningBook::load(&path).unwrap_or_default());
+                if !book.contains(&key) {
+                    book.add_line(key);
+                    if let Err(err) =
+                        fs::create_dir_all(data_dir::profile_dir()).and_then(|_| book.save(&path))
+                    {
+                        self.output_notice(format!("Opening book not saved: {}", err));
+                    }
+                }
+                self.book
+                    .as_ref()
+                    .unwrap()
+                    .get_move(&key, self.game.get_spells_used())
+            }
+            None => BookMove::Unknown,
+        };
+        let hint = match book_move {
+            BookMove::Best(spell) => format!("Hint: cast {} (opening book)", spell),
+            BookMove::NoWin => format!(
+                "Hint: no win within {} rounds",
+                BOOK_SOLVE_OPTIONS.max_depth
+            ),
+            BookMove::Unknown | BookMove::OutOfBook => {
+                match solve_cheapest_win(&self.game, BOOK_SOLVE_OPTIONS).solution {
+                    Some(solution) => match solution.spells.first() {
+                        Some(spell) => format!("Hint: cast {}", spell),
+                        None => return,
+                    },
+                    None => format!(
+                        "Hint: no win within {} rounds",
+                        BOOK_SOLVE_OPTIONS.max_depth
+                    ),
+                }
+            }
+        };
+        self.output_notice(hint);
+    }
+
+    /// Summary of the battle to share, None until it is over
+    fn get_summary(&self) -> Option<BattleSummary> {
+        let boss = self.boss_name.as_deref().unwrap_or("the boss");
+        BattleSummary::from_battle(boss, &self.game)
+    }
+
+    /// Copy the summary of the finished battle, written by `format`
+    fn copy_summary(&mut self, format: fn(&BattleSummary) -> String) {
+        let Some(summary) = self.get_summary() else {
+            self.output_notice("The result can be copied once the battle is over".to_string());
+            return;
+        };
+        let line = match ui::copy_to_clipboard(&format(&summary)) {
+            Ok(true) => "Copied to the clipboard".to_string(),
+            Ok(false) => "Clipboard support isn't built in, printing it on exit".to_string(),
+            Err(err) => format!("Copy failed: {}", err),
+        };
+        self.output_notice(line);
+    }
+
+    /// Open the sandbox console, if the sandbox is enabled
+    fn open_console(&mut self) {
+        #[cfg(feature = "sandbox")]
+        if self.sandbox {
+            let mut input = ui::TextInput::default();
+            input.set_focused(true);
+            self.console = Some(input);
+        }
+    }
+
+    /// Type into the console, running the command on Enter. Returns false if the console
+    /// isn't open.
+    #[cfg(feature = "sandbox")]
+    fn handle_console_key(&mut self, key: event::KeyCode) -> bool {
+        let Some(input) = &mut self.console else {
+            return false;
+        };
         match key {
-            // Quit
-            KeyCode::Char('q') | KeyCode::Esc => self.exit = true,
-            // Scroll event window
-            KeyCode::Char('j') | KeyCode::Down => self.event_window_scroll_down(),
-            KeyCode::Char('k') | KeyCode::Up => self.event_window_scroll_up(),
-            // Change spell selection
-            KeyCode::Char('w') => self.select_spell_up(),
-            KeyCode::Char('a') => self.select_spell_left(),
-            KeyCode::Char('s') => self.select_spell_down(),
-            KeyCode::Char('d') => self.select_spell_right(),
-            // Cast selected spell
-            KeyCode::Enter => self.step_game(),
-            _ => (),
+            event::KeyCode::Esc => self.console = None,
+            event::KeyCode::Enter => {
+                let command = input.get_value().to_string();
+                self.console = None;
+                match sandbox::run_command(&mut self.game, &command) {
+                    Ok(change) => self.output_notice(format!("Sandbox: {}", change)),
+                    Err(err) => self.output_notice(format!("Sandbox error: {}", err)),
+                }
+                crash_report::record_battle(&self.game);
+            }
+            key => {
+                input.handle_key_event(key);
+            }
         }
+        true
     }
 
-    fn event_window_scroll_down(&mut self) {
-        self.event_window_scroll = self.event_window_scroll.saturating_add(1);
-        self.event_window_scroll_state = self
-            .event_window_scroll_state
-            .position(self.event_window_scroll);
+    fn select_spell(&mut self, step: ui::SelectionStep) {
+        self.spell_selected = self.selection_geometry.step(self.spell_selected, step);
     }
 
-    fn event_window_scroll_up(&mut self) {
-        self.event_window_scroll = self.event_window_scroll.saturating_sub(1);
-        self.event_window_scroll_state = self
-            .event_window_scroll_state
-            .position(self.event_window_scroll);
+    /// Whether the spellbook has extra spells unsealed, which get rows of buttons
+    fn has_extra_spells(&self) -> bool {
+        Spell::ALL
+            .iter()
+            .any(|spell| spell.is_extra() && !self.game.get_spellbook().is_sealed(spell))
     }
 
-    fn select_spell_up(&mut self) {
-        if self.spell_selected != 0 && self.spell_selected != 1 {
-            self.spell_selected -= 2;
+    /// Play without assists: no hints, forecast, sandbox, blunder check or confirmation of
+    /// fatal casts. The stats are marked as ironman.
+    fn set_ironman(&mut self) {
+        self.ironman = true;
+        self.blunder_check = None;
+        self.insurance = false;
+        self.stats.set_ironman(true);
+    }
+
+    fn is_run_timer_ticking(&self) -> bool {
+        self.run_timer.is_some() && self.run_time.is_none()
+    }
+
+    /// Time of the timed run the battle is part of, stopped when the battle ended
+    fn get_run_time(&self) -> Option<Duration> {
+        self.run_time
+            .or_else(|| self.run_timer.map(|timer| timer.elapsed()))
+    }
+
+    /// Save the battle to a slot once the next frame is drawn, if it can be saved now
+    fn request_slot_save(&mut self) {
+        let reason = if self.slot_saver.is_none() {
+            Some("This battle can't be saved")
+        } else if self.game.get_outcome().is_some() {
+            Some("The battle is over")
+        } else if !self.scheduler.is_idle() {
+            Some("Wait for the round to end to save")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => self.output_notice(reason.to_string()),
+            None => self.slot_save_requested = true,
         }
     }
 
-    fn select_spell_left(&mut self) {
-        if self.spell_selected % 2 != 0 {
-            self.spell_selected -= 1;
+    /// Let the player pick a slot and save the battle to it, with `preview` of the screen
+    fn save_to_slot(&mut self, terminal: &mut tui::Tui, preview: &str) -> io::Result<()> {
+        self.slot_save_requested = false;
+        let Some(slot_saver) = &self.slot_saver else {
+            return Ok(());
+        };
+        let line = match slot_saver.save(terminal, &self.game, preview) {
+            Ok(Some(name)) => format!("Saved to slot {}", name),
+            Ok(None) => return Ok(()),
+            Err(err) => format!("Save failed: {}", err),
+        };
+        self.output_notice(line);
+        Ok(())
+    }
+
+    /// Start timing the player's decision of a spell, held while the terminal is unfocused
+    fn start_turn_timer(&mut self) {
+        let mut turn_timer = Stopwatch::start();
+        if self.unfocused {
+            turn_timer.pause();
         }
+        self.turn_timer = Some(turn_timer);
     }
 
-    fn select_spell_down(&mut self) {
-        if self.spell_selected != 4 && self.spell_selected != 5 {
-            self.spell_selected += 2;
+    /// Pause the timers, animations and phases while the terminal is unfocused, resuming
+    /// them once it's focused again. Phases the player paused stay paused.
+    fn set_focused(&mut self, focused: bool) {
+        if self.unfocused != focused {
+            return;
+        }
+        self.unfocused = !focused;
+        let timers = [
+            self.turn_timer.as_mut(),
+            self.run_timer.as_mut(),
+            Some(&mut self.battle_timer),
+        ];
+        for timer in timers.into_iter().flatten() {
+            if focused {
+                timer.resume();
+            } else {
+                timer.pause();
+            }
         }
+        if focused {
+            if self.unfocused_paused_phases {
+                self.scheduler.resume();
+                self.unfocused_paused_phases = false;
+            }
+        } else if !self.scheduler.is_paused() {
+            self.scheduler.pause();
+            self.unfocused_paused_phases = true;
+        }
+    }
+
+    fn is_animating(&self) -> bool {
+        self.event_window_typewriter
+            .is_typing(self.event_log.get_lines())
+    }
+
+    /// Crudely animate text: reveal event lines char by char according to elapsed time.
+    /// Returns whether anything more was revealed.
+    fn animate_event_window(&mut self, elapsed: Duration) -> bool {
+        let (lines_finished, changed) = self.event_window_typewriter.advance(
+            elapsed,
+            EVENT_WINDOW_TEXT_CHARS_PER_SECOND,
+            self.event_log.get_lines(),
+        );
+        let line_index = self.event_window_typewriter.get_line_index();
+        if self.focus_follow && self.is_animating() {
+            if changed {
+                self.event_window_scroll.center_on(line_index);
+            }
+        } else {
+            // Automatically scroll down as new lines are output
+            for line in line_index - lines_finished + 1..=line_index {
+                if line > self.event_window_scroll.get_height().saturating_sub(1) {
+                    self.event_window_scroll.scroll_down();
+                }
+            }
+        }
+        changed
     }
 
-    fn select_spell_right(&mut self) {
-        if self.spell_selected % 2 == 0 {
-            self.spell_selected += 1;
+    /// Event lines that have been animated so far. In focus-follow mode, the line being
+    /// animated is highlighted and the lines before it are dimmed.
+    fn visible_event_window_text(&self) -> Vec<Line<'static>> {
+        let mut lines = self
+            .event_window_typewriter
+            .get_typed_lines(self.event_log.get_lines());
+        if self.focus_follow && self.is_animating() {
+            let line_index = self.event_window_typewriter.get_line_index();
+            for (i, line) in lines.iter_mut().enumerate() {
+                let styled = std::mem::take(line);
+                *line = if i == line_index {
+                    styled.fg(theme::get().accent).bold()
+                } else {
+                    styled.dark_gray()
+                };
+            }
         }
+        lines
     }
 
     fn draw(&mut self, frame: &mut Frame) {
-        let area = frame.area();
-
-        // Partition UI chunks
-        let chunks = Layout::vertical([
-            Constraint::Min(1),
-            Constraint::Percentage(70),
-            Constraint::Percentage(10),
-            Constraint::Percentage(10),
-            Constraint::Percentage(10),
-        ])
-        .split(area);
+        let area = self.effects.shake(frame.area());
+
+        // The terminal's size picks the layout, which says where each part of the screen goes
+        let areas = ui::select_battle_layout(area, self.spell_arrangement)
+            .split(area, &self.get_spells_shown());
+        self.selection_geometry = areas.selection.clone();
 
         // Title
-        let title = Block::new()
+        let mut title = Block::new()
             .title_alignment(Alignment::Center)
             .title("Wizard RPG".bold());
-        frame.render_widget(title, chunks[0]);
+        if let Some(run_time) = self.get_run_time() {
+            title = title.title(
+                Line::from(format_run_time(run_time))
+                    .fg(theme::get().accent)
+                    .right_aligned(),
+            );
+        }
+        frame.render_widget(title, areas.title);
 
-        // Layout for 3 game screens
-        let game_windows = Layout::horizontal([
-            Constraint::Percentage(25),
-            Constraint::Percentage(50),
+        // Status strip: wizard bars, active effects, boss bar
+        let [wizard_bars_area, badges_area, boss_bar_area] = Layout::horizontal([
+            Constraint::Percentage(35),
+            Constraint::Min(0),
             Constraint::Percentage(25),
         ])
-        .split(chunks[1]);
-
-        // Crudely animate text
-        let mut should_scroll_down = false;
-        let event_window_text =
-            if !self.event_window_text.is_empty() && self.event_window_text_index.is_none() {
-                // Special case: when first line is outputted, set animation line index
-                self.event_window_text_index = Some(0);
-                Vec::default()
-            } else if let Some(line_index) = self.event_window_text_index.as_mut() {
-                // Usual case
-                if *line_index >= self.event_window_text.len() {
-                    // No new line
-                    self.event_window_text.clone()
-                } else {
-                    // There are new lines
-                    let mut existing_lines = self.event_window_text[0..*line_index].to_owned();
-                    let current_line = self.event_window_text[*line_index].to_string();
-                    if self.event_window_text_char_index < current_line.len() {
-                        // Output line 2 chars at a time (not safe for utf8)
-                        self.event_window_text_char_index =
-                            if self.event_window_text_char_index + 2 > current_line.len() {
-                                self.event_window_text_char_index + 1
-                            } else {
-                                self.event_window_text_char_index + 2
-                            };
-                        existing_lines.push(Line::from(
-                            current_line[0..self.event_window_text_char_index].to_string(),
-                        ));
-                        existing_lines
-                    } else {
-                        // Max char index reached, output whole line
-                        should_scroll_down = true;
-                        *line_index += 1; // Update to next line
-                        self.event_window_text_char_index = 0; // Wrap char index to 0
-                        existing_lines.push(Line::from(current_line));
-                        existing_lines
-                    }
-                }
-            } else {
-                // Initial case: no lines to output
-                Vec::default()
-            };
-
-        // Automatically scroll down as new line is outputted
-        if let Some(line_index) = self.event_window_text_index {
-            if should_scroll_down && (line_index > (self.event_window_height - 3) as usize) {
-                self.event_window_scroll_down();
-            }
+        .spacing(1)
+        .areas(areas.status);
+        let [wizard_hitpoints_meter, mana_meter, boss_hitpoints_meter] = self.status_meters();
+        let [wizard_hitpoints_area, mana_area] =
+            Layout::horizontal([Constraint::Percentage(50); 2])
+                .spacing(1)
+                .areas(wizard_bars_area);
+        frame.render_widget(&wizard_hitpoints_meter, wizard_hitpoints_area);
+        frame.render_widget(&mana_meter, mana_area);
+        ui::render_badges(&self.status_badges(), badges_area, frame.buffer_mut());
+        #[cfg(feature = "sandbox")]
+        if let Some(input) = &self.console {
+            let [prompt_area, input_area] =
+                Layout::horizontal([Constraint::Length(1), Constraint::Min(0)]).areas(badges_area);
+            frame.render_widget(Clear, badges_area);
+            frame.render_widget(Paragraph::new(":").fg(theme::get().playback), prompt_area);
+            frame.render_widget(input, input_area);
         }
+        frame.render_widget(&boss_hitpoints_meter, boss_bar_area);
+
+        let event_window_text = self.visible_event_window_text();
 
         // Middle game screen: scrollable text displaying game events
         let event_window = Paragraph::new(event_window_text)
@@ -217,89 +1057,695 @@ impl<'a> App<'a> {
                     .title("Events".bold())
                     .title_alignment(Alignment::Center),
             )
-            .scroll((self.event_window_scroll as u16, 0));
-        // Scrollbar state
-        self.event_window_scroll_state = self
-            .event_window_scroll_state
-            .content_length(self.event_window_text.len());
-        self.event_window_height = game_windows[1].height;
-        frame.render_widget(event_window, game_windows[1]); // Middle window
+            .scroll((self.event_window_scroll.get_offset() as u16, 0));
+        self.event_window_scroll
+            .set_content_length(self.event_log.get_lines().len());
+        self.event_window_scroll
+            .set_height(areas.events.height.saturating_sub(2) as usize);
+        frame.render_widget(event_window, areas.events); // Middle window
+        if let Some(animation) = &self.cast_animation {
+            let inner = areas.events.inner(Margin {
+                vertical: 1,
+                horizontal: 1,
+            });
+            if inner.height > 0 {
+                let line_area = Rect {
+                    y: inner.bottom() - 1,
+                    height: 1,
+                    ..inner
+                };
+                frame.render_widget(Clear, line_area);
+                frame.render_widget(
+                    Paragraph::new(animation.render_line(line_area.width as usize)),
+                    line_area,
+                );
+            }
+        }
         frame.render_stateful_widget(
             Scrollbar::new(ScrollbarOrientation::VerticalRight)
                 .symbols(scrollbar::VERTICAL)
                 .begin_symbol(None)
                 .track_symbol(None)
                 .end_symbol(None),
-            game_windows[1].inner(Margin {
+            areas.events.inner(Margin {
                 vertical: 1,
                 horizontal: 0,
             }),
-            &mut self.event_window_scroll_state,
+            self.event_window_scroll.get_scrollbar_state_mut(),
         );
 
+        // Below events: HP/mana graphs over rounds
+        if let Some(stats_area) = areas.stats {
+            self.draw_stats(frame, stats_area);
+        }
+
+        // Side panels: text displaying Wizard and Boss information
+        self.sprite_areas = match areas.panels {
+            Some(panels) => self.draw_side_panels(frame, panels),
+            None => [None; 2],
+        };
+
+        // Spell selection buttons
+        let selected = self.get_selected_spell();
+        for (spell, button_area) in &areas.spell_buttons {
+            let is_selected = *spell == selected;
+            let button = match areas.button_style {
+                ui::ButtonStyle::Full => {
+                    self.create_spell_select_button(spell.clone(), is_selected)
+                }
+                ui::ButtonStyle::Short | ui::ButtonStyle::Bare => self.create_short_spell_button(
+                    spell,
+                    is_selected,
+                    areas.button_style == ui::ButtonStyle::Short,
+                    button_area.width,
+                ),
+            };
+            frame.render_widget(button, *button_area);
+        }
+
+        // Game over screen once the final events have been animated and the ending played
+        let animation_done = !self.is_animating() && self.ending.is_none();
+        if let (Some(won), true) = (self.game.get_outcome(), animation_done) {
+            self.draw_game_over(frame, areas.battle, won);
+            // Popups cover the sprites
+            self.sprite_areas = [None; 2];
+        }
+
+        if let Some((spell, reason)) = &self.pending_confirmation {
+            Self::draw_confirmation(frame, areas.battle, &self.game, spell, reason);
+            self.sprite_areas = [None; 2];
+        } else if let Some(selected) = self.menu {
+            Self::draw_menu(frame, areas.battle, selected);
+            self.sprite_areas = [None; 2];
+        } else if self.debug_overlay == Some(true) {
+            self.draw_debug_overlay(frame, areas.overlays);
+        } else if self.show_forecast && self.game.get_outcome().is_none() {
+            self.draw_forecast(frame, areas.overlays);
+        }
+
+        if let Some(ending) = &self.ending {
+            ending.apply(area, frame.buffer_mut());
+        }
+
+        // The intro covers the battle until it's over
+        if let Some(intro) = &self.intro {
+            frame.render_widget(intro, areas.battle);
+            self.sprite_areas = [None; 2];
+        }
+    }
+
+    /// Draw the side panels of the wizard and the boss in `wizard_area` and `boss_area`.
+    /// Returns where their sprites were drawn.
+    fn draw_side_panels(
+        &self,
+        frame: &mut Frame,
+        [wizard_area, boss_area]: [Rect; 2],
+    ) -> [Option<Rect>; 2] {
         // Left game screen: text displaying Wizard information
-        let wizard_info = Paragraph::new(self.display_wizard_info())
-            .gray()
-            .alignment(Alignment::Left)
-            .wrap(Wrap::default())
-            .block(
-                Block::bordered()
-                    .light_blue()
-                    .title("Wizard".bold().gray())
-                    .title_alignment(Alignment::Center),
-            );
-        frame.render_widget(wizard_info, game_windows[0]);
+        let mut wizard_block = Block::bordered()
+            .border_style(Style::default().fg(theme::get().wizard))
+            .title("Wizard".bold().gray())
+            .title_alignment(Alignment::Center);
+        if self.game.get_outcome().is_none() && self.position_evaluation == Evaluation::Lost {
+            // Subtle hint that no line of spells can win from here
+            wizard_block =
+                wizard_block.title_bottom(Line::from("doomed").dark_gray().right_aligned());
+        }
+        let wizard_info = Paragraph::new(self.stat_diffs.annotate(
+            self.display_wizard_info(),
+            &[
+                ("Hitpoints:", ui::StatField::WizardHitpoints),
+                ("Mana:", ui::StatField::WizardMana),
+            ],
+        ))
+        .gray()
+        .alignment(Alignment::Left)
+        .wrap(Wrap::default());
+        let wizard_sprite_area = Self::draw_side_panel(
+            frame,
+            wizard_area,
+            wizard_block,
+            wizard_info,
+            ui::WIZARD_SPRITE.idle_at(self.idle_tick),
+        );
 
         // Right game screen: text displaying Boss information
-        let boss_info = Paragraph::new(self.display_boss_info())
+        let mut boss_block = Block::bordered()
+            .border_style(Style::default().fg(theme::get().boss))
+            .title(
+                self.boss_name
+                    .as_ref()
+                    .map_or("Boss".to_string(), |name| format!("Boss: {}", name))
+                    .bold()
+                    .gray(),
+            )
+            .title_alignment(Alignment::Center);
+        if self.effects.is_active(ui::TransientEffect::BossFlash) {
+            boss_block = boss_block.style(Style::default().reversed());
+        }
+        // Changes of hitpoints the wizard doesn't know would give them away
+        let boss_fields: &[(&str, ui::StatField)] =
+            if self.game.get_boss_view().hitpoints.get_exact().is_some() {
+                &[("Hitpoints:", ui::StatField::BossHitpoints)]
+            } else {
+                &[]
+            };
+        let boss_info = Paragraph::new(
+            self.stat_diffs
+                .annotate(self.display_boss_info(), boss_fields),
+        )
+        .gray()
+        .alignment(Alignment::Left)
+        .wrap(Wrap::default());
+        let boss_sprite_area = Self::draw_side_panel(
+            frame,
+            boss_area,
+            boss_block,
+            boss_info,
+            ui::BOSS_SPRITE.idle_at(self.idle_tick),
+        );
+        [wizard_sprite_area, boss_sprite_area]
+    }
+
+    /// Draw a bordered side panel of text, with `sprite` at the bottom if it fits. Returns
+    /// where the sprite was drawn.
+    fn draw_side_panel(
+        frame: &mut Frame,
+        area: Rect,
+        block: Block,
+        text: Paragraph,
+        sprite: ui::IdleSprite,
+    ) -> Option<Rect> {
+        let inner_area = block.inner(area);
+        frame.render_widget(block, area);
+        if inner_area.height < SPRITE_MIN_PANEL_HEIGHT || inner_area.width < ui::SPRITE_WIDTH {
+            frame.render_widget(text, inner_area);
+            return None;
+        }
+        let [text_area, sprite_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(ui::SPRITE_HEIGHT)])
+                .areas(inner_area);
+        let [sprite_area] = Layout::horizontal([Constraint::Length(ui::SPRITE_WIDTH)])
+            .flex(Flex::Center)
+            .areas(sprite_area);
+        frame.render_widget(text, text_area);
+        frame.render_widget(sprite, sprite_area);
+        Some(sprite_area)
+    }
+
+    /// Raw state of the engine and the phase queue, for diagnosing rule bugs
+    fn draw_debug_overlay(&self, frame: &mut Frame, area: Rect) {
+        let state = self.game.get_state();
+        let phases = self.scheduler.get_queued_phases();
+        let timer = |symbol: char, name: &str, timer: u8| format!("{} {}: {}", symbol, name, timer);
+        let text = vec![
+            Line::from(format!(
+                "Round {}, outcome {:?}",
+                self.game.get_spells_used().len() + 1,
+                self.game.get_outcome()
+            )),
+            Line::from(format!(
+                "Rules {}, hard mode {}",
+                self.game.get_rules_version(),
+                self.game.get_hard_mode()
+            )),
+            Line::from(format!(
+                "Next phase: {}",
+                phases
+                    .front()
+                    .map_or("none".to_string(), |phase| format!("{:?}", phase))
+            )),
+            Line::from(format!(
+                "Queued phases: {}, speed {}x{}",
+                phases.len(),
+                self.scheduler.get_speed(),
+                if self.scheduler.is_paused() {
+                    ", paused"
+                } else {
+                    ""
+                }
+            )),
+            Line::from(""),
+            Line::from(format!(
+                "wizard_hitpoints: {}, wizard_mana: {}",
+                state.wizard_hitpoints, state.wizard_mana
+            )),
+            Line::from(format!(
+                "boss_hitpoints: {}, mana_used: {}",
+                state.boss_hitpoints,
+                self.game.get_mana_used()
+            )),
+            Line::from(vec![
+                timer(theme::SHIELD_SYMBOL, "shield_timer", state.shield_timer)
+                    .fg(theme::get().shield),
+                "  ".into(),
+                timer(
+                    theme::RECHARGE_SYMBOL,
+                    "recharge_timer",
+                    state.recharge_timer,
+                )
+                .fg(theme::get().recharge),
+                "  ".into(),
+                timer(theme::POISON_SYMBOL, "poison_timer", state.poison_timer)
+                    .fg(theme::get().poison),
+            ]),
+            Line::from(format!("{:?}", state)),
+        ];
+        let popup = Paragraph::new(text).gray().wrap(Wrap::default()).block(
+            Block::bordered()
+                .border_style(Style::default().fg(theme::get().playback))
+                .title("Debug".bold().gray())
+                .title_alignment(Alignment::Center)
+                .title_bottom(
+                    Line::from("n to step a phase, p to pause, F12 to close")
+                        .dark_gray()
+                        .centered(),
+                ),
+        );
+
+        let [popup_area] = Layout::vertical([Constraint::Length(14)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(95)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(popup, popup_area);
+    }
+
+    /// Table of the next rounds if the wizard casts nothing more
+    fn draw_forecast(&self, frame: &mut Frame, area: Rect) {
+        let forecast = forecast_battle(&self.game, FORECAST_ROUNDS);
+        // What the running effects still have to give, whenever they wear off
+        let mut footer = Vec::new();
+        let poison_damage = self.game.projected_poison_damage(i32::MAX);
+        if poison_damage > 0 {
+            footer.push(format!(
+                "{} {} damage left",
+                theme::POISON_SYMBOL,
+                poison_damage
+            ));
+        }
+        let familiar_damage = self.game.projected_familiar_damage(i32::MAX);
+        if familiar_damage > 0 {
+            footer.push(format!(
+                "{} {} damage left",
+                theme::FAMILIAR_SYMBOL,
+                familiar_damage
+            ));
+        }
+        let recharge_mana = self.game.projected_recharge_mana(i32::MAX);
+        if recharge_mana > 0 {
+            footer.push(format!(
+                "{} {} mana left",
+                theme::RECHARGE_SYMBOL,
+                recharge_mana
+            ));
+        }
+        footer.push("f to close".to_string());
+        let footer = footer.join("  ");
+        let header = Row::new([
+            "Round", "Wizard", "Mana", "Boss", "SHD", "RCH", "PSN", "FAM", "",
+        ])
+        .bold()
+        .gray();
+        // Symbols tell the effects apart without relying on color
+        let timer = |symbol: char, timer: u8| {
+            if timer == 0 {
+                "-".to_string()
+            } else {
+                format!("{}{}", symbol, timer)
+            }
+        };
+        let rows = forecast.iter().map(|round| {
+            let state = &round.state;
+            let note = match round.outcome {
+                Some(true) => "Win".fg(theme::get().wizard),
+                Some(false) => "Loss".fg(theme::get().boss),
+                None => "".into(),
+            };
+            Row::new([
+                Cell::from(format!("+{}", round.round)),
+                Cell::from(state.wizard_hitpoints.to_string()),
+                Cell::from(state.wizard_mana.to_string()),
+                Cell::from(state.boss_hitpoints.to_string()),
+                Cell::from(timer(theme::SHIELD_SYMBOL, state.shield_timer)).fg(theme::get().shield),
+                Cell::from(timer(theme::RECHARGE_SYMBOL, state.recharge_timer))
+                    .fg(theme::get().recharge),
+                Cell::from(timer(theme::POISON_SYMBOL, state.poison_timer)).fg(theme::get().poison),
+                Cell::from(timer(theme::FAMILIAR_SYMBOL, state.familiar_timer))
+                    .fg(theme::get().wizard),
+                Cell::from(note),
+            ])
             .gray()
-            .alignment(Alignment::Left)
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(5),
+                Constraint::Length(6),
+                Constraint::Length(5),
+                Constraint::Length(5),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Min(0),
+            ],
+        )
+        .header(header)
+        .block(
+            Block::bordered()
+                .border_style(Style::default().fg(theme::get().accent))
+                .title(
+                    if self.game.get_knowledge().is_full() {
+                        "Forecast if nothing is cast"
+                    } else {
+                        "No forecast until the boss is scouted"
+                    }
+                    .bold()
+                    .gray(),
+                )
+                .title_alignment(Alignment::Center)
+                .title_bottom(Line::from(footer).dark_gray().centered()),
+        );
+
+        let [popup_area] = Layout::vertical([Constraint::Length(forecast.len() as u16 + 3)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(90)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(table, popup_area);
+    }
+
+    fn draw_confirmation(
+        frame: &mut Frame,
+        area: Rect,
+        game: &Battle,
+        spell: &Spell,
+        reason: &CastWarning,
+    ) {
+        let spellbook = game.get_spellbook();
+        let mut text = match reason {
+            CastWarning::Fatal => {
+                let mut text = vec![Line::from(match game.get_boss_view().damage.get_exact() {
+                    Some(damage) => format!(
+                        "The boss hits for {} and the wizard won't survive its turn.",
+                        damage
+                    ),
+                    None => "The wizard won't survive the boss's turn.".to_string(),
+                })];
+                // Point out the rule that would have helped
+                if game.get_wizard().get_shielded().is_none()
+                    && *spell != Spell::Shield
+                    && game
+                        .get_wizard()
+                        .get_possible_spells()
+                        .contains(&Spell::Shield)
+                    && !game.is_cast_fatal(&Spell::Shield)
+                {
+                    text.push(Line::from(format!(
+                        "Shield's {} armor would lessen the attack.",
+                        spellbook.get(&Spell::Shield).armor
+                    )));
+                }
+                text
+            }
+            CastWarning::Wasted(WastedCast::BossDiesToPoison) => vec![Line::from(format!(
+                "The boss will die to poison's {} damage before it can attack.",
+                game.projected_poison_damage(1)
+            ))],
+            CastWarning::Wasted(WastedCast::CheaperSpellWins(cheaper)) => {
+                vec![Line::from(format!(
+                    "{} ({} mana) would defeat the boss.",
+                    cheaper.get_display_name(),
+                    spellbook.get_mana(cheaper)
+                ))]
+            }
+            CastWarning::Wasted(WastedCast::ManaFull) => vec![Line::from(format!(
+                "The wizard's mana is full at {}, so Recharge only wins back what it costs.",
+                game.get_wizard().get_mana()
+            ))],
+            CastWarning::Wasted(_) => vec![Line::from("Casting it would waste mana.")],
+            CastWarning::BloodPrice(hitpoints) => {
+                let wizard = game.get_wizard();
+                vec![Line::from(format!(
+                    "The wizard is {} mana short and pays {} hitpoints for it ({} -> {}).",
+                    spellbook.get_mana(spell) - wizard.get_mana(),
+                    hitpoints,
+                    wizard.get_hitpoints(),
+                    wizard.get_hitpoints() - hitpoints
+                ))]
+            }
+        };
+        text.extend([
+            Line::default(),
+            Line::from(format!(
+                "Cast {} for {} mana anyway? (y/n)",
+                spell.get_display_name(),
+                spellbook.get_mana(spell)
+            )),
+        ]);
+
+        let [popup_area] = Layout::vertical([Constraint::Length(text.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+        let popup = Paragraph::new(text)
+            .gray()
+            .alignment(Alignment::Center)
             .wrap(Wrap::default())
             .block(
                 Block::bordered()
-                    .light_red()
-                    .title("Boss".bold().gray())
+                    .border_style(Style::default().fg(theme::get().accent))
+                    .title("Are you sure?".bold().gray())
                     .title_alignment(Alignment::Center),
             );
-        frame.render_widget(boss_info, game_windows[2]);
-
-        // Spell selection table
-        let spell_row1 = Layout::horizontal([Constraint::Percentage(50); 2]).split(chunks[2]);
-        let spell_row2 = Layout::horizontal([Constraint::Percentage(50); 2]).split(chunks[3]);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(popup, popup_area);
+    }
 
-        frame.render_widget(
-            self.create_spell_select_button(Spell::MagicMissile, self.spell_selected == 0),
-            spell_row1[0],
+    /// Menu of the actions controllers have no buttons for, with entry `selected`
+    /// highlighted
+    fn draw_menu(frame: &mut Frame, area: Rect, selected: usize) {
+        let text: Vec<Line> = MENU
+            .iter()
+            .enumerate()
+            .map(|(index, (label, _))| {
+                if index == selected {
+                    Line::from(format!("> {} <", label).bold().fg(theme::get().accent))
+                } else {
+                    Line::from(label.gray())
+                }
+            })
+            .collect();
+        let [popup_area] = Layout::vertical([Constraint::Length(MENU.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Length(24)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+        let popup = Paragraph::new(text).alignment(Alignment::Center).block(
+            Block::bordered()
+                .border_style(Style::default().fg(theme::get().accent))
+                .title("Menu".bold().gray())
+                .title_alignment(Alignment::Center),
         );
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(popup, popup_area);
+    }
+
+    fn draw_game_over(&self, frame: &mut Frame, area: Rect, won: bool) {
+        let [popup_area] = Layout::vertical([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let (title, color) = if won {
+            ("Victory", theme::get().wizard)
+        } else {
+            ("Defeat", theme::get().boss)
+        };
+        let popup_block = Block::bordered()
+            .border_style(Style::default().fg(color))
+            .title(title.bold().gray())
+            .title_alignment(Alignment::Center)
+            .title_bottom(
+                Line::from("Press q to quit, x to copy the result, X the Markdown report")
+                    .gray()
+                    .centered(),
+            );
+        let inner_area = popup_block.inner(popup_area);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(popup_block, popup_area);
+
+        let rows = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .split(inner_area);
+        let charts = Layout::horizontal([Constraint::Percentage(50); 2]).split(rows[0]);
+
+        let breakdown = DamageBreakdown::from_events(self.game.get_events());
         frame.render_widget(
-            self.create_spell_select_button(Spell::Drain, self.spell_selected == 1),
-            spell_row1[1],
+            Self::create_damage_chart("Damage Dealt", &breakdown.dealt, theme::get().wizard),
+            charts[0],
         );
         frame.render_widget(
-            self.create_spell_select_button(Spell::Poison, self.spell_selected == 2),
-            spell_row2[0],
+            Self::create_damage_chart("Damage Taken", &breakdown.taken, theme::get().boss),
+            charts[1],
         );
+        self.draw_stats(frame, rows[1]);
         frame.render_widget(
-            self.create_spell_select_button(Spell::Shield, self.spell_selected == 3),
-            spell_row2[1],
+            Line::from(session_stats::get().to_string())
+                .gray()
+                .centered(),
+            rows[2],
         );
-        frame.render_widget(
-            self.create_spell_select_button(
-                Spell::Recharge,
-                self.spell_selected == 4 || self.spell_selected == 5,
+    }
+
+    fn create_damage_chart<'b>(
+        title: &'b str,
+        totals: &[(DamageSource, i32)],
+        color: Color,
+    ) -> BarChart<'b> {
+        let data: Vec<(&str, u64)> = totals
+            .iter()
+            .map(|(source, total)| (source.get_display_name(), (*total).max(0) as u64))
+            .collect();
+        BarChart::default()
+            .block(
+                Block::bordered()
+                    .gray()
+                    .title(title.bold())
+                    .title_alignment(Alignment::Center),
+            )
+            .direction(Direction::Horizontal)
+            .bar_width(1)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(color))
+            .value_style(Style::default().fg(Color::Black).bg(color))
+            .data(&data)
+    }
+
+    fn draw_stats(&self, frame: &mut Frame, area: Rect) {
+        let stats_block = Block::bordered()
+            .gray()
+            .title(if self.stats.is_ironman() {
+                "Stats (Ironman)".bold()
+            } else {
+                "Stats".bold()
+            })
+            .title_alignment(Alignment::Center);
+        let stats_block = match (
+            self.stats.get_average_think_time(),
+            self.stats.get_longest_think_time(),
+        ) {
+            (Some(average), Some(longest)) => stats_block.title_bottom(
+                Line::from(format!(
+                    "Think time: {:.1}s average, {:.1}s longest",
+                    average.as_secs_f64(),
+                    longest.as_secs_f64()
+                ))
+                .centered(),
             ),
-            chunks[4],
-        );
+            _ => stats_block,
+        };
+        let inner_area = stats_block.inner(area);
+        frame.render_widget(stats_block, area);
+
+        let rows = Layout::vertical([Constraint::Length(2); 3]).split(inner_area);
+        let series = [
+            (
+                "Wizard HP",
+                self.stats.series(|s| s.wizard_hitpoints),
+                theme::get().wizard,
+            ),
+            (
+                "Boss HP",
+                self.stats.series(|s| s.boss_hitpoints),
+                theme::get().boss,
+            ),
+            ("Mana", self.stats.series(|s| s.mana), theme::get().mana),
+        ];
+        for (row, (label, data, color)) in rows.iter().zip(series) {
+            let columns =
+                Layout::horizontal([Constraint::Length(10), Constraint::Min(0)]).split(*row);
+            frame.render_widget(Paragraph::new(label).gray(), columns[0]);
+            // Only the most recent rounds fit in the pane
+            let start = data.len().saturating_sub(columns[1].width as usize);
+            frame.render_widget(
+                Sparkline::default()
+                    .data(&data[start..])
+                    .style(Style::default().fg(color)),
+                columns[1],
+            );
+        }
+    }
+
+    /// Change of the boss's hitpoints to add to an event, if the wizard knows them
+    fn describe_boss_hitpoints_change(&self, old: i32, new: i32) -> String {
+        if self.game.get_knowledge().hitpoints {
+            format!(" ({} -> {})", old, new)
+        } else {
+            String::new()
+        }
     }
 
     fn output_event(&mut self, line: String) {
-        self.event_window_text.push(Line::from(line));
+        #[cfg(feature = "logging")]
+        tracing::debug!("{}", line);
+        crash_report::record_event(&line);
+        self.event_log.push(line);
+    }
+
+    /// Output a notice from the game rather than an event of the battle
+    fn output_notice(&mut self, line: String) {
+        #[cfg(feature = "logging")]
+        tracing::debug!("{}", line);
+        crash_report::record_event(&line);
+        self.event_log.push_notice(line);
+    }
+
+    /// Output a line of the boss for `trigger`, unless taunts are turned off
+    fn taunt(&mut self, trigger: TauntTrigger) {
+        let Some(taunts) = &self.taunts else {
+            return;
+        };
+        let line = format!(
+            "{}: \"{}\"",
+            self.boss_name.as_deref().unwrap_or("Boss"),
+            taunts.choose(trigger, self.game.get_spells_used().len())
+        );
+        #[cfg(feature = "logging")]
+        tracing::debug!("{}", line);
+        crash_report::record_event(&line);
+        self.event_log.push_taunt(line);
+    }
+
+    /// Taunt the first time the boss falls below half of its hitpoints
+    fn check_boss_wounded(&mut self) {
+        if !self.boss_wounded
+            && self.game.get_outcome().is_none()
+            && self.game.get_boss().get_hitpoints() * 2 < self.boss_max_hitpoints
+        {
+            self.boss_wounded = true;
+            self.taunt(TauntTrigger::Wounded);
+        }
     }
 
     fn output_win_loss_event(&mut self, won: bool) {
         if won {
             self.output_event("Glory! Magic has defeated the enemy!".to_string());
         } else {
+            self.taunt(TauntTrigger::KillingBlow);
             self.output_event("Grief... Evil has consumed the wizard...".to_string());
         }
     }
@@ -314,10 +1760,10 @@ impl<'a> App<'a> {
         let wizard_mana_old = self.game.get_wizard().get_mana();
         let boss_hitpoint_old = self.game.get_boss().get_hitpoints();
 
-        // Output empty line unless first turn
-        if !self.game.get_spells_used().is_empty() {
-            self.output_event(String::with_capacity(0));
-        }
+        let events_old = self.game.get_events().len();
+
+        self.event_log
+            .begin_phase(self.game.get_spells_used().len() + 1, EventPhase::Wizard);
         self.output_event("Wizard's turn:".to_string());
         let outcome = self.game.wizard_turn_apply_effects();
 
@@ -326,14 +1772,16 @@ impl<'a> App<'a> {
         let wizard_mana_new = self.game.get_wizard().get_mana();
         let boss_hitpoint_new = self.game.get_boss().get_hitpoints();
 
-        // Can lose hitpoints on hard mode
-        let wizard_hitpoint_diff = wizard_hitpoint_new - wizard_hitpoint_old;
-        if wizard_hitpoint_diff < 0 {
+        // Can lose hitpoints on hard mode, then to burn
+        let burn_damage = self.get_burn_damage(events_old);
+        let wizard_hitpoint_faded = wizard_hitpoint_new + burn_damage;
+        if wizard_hitpoint_faded < wizard_hitpoint_old {
             self.output_event(format!(
                 "Wizard's magic fades (hitpoints: {} -> {})",
-                wizard_hitpoint_old, wizard_hitpoint_new
+                wizard_hitpoint_old, wizard_hitpoint_faded
             ));
         }
+        self.output_burn_event(burn_damage, wizard_hitpoint_faded);
 
         // Can lose armor if shield ends
         let wizard_armor_diff = wizard_armor_new - wizard_armor_old;
@@ -353,14 +1801,31 @@ impl<'a> App<'a> {
             ))
         }
 
-        // Can lose hitpoints to poison
-        let boss_hitpoint_diff = boss_hitpoint_new - boss_hitpoint_old;
+        // Can lose hitpoints to poison, then to the familiar
+        let familiar_damage: i32 = self.game.get_events()[events_old..]
+            .iter()
+            .map(|event| match event {
+                BattleEvent::BossDamaged {
+                    source: DamageSource::Familiar,
+                    amount,
+                } => *amount,
+                _ => 0,
+            })
+            .sum();
+        let boss_hitpoint_poisoned = boss_hitpoint_new + familiar_damage;
+        let boss_hitpoint_diff = boss_hitpoint_poisoned - boss_hitpoint_old;
         if boss_hitpoint_diff < 0 {
             self.output_event(format!(
-                "Boss poisoned for {} damage ({} -> {})",
+                "Boss poisoned for {} damage{}",
                 boss_hitpoint_diff.abs(),
-                boss_hitpoint_old,
-                boss_hitpoint_new
+                self.describe_boss_hitpoints_change(boss_hitpoint_old, boss_hitpoint_poisoned)
+            ))
+        }
+        if familiar_damage > 0 {
+            self.output_event(format!(
+                "Familiar attacks the boss for {} damage{}",
+                familiar_damage,
+                self.describe_boss_hitpoints_change(boss_hitpoint_poisoned, boss_hitpoint_new)
             ))
         }
 
@@ -378,6 +1843,7 @@ impl<'a> App<'a> {
         let wizard_armor_old = self.game.get_wizard().get_armor();
         let wizard_mana_old = self.game.get_wizard().get_mana();
         let boss_hitpoint_old = self.game.get_boss().get_hitpoints();
+        let events_old = self.game.get_events().len();
 
         self.output_event(format!("Wizard casts {}", spell.get_display_name()));
         let outcome = self.game.wizard_turn_cast_spell(spell);
@@ -387,12 +1853,47 @@ impl<'a> App<'a> {
         let wizard_mana_new = self.game.get_wizard().get_mana();
         let boss_hitpoint_new = self.game.get_boss().get_hitpoints();
 
+        // Can pay hitpoints for the mana the wizard is short of under blood magic
+        let blood_price: i32 = self.game.get_events()[events_old..]
+            .iter()
+            .filter_map(|event| match event {
+                BattleEvent::WizardDamaged {
+                    source: DamageSource::BloodMagic,
+                    amount,
+                } => Some(*amount),
+                _ => None,
+            })
+            .sum();
+        if blood_price > 0 {
+            self.output_event(format!(
+                "Wizard pays {} hitpoints in blood ({} -> {})",
+                blood_price,
+                wizard_hitpoint_old,
+                wizard_hitpoint_old - blood_price
+            ));
+        }
+
+        // Can lose hitpoints to the boss's spiky skin
+        let spiky_skin: i32 = self.game.get_events()[events_old..]
+            .iter()
+            .filter_map(|event| match event {
+                BattleEvent::WizardDamaged {
+                    source: DamageSource::SpikySkin,
+                    amount,
+                } => Some(*amount),
+                _ => None,
+            })
+            .sum();
+
         // Can gain hitpoints from Drain
-        let wizard_hitpoint_diff = wizard_hitpoint_new - wizard_hitpoint_old;
+        let wizard_hitpoint_diff =
+            wizard_hitpoint_new - wizard_hitpoint_old + blood_price + spiky_skin;
         if wizard_hitpoint_diff > 0 {
             self.output_event(format!(
                 "Wizard regenerates {} hitpoints ({} -> {})",
-                wizard_hitpoint_diff, wizard_hitpoint_old, wizard_hitpoint_new
+                wizard_hitpoint_diff,
+                wizard_hitpoint_old - blood_price,
+                wizard_hitpoint_new
             ));
         }
 
@@ -416,14 +1917,52 @@ impl<'a> App<'a> {
             ));
         }
 
+        // Can end afflictions with Cleanse
+        let cleansed = self.game.get_events()[events_old..]
+            .iter()
+            .find_map(|event| match event {
+                BattleEvent::WizardCleansed {
+                    silenced,
+                    burn_stacks,
+                    poisoned,
+                } => {
+                    let mut ended = Vec::new();
+                    if *silenced {
+                        ended.push("silence".to_string());
+                    }
+                    if *burn_stacks > 0 {
+                        ended.push(format!(
+                            "{} {} of burn",
+                            burn_stacks,
+                            if *burn_stacks == 1 { "stack" } else { "stacks" }
+                        ));
+                    }
+                    if *poisoned {
+                        ended.push("poison".to_string());
+                    }
+                    Some(format!("Wizard cleanses {}", ended.join(", ")))
+                }
+                _ => None,
+            });
+        if let Some(cleansed) = cleansed {
+            self.output_event(cleansed);
+        }
+
         // Can lose hitpoints to poison
         let boss_hitpoint_diff = boss_hitpoint_new - boss_hitpoint_old;
         if boss_hitpoint_diff < 0 {
             self.output_event(format!(
-                "Boss receives {} damage ({} -> {})",
+                "Boss receives {} damage{}",
                 boss_hitpoint_diff.abs(),
-                boss_hitpoint_old,
-                boss_hitpoint_new
+                self.describe_boss_hitpoints_change(boss_hitpoint_old, boss_hitpoint_new)
+            ));
+        }
+        if spiky_skin > 0 {
+            self.output_event(format!(
+                "Boss's spiky skin pricks the wizard for {} damage ({} -> {})",
+                spiky_skin,
+                wizard_hitpoint_new + spiky_skin,
+                wizard_hitpoint_new
             ));
         }
 
@@ -438,12 +1977,18 @@ impl<'a> App<'a> {
         if let Some(_outcome) = self.game.get_outcome() {
             return;
         }
+        let wizard_hitpoint_old = self.game.get_wizard().get_hitpoints();
         let wizard_mana_old = self.game.get_wizard().get_mana();
         let boss_hitpoint_old = self.game.get_boss().get_hitpoints();
+        let events_old = self.game.get_events().len();
 
+        self.event_log
+            .begin_phase(self.game.get_spells_used().len(), EventPhase::Boss);
         self.output_event(String::with_capacity(0));
         self.output_event("Boss' turn:".to_string());
         let outcome = self.game.boss_turn_apply_effects();
+        let burn_damage = self.get_burn_damage(events_old);
+        self.output_burn_event(burn_damage, wizard_hitpoint_old);
 
         let wizard_mana_new = self.game.get_wizard().get_mana();
         let boss_hitpoint_new = self.game.get_boss().get_hitpoints();
@@ -461,112 +2006,698 @@ impl<'a> App<'a> {
         let boss_hitpoint_diff = boss_hitpoint_new - boss_hitpoint_old;
         if boss_hitpoint_diff < 0 {
             self.output_event(format!(
-                "Boss poisoned for {} damage ({} -> {})",
+                "Boss poisoned for {} damage{}",
                 boss_hitpoint_diff.abs(),
-                boss_hitpoint_old,
-                boss_hitpoint_new
+                self.describe_boss_hitpoints_change(boss_hitpoint_old, boss_hitpoint_new)
             ))
         }
 
-        if let Some(won) = outcome {
-            self.output_win_loss_event(won)
+        if let Some(won) = outcome {
+            self.output_win_loss_event(won)
+        }
+    }
+
+    /// Damage burn dealt the wizard in the battle's events from `start` on
+    fn get_burn_damage(&self, start: usize) -> i32 {
+        self.game.get_events()[start..]
+            .iter()
+            .map(|event| match event {
+                BattleEvent::WizardDamaged {
+                    source: DamageSource::Burn,
+                    amount,
+                } => *amount,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Output the wizard losing `damage` to burn from `hitpoints`, if any
+    fn output_burn_event(&mut self, damage: i32, hitpoints: i32) {
+        if damage > 0 {
+            self.output_event(format!(
+                "Wizard burns for {} damage ({} -> {})",
+                damage,
+                hitpoints,
+                hitpoints - damage
+            ));
+        }
+    }
+
+    fn boss_turn_attack(&mut self) {
+        if let Some(_outcome) = self.game.get_outcome() {
+            return;
+        }
+        let wizard_hitpoint_old = self.game.get_wizard().get_hitpoints();
+        let boss_hitpoint_old = self.game.get_boss().get_hitpoints();
+        let events_old = self.game.get_events().len();
+
+        self.output_event("Boss attacks".to_string());
+        let outcome = self.game.boss_turn_attack();
+
+        // Loses hitpoints to attack, not counting what the boss's ability took after it
+        let attack_damage: i32 = self.game.get_events()[events_old..]
+            .iter()
+            .map(|event| match event {
+                BattleEvent::WizardDamaged {
+                    source: DamageSource::BossAttack,
+                    amount,
+                } => *amount,
+                _ => 0,
+            })
+            .sum();
+        let wizard_hitpoint_new = wizard_hitpoint_old - attack_damage;
+        let wizard_hitpoint_diff = wizard_hitpoint_new - wizard_hitpoint_old;
+        if wizard_hitpoint_diff.abs() != self.game.get_boss().get_damage() {
+            // Wizard has lost less hitpoints than boss' damage
+            self.output_event(format!(
+                "Wizard resists attack ({} -> {})",
+                wizard_hitpoint_old, wizard_hitpoint_new
+            ));
+        } else if wizard_hitpoint_diff.abs() == self.game.get_boss().get_damage() {
+            self.output_event(format!(
+                "Wizard receives {} damage ({} -> {})",
+                wizard_hitpoint_diff.abs(),
+                wizard_hitpoint_old,
+                wizard_hitpoint_new
+            ));
+        }
+        let abilities: Vec<String> = self.game.get_events()[events_old..]
+            .iter()
+            .filter_map(|event| match event {
+                BattleEvent::BossDamaged {
+                    source: DamageSource::Thorns,
+                    amount,
+                } => Some(format!(
+                    "Thorns reflect {} damage at the boss{}",
+                    amount,
+                    self.describe_boss_hitpoints_change(
+                        boss_hitpoint_old,
+                        boss_hitpoint_old - amount
+                    )
+                )),
+                BattleEvent::WizardSilenced { turns } => Some(format!(
+                    "Boss silences the wizard: no effect spells for {} turns",
+                    turns
+                )),
+                BattleEvent::WizardBurned { stacks } => Some(format!(
+                    "Boss sets the wizard on fire ({} {} of burn)",
+                    stacks,
+                    if *stacks == 1 { "stack" } else { "stacks" }
+                )),
+                BattleEvent::EffectsDispelled { spells } => {
+                    let names: Vec<&str> = spells
+                        .iter()
+                        .map(|spell| spell.get_display_name())
+                        .collect();
+                    Some(format!("Boss dispels the wizard's {}", names.join(" and ")))
+                }
+                BattleEvent::WizardDamaged {
+                    source: DamageSource::Lifesteal,
+                    amount,
+                } => Some(format!(
+                    "Boss steals {} hitpoints from the wizard ({} -> {})",
+                    amount,
+                    wizard_hitpoint_new,
+                    wizard_hitpoint_new - amount
+                )),
+                BattleEvent::BossHealed { amount } => {
+                    let boss_hitpoint_new = self.game.get_boss().get_hitpoints();
+                    Some(format!(
+                        "Boss heals {} hitpoints{}",
+                        amount,
+                        self.describe_boss_hitpoints_change(
+                            boss_hitpoint_new - amount,
+                            boss_hitpoint_new
+                        )
+                    ))
+                }
+                _ => None,
+            })
+            .collect();
+        for ability in abilities {
+            self.output_event(ability);
+        }
+
+        if let Some(won) = outcome {
+            self.output_win_loss_event(won)
+        }
+    }
+
+    fn get_selected_spell(&self) -> Spell {
+        SPELL_BUTTONS[self.spell_selected].clone()
+    }
+
+    /// Cast the selected spell, or add it to the plan while planning
+    fn cast_selected_spell(&mut self) {
+        if self.planning {
+            self.plan.push_back(self.get_selected_spell());
+        } else {
+            self.step_game();
+        }
+    }
+
+    /// Cast the next planned spell once the current round is over. If it can't be cast,
+    /// the plan goes back to planning with the spell still first. Returns whether
+    /// anything changed.
+    fn advance_plan(&mut self) -> bool {
+        if self.planning
+            || !self.scheduler.is_idle()
+            || self.pending_confirmation.is_some()
+            || self.game.get_outcome().is_some()
+        {
+            return false;
+        }
+        let Some(spell) = self.plan.pop_front() else {
+            return false;
+        };
+        if !self
+            .game
+            .get_wizard()
+            .get_possible_spells()
+            .contains(&spell)
+        {
+            self.output_notice(format!(
+                "Plan paused: {} can't be cast now.",
+                spell.get_display_name()
+            ));
+            self.plan.push_front(spell);
+            self.planning = true;
+            return true;
+        }
+        if let Some(index) = Self::get_spell_button(&spell) {
+            self.spell_selected = index;
+            self.step_game();
+        }
+        true
+    }
+
+    fn step_game(&mut self) {
+        // Wait for the current round to finish
+        if !self.scheduler.is_idle() {
+            return;
+        }
+
+        // Skip currently animating lines
+        self.event_window_typewriter
+            .finish(self.event_log.get_lines());
+
+        let spell_cast = self.get_selected_spell();
+
+        // If selected spell is unavailable
+        if !self
+            .game
+            .get_wizard()
+            .get_possible_spells()
+            .contains(&spell_cast)
+        {
+            return;
+        }
+
+        // If game is over
+        if let Some(_outcome) = self.game.get_outcome() {
+            return;
+        }
+
+        // Ask before casting a spell that loses the battle or would be wasted
+        if self.insurance && self.game.is_cast_fatal(&spell_cast) {
+            self.pending_confirmation = Some((spell_cast, CastWarning::Fatal));
+            return;
+        }
+        if let Some(reason) = self.game.evaluate_cast(&spell_cast) {
+            self.pending_confirmation = Some((spell_cast, CastWarning::Wasted(reason)));
+            return;
+        }
+        let blood_price = self.game.get_blood_price(&spell_cast);
+        if blood_price > 0 {
+            self.pending_confirmation = Some((spell_cast, CastWarning::BloodPrice(blood_price)));
+            return;
+        }
+
+        self.schedule_cast(spell_cast);
+    }
+
+    /// Play a round casting `spell`, recording how long the player took to decide it.
+    /// Under the action point economy only the cast is played, as the turn may go on.
+    fn schedule_cast(&mut self, spell: Spell) {
+        if let Some(turn_timer) = self.turn_timer.take() {
+            self.stats.record_think_time(turn_timer.elapsed());
+        }
+        if self.game.get_action_points().is_some() {
+            self.scheduler.schedule_cast(spell);
+        } else {
+            self.scheduler.schedule_round(spell);
+        }
+    }
+
+    /// End the turn with action points left, once the wizard has cast this turn
+    fn end_turn(&mut self) {
+        if !self.scheduler.is_idle() || !self.game.can_end_turn() {
+            return;
+        }
+        if let Some(turn_timer) = self.turn_timer.take() {
+            self.stats.record_think_time(turn_timer.elapsed());
+        }
+        self.scheduler.schedule_turn_end();
+    }
+
+    /// After a cast under the action point economy, wait for another cast if one can be
+    /// afforded, otherwise play the rest of the round
+    fn continue_turn(&mut self) {
+        let Some(action_points) = self.game.get_action_points() else {
+            return;
+        };
+        if self.game.get_outcome().is_some() {
+            return;
+        }
+        if self.game.can_cast_again() {
+            self.output_notice(format!(
+                "{} action {} left: cast again or press E to end the turn",
+                action_points,
+                if action_points == 1 {
+                    "point"
+                } else {
+                    "points"
+                }
+            ));
+            self.start_turn_timer();
+        } else {
+            self.scheduler.schedule_turn_end();
+        }
+    }
+
+    /// Search whether the position is now lost, if the blunder check is enabled and the
+    /// boss's stats are all known
+    fn check_blunder(&mut self) {
+        if let Some(budget) = self.blunder_check {
+            self.position_evaluation = if self.game.get_knowledge().is_full() {
+                evaluate_position(&self.game, budget)
+            } else {
+                Evaluation::Unknown
+            };
+        }
+    }
+
+    /// Schedule the rounds played in a watched battle since they were last read, leaving
+    /// if it gave way to another battle. Returns whether anything changed.
+    fn follow_rounds(&mut self) -> bool {
+        let Some(follower) = &mut self.follower else {
+            return false;
+        };
+        let spells = follower.read_new_rounds();
+        if follower.is_replaced() {
+            self.exit = true;
+            return true;
+        }
+        let changed = !spells.is_empty();
+        for spell in spells {
+            self.scheduler.schedule_round(spell);
+        }
+        changed
+    }
+
+    /// Run the phases of the current round that are due. Returns whether any phase ran.
+    fn run_due_phases(&mut self) -> bool {
+        let mut phase_ran = false;
+        while let Some(phase) = self.scheduler.next_due_phase() {
+            if let Phase::WizardCastSpell(spell) = &phase {
+                self.start_cast_animation(spell.clone());
+            }
+            let wizard_hitpoints = self.game.get_wizard().get_hitpoints();
+            let outcome = self.game.get_outcome();
+            let boss_attacks = matches!(phase, Phase::BossAttack);
+            self.run_phase(phase, false);
+            // A hit taking a big share of the wizard's hitpoints shakes the screen
+            let lost = wizard_hitpoints - self.game.get_wizard().get_hitpoints();
+            if boss_attacks && lost * BIG_HIT_SHARE >= wizard_hitpoints {
+                self.effects.trigger(ui::TransientEffect::Shake);
+            }
+            if outcome.is_none() && self.game.get_outcome() == Some(true) {
+                self.effects.trigger(ui::TransientEffect::BossFlash);
+            }
+            phase_ran = true;
+        }
+        phase_ran
+    }
+
+    /// Start the ending once the battle is over and its final events have been animated,
+    /// unless the game moves on to the next battle of a run. Returns whether it started.
+    fn start_ending(&mut self) -> bool {
+        let Some(won) = self.game.get_outcome() else {
+            return false;
+        };
+        if self.ending_started || self.is_animating() {
+            return false;
         }
+        self.ending_started = true;
+        ambiance::stop();
+        if !self.animate || (won && self.exit_on_win) {
+            return false;
+        }
+        self.ending = Some(ui::EndingCinematic::new(won));
+        true
     }
 
-    fn boss_turn_attack(&mut self) {
-        if let Some(_outcome) = self.game.get_outcome() {
-            return;
+    /// Lines of the boss's and wizard's stats revealed by the intro, as far as they are
+    /// known
+    fn get_intro_stats(&self) -> Vec<String> {
+        let boss = self.game.get_boss_view();
+        let wizard = self.game.get_wizard();
+        let mut stats = vec![
+            format!("Hitpoints: {}", boss.hitpoints),
+            format!("Damage: {}", boss.damage),
+        ];
+        if self.game.get_hard_mode() {
+            stats.push("Hard mode: the wizard loses a hitpoint each turn".to_string());
         }
-        let wizard_hitpoint_old = self.game.get_wizard().get_hitpoints();
-
-        self.output_event("Boss attacks".to_string());
-        let outcome = self.game.boss_turn_attack();
-
-        let wizard_hitpoint_new = self.game.get_wizard().get_hitpoints();
-
-        // Loses hitpoints to attack
-        let wizard_hitpoint_diff = wizard_hitpoint_new - wizard_hitpoint_old;
-        if wizard_hitpoint_diff.abs() != self.game.get_boss().get_damage() {
-            // Wizard has lost less hitpoints than boss' damage
-            self.output_event(format!(
-                "Wizard resists attack ({} -> {})",
-                wizard_hitpoint_old, wizard_hitpoint_new
+        if let Some(ability) = self.game.get_boss().get_ability() {
+            stats.push(format!("{}: {}", ability, ability.get_description()));
+        }
+        if let Some(ai) = self.describe_boss_ai() {
+            stats.push(format!("Boss AI: {}", ai));
+        }
+        if self.game.get_boss().get_spiky_skin() > 0 {
+            stats.push(format!(
+                "Spiky skin: {} damage to the wizard for each spell that hits",
+                self.game.get_boss().get_spiky_skin()
             ));
-        } else if wizard_hitpoint_diff.abs() == self.game.get_boss().get_damage() {
-            self.output_event(format!(
-                "Wizard receives {} damage ({} -> {})",
-                wizard_hitpoint_diff.abs(),
-                wizard_hitpoint_old,
-                wizard_hitpoint_new
+        }
+        if self.game.get_action_points().is_some() {
+            stats.push(format!(
+                "Action points: {} a turn, cheap spells take 1",
+                ACTION_POINTS_PER_TURN
             ));
         }
-
-        if let Some(won) = outcome {
-            self.output_win_loss_event(won)
+        if self.game.get_blood_magic() {
+            stats.push(format!(
+                "Blood magic: short of mana, pay a hitpoint for every {}",
+                BLOOD_MAGIC_MANA_PER_HITPOINT
+            ));
         }
+        stats.push(format!(
+            "Your wizard: {} hitpoints, {} mana",
+            wizard.get_hitpoints(),
+            wizard.get_mana()
+        ));
+        stats
     }
 
-    fn step_game(&mut self) {
-        // Skip currently animating lines
-        let new_event_window_text_index = self.event_window_text.len();
-        self.event_window_text_index = Some(new_event_window_text_index);
+    /// Whether the sprites may play their idle animation: nothing is animating or due to
+    /// happen, so it doesn't hold up anything
+    fn is_idle(&self, was_animating: bool) -> bool {
+        self.animate
+            && !was_animating
+            && self.intro.is_none()
+            && self.ending.is_none()
+            && self.cast_animation.is_none()
+            && self.effects.is_empty()
+            && self.scheduler.time_until_next_phase().is_none()
+    }
 
-        let spell_cast = match self.spell_selected {
-            0 => Spell::MagicMissile,
-            1 => Spell::Drain,
-            2 => Spell::Poison,
-            3 => Spell::Shield,
-            4 | 5 => Spell::Recharge,
-            _ => unreachable!(),
+    /// Animate `spell` until the next phase of the round is due, or briefly if the phases
+    /// run without a delay
+    fn start_cast_animation(&mut self, spell: Spell) {
+        if !self.animate {
+            return;
+        }
+        let delay = self.scheduler.get_phase_delay();
+        let duration = if delay.is_zero() {
+            ui::CAST_ANIMATION_DURATION
+        } else {
+            delay
         };
+        self.cast_animation = Some(ui::CastAnimation::new(spell, duration));
+    }
 
-        // If selected spell is unavailable
-        if !self
-            .game
-            .get_wizard()
-            .get_possible_spells()
-            .contains(&spell_cast)
-        {
-            return;
+    #[cfg_attr(feature = "logging", tracing::instrument(level = "trace", skip(self)))]
+    /// Play `phase`, outputting its events. `replaying` rounds of a recovered battle were
+    /// played already, so they leave out the blunder check, autosave and turn timer.
+    fn run_phase(&mut self, phase: Phase, replaying: bool) {
+        match phase {
+            Phase::WizardCastSpell(spell) => {
+                self.wizard_turn_cast_spell(&spell);
+                if !replaying {
+                    self.continue_turn();
+                }
+            }
+            Phase::BossApplyEffects => self.boss_turn_apply_effects(),
+            Phase::BossAttack => self.boss_turn_attack(),
+            Phase::WizardApplyEffects => {
+                self.wizard_turn_apply_effects();
+                self.stats.record_round(&self.game);
+                if !replaying {
+                    self.check_blunder();
+                    self.autosave();
+                    self.start_turn_timer();
+                }
+            }
         }
+        self.check_boss_wounded();
+        crash_report::record_battle(&self.game);
+    }
 
-        // If game is over
-        if let Some(_outcome) = self.game.get_outcome() {
+    /// Write the battle to the next autosave slot, reporting failures as an event
+    fn autosave(&mut self) {
+        let Some(autosaver) = &self.autosaver else {
             return;
+        };
+        if let Err(err) = autosaver.save(&self.game) {
+            self.output_notice(format!("Autosave failed: {}", err));
         }
+    }
 
-        self.wizard_turn_cast_spell(&spell_cast);
-        self.boss_turn_apply_effects();
-        self.boss_turn_attack();
-        self.wizard_turn_apply_effects();
+    /// Bars of the wizard's hitpoints and mana and the boss's hitpoints, each out of its
+    /// peak so far in the battle
+    fn status_meters(&self) -> [Meter; 3] {
+        let wizard = self.game.get_wizard();
+        let boss = self.game.get_boss();
+        // Hidden hitpoints are shown as the top of their range
+        let known = self.game.get_knowledge().hitpoints;
+        let shown = |hitpoints: i32| {
+            Known::hitpoints(hitpoints, known)
+                .get_upper_bound()
+                .unwrap_or(hitpoints)
+        };
+        let peak = |field: fn(&RoundSample) -> i32, value: i32| {
+            self.stats
+                .get_samples()
+                .iter()
+                .map(field)
+                .fold(value, i32::max)
+        };
+        [
+            Meter::new(
+                format!("HP {:>2}", wizard.get_hitpoints().max(0)),
+                wizard.get_hitpoints(),
+                peak(|s| s.wizard_hitpoints, wizard.get_hitpoints()),
+                theme::get().wizard,
+            ),
+            Meter::new(
+                format!("MP {:>3}", wizard.get_mana()),
+                wizard.get_mana(),
+                peak(|s| s.mana, wizard.get_mana()),
+                theme::get().mana,
+            ),
+            Meter::new(
+                format!(
+                    "Boss {:>2}",
+                    Known::hitpoints(boss.get_hitpoints().max(0), known)
+                ),
+                shown(boss.get_hitpoints()),
+                shown(peak(|s| s.boss_hitpoints, boss.get_hitpoints())),
+                theme::get().boss,
+            ),
+        ]
+    }
+
+    /// Short badges for the active effects, hard mode, the plan, the last spell cast and
+    /// playback speed
+    fn status_badges(&self) -> Vec<Badge> {
+        let wizard = self.game.get_wizard();
+        let theme = theme::get();
+        let effects = [
+            (
+                theme::SHIELD_SYMBOL,
+                "SHD",
+                wizard.get_shielded(),
+                theme.shield,
+            ),
+            (
+                theme::RECHARGE_SYMBOL,
+                "RCH",
+                wizard.get_recharging(),
+                theme.recharge,
+            ),
+            (
+                theme::POISON_SYMBOL,
+                "PSN",
+                self.game.get_boss().get_poisoned(),
+                theme.poison,
+            ),
+            (
+                theme::THORNS_SYMBOL,
+                "THN",
+                wizard.get_thorns(),
+                theme.wizard,
+            ),
+            (
+                theme::FAMILIAR_SYMBOL,
+                "FAM",
+                wizard.get_familiar(),
+                theme.wizard,
+            ),
+            (
+                theme::SILENCE_SYMBOL,
+                "SIL",
+                wizard.get_silenced(),
+                theme.boss,
+            ),
+        ];
+        let mut badges: Vec<Badge> = effects
+            .into_iter()
+            .filter_map(|(symbol, code, timer, color)| {
+                timer.map(|timer| Badge::new(format!("{} {} {}", symbol, code, timer), color))
+            })
+            .collect();
+        let burns = wizard.get_burns().get_stacks();
+        if burns > 0 {
+            badges.push(Badge::new(
+                format!("{} BRN x{}", theme::BURN_SYMBOL, burns),
+                theme.boss,
+            ));
+        }
+        if self.game.get_hard_mode() {
+            badges.push(Badge::new("HARD".to_string(), theme.hard_mode));
+        }
+        if let Some(action_points) = self.game.get_action_points() {
+            badges.push(Badge::new(
+                format!("AP {}/{}", action_points, ACTION_POINTS_PER_TURN),
+                theme.accent,
+            ));
+        }
+        if self.game.get_blood_magic() {
+            badges.push(Badge::new("BLOOD".to_string(), theme.boss));
+        }
+        if self.planning || !self.plan.is_empty() {
+            let spells: Vec<Spell> = self.plan.iter().cloned().collect();
+            let label = if self.planning { "PLANNING" } else { "PLAN" };
+            badges.push(Badge::new(
+                format!("{} {}", label, format_line(&spells))
+                    .trim_end()
+                    .to_string(),
+                theme.plan,
+            ));
+        }
+        if let Some(spell) = self.game.get_last_spell_cast() {
+            badges.push(Badge::new(
+                format!("LAST {}", format_spell(spell)),
+                theme.hint,
+            ));
+        }
+        if self.scheduler.is_paused() || self.unfocused {
+            badges.push(Badge::new("PAUSED".to_string(), theme.playback));
+        } else if self.scheduler.get_speed() != 1.0 {
+            badges.push(Badge::new(
+                format!("{}x", self.scheduler.get_speed()),
+                theme.playback,
+            ));
+        }
+        badges
     }
 
     fn display_wizard_info(&self) -> String {
         let wizard = self.game.get_wizard();
+        let action_points = self
+            .game
+            .get_action_points()
+            .map_or(String::new(), |action_points| {
+                format!(
+                    "Action Points: {}/{}\n\n",
+                    action_points, ACTION_POINTS_PER_TURN
+                )
+            });
         format!(
             "Hitpoints: {}\n
 Armor: {}\n
 Mana: {}\n
-Total Mana Used: {}\n
+{}Total Mana Used: {}\n
 Effects: {}\n
+Items: {}\n
 Spells Used: {}",
             wizard.get_hitpoints(),
             wizard.get_armor(),
             wizard.get_mana(),
+            action_points,
             self.game.get_mana_used(),
             self.display_wizard_effects(),
+            self.display_wizard_items(),
             self.display_wizard_spells_used()
         )
     }
 
+    fn display_wizard_items(&self) -> String {
+        let mut items = String::new();
+        for item in self.game.get_wizard().get_equipment().get_items() {
+            items.push_str(&format!(
+                "\n{}: {}",
+                item.get_display_name(),
+                item.get_description()
+            ));
+        }
+        items
+    }
+
     fn display_wizard_effects(&self) -> String {
         let mut effects = String::new();
         let wizard = self.game.get_wizard();
         if let Some(timer) = wizard.get_shielded() {
-            effects.push_str(&format!("\n- Shielded: {} turns left", timer));
+            effects.push_str(&format!(
+                "\n{} Shielded: {} turns left",
+                theme::SHIELD_SYMBOL,
+                timer
+            ));
         }
         if let Some(timer) = wizard.get_recharging() {
-            effects.push_str(&format!("\n- Recharging: {} turns left", timer));
+            effects.push_str(&format!(
+                "\n{} Recharging: {} turns left",
+                theme::RECHARGE_SYMBOL,
+                timer
+            ));
+        }
+        if let Some(timer) = wizard.get_thorns() {
+            effects.push_str(&format!(
+                "\n{} Thorns: {} turns left",
+                theme::THORNS_SYMBOL,
+                timer
+            ));
+        }
+        if let Some(timer) = wizard.get_familiar() {
+            effects.push_str(&format!(
+                "\n{} Familiar: {} turns left",
+                theme::FAMILIAR_SYMBOL,
+                timer
+            ));
+        }
+        if let Some(timer) = wizard.get_silenced() {
+            effects.push_str(&format!(
+                "\n{} Silenced: {} turns left",
+                theme::SILENCE_SYMBOL,
+                timer
+            ));
+        }
+        let burns = wizard.get_burns();
+        if burns.get_stacks() > 0 {
+            let timers: Vec<String> = burns
+                .get_timers()
+                .iter()
+                .filter(|timer| **timer > 0)
+                .map(ToString::to_string)
+                .collect();
+            effects.push_str(&format!(
+                "\n{} Burning x{}: {} turns",
+                theme::BURN_SYMBOL,
+                burns.get_stacks(),
+                timers.join(", ")
+            ));
         }
         effects
     }
@@ -578,57 +2709,166 @@ Spells Used: {}",
                 "\n{}. {} (-{} mana)",
                 i + 1,
                 spell.get_display_name(),
-                spell.get_mana()
+                self.game.get_spellbook().get_mana(spell)
             ));
         }
         spells_used
     }
 
-    fn display_boss_info(&self) -> String {
+    /// Name of the AI the boss decides on its ability with, None for the usual greedy one
+    /// or a boss without an ability
+    fn describe_boss_ai(&self) -> Option<String> {
         let boss = self.game.get_boss();
+        let mode = boss.get_ai();
+        if boss.get_ability().is_none() || mode == BossAiMode::Greedy {
+            return None;
+        }
+        let name = mode.get_ai(self.game.get_hard_mode()).get_name();
+        Some(if mode.get_key() == name {
+            name.to_string()
+        } else {
+            format!("{} ({})", mode.get_key(), name)
+        })
+    }
+
+    fn display_boss_info(&self) -> String {
+        let boss = self.game.get_boss_view();
+        let ability = match (
+            self.game.get_boss().get_ability(),
+            self.game.get_boss().get_ability_countdown(),
+        ) {
+            (Some(ability), Some(countdown)) => format!(
+                "{} in: {} {}\n\n",
+                ability,
+                countdown,
+                if countdown == 1 { "attack" } else { "attacks" }
+            ),
+            _ => String::new(),
+        };
+        let ai = match self.describe_boss_ai() {
+            Some(ai) => format!("AI: {}\n\n", ai),
+            None => String::new(),
+        };
+        let spiky_skin = match self.game.get_boss().get_spiky_skin() {
+            0 => String::new(),
+            damage => format!("Spiky skin: {} per hit\n\n", damage),
+        };
         format!(
             "Hitpoints: {}\n
 Armor: (ignored)\n
 Damage: {}\n
-Effects: {}",
-            boss.get_hitpoints(),
-            boss.get_damage(),
+{}{}{}Effects: {}",
+            boss.hitpoints,
+            boss.damage,
+            ability,
+            ai,
+            spiky_skin,
             self.display_boss_effects()
         )
     }
 
     fn display_boss_effects(&self) -> String {
         if let Some(timer) = self.game.get_boss().get_poisoned() {
-            format!("\n- Poisoned: {} turns left", timer)
+            format!("\n{} Poisoned: {} turns left", theme::POISON_SYMBOL, timer)
         } else {
             String::with_capacity(0)
         }
     }
 
+    /// Index of the button of `spell`, in reading order
+    fn get_spell_button(spell: &Spell) -> Option<usize> {
+        SPELL_BUTTONS.iter().position(|button| button == spell)
+    }
+
+    /// Spells with a button, in reading order: the extra spells only if any is unsealed
+    fn get_spells_shown(&self) -> Vec<Spell> {
+        let shown = if self.has_extra_spells() {
+            SPELL_BUTTONS.len()
+        } else {
+            5
+        };
+        SPELL_BUTTONS[..shown].to_vec()
+    }
+
     fn create_spell_select_button<'b>(
         &self,
         spell: Spell,
         is_selected: bool,
     ) -> CenterPosition<'b> {
         let color = if is_selected {
-            Color::Magenta
+            theme::get().accent
         } else {
             Color::Gray
         };
 
+        let key = Self::get_spell_button(&spell)
+            .and_then(|index| self.spell_keys.get(index))
+            .map_or(String::new(), |key| format!("[{}] ", key));
+        let spellbook = self.game.get_spellbook();
+        let action_points = match self.game.get_action_points() {
+            Some(_) => format!(", {} AP", spellbook.get_action_points(&spell)),
+            None => String::new(),
+        };
+        let availability = self.game.get_spell_availability(&spell);
+        let reason = match availability {
+            SpellAvailability::AvailableAtCost { hitpoints } => format!(" (-{} HP)", hitpoints),
+            _ => availability
+                .get_reason()
+                .map_or(String::new(), |reason| format!(" ({})", reason)),
+        };
         let center_pos = CenterPosition::default()
             .text(format!(
-                "{}: {} Mana",
+                "{}{}: {} Mana{}{}",
+                key,
                 spell.get_display_name(),
-                spell.get_mana()
+                spellbook.get_mana(&spell),
+                action_points,
+                reason
             ))
             .block(Block::bordered().border_style(Style::default().fg(color)));
-        if !self
-            .game
-            .get_wizard()
-            .get_possible_spells()
-            .contains(&spell)
-        {
+        if !availability.is_available() {
+            center_pos.unavailable()
+        } else {
+            center_pos
+        }
+    }
+
+    /// Button of a spell bar, `width` wide: just the key, the initials and the mana.
+    /// `bordered` buttons show the selection by the border's color like the other buttons,
+    /// and the rest by being reversed.
+    fn create_short_spell_button<'b>(
+        &self,
+        spell: &Spell,
+        is_selected: bool,
+        bordered: bool,
+        width: u16,
+    ) -> CenterPosition<'b> {
+        let key = Self::get_spell_button(spell)
+            .and_then(|index| self.spell_keys.get(index))
+            .map_or(String::new(), |key| format!("{} ", key));
+        let text: String = format!(
+            "{}{} {}",
+            key,
+            format_spell(spell),
+            self.game.get_spellbook().get_mana(spell)
+        )
+        .chars()
+        .take(width.saturating_sub(if bordered { 2 } else { 0 }) as usize)
+        .collect();
+        let mut center_pos = CenterPosition::default().text(text);
+        if bordered {
+            let color = if is_selected {
+                theme::get().accent
+            } else {
+                Color::Gray
+            };
+            center_pos =
+                center_pos.block(Block::bordered().border_style(Style::default().fg(color)));
+        } else if is_selected {
+            center_pos = center_pos
+                .block(Block::new().style(Style::default().fg(theme::get().accent).reversed()));
+        }
+        if !self.game.get_spell_availability(spell).is_available() {
             center_pos.unavailable()
         } else {
             center_pos
@@ -636,12 +2876,757 @@ Effects: {}",
     }
 }
 
-fn main() -> io::Result<()> {
-    let matches = command!()
+/// Summary of a challenge file for the file picker's preview
+fn describe_challenge(path: &Path) -> String {
+    let challenge = match Challenge::load(path) {
+        Ok(challenge) => challenge,
+        Err(err) => return format!("Not a valid challenge: {}", err),
+    };
+    let default = Spellbook::default();
+    let changed_spells: Vec<&str> = Spell::ALL
+        .iter()
+        .filter(|spell| {
+            challenge.spellbook.get(spell) != default.get(spell)
+                || challenge.spellbook.is_sealed(spell) != default.is_sealed(spell)
+        })
+        .map(|spell| spell.get_display_name())
+        .collect();
+    let loadout = challenge
+        .loadout
+        .as_ref()
+        .map_or("none".to_string(), |loadout| {
+            let spells: Vec<&str> = loadout
+                .spells
+                .iter()
+                .map(|spell| spell.get_display_name())
+                .collect();
+            format!(
+                "{}, {}",
+                spells.join(", "),
+                loadout.passive.get_display_name()
+            )
+        });
+    format!(
+        "{}\n\nBoss: {}, {} hitpoints, {} damage\nDifficulty: {}\nTurns: {}\nShort of mana: {}\nChanged spells: {}\nLoadout: {}\nItems: {}\nSeed: {}\nRules: {} ({})",
+        challenge.name,
+        challenge.boss.name,
+        challenge.boss.hitpoints,
+        challenge.boss.damage,
+        if challenge.hard_mode { "hard" } else { "normal" },
+        if challenge.action_points {
+            "action points"
+        } else {
+            "one cast each"
+        },
+        if challenge.blood_magic {
+            "pay in hitpoints"
+        } else {
+            "can't cast"
+        },
+        if changed_spells.is_empty() {
+            "none".to_string()
+        } else {
+            changed_spells.join(", ")
+        },
+        loadout,
+        if challenge.equipment.is_empty() {
+            "none".to_string()
+        } else {
+            let items: Vec<&str> = challenge
+                .equipment
+                .get_items()
+                .map(|item| item.get_display_name())
+                .collect();
+            items.join(", ")
+        },
+        challenge.seed,
+        challenge.rules_version,
+        challenge.get_bucket()
+    )
+}
+
+/// Command line of the game and its subcommands
+fn command() -> Command {
+    let command = command!()
         .arg(arg!(--hard "Set difficulty to hard"))
-        .get_matches();
+        .arg(arg!(--"action-points" "Give the wizard action points each turn, so two cheap spells can be cast in one turn"))
+        .arg(arg!(--"blood-magic" "Let the wizard cast spells they are short of mana for, paying a hitpoint for every 2 mana short"))
+        .arg(
+            arg!(--"max-mana" <MANA> "Most mana the wizard can hold, so Recharge can't stock up beyond it")
+                .value_parser(value_parser!(i32).range(1..)),
+        )
+        .arg(arg!(--"extra-spells" "Unseal the spells beyond the original puzzle's: Cleanse, Thorns and Summon Familiar"))
+        .arg(
+            cli::challenge_arg()
+                .num_args(0..=1)
+                .help("Challenge file to play, or browse for one if FILE is left out")
+                .conflicts_with("hard"),
+        )
+        .arg(
+            arg!(--fps <FPS> "Frames rendered per second")
+                .value_parser(value_parser!(u32).range(1..=240))
+                .default_value("20"),
+        )
+        .arg(
+            arg!(--"phase-delay" <MS> "Milliseconds to wait between the phases of a round")
+                .value_parser(value_parser!(u64))
+                .default_value("0"),
+        )
+        .arg(aoc::arg().conflicts_with("challenge"))
+        .arg(presets::arg().conflicts_with_all(["challenge", "aoc-session", "random"]))
+        .arg(webhook::arg())
+        .args(ambiance::args())
+        .arg(
+            arg!(--random <TIER> "Fight a generated boss, with up to two mutators, of this difficulty")
+                .value_parser(DifficultyTier::ALL.map(DifficultyTier::get_name))
+                .num_args(0..=1)
+                .default_missing_value("normal")
+                .conflicts_with_all(["challenge", "aoc-session"]),
+        )
+        .arg(
+            arg!(--"random-seed" <SEED> "Seed of the generated boss [default: the current time]")
+                .value_parser(value_parser!(u64))
+                .requires("random"),
+        )
+        .args(rush::args())
+        .arg(endless::arg().conflicts_with_all([
+            "boss-rush",
+            "challenge",
+            "aoc-session",
+            "random",
+            "preset",
+            "campaign",
+            "export-csv",
+        ]))
+        .arg(
+            arg!(--campaign <FILE> "Campaign file to play, or the built-in campaign if FILE is left out")
+                .value_parser(value_parser!(PathBuf))
+                .num_args(0..=1)
+                .conflicts_with_all(["challenge", "aoc-session", "random", "preset", "export-csv"]),
+        )
+        .arg(
+            arg!(--"spell-keys" <KEYS> "Keys casting Magic Missile, Drain, Poison, Shield, Recharge, Cleanse, Thorns and Summon Familiar, in that order; the last three may be left out")
+                .value_parser(action::parse_spell_keys)
+                .default_value(action::DEFAULT_SPELL_KEYS),
+        )
+        .arg(
+            arg!(--"spell-layout" <LAYOUT> "Arrange the spell buttons in a grid, or in a single bar moved along with left and right")
+                .value_parser(ui::SpellArrangement::ALL.map(|arrangement| arrangement.get_key()))
+                .default_value("grid"),
+        )
+        .arg(arg!(--"no-insurance" "Don't ask before casting a spell that leaves the wizard dead to the boss's turn"))
+        .arg(arg!(--classic "Show the boss's hitpoints and damage from the start, instead of scouting them"))
+        .arg(arg!(--loadout "Pick the spells and trait the wizard takes into the battle before it starts"))
+        .arg(arg!(--ironman "Play without hints, the forecast, sandbox, blunder check, confirmations or autosave"))
+        .arg(arg!(--"no-taunts" "Don't show what the boss says during the battle"))
+        .arg(arg!(--"reduced-motion" "Don't play the battle intro and ending, animate spells as they are cast or the idle sprites, or shake the screen"))
+        .arg(arg!(--"no-intro" "Don't play the intro when a battle starts"))
+        .arg(arg!(--"no-flash" "Don't flash the boss panel on the killing blow"))
+        .arg(arg!(--"round-markers" "Prefix event lines with their round and turn, such as R3 • Boss"))
+        .arg(arg!(--"focus-follow" "Highlight the event line being typed out and keep it in the middle of the event window, dimming the lines before it"))
+        .arg(arg!(--debug "Enable the debug overlay (F12) and stepping through phases (n)"))
+        .arg(arg!(--"blunder-check" "Indicate when the position can no longer be won"))
+        .arg(
+            arg!(--replay <LINE> "Play a line of spells in notation, such as \"P R S MM\", before handing the battle over")
+                .value_parser(|line: &str| parse_line(line)),
+        )
+        .arg(arg!(--review "After a finished battle, grade each cast against the solver"))
+        .arg(
+            arg!(--"history" <ROUNDS> "Rounds of the battle to keep snapshots of, for the review")
+                .value_parser(value_parser!(usize))
+                .default_value("100"),
+        )
+        .arg(
+            arg!(--"blunder-depth" <ROUNDS> "Rounds to look ahead for the blunder check")
+                .value_parser(value_parser!(usize))
+                .default_value("8"),
+        )
+        .arg(
+            arg!(--"autosave-dir" <DIR> "Directory to autosave to [default: ~/.local/state/advent-wizard-rpg/local/autosaves]")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"autosave-keep" <COUNT> "Autosaves to keep, or 0 to turn autosaving off")
+                .value_parser(value_parser!(usize))
+                .default_value("5"),
+        )
+        .args(save_slots::args())
+        .arg(
+            arg!(--"export-csv" <FILE> "Write a row per round of the battle to a CSV file on exit")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--palette <PALETTE> "Colors to draw with, including palettes safe for color blindness")
+                .value_parser(theme::PALETTE_NAMES)
+                .default_value("default")
+                .global(true),
+        )
+        .subcommand(analyze::command())
+        .subcommand(solve::command())
+        .subcommand(simulate::command())
+        .subcommand(mirror::command())
+        .subcommand(ranking::command())
+        .subcommand(audit::command())
+        .subcommand(boss_editor::command())
+        .subcommand(spellbook_editor::command())
+        .subcommand(battle_form::command())
+        .subcommand(book_builder::command())
+        .subcommand(presets::command())
+        .subcommand(spectate::command())
+        .subcommand(data_dir::command())
+        .subcommand(profile::command())
+        .subcommand(completions::command())
+        .subcommand(manpage::command());
+    #[cfg(feature = "logging")]
+    let command = command.args(logger::args());
+    #[cfg(feature = "sandbox")]
+    let command = command.arg(sandbox::arg());
+    #[cfg(feature = "results-db")]
+    let command = command.subcommand(tally::command());
+    command
+}
+
+fn main() -> io::Result<()> {
+    let matches = command().get_matches();
+    // Before anything reads the data directory, and before the log is opened in it. The
+    // `migrate` subcommand migrates it itself, listing the changes.
+    let migrated = match matches.subcommand_name() {
+        Some("migrate") => Ok(()),
+        _ => data_dir::migrate_if_outdated(),
+    };
+    if let Err(err) = migrated {
+        eprintln!(
+            "Could not migrate the data directory {}: {}",
+            data_dir::root().display(),
+            err
+        );
+    }
+    #[cfg(feature = "logging")]
+    logger::init_from_matches(&matches);
+    crash_report::install_panic_hook();
+    theme::set_palette(
+        theme::Palette::from_name(matches.get_one::<String>("palette").unwrap()).unwrap(),
+    );
+    theme::set_effects(theme::EffectFlags {
+        shake: !matches.get_flag("reduced-motion"),
+        flash: !matches.get_flag("no-flash"),
+    });
+
+    // Custom battle described by `new`
+    let mut custom_challenge = None;
+    match matches.subcommand() {
+        Some(("analyze", sub_matches)) => {
+            analyze::run(sub_matches);
+            return Ok(());
+        }
+        Some(("solve", sub_matches)) => {
+            solve::run(sub_matches);
+            return Ok(());
+        }
+        Some(("simulate", sub_matches)) => {
+            simulate::run(sub_matches);
+            return Ok(());
+        }
+        Some(("duel", sub_matches)) => {
+            mirror::run(sub_matches);
+            return Ok(());
+        }
+        Some(("tournament", sub_matches)) => {
+            ranking::run(sub_matches);
+            return Ok(());
+        }
+        Some(("audit", sub_matches)) => return audit::run(sub_matches),
+        Some(("edit-bestiary", sub_matches)) => return boss_editor::run(sub_matches),
+        Some(("edit-spellbook", sub_matches)) => return spellbook_editor::run(sub_matches),
+        Some(("watch", sub_matches)) => return spectate::run(&matches, sub_matches),
+        Some(("book", sub_matches)) => return book_builder::run(sub_matches),
+        Some(("migrate", _)) => return data_dir::run(),
+        Some(("profile", sub_matches)) => return profile::run(sub_matches),
+        Some(("presets", sub_matches)) => {
+            presets::run(sub_matches);
+            return Ok(());
+        }
+        Some(("new", sub_matches)) => match battle_form::run(sub_matches)? {
+            Some(challenge) => custom_challenge = Some(challenge),
+            None => return Ok(()),
+        },
+        #[cfg(feature = "results-db")]
+        Some(("stats", sub_matches)) => return tally::run(sub_matches),
+        Some(("completions", sub_matches)) => {
+            completions::run(sub_matches, command());
+            return Ok(());
+        }
+        Some(("manpage", _)) => return manpage::run(command()),
+        _ => (),
+    }
+
+    let blunder_check = matches.get_flag("blunder-check").then(|| SearchBudget {
+        max_depth: *matches.get_one::<usize>("blunder-depth").unwrap(),
+        time_limit: Some(BLUNDER_CHECK_TIME_LIMIT),
+    });
+    let challenge = custom_challenge
+        .or_else(|| cli::challenge_from_matches(&matches))
+        .or_else(|| aoc::challenge_from_matches(&matches))
+        .or_else(|| preset_challenge_from_matches(&matches))
+        .or_else(|| random_challenge_from_matches(&matches));
+    // Ironman battles can't be saved or recovered
+    let autosave_keep = if matches.get_flag("ironman") {
+        0
+    } else {
+        *matches.get_one::<usize>("autosave-keep").unwrap()
+    };
+    let autosave_dir = matches
+        .get_one::<PathBuf>("autosave-dir")
+        .cloned()
+        .unwrap_or_else(autosave::default_dir);
+
+    let campaign = campaign_from_matches(&matches);
+
     let mut terminal = tui::init()?;
-    let app_result = App::new(matches.get_flag("hard")).run(&mut terminal);
+    if let Some(campaign) = campaign {
+        let result = play_campaign(&mut terminal, &matches, &campaign, blunder_check);
+        tui::restore()?;
+        return result;
+    }
+    if matches.get_flag("survival") {
+        let result = endless::play(&mut terminal, &matches, blunder_check);
+        tui::restore()?;
+        return result;
+    }
+    if let Some(bestiary) = rush::bestiary_from_matches(&matches) {
+        let result = rush::play(&mut terminal, &matches, &bestiary, blunder_check);
+        tui::restore()?;
+        return result;
+    }
+    let recovery_dir = (autosave_keep > 0).then_some(autosave_dir.as_path());
+    let app_result =
+        set_up_game(&mut terminal, &matches, challenge, recovery_dir).and_then(|setup| {
+            let Some(mut setup) = setup else {
+                return Ok(());
+            };
+            // Recovered battles keep the rules they were played with
+            if matches.get_flag("action-points") && setup.spells.is_empty() {
+                setup.challenge.action_points = true;
+            }
+            if matches.get_flag("blood-magic") && setup.spells.is_empty() {
+                setup.challenge.blood_magic = true;
+            }
+            if let Some(max_mana) = matches.get_one::<i32>("max-mana") {
+                if setup.spells.is_empty() {
+                    setup.challenge.max_mana = Some(*max_mana);
+                }
+            }
+            if matches.get_flag("extra-spells") && setup.spells.is_empty() {
+                setup.challenge.spellbook = setup.challenge.spellbook.clone().with_extra_spells();
+            }
+            if let Some(spells) = matches.get_one::<Vec<Spell>>("replay") {
+                // Lines hold one cast per round, so they can't say where turns end
+                if setup.challenge.action_points {
+                    tui::restore()?;
+                    eprintln!(
+                        "Could not replay {}: lines can't be replayed with action points",
+                        format_line(spells)
+                    );
+                    process::exit(2);
+                }
+                let replay = SaveGame {
+                    challenge: setup.challenge.clone(),
+                    spells: spells.clone(),
+                };
+                if replay.restore().is_none() {
+                    tui::restore()?;
+                    eprintln!(
+                        "Could not replay {}: a spell can't be cast or the battle ends before it",
+                        format_line(spells)
+                    );
+                    process::exit(2);
+                }
+                setup.spells = replay.spells;
+            }
+            // Challenges and recovered battles keep the loadout they were played with
+            if matches.get_flag("loadout")
+                && setup.spells.is_empty()
+                && setup.challenge.loadout.is_none()
+            {
+                match ui::pick_loadout(&mut terminal, &setup.challenge.spellbook)? {
+                    Some(loadout) => setup.challenge.loadout = Some(loadout),
+                    None => return Ok(()),
+                }
+            }
+            crash_report::set_challenge(&setup.challenge);
+            // Sandbox changes can't be replayed from the spells cast, and neither can turns
+            // with several casts
+            let autosave_keep = if setup.challenge.action_points {
+                0
+            } else {
+                autosave_keep
+            };
+            #[cfg(feature = "sandbox")]
+            let autosave_keep = if matches.get_flag("sandbox") {
+                0
+            } else {
+                autosave_keep
+            };
+            let autosaver = (autosave_keep > 0).then(|| {
+                Autosaver::new(autosave_dir.clone(), autosave_keep, setup.challenge.clone())
+            });
+            // Slots can't hold what autosaves can't, nor ironman battles
+            #[cfg(feature = "sandbox")]
+            let saveable = !matches.get_flag("sandbox");
+            #[cfg(not(feature = "sandbox"))]
+            let saveable = true;
+            let saveable =
+                saveable && !setup.challenge.action_points && !matches.get_flag("ironman");
+            let slot_saver = saveable.then(|| {
+                save_slots::SlotSaver::new(
+                    save_slots::dir_from_matches(&matches),
+                    setup.challenge.clone(),
+                )
+            });
+            let mut app = create_app(
+                &matches,
+                setup
+                    .challenge
+                    .create_battle()
+                    .with_history(*matches.get_one::<usize>("history").unwrap()),
+                setup.boss_name,
+                &setup.challenge,
+                blunder_check,
+                autosaver,
+            );
+            app.slot_saver = slot_saver;
+            app.run(&mut terminal, &setup.spells)?;
+            #[cfg(feature = "sandbox")]
+            let review = matches.get_flag("review")
+                && !matches.get_flag("sandbox")
+                && !setup.challenge.action_points;
+            #[cfg(not(feature = "sandbox"))]
+            let review = matches.get_flag("review") && !setup.challenge.action_points;
+            if review && app.game.get_outcome().is_some() {
+                let mut start = setup.challenge.create_battle();
+                start.wizard_turn_apply_effects();
+                ui::review_battle(
+                    &mut terminal,
+                    &start,
+                    app.game.get_spells_used(),
+                    app.game.history(),
+                )?;
+            }
+            match matches.get_one::<PathBuf>("export-csv") {
+                Some(path) => fs::write(path, app.stats.journal_to_csv()),
+                None => Ok(()),
+            }
+        });
     tui::restore()?;
+    // An error may have left the battle before it ended
+    ambiance::stop();
+    if let Err(err) = &app_result {
+        crash_report::print_report_path(crash_report::write_report(&err.to_string()));
+    }
     app_result
 }
+
+/// App for `battle` of `challenge`, set up from the arguments
+fn create_app(
+    matches: &ArgMatches,
+    battle: Battle,
+    boss_name: Option<String>,
+    challenge: &Challenge,
+    blunder_check: Option<SearchBudget>,
+    autosaver: Option<Autosaver>,
+) -> App {
+    // The search plays one cast per round, so can't tell when a battle with action points
+    // is lost
+    let blunder_check = blunder_check.filter(|_| !challenge.action_points);
+    let mut app = App::new(
+        battle,
+        boss_name,
+        *matches.get_one::<u32>("fps").unwrap(),
+        Duration::from_millis(*matches.get_one::<u64>("phase-delay").unwrap()),
+        blunder_check,
+        autosaver,
+        matches.get_flag("debug"),
+    );
+    app.insurance = !matches.get_flag("no-insurance");
+    if matches.get_flag("classic") {
+        app.game.reveal(KnowledgeMask::FULL);
+    }
+    app.event_log = EventLog::new(matches.get_flag("round-markers"));
+    app.focus_follow = matches.get_flag("focus-follow");
+    app.animate = !matches.get_flag("reduced-motion");
+    app.intro_banner =
+        (app.animate && !matches.get_flag("no-intro")).then(|| challenge.boss.get_banner());
+    app.taunts = (!matches.get_flag("no-taunts")).then(|| challenge.boss.taunts.clone());
+    app.spell_keys = matches.get_one::<Vec<char>>("spell-keys").unwrap().clone();
+    app.spell_arrangement =
+        ui::SpellArrangement::from_key(matches.get_one::<String>("spell-layout").unwrap()).unwrap();
+    app.book_key = BookKey::from_challenge(challenge);
+    app.webhook = webhook::url_from_matches(matches);
+    app.ambiance = ambiance::Ambiance::from_matches(matches, &challenge.boss);
+    #[cfg(feature = "sandbox")]
+    {
+        app.sandbox = matches.get_flag("sandbox");
+        // Sandbox changes take the battle off the book's lines, and its results aren't
+        // worth sharing or counting
+        if app.sandbox {
+            app.book_key = None;
+            app.webhook = None;
+            app.session_recorded = true;
+        }
+    }
+    if matches.get_flag("ironman") {
+        app.set_ironman();
+    }
+    app
+}
+
+/// Challenge of the preset given by `--preset`, if any, in hard mode if asked for
+fn preset_challenge_from_matches(matches: &ArgMatches) -> Option<Challenge> {
+    let mut challenge = presets::challenge_from_matches(matches)?;
+    challenge.hard_mode |= matches.get_flag("hard");
+    Some(challenge)
+}
+
+/// Challenge against a boss generated by `--random`, if given. The name lists the
+/// mutators, so they show in the boss panel.
+fn random_challenge_from_matches(matches: &ArgMatches) -> Option<Challenge> {
+    let tier = DifficultyTier::from_name(matches.get_one::<String>("random")?).unwrap();
+    let seed = matches
+        .get_one::<u64>("random-seed")
+        .copied()
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since_epoch| since_epoch.as_millis() as u64)
+        });
+    let encounter = generate_encounter(tier, seed);
+    let mut challenge = encounter.challenge;
+    challenge.hard_mode |= matches.get_flag("hard");
+    if !encounter.mutators.is_empty() {
+        let mutators: Vec<String> = encounter.mutators.iter().map(ToString::to_string).collect();
+        challenge.name = format!("{} ({})", challenge.name, mutators.join(", "));
+    }
+    Some(challenge)
+}
+
+/// Campaign given by `--campaign`, the built-in one if no file was given. Exits if it
+/// can't be loaded.
+fn campaign_from_matches(matches: &ArgMatches) -> Option<Campaign> {
+    if !matches.contains_id("campaign") {
+        return None;
+    }
+    let Some(path) = matches.get_one::<PathBuf>("campaign") else {
+        return Some(Campaign::builtin());
+    };
+    match Campaign::load(path) {
+        Ok(campaign) => Some(campaign),
+        Err(err) => {
+            eprintln!("Could not load campaign {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Tell each chapter of `campaign` and fight its boss, starting the battle with the
+/// bonus of the choice made, until the wizard loses or the player quits. Rewards are worn
+/// in every battle after they are won, and the gold won is spent in a shop before the
+/// next chapter. Campaign battles aren't autosaved, since a save can't hold the
+/// campaign's progress.
+fn play_campaign(
+    terminal: &mut tui::Tui,
+    matches: &ArgMatches,
+    campaign: &Campaign,
+    blunder_check: Option<SearchBudget>,
+) -> io::Result<()> {
+    let mut progress = CampaignProgress::default();
+    for (i, chapter) in campaign.chapters.iter().enumerate() {
+        let title = format!("Chapter {}: {}", i + 1, chapter.title);
+        let mut text = chapter.text.clone();
+        if progress.intel {
+            text.push_str(&format!("\n\n{}", describe_intel(&chapter.boss)));
+        }
+        let labels: Vec<&str> = chapter
+            .choices
+            .iter()
+            .map(|choice| choice.label.as_str())
+            .collect();
+        let Some(choice) = ui::tell_story(terminal, &title, &text, &labels)? else {
+            return Ok(());
+        };
+        let choice = &chapter.choices[choice];
+
+        let challenge = Challenge {
+            hard_mode: matches.get_flag("hard"),
+            equipment: progress.equipment,
+            ..Challenge::new(chapter.boss.clone())
+        };
+        crash_report::set_challenge(&challenge);
+        let mut battle = challenge.create_battle().with_wizard_bonus(
+            choice.hitpoints + progress.hitpoints,
+            choice.mana + progress.mana,
+        );
+        if progress.intel {
+            battle.reveal(KnowledgeMask::FULL);
+        }
+        progress.start_battle();
+        let mut app = create_app(
+            matches,
+            battle,
+            Some(chapter.boss.name.clone()),
+            &challenge,
+            blunder_check,
+            None,
+        );
+        app.run(terminal, &[])?;
+        if app.game.get_outcome() != Some(true) {
+            return Ok(());
+        }
+        if let Some(item) = chapter.reward {
+            let mut text = format!(
+                "The boss dropped the {}: {}.",
+                item.get_display_name(),
+                item.get_description()
+            );
+            if let Some(replaced) = progress.equipment.equip(item) {
+                text.push_str(&format!(
+                    "\n\nThe wizard takes off the {}.",
+                    replaced.get_display_name()
+                ));
+            }
+            ui::tell_story(terminal, "Reward", &text, &[])?;
+        }
+        let gold = get_victory_gold(&app.game);
+        progress.gold += gold;
+        if i + 1 < campaign.chapters.len() && !visit_shop(terminal, &mut progress, gold)? {
+            return Ok(());
+        }
+    }
+    ui::tell_story(terminal, &campaign.name, &campaign.ending, &[])?;
+    Ok(())
+}
+
+/// Let the player spend the campaign's gold, after winning `gold` in the last battle.
+/// Returns false if the player quit.
+fn visit_shop(
+    terminal: &mut tui::Tui,
+    progress: &mut CampaignProgress,
+    gold: u32,
+) -> io::Result<bool> {
+    let mut text = format!("Victory! The wizard finds {} gold.", gold);
+    loop {
+        text.push_str(&format!("\n\nGold: {}", progress.gold));
+        let offers = ShopOffer::get_offers(progress);
+        let mut labels: Vec<String> = offers.iter().map(|offer| offer.get_label()).collect();
+        labels.push("Leave the shop".to_string());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let Some(choice) = ui::tell_story(terminal, "Shop", &text, &labels)? else {
+            return Ok(false);
+        };
+        let Some(offer) = offers.get(choice) else {
+            return Ok(true);
+        };
+        text = if progress.buy(*offer) {
+            "Bought.".to_string()
+        } else {
+            "Not enough gold.".to_string()
+        };
+    }
+}
+
+/// What to play when the game starts
+struct GameSetup {
+    challenge: Challenge,
+    /// Shown in the boss panel when playing a challenge
+    boss_name: Option<String>,
+    /// Rounds already played in a recovered autosave
+    spells: Vec<Spell>,
+}
+
+/// Resume a save slot or browse for a challenge if asked to, otherwise offer to recover an
+/// autosave from `recovery_dir`. Returns None if the user cancelled.
+fn set_up_game(
+    terminal: &mut tui::Tui,
+    matches: &ArgMatches,
+    challenge: Option<Challenge>,
+    recovery_dir: Option<&Path>,
+) -> io::Result<Option<GameSetup>> {
+    if matches.contains_id("load") {
+        let dir = save_slots::dir_from_matches(matches);
+        let save = match matches.get_one::<String>("load") {
+            Some(name) => match save_slots::load(&dir, name) {
+                Ok(save) => save,
+                Err(err) => {
+                    tui::restore()?;
+                    eprintln!("Could not load slot {}: {}", name, err);
+                    process::exit(1);
+                }
+            },
+            None => match save_slots::pick_to_load(terminal, &dir)? {
+                Some(save) => save,
+                None => return Ok(None),
+            },
+        };
+        return Ok(Some(GameSetup {
+            boss_name: Some(save.challenge.name.clone()),
+            challenge: save.challenge,
+            spells: save.spells,
+        }));
+    }
+
+    let challenge = match challenge {
+        Some(challenge) => Some(challenge),
+        None if matches.contains_id("challenge") => {
+            let picked = ui::pick_file(
+                terminal,
+                "Load Challenge",
+                Path::new("."),
+                CHALLENGE_FILE_SUFFIX,
+                &describe_challenge,
+            )?;
+            match picked {
+                Some(path) => Some(Challenge::load(&path)?),
+                None => return Ok(None),
+            }
+        }
+        None => None,
+    };
+    if let Some(challenge) = challenge {
+        return Ok(Some(GameSetup {
+            boss_name: Some(challenge.name.clone()),
+            challenge,
+            spells: Vec::new(),
+        }));
+    }
+
+    let recovered = recovery_dir
+        .and_then(autosave::find_recoverable)
+        .and_then(|path| SaveGame::load(&path).ok())
+        .filter(|save| {
+            save.restore()
+                .is_some_and(|battle| battle.get_outcome().is_none())
+        });
+    if let Some(save) = recovered {
+        let question = format!(
+            "The last game didn't exit cleanly. Resume {} from round {}?",
+            save.challenge.name,
+            save.spells.len() + 1
+        );
+        if ui::confirm(terminal, "Recover Autosave", &question)? {
+            return Ok(Some(GameSetup {
+                boss_name: Some(save.challenge.name.clone()),
+                challenge: save.challenge,
+                spells: save.spells,
+            }));
+        }
+    }
+
+    let boss = Bestiary::builtin().get_entries()[0].clone();
+    Ok(Some(GameSetup {
+        challenge: Challenge {
+            hard_mode: matches.get_flag("hard"),
+            ..Challenge::new(boss)
+        },
+        boss_name: None,
+        spells: Vec::new(),
+    }))
+}
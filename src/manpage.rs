@@ -0,0 +1,13 @@
+//! `manpage` subcommand: print the man page of the game in roff.
+
+use clap::Command;
+use clap_mangen::Man;
+use std::io;
+
+pub fn command() -> Command {
+    Command::new("manpage").about("Print the man page, to be read with `man -l -`")
+}
+
+pub fn run(command: Command) -> io::Result<()> {
+    Man::new(command).render(&mut io::stdout())
+}
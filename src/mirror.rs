@@ -0,0 +1,86 @@
+//! `duel` subcommand: two strategy bots fight a wizard mirror match.
+
+use advent_wizard_rpg::{
+    duel::Duel,
+    strategy::{create_strategy, STRATEGY_NAMES},
+};
+use clap::{arg, value_parser, ArgMatches, Command};
+
+/// Upper bound on turns, in case neither bot can finish the other off
+const MAX_TURNS: usize = 1000;
+
+pub fn command() -> Command {
+    Command::new("duel")
+        .about("Watch two strategy bots fight a wizard mirror match")
+        .arg(
+            arg!(--blue <STRATEGY> "Strategy of the wizard who casts first")
+                .value_parser(STRATEGY_NAMES)
+                .default_value("greedy"),
+        )
+        .arg(
+            arg!(--red <STRATEGY> "Strategy of the other wizard")
+                .value_parser(STRATEGY_NAMES)
+                .default_value("random"),
+        )
+        .arg(
+            arg!(--seed <SEED> "Seed for random strategies")
+                .value_parser(value_parser!(u64))
+                .default_value("0"),
+        )
+}
+
+pub fn run(matches: &ArgMatches) {
+    let seed = *matches.get_one::<u64>("seed").unwrap();
+    let names = ["Blue", "Red"];
+    let mut strategies = [
+        create_strategy(matches.get_one::<String>("blue").unwrap(), seed).unwrap(),
+        create_strategy(matches.get_one::<String>("red").unwrap(), seed + 1).unwrap(),
+    ];
+
+    let mut duel = Duel::new();
+    for turn in 1..=MAX_TURNS {
+        let side = duel.get_turn();
+        let (spell, winner) = duel.play_turn(strategies[side].as_mut());
+        match spell {
+            Some(spell) => println!(
+                "Turn {}: {} ({}) casts {}",
+                turn,
+                names[side],
+                strategies[side].get_name(),
+                spell.get_display_name()
+            ),
+            None if duel.get_wizard(side).get_hitpoints() > 0 => println!(
+                "Turn {}: {} ({}) has no spell to cast",
+                turn,
+                names[side],
+                strategies[side].get_name()
+            ),
+            None => println!(
+                "Turn {}: {} ({}) succumbs to poison",
+                turn,
+                names[side],
+                strategies[side].get_name()
+            ),
+        }
+        for (side, name) in names.iter().enumerate() {
+            let wizard = duel.get_wizard(side);
+            println!(
+                "  {}: {} hitpoints, {} armor, {} mana",
+                name,
+                wizard.get_hitpoints(),
+                wizard.get_armor(),
+                wizard.get_mana()
+            );
+        }
+        if let Some(winner) = winner {
+            println!(
+                "{} ({}) wins after {} turns",
+                names[winner],
+                strategies[winner].get_name(),
+                turn
+            );
+            return;
+        }
+    }
+    println!("No winner after {} turns", MAX_TURNS);
+}
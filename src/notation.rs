@@ -0,0 +1,132 @@
+//! Compact notation for lines of spells: each spell by its initials in capitals, separated
+//! by spaces, such as `P R S MM MM P MM`. Spells may be annotated with their round like
+//! moves in chess, `1.P 2.R 3.S`, and names or lowercase initials are accepted too.
+
+use crate::{ParseSpellError, Spell};
+use std::fmt;
+
+/// Why a line of spells couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseLineError {
+    Spell(ParseSpellError),
+    /// A round annotation that isn't the round the spell is cast in
+    Round {
+        annotation: String,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for ParseLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spell(err) => err.fmt(f),
+            Self::Round {
+                annotation,
+                expected,
+            } => write!(
+                f,
+                "`{}` should be annotated with round {}",
+                annotation, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseLineError {}
+
+/// Notation of a spell, its initials in capitals
+pub fn format_spell(spell: &Spell) -> String {
+    spell.get_initials().to_uppercase()
+}
+
+/// Notation of `spells`, such as `P R S MM`
+pub fn format_line(spells: &[Spell]) -> String {
+    spells
+        .iter()
+        .map(format_spell)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Notation of `spells` with the round of each, the first cast in `first_round`, such as
+/// `1.P 2.R 3.S`
+pub fn format_numbered_line(spells: &[Spell], first_round: usize) -> String {
+    spells
+        .iter()
+        .enumerate()
+        .map(|(i, spell)| format!("{}.{}", first_round + i, format_spell(spell)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse a line written in either notation, cast from round 1. Spells may also be
+/// separated by commas.
+pub fn parse_line(text: &str) -> Result<Vec<Spell>, ParseLineError> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .enumerate()
+        .map(|(i, token)| {
+            let spell = match token.split_once('.') {
+                Some((round, spell)) => {
+                    if round.parse::<usize>().ok() != Some(i + 1) {
+                        return Err(ParseLineError::Round {
+                            annotation: token.to_string(),
+                            expected: i + 1,
+                        });
+                    }
+                    spell
+                }
+                None => token,
+            };
+            spell.parse::<Spell>().map_err(ParseLineError::Spell)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_formatted_line() {
+        let line = Spell::ALL.to_vec();
+        assert_eq!(parse_line(&format_line(&line)), Ok(line));
+    }
+
+    #[test]
+    fn parses_formatted_numbered_line() {
+        let line = Spell::ALL.to_vec();
+        assert_eq!(parse_line(&format_numbered_line(&line, 1)), Ok(line));
+    }
+
+    #[test]
+    fn tells_summon_familiar_from_shield() {
+        assert_eq!(format_spell(&Spell::Summon), "SF");
+        assert_eq!(format_spell(&Spell::Shield), "S");
+        assert_eq!(parse_line("SF S"), Ok(vec![Spell::Summon, Spell::Shield]));
+    }
+
+    #[test]
+    fn accepts_commas() {
+        assert_eq!(
+            parse_line("P,R, S ,MM"),
+            Ok(vec![
+                Spell::Poison,
+                Spell::Recharge,
+                Spell::Shield,
+                Spell::MagicMissile
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_round() {
+        assert_eq!(
+            parse_line("1.P 3.R"),
+            Err(ParseLineError::Round {
+                annotation: "3.R".to_string(),
+                expected: 2,
+            })
+        );
+    }
+}
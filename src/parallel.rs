@@ -0,0 +1,76 @@
+use rayon::{iter::IntoParallelIterator, iter::ParallelIterator, ThreadPoolBuilder};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Work done by a parallel run, shared between its workers and whoever reports on them.
+/// Cancelling stops the workers from starting new jobs.
+#[derive(Debug)]
+pub struct Progress {
+    total: u64,
+    done: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub fn new(total: u64) -> Self {
+        Self {
+            total,
+            done: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    pub fn advance(&self, amount: u64) {
+        self.done.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    pub fn get_done(&self) -> u64 {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    pub fn get_total(&self) -> u64 {
+        self.total
+    }
+
+    /// Share of the work done, between 0 and 1
+    pub fn get_ratio(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.get_done() as f64 / self.total as f64).min(1.0)
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Worker threads to use when none are given, one per core
+pub fn default_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+/// Run `job` on each of `jobs` over `threads` threads. Returns the results in the order
+/// of `jobs`, None for the jobs not started before `progress` was cancelled.
+pub fn run_jobs<T: Send, R: Send>(
+    jobs: Vec<T>,
+    threads: usize,
+    progress: &Progress,
+    job: impl Fn(T) -> R + Sync + Send,
+) -> Vec<Option<R>> {
+    let threads = threads.clamp(1, jobs.len().max(1));
+    let run = || {
+        jobs.into_par_iter()
+            .map(|item| (!progress.is_cancelled()).then(|| job(item)))
+            .collect()
+    };
+    match ThreadPoolBuilder::new().num_threads(threads).build() {
+        Ok(pool) => pool.install(run),
+        // The global pool, when no more threads can be spawned
+        Err(_) => run(),
+    }
+}
@@ -0,0 +1,455 @@
+use crate::{
+    item::StatModifier,
+    parallel::{run_jobs, Progress},
+    rng::Rng,
+    spellbook::get_blood_price,
+    Battle, Spell,
+};
+
+/// Number of battles advanced together by the batch simulator
+pub const LANES: usize = 16;
+/// Playouts in each job of `estimate_win_probability_parallel`
+pub const PLAYOUTS_PER_JOB: u64 = 10_000;
+
+/// Results of random playouts
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PlayoutStats {
+    pub playouts: u64,
+    pub wins: u64,
+    /// Total mana used in won playouts
+    pub mana_used_in_wins: u64,
+}
+
+impl PlayoutStats {
+    pub fn get_win_probability(&self) -> f64 {
+        if self.playouts == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.playouts as f64
+        }
+    }
+
+    /// Add the playouts of `other`
+    pub fn merge(&mut self, other: &PlayoutStats) {
+        self.playouts += other.playouts;
+        self.wins += other.wins;
+        self.mana_used_in_wins += other.mana_used_in_wins;
+    }
+}
+
+/// How one random playout ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Playout {
+    pub won: bool,
+    pub mana_used: i32,
+    /// Spells cast
+    pub rounds: u32,
+}
+
+/// Plays many battles at once where the wizard casts uniformly random possible spells.
+/// Battle state is kept as one array per field so each step of the round runs over all
+/// lanes together.
+///
+/// Lanes don't track the boss's abilities, the wizard's ailments or action points, so
+/// battles with any of them are played out one at a time on clones of the battle instead.
+#[derive(Debug)]
+pub struct BatchSimulator {
+    start: Battle,
+    wizard_hitpoints: [i32; LANES],
+    wizard_mana: [i32; LANES],
+    shield_timer: [u8; LANES],
+    recharge_timer: [u8; LANES],
+    thorns_timer: [u8; LANES],
+    familiar_timer: [u8; LANES],
+    boss_hitpoints: [i32; LANES],
+    poison_timer: [u8; LANES],
+    /// One bit per spell in the order of `Spell::ALL`
+    possible_spells: [u8; LANES],
+    mana_used: [i32; LANES],
+    rounds: [u32; LANES],
+    /// Some(true) if the wizard won the lane's battle, Some(false) if the boss won
+    outcome: [Option<bool>; LANES],
+}
+
+impl BatchSimulator {
+    /// Playouts start from `start`, at the start of the wizard's turn
+    pub fn new(start: &Battle) -> Self {
+        let mut simulator = Self {
+            start: start.for_search(),
+            wizard_hitpoints: [0; LANES],
+            wizard_mana: [0; LANES],
+            shield_timer: [0; LANES],
+            recharge_timer: [0; LANES],
+            thorns_timer: [0; LANES],
+            familiar_timer: [0; LANES],
+            boss_hitpoints: [0; LANES],
+            poison_timer: [0; LANES],
+            possible_spells: [0; LANES],
+            mana_used: [0; LANES],
+            rounds: [0; LANES],
+            outcome: [None; LANES],
+        };
+        for lane in 0..LANES {
+            simulator.reset_lane(lane);
+        }
+        simulator
+    }
+
+    fn reset_lane(&mut self, lane: usize) {
+        let state = self.start.get_state();
+        self.wizard_hitpoints[lane] = state.wizard_hitpoints;
+        self.wizard_mana[lane] = state.wizard_mana;
+        self.shield_timer[lane] = state.shield_timer;
+        self.recharge_timer[lane] = state.recharge_timer;
+        self.thorns_timer[lane] = state.thorns_timer;
+        self.familiar_timer[lane] = state.familiar_timer;
+        self.boss_hitpoints[lane] = state.boss_hitpoints;
+        self.poison_timer[lane] = state.poison_timer;
+        self.possible_spells[lane] = state.possible_spells;
+        self.mana_used[lane] = 0;
+        self.rounds[lane] = 0;
+        self.outcome[lane] = self.start.get_outcome();
+    }
+
+    /// Run `playouts` random battles
+    pub fn run(&mut self, playouts: u64, rng: &mut Rng) -> PlayoutStats {
+        self.run_with(playouts, rng, |_| ())
+    }
+
+    /// Run `playouts` random battles, calling `on_playout` as each one ends
+    pub fn run_with(
+        &mut self,
+        playouts: u64,
+        rng: &mut Rng,
+        mut on_playout: impl FnMut(Playout),
+    ) -> PlayoutStats {
+        let mut stats = PlayoutStats::default();
+        if !self.lanes_follow_rules() {
+            for _ in 0..playouts {
+                let playout = self.play_out_clone(rng);
+                on_playout(playout);
+                stats.playouts += 1;
+                if playout.won {
+                    stats.wins += 1;
+                    stats.mana_used_in_wins += playout.mana_used as u64;
+                }
+            }
+            return stats;
+        }
+        let mut started = LANES as u64;
+        let mut active = [false; LANES];
+        for (lane, lane_active) in active.iter_mut().enumerate() {
+            self.reset_lane(lane);
+            *lane_active = (lane as u64) < playouts;
+        }
+
+        while active.iter().any(|&lane_active| lane_active) {
+            self.play_round(rng);
+            for (lane, lane_active) in active.iter_mut().enumerate() {
+                let Some(won) = self.outcome[lane].filter(|_| *lane_active) else {
+                    continue;
+                };
+                on_playout(Playout {
+                    won,
+                    mana_used: self.mana_used[lane],
+                    rounds: self.rounds[lane],
+                });
+                stats.playouts += 1;
+                if won {
+                    stats.wins += 1;
+                    stats.mana_used_in_wins += self.mana_used[lane] as u64;
+                }
+                if started < playouts {
+                    started += 1;
+                    self.reset_lane(lane);
+                } else {
+                    *lane_active = false;
+                }
+            }
+        }
+        stats
+    }
+
+    /// Whether the lanes can play out the start: the boss has no ability, the wizard has no
+    /// ailment for Cleanse to end, and there is one cast a turn
+    fn lanes_follow_rules(&self) -> bool {
+        let wizard = self.start.get_wizard();
+        self.start.get_boss().get_ability().is_none()
+            && self.start.get_action_points().is_none()
+            && wizard.get_silenced().is_none()
+            && wizard.get_burns().get_stacks() == 0
+    }
+
+    /// Play one random battle on a clone of the start, under all of `Battle`'s rules. Under
+    /// the action point economy, ending the turn is one more choice once the wizard has cast.
+    fn play_out_clone(&self, rng: &mut Rng) -> Playout {
+        let mut battle = self.start.clone();
+        let mut rounds = 0;
+        let won = loop {
+            if let Some(won) = battle.get_outcome() {
+                break won;
+            }
+            let possible: Vec<Spell> = Spell::ALL
+                .into_iter()
+                .filter(|spell| battle.get_wizard().get_possible_spells().contains(spell))
+                .collect();
+            let choices = possible.len() as u32 + battle.can_end_turn() as u32;
+            if choices == 0 {
+                // Without a spell to cast the wizard loses
+                break false;
+            }
+            match possible.get(rng.below(choices) as usize) {
+                Some(spell) => {
+                    battle
+                        .wizard_turn_cast_spell(spell)
+                        .expect("possible spells can be cast");
+                    rounds += 1;
+                    if battle.get_outcome().is_none() && !battle.can_end_turn() {
+                        battle.end_turn();
+                    }
+                }
+                None => {
+                    battle.end_turn();
+                }
+            }
+        };
+        Playout {
+            won,
+            mana_used: battle.get_mana_used() - self.start.get_mana_used(),
+            rounds,
+        }
+    }
+
+    /// Advance every unfinished lane by one round, following the rules of `Battle::play_round`
+    /// for the battles `lanes_follow_rules` allows
+    fn play_round(&mut self, rng: &mut Rng) {
+        let damage = self.start.get_boss().get_damage();
+        let spiky_skin = self.start.get_boss().get_spiky_skin();
+        let hard_mode = self.start.get_hard_mode();
+        let equipment = *self.start.get_wizard().get_equipment();
+
+        // Wizard casts a random possible spell
+        for lane in 0..LANES {
+            if self.outcome[lane].is_some() {
+                continue;
+            }
+            let possible = self.possible_spells[lane];
+            if possible == 0 {
+                // Without a spell to cast the wizard loses
+                self.outcome[lane] = Some(false);
+                continue;
+            }
+            let mut choice = rng.below(possible.count_ones());
+            let mut spell_index = 0;
+            for i in 0..Spell::ALL.len() {
+                if possible & (1 << i) != 0 {
+                    if choice == 0 {
+                        spell_index = i;
+                        break;
+                    }
+                    choice -= 1;
+                }
+            }
+            let spell = &Spell::ALL[spell_index];
+            let stats = self.start.get_spellbook().get(spell);
+            let duration = equipment.modify_duration(spell, stats.duration) as u8;
+            // Under blood magic the mana the wizard is short of is paid in hitpoints
+            self.wizard_hitpoints[lane] -= get_blood_price(stats.mana - self.wizard_mana[lane]);
+            self.wizard_mana[lane] = (self.wizard_mana[lane] - stats.mana).max(0);
+            self.mana_used[lane] += stats.mana;
+            self.rounds[lane] += 1;
+            match spell {
+                Spell::MagicMissile => self.boss_hitpoints[lane] -= stats.damage,
+                Spell::Drain => {
+                    self.boss_hitpoints[lane] -= stats.damage;
+                    self.wizard_hitpoints[lane] += stats.heal;
+                }
+                Spell::Shield => self.shield_timer[lane] = duration,
+                Spell::Poison => self.poison_timer[lane] = duration,
+                Spell::Recharge => self.recharge_timer[lane] = duration,
+                Spell::Thorns => self.thorns_timer[lane] = duration,
+                Spell::Summon => self.familiar_timer[lane] = duration,
+                // Starts with something to cleanse are played out on clones instead
+                Spell::Cleanse => (),
+            }
+            if self.boss_hitpoints[lane] <= 0 {
+                self.outcome[lane] = Some(true);
+            } else if matches!(spell, Spell::MagicMissile | Spell::Drain) && spiky_skin > 0 {
+                self.wizard_hitpoints[lane] -= spiky_skin;
+                if self.wizard_hitpoints[lane] <= 0 {
+                    self.outcome[lane] = Some(false);
+                }
+            }
+        }
+
+        // Boss' turn effects
+        self.apply_effects(false);
+
+        // Boss attacks
+        let shield_armor = self.start.get_spellbook().get(&Spell::Shield).armor;
+        let thorns_damage = self.start.get_spellbook().get(&Spell::Thorns).damage;
+        for lane in 0..LANES {
+            if self.outcome[lane].is_some() {
+                continue;
+            }
+            let armor = equipment.modify_armor(if self.shield_timer[lane] > 0 {
+                shield_armor
+            } else {
+                0
+            });
+            self.wizard_hitpoints[lane] -= (damage - armor).max(1);
+            if self.wizard_hitpoints[lane] <= 0 {
+                self.outcome[lane] = Some(false);
+                continue;
+            }
+            if self.thorns_timer[lane] > 0 {
+                self.boss_hitpoints[lane] -= thorns_damage;
+                if self.boss_hitpoints[lane] <= 0 {
+                    self.outcome[lane] = Some(true);
+                    continue;
+                }
+            }
+            self.possible_spells[lane] = self.compute_possible_spells(lane);
+        }
+
+        // Wizard's turn effects
+        if hard_mode {
+            for lane in 0..LANES {
+                if self.outcome[lane].is_some() {
+                    continue;
+                }
+                self.wizard_hitpoints[lane] -= 1;
+                if self.wizard_hitpoints[lane] <= 0 {
+                    self.outcome[lane] = Some(false);
+                }
+            }
+        }
+        self.apply_effects(true);
+    }
+
+    /// Effects at the start of a turn, with the familiar attacking on the wizard's
+    fn apply_effects(&mut self, wizard_turn: bool) {
+        let spellbook = self.start.get_spellbook();
+        let mana_gain = spellbook.get(&Spell::Recharge).mana_gain;
+        let poison_damage = spellbook.get(&Spell::Poison).damage;
+        let familiar_damage = spellbook.get(&Spell::Summon).damage;
+        let turn_mana = self.start.get_wizard().get_equipment().modify_turn_mana(0);
+        for lane in 0..LANES {
+            if self.outcome[lane].is_some() {
+                continue;
+            }
+            self.wizard_mana[lane] += turn_mana;
+            self.shield_timer[lane] = self.shield_timer[lane].saturating_sub(1);
+            self.thorns_timer[lane] = self.thorns_timer[lane].saturating_sub(1);
+            if self.recharge_timer[lane] > 0 {
+                self.wizard_mana[lane] += mana_gain;
+                self.recharge_timer[lane] -= 1;
+            }
+            if self.poison_timer[lane] > 0 {
+                self.boss_hitpoints[lane] -= poison_damage;
+                self.poison_timer[lane] -= 1;
+            }
+            if wizard_turn && self.familiar_timer[lane] > 0 {
+                self.boss_hitpoints[lane] -= familiar_damage;
+                self.familiar_timer[lane] -= 1;
+            }
+            if self.boss_hitpoints[lane] <= 0 {
+                self.outcome[lane] = Some(true);
+            }
+        }
+    }
+
+    /// Same rules as the wizard's possible spells: not sealed, enough mana or hitpoints to
+    /// pay for it under blood magic, and effects must be ending. Never Cleanse, as lanes
+    /// don't track what it ends.
+    fn compute_possible_spells(&self, lane: usize) -> u8 {
+        let mana = self.wizard_mana[lane];
+        let blood_magic = self.start.get_blood_magic();
+        let mut possible = 0;
+        for (i, spell) in Spell::ALL.iter().enumerate() {
+            let timer = match spell {
+                Spell::Shield => self.shield_timer[lane],
+                Spell::Poison => self.poison_timer[lane],
+                Spell::Recharge => self.recharge_timer[lane],
+                Spell::Thorns => self.thorns_timer[lane],
+                Spell::Summon => self.familiar_timer[lane],
+                _ => 0,
+            };
+            let spellbook = self.start.get_spellbook();
+            let cost = spellbook.get_mana(spell);
+            let affordable = mana >= cost
+                || blood_magic && self.wizard_hitpoints[lane] > get_blood_price(cost - mana);
+            if !spellbook.is_sealed(spell) && affordable && timer <= 1 && *spell != Spell::Cleanse {
+                possible |= 1 << i;
+            }
+        }
+        possible
+    }
+}
+
+/// Estimate the chance of winning from `battle` by casting random possible spells
+pub fn estimate_win_probability(battle: &Battle, playouts: u64, seed: u64) -> PlayoutStats {
+    BatchSimulator::new(battle).run(playouts, &mut Rng::new(seed))
+}
+
+/// Estimate like `estimate_win_probability` over `threads` threads, splitting the playouts
+/// into jobs that each draw from their own stream of `seed`, so the estimate is the same
+/// for any number of threads. `progress` counts playouts; cancelling it stops the estimate
+/// with the playouts finished so far.
+pub fn estimate_win_probability_parallel(
+    battle: &Battle,
+    playouts: u64,
+    seed: u64,
+    threads: usize,
+    progress: &Progress,
+) -> PlayoutStats {
+    run_playout_jobs(battle, playouts, seed, threads, progress, false).0
+}
+
+/// Estimate like `estimate_win_probability_parallel`, also returning every finished playout
+/// in the order of the jobs
+pub fn record_playouts_parallel(
+    battle: &Battle,
+    playouts: u64,
+    seed: u64,
+    threads: usize,
+    progress: &Progress,
+) -> (PlayoutStats, Vec<Playout>) {
+    run_playout_jobs(battle, playouts, seed, threads, progress, true)
+}
+
+fn run_playout_jobs(
+    battle: &Battle,
+    playouts: u64,
+    seed: u64,
+    threads: usize,
+    progress: &Progress,
+    record: bool,
+) -> (PlayoutStats, Vec<Playout>) {
+    // Each job gets its own copy of the battle, which can't be shared between threads
+    let jobs: Vec<(u64, Battle)> = (0..playouts.div_ceil(PLAYOUTS_PER_JOB))
+        .map(|job| (job, battle.for_search()))
+        .collect();
+    let results = run_jobs(jobs, threads, progress, |(job, battle)| {
+        let count = PLAYOUTS_PER_JOB.min(playouts - job * PLAYOUTS_PER_JOB);
+        let mut recorded = Vec::new();
+        let stats = BatchSimulator::new(&battle).run_with(
+            count,
+            &mut Rng::from_stream(seed, job),
+            |playout| {
+                if record {
+                    recorded.push(playout);
+                }
+            },
+        );
+        progress.advance(count);
+        (stats, recorded)
+    });
+    let mut stats = PlayoutStats::default();
+    let mut recorded = Vec::new();
+    for (job_stats, job_recorded) in results.into_iter().flatten() {
+        stats.merge(&job_stats);
+        recorded.extend(job_recorded);
+    }
+    (stats, recorded)
+}
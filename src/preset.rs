@@ -0,0 +1,220 @@
+use crate::{
+    bestiary::BestiaryEntry,
+    challenge::Challenge,
+    encounter::EncounterMutator,
+    toml::{self, ParseError, Table, Value},
+    RulesVersion,
+};
+use std::{fs, io, path::Path};
+
+/// A named battle setup: the boss's stats, the rules and the mutators changing them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BattlePreset {
+    /// Name the preset is picked by, such as `aoc-part2`
+    pub name: String,
+    pub description: String,
+    /// Name of the boss fought
+    pub boss: String,
+    pub hitpoints: i32,
+    pub damage: i32,
+    pub hard_mode: bool,
+    pub rules_version: RulesVersion,
+    pub mutators: Vec<EncounterMutator>,
+}
+
+impl BattlePreset {
+    fn new(name: &str, description: &str, boss: &str, hitpoints: i32, damage: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            boss: boss.to_string(),
+            hitpoints,
+            damage,
+            hard_mode: false,
+            rules_version: RulesVersion::LATEST,
+            mutators: Vec::new(),
+        }
+    }
+
+    /// The challenge the preset describes. The name lists the mutators, so they show in
+    /// the boss panel.
+    pub fn create_challenge(&self) -> Challenge {
+        let mut challenge =
+            Challenge::new(BestiaryEntry::new(&self.boss, self.hitpoints, self.damage));
+        challenge.hard_mode = self.hard_mode;
+        challenge.rules_version = self.rules_version;
+        for mutator in &self.mutators {
+            mutator.apply(&mut challenge);
+        }
+        if !self.mutators.is_empty() {
+            let mutators: Vec<String> = self.mutators.iter().map(ToString::to_string).collect();
+            challenge.name = format!("{} ({})", challenge.name, mutators.join(", "));
+        }
+        challenge
+    }
+
+    fn from_table(table: &Table) -> Result<Self, ParseError> {
+        let missing = |key: &str| table.error(format!("preset is missing `{}`", key));
+        let name = table.get_string("name")?.ok_or_else(|| missing("name"))?;
+        let out_of_range = |key: &str| table.error(format!("`{}` is out of range", key));
+        let hitpoints = table
+            .get_integer("hitpoints")?
+            .ok_or_else(|| missing("hitpoints"))?;
+        let hitpoints = i32::try_from(hitpoints).map_err(|_| out_of_range("hitpoints"))?;
+        let damage = table
+            .get_integer("damage")?
+            .ok_or_else(|| missing("damage"))?;
+        let damage = i32::try_from(damage).map_err(|_| out_of_range("damage"))?;
+        let mut preset = Self::new(
+            &name,
+            &table.get_string("description")?.unwrap_or_default(),
+            &table.get_string("boss")?.unwrap_or_else(|| name.clone()),
+            hitpoints,
+            damage,
+        );
+        preset.hard_mode = table.get_boolean("hard_mode")?.unwrap_or(false);
+        if let Some(number) = table.get_integer("rules")? {
+            preset.rules_version = RulesVersion::from_number(number)
+                .ok_or_else(|| table.error(format!("unknown rules version {}", number)))?;
+        }
+        for key in table.get_string("mutators")?.unwrap_or_default().split(',') {
+            let key = key.trim();
+            if key.is_empty() {
+                continue;
+            }
+            let mutator = EncounterMutator::from_key(key)
+                .ok_or_else(|| table.error(format!("unknown mutator `{}`", key)))?;
+            preset.mutators.push(mutator);
+        }
+        BestiaryEntry::new(&preset.boss, preset.hitpoints, preset.damage)
+            .validate()
+            .map_err(|message| table.error(message))?;
+        Ok(preset)
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new("preset");
+        table.push("name", Value::String(self.name.clone()));
+        if !self.description.is_empty() {
+            table.push("description", Value::String(self.description.clone()));
+        }
+        table.push("boss", Value::String(self.boss.clone()));
+        table.push("hitpoints", Value::Integer(self.hitpoints as i64));
+        table.push("damage", Value::Integer(self.damage as i64));
+        table.push("hard_mode", Value::Boolean(self.hard_mode));
+        table.push("rules", Value::Integer(self.rules_version.get_number()));
+        if !self.mutators.is_empty() {
+            let keys: Vec<&str> = self
+                .mutators
+                .iter()
+                .map(|mutator| mutator.get_key())
+                .collect();
+            table.push("mutators", Value::String(keys.join(",")));
+        }
+        table
+    }
+}
+
+/// Presets that can be picked by name, stored as `[[preset]]` tables in a TOML file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PresetRegistry {
+    presets: Vec<BattlePreset>,
+}
+
+impl PresetRegistry {
+    /// Presets that ship with the game
+    pub fn builtin() -> Self {
+        let glass_cannon = BattlePreset {
+            mutators: vec![EncounterMutator::CostlyShield],
+            ..BattlePreset::new(
+                "glass-cannon",
+                "A frail boss that hits hard, and Shield costs more",
+                "Glass Golem",
+                30,
+                12,
+            )
+        };
+        let slow_burn = BattlePreset {
+            mutators: vec![
+                EncounterMutator::WeakPoison,
+                EncounterMutator::ShortRecharge,
+            ],
+            ..BattlePreset::new(
+                "slow-burn",
+                "A sturdy boss, with weaker Poison and shorter Recharge",
+                "Iron Tortoise",
+                80,
+                5,
+            )
+        };
+        Self {
+            presets: vec![
+                BattlePreset::new(
+                    "aoc-part1",
+                    "The 2015 day 22 example boss, part 1 rules",
+                    "Advent Boss",
+                    55,
+                    8,
+                ),
+                BattlePreset {
+                    hard_mode: true,
+                    ..BattlePreset::new(
+                        "aoc-part2",
+                        "The 2015 day 22 example boss in hard mode, part 2 rules",
+                        "Advent Boss",
+                        55,
+                        8,
+                    )
+                },
+                glass_cannon,
+                slow_burn,
+            ],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut presets = Vec::new();
+        for table in toml::parse(text)? {
+            if table.name != "preset" {
+                return Err(table.error(format!("unknown table `[[{}]]`", table.name)));
+            }
+            presets.push(BattlePreset::from_table(&table)?);
+        }
+        Ok(Self { presets })
+    }
+
+    pub fn to_toml(&self) -> String {
+        let tables: Vec<Table> = self.presets.iter().map(BattlePreset::to_table).collect();
+        toml::write(&tables)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Add the presets of `other`, replacing those with the same name
+    pub fn merge(&mut self, other: Self) {
+        for preset in other.presets {
+            match self
+                .presets
+                .iter_mut()
+                .find(|own| own.name.eq_ignore_ascii_case(&preset.name))
+            {
+                Some(own) => *own = preset,
+                None => self.presets.push(preset),
+            }
+        }
+    }
+
+    /// Find a preset by name, ignoring case
+    pub fn get_preset(&self, name: &str) -> Option<&BattlePreset> {
+        self.presets
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_presets(&self) -> &Vec<BattlePreset> {
+        &self.presets
+    }
+}
@@ -0,0 +1,98 @@
+//! Named battle presets: `--preset` fights one, and the `presets` subcommand lists them.
+//! The built-in presets can be added to or replaced in a presets file.
+
+use crate::data_dir;
+use advent_wizard_rpg::{
+    challenge::Challenge,
+    preset::{BattlePreset, PresetRegistry},
+};
+use clap::{arg, Arg, ArgMatches, Command};
+use std::{path::PathBuf, process};
+
+/// File with the player's own presets, read if it exists
+pub fn default_path() -> PathBuf {
+    data_dir::profile_dir().join("presets.toml")
+}
+
+/// The built-in presets, with those of the presets file. Exits if the file can't be
+/// loaded.
+pub fn load_registry() -> PresetRegistry {
+    let mut registry = PresetRegistry::builtin();
+    let path = default_path();
+    if path.exists() {
+        match PresetRegistry::load(&path) {
+            Ok(presets) => registry.merge(presets),
+            Err(err) => {
+                eprintln!("Could not load presets {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        }
+    }
+    registry
+}
+
+pub fn arg() -> Arg {
+    arg!(--preset <NAME> "Fight a named preset, as listed by the presets subcommand")
+}
+
+/// Challenge of the preset given by `arg`, if any. Exits if there is no such preset.
+pub fn challenge_from_matches(matches: &ArgMatches) -> Option<Challenge> {
+    let name = matches.get_one::<String>("preset")?;
+    let registry = load_registry();
+    match registry.get_preset(name) {
+        Some(preset) => Some(preset.create_challenge()),
+        None => {
+            let names: Vec<&str> = registry
+                .get_presets()
+                .iter()
+                .map(|preset| preset.name.as_str())
+                .collect();
+            eprintln!("No preset named {}, try one of: {}", name, names.join(", "));
+            process::exit(1);
+        }
+    }
+}
+
+/// One line about `preset`: the boss, its stats and the rules
+pub fn describe_preset(preset: &BattlePreset) -> String {
+    let mut rules = Vec::new();
+    if preset.hard_mode {
+        rules.push("Hard Mode".to_string());
+    }
+    rules.extend(preset.mutators.iter().map(ToString::to_string));
+    format!(
+        "{}: {} HP, {} damage{}{}",
+        preset.boss,
+        preset.hitpoints,
+        preset.damage,
+        if rules.is_empty() { "" } else { ", " },
+        rules.join(", ")
+    )
+}
+
+pub fn command() -> Command {
+    Command::new("presets").about(
+        "List the named battle presets, built in and from presets.toml in the state directory",
+    )
+}
+
+pub fn run(_matches: &ArgMatches) {
+    let registry = load_registry();
+    let width = registry
+        .get_presets()
+        .iter()
+        .map(|preset| preset.name.len())
+        .max()
+        .unwrap_or(0);
+    for preset in registry.get_presets() {
+        println!(
+            "{:width$}  {}",
+            preset.name,
+            describe_preset(preset),
+            width = width
+        );
+        if !preset.description.is_empty() {
+            println!("{:width$}  {}", "", preset.description, width = width);
+        }
+    }
+}
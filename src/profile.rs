@@ -0,0 +1,274 @@
+//! `profile export` and `profile import` subcommands: move the player's files between
+//! machines in a single tar archive. The archive holds the `layout` file and the `profile/`
+//! and `saves/` directories of the data directory, so the presets, opening book, survival
+//! high score and save slots with their previews.
+//!
+//! Importing keeps the best of each file the machine already has: the higher high score,
+//! the lines of both opening books, and the newer of other files, a save slot and its
+//! preview going together.
+
+use crate::{
+    data_dir::{self, LAYOUT_FILE, LAYOUT_FORMAT},
+    endless,
+};
+use advent_wizard_rpg::{
+    archive::{read_tar, write_tar, ArchiveEntry},
+    book::OpeningBook,
+    file_format::write_atomic,
+    preset::PresetRegistry,
+    save::{SaveGame, SAVE_FILE_SUFFIX},
+};
+use clap::{arg, value_parser, ArgMatches, Command};
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Directories of the data directory that are archived
+const ARCHIVED_DIRS: [&str; 2] = ["profile", "saves"];
+/// Ending of the names of save slot previews
+const PREVIEW_FILE_SUFFIX: &str = ".preview.txt";
+
+pub fn command() -> Command {
+    Command::new("profile")
+        .about("Move the player's profile and save slots between machines")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("export")
+                .about("Write the profile and save slots to a tar archive")
+                .arg(arg!(<FILE> "Archive to write").value_parser(value_parser!(PathBuf))),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Add the profile and save slots of an exported archive, keeping the best of each file already here")
+                .arg(arg!(<FILE> "Archive to read").value_parser(value_parser!(PathBuf))),
+        )
+}
+
+pub fn run(sub_matches: &ArgMatches) -> io::Result<()> {
+    match sub_matches.subcommand() {
+        Some(("export", matches)) => {
+            let path = matches.get_one::<PathBuf>("FILE").unwrap();
+            let count = export(&data_dir::root(), path)?;
+            println!("Exported {} files to {}", count, path.display());
+        }
+        Some(("import", matches)) => {
+            let path = matches.get_one::<PathBuf>("FILE").unwrap();
+            for change in import(&data_dir::root(), path)? {
+                println!("{}", change);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Seconds since the Unix epoch of `time`
+fn to_unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// When the file at `path` was modified, in seconds since the Unix epoch, None if there is
+/// no file
+fn get_modified(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(to_unix_secs(modified))
+}
+
+/// Archive the files of the data directory at `root` to `path`, returning how many there
+/// were. Temporary files of writes in progress are left out.
+pub fn export(root: &Path, path: &Path) -> io::Result<usize> {
+    // The layout file is written as the game starts, so it's there for any data directory
+    let mut entries = vec![ArchiveEntry {
+        path: LAYOUT_FILE.to_string(),
+        modified: to_unix_secs(SystemTime::now()),
+        contents: LAYOUT_FORMAT.with_header("").into_bytes(),
+    }];
+    for dir in ARCHIVED_DIRS {
+        let files = match fs::read_dir(root.join(dir)) {
+            Ok(files) => files,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        let mut dir_entries = Vec::new();
+        for file in files {
+            let file = file?;
+            let name = file.file_name().to_string_lossy().to_string();
+            let metadata = file.metadata()?;
+            if !metadata.is_file() || name.starts_with('.') {
+                continue;
+            }
+            dir_entries.push(ArchiveEntry {
+                path: format!("{}/{}", dir, name),
+                modified: to_unix_secs(metadata.modified()?),
+                contents: fs::read(file.path())?,
+            });
+        }
+        dir_entries.sort_by(|a, b| a.path.cmp(&b.path));
+        entries.extend(dir_entries);
+    }
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomic(path, write_tar(&entries)?)?;
+    Ok(entries.len() - 1)
+}
+
+/// Whether `contents` is a file the game can read at `path` in the archive, Err with why
+/// not otherwise. Files the game doesn't read, such as previews, are always valid.
+fn check_contents(path: &str, contents: &[u8]) -> Result<(), String> {
+    let text = String::from_utf8(contents.to_vec()).map_err(|_| "not text".to_string())?;
+    match path {
+        "profile/opening-book.toml" => OpeningBook::parse(&text).map(|_| ()),
+        "profile/presets.toml" => PresetRegistry::parse(&text).map(|_| ()),
+        "profile/survival-high-score.txt" => {
+            return endless::parse_high_score(&text)
+                .map(|_| ())
+                .ok_or_else(|| "not a high score".to_string());
+        }
+        _ if path.ends_with(SAVE_FILE_SUFFIX) => SaveGame::parse(&text).map(|_| ()),
+        _ => Ok(()),
+    }
+    .map_err(|err| err.to_string())
+}
+
+/// Path of the file deciding whether the archived `path` replaces the one here: the save
+/// of a slot for its preview, otherwise the file itself
+fn get_deciding_path(path: &str) -> String {
+    match path.strip_suffix(PREVIEW_FILE_SUFFIX) {
+        Some(slot) => format!("{}{}", slot, SAVE_FILE_SUFFIX),
+        None => path.to_string(),
+    }
+}
+
+/// Write `contents` to `path` as modified at `modified`, so save slots keep when they were
+/// saved
+fn write_file(path: &Path, contents: &[u8], modified: u64) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomic(path, contents)?;
+    File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(UNIX_EPOCH + Duration::from_secs(modified))
+}
+
+/// Add the files of the archive at `path` to the data directory at `root`, keeping the
+/// best of each file already there. Files the game can't read, and paths outside the
+/// archived directories, are left out. Returns a line describing what became of each
+/// file.
+pub fn import(root: &Path, path: &Path) -> io::Result<Vec<String>> {
+    let entries = read_tar(&fs::read(path)?)?;
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let layout = entries
+        .iter()
+        .find(|entry| entry.path == LAYOUT_FILE)
+        .ok_or_else(|| invalid(format!("{} is not an exported profile", path.display())))?;
+    LAYOUT_FORMAT
+        .read_version(&String::from_utf8_lossy(&layout.contents))
+        .map_err(|err| invalid(format!("{}: {}", path.display(), err)))?;
+    // Before importing anything, as a slot's save is imported before or after its preview
+    let modified_here: Vec<Option<u64>> = entries
+        .iter()
+        .map(|entry| get_modified(&root.join(get_deciding_path(&entry.path))))
+        .collect();
+
+    let mut changes = Vec::new();
+    for (entry, modified) in entries.iter().zip(modified_here) {
+        if entry.path == LAYOUT_FILE {
+            continue;
+        }
+        let archived = entry.path.split_once('/').is_some_and(|(dir, name)| {
+            ARCHIVED_DIRS.contains(&dir)
+                && !name.is_empty()
+                && !name.contains(['/', '\\'])
+                && !name.starts_with('.')
+        });
+        if !archived {
+            changes.push(format!(
+                "Left out {}, as it isn't part of a profile",
+                entry.path
+            ));
+            continue;
+        }
+        if let Err(err) = check_contents(&entry.path, &entry.contents) {
+            changes.push(format!(
+                "Left out {}, as it can't be read: {}",
+                entry.path, err
+            ));
+            continue;
+        }
+        let target = root.join(&entry.path);
+        let current = match fs::read(&target) {
+            Ok(current) => current,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                write_file(&target, &entry.contents, entry.modified)?;
+                changes.push(format!("Imported {}", entry.path));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if current == entry.contents {
+            continue;
+        }
+
+        match entry.path.as_str() {
+            "profile/survival-high-score.txt" => {
+                let score = |contents: &[u8]| {
+                    endless::parse_high_score(&String::from_utf8_lossy(contents)).unwrap_or(0)
+                };
+                if score(&entry.contents) > score(&current) {
+                    write_file(&target, &entry.contents, entry.modified)?;
+                    changes.push(format!("Imported {}, a higher score", entry.path));
+                } else {
+                    changes.push(format!("Kept {}, a score as high", target.display()));
+                }
+            }
+            "profile/opening-book.toml" => {
+                let mut book = match OpeningBook::load(&target) {
+                    Ok(book) => book,
+                    Err(err) => {
+                        changes.push(format!(
+                            "Left out {}, as {} can't be read: {}",
+                            entry.path,
+                            target.display(),
+                            err
+                        ));
+                        continue;
+                    }
+                };
+                let count = book.get_line_count();
+                book.merge(OpeningBook::parse(&String::from_utf8_lossy(&entry.contents)).unwrap());
+                book.save(&target)?;
+                changes.push(format!(
+                    "Added {} lines of {} to {}",
+                    book.get_line_count() - count,
+                    entry.path,
+                    target.display()
+                ));
+            }
+            _ => {
+                let deciding = get_deciding_path(&entry.path);
+                let archived_modified = entries
+                    .iter()
+                    .find(|other| other.path == deciding)
+                    .map_or(entry.modified, |other| other.modified);
+                if Some(archived_modified) > modified {
+                    write_file(&target, &entry.contents, entry.modified)?;
+                    changes.push(format!("Imported {}, a newer version", entry.path));
+                } else {
+                    changes.push(format!("Kept {}, a version as new", target.display()));
+                }
+            }
+        }
+    }
+    if changes.is_empty() {
+        changes.push("Everything in the archive is here already".to_string());
+    }
+    Ok(changes)
+}
@@ -0,0 +1,182 @@
+//! `tournament` subcommand: rank the strategy bots against bosses and each other.
+
+#[cfg(feature = "results-db")]
+use crate::tally;
+use crate::{
+    cli::{threads_arg, threads_from_matches},
+    report::{self, OutputFormat},
+    ui,
+};
+use advent_wizard_rpg::{
+    bestiary::BestiaryEntry,
+    parallel::Progress,
+    rating::DEFAULT_K_FACTOR,
+    tournament::{play_tournament, TournamentOptions},
+};
+use clap::{arg, value_parser, ArgMatches, Command};
+
+/// Columns of the rating history sparkline
+const HISTORY_WIDTH: usize = 16;
+/// Sparkline levels, lowest first
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+pub fn command() -> Command {
+    let command = Command::new("tournament")
+        .about("Rank the strategy bots by playing them against bosses and each other")
+        .arg(
+            arg!(--"boss-hp" <HP> "Boss hitpoints to sweep, separated by commas")
+                .value_parser(value_parser!(i32).range(1..))
+                .value_delimiter(',')
+                .default_value("40,55,70"),
+        )
+        .arg(
+            arg!(--"boss-damage" <DAMAGE> "Boss damage to sweep, separated by commas")
+                .value_parser(value_parser!(i32).range(0..))
+                .value_delimiter(',')
+                .default_value("6,8,10"),
+        )
+        .arg(arg!(--hard "Set difficulty to hard"))
+        .arg(arg!(--scouting "Hide the bosses' hitpoints and damage from the bots until they learn them"))
+        .arg(
+            arg!(--games <COUNT> "Games per strategy and boss, and per pairing in duels")
+                .value_parser(value_parser!(u64))
+                .default_value("100"),
+        )
+        .arg(
+            arg!(--seed <SEED> "Seed for random strategies")
+                .value_parser(value_parser!(u64))
+                .default_value("0"),
+        )
+        .arg(
+            arg!(--"k-factor" <K> "Most a bot's rating changes in one duel")
+                .value_parser(value_parser!(f64))
+                .default_value(DEFAULT_K_FACTOR.to_string()),
+        )
+        .arg(report::format_arg(&["csv"]))
+        .arg(threads_arg());
+    #[cfg(feature = "results-db")]
+    let command = command.arg(tally::db_arg());
+    command
+}
+
+pub fn run(matches: &ArgMatches) {
+    let mut bosses = Vec::new();
+    for hitpoints in matches.get_many::<i32>("boss-hp").unwrap() {
+        for damage in matches.get_many::<i32>("boss-damage").unwrap() {
+            let name = format!("{}/{}", hitpoints, damage);
+            bosses.push(BestiaryEntry::new(&name, *hitpoints, *damage));
+        }
+    }
+    let options = TournamentOptions {
+        bosses,
+        hard_mode: matches.get_flag("hard"),
+        scouting: matches.get_flag("scouting"),
+        games: *matches.get_one::<u64>("games").unwrap(),
+        seed: *matches.get_one::<u64>("seed").unwrap(),
+        k_factor: *matches.get_one::<f64>("k-factor").unwrap(),
+        threads: threads_from_matches(matches),
+    };
+    let progress = Progress::new(options.get_game_count());
+    let results = ui::with_progress("games", &progress, || play_tournament(&options, &progress));
+    let standings = results.0;
+    #[cfg(feature = "results-db")]
+    if let Some(path) = tally::db_from_matches(matches) {
+        tally::record_tournament(path, &options, &results.1);
+    }
+    if progress.is_cancelled() {
+        eprintln!(
+            "Stopped after {} of {} games",
+            progress.get_done(),
+            progress.get_total()
+        );
+    }
+
+    match report::format_from_matches(matches) {
+        OutputFormat::Csv => {
+            println!(
+                "rank,strategy,battles,battle_wins,average_mana,duels,duel_wins,overall_win_rate,rating"
+            );
+            for (i, standing) in standings.iter().enumerate() {
+                println!(
+                    "{},{},{},{},{},{},{},{:.4},{:.1}",
+                    i + 1,
+                    standing.strategy,
+                    standing.battles.playouts,
+                    standing.battles.wins,
+                    standing
+                        .get_average_mana()
+                        .map_or(String::new(), |mana| format!("{:.1}", mana)),
+                    standing.duels,
+                    standing.duel_wins,
+                    standing.get_overall_win_rate(),
+                    standing.rating
+                );
+            }
+        }
+        format @ (OutputFormat::Json | OutputFormat::Jsonl) => {
+            report::tournament_report(&options, &standings).print(format);
+        }
+        OutputFormat::Human => {
+            println!(
+                "{} bosses{}, {} games per boss and pairing",
+                options.bosses.len(),
+                if options.hard_mode { " (hard)" } else { "" },
+                options.games
+            );
+            println!();
+            println!(
+                "{:<6}{:<10}{:>16}{:>12}{:>16}{:>12}{:>10}  History",
+                "Rank",
+                "Strategy",
+                "Boss win rate",
+                "Avg mana",
+                "Duel win rate",
+                "Overall",
+                "Rating"
+            );
+            for (i, standing) in standings.iter().enumerate() {
+                println!(
+                    "{:<6}{:<10}{:>15.1}%{:>12}{:>15.1}%{:>11.1}%{:>10.0}  {}",
+                    i + 1,
+                    standing.strategy,
+                    standing.battles.get_win_probability() * 100.0,
+                    standing
+                        .get_average_mana()
+                        .map_or("-".to_string(), |mana| format!("{:.1}", mana)),
+                    standing.get_duel_win_rate() * 100.0,
+                    standing.get_overall_win_rate() * 100.0,
+                    standing.rating,
+                    sparkline(&standing.rating_history)
+                );
+            }
+        }
+    }
+}
+
+/// `values` drawn with one bar per column, each column the average of its share of the
+/// values
+fn sparkline(values: &[f64]) -> String {
+    if values.is_empty() {
+        return String::new();
+    }
+    let columns: Vec<f64> = (0..HISTORY_WIDTH.min(values.len()))
+        .map(|column| {
+            let start = column * values.len() / HISTORY_WIDTH.min(values.len());
+            let end = (column + 1) * values.len() / HISTORY_WIDTH.min(values.len());
+            values[start..end].iter().sum::<f64>() / (end - start) as f64
+        })
+        .collect();
+    let low = columns.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = columns.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    columns
+        .iter()
+        .map(|value| {
+            let level = if high > low {
+                ((value - low) / (high - low) * (SPARKLINE_BARS.len() - 1) as f64).round()
+            } else {
+                0.0
+            };
+            SPARKLINE_BARS[level as usize]
+        })
+        .collect()
+}
@@ -0,0 +1,16 @@
+/// Rating players start with
+pub const INITIAL_RATING: f64 = 1500.0;
+/// Most a rating changes in one match, unless configured otherwise
+pub const DEFAULT_K_FACTOR: f64 = 32.0;
+
+/// Elo expected score of a player rated `rating` against one rated `opponent`, from 0 to 1
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10_f64.powf((opponent - rating) / 400.0))
+}
+
+/// Ratings of two players after a match, where `score` is 1 if the first player won, 0.5
+/// for a draw and 0 if the second player won
+pub fn update_ratings(first: f64, second: f64, score: f64, k_factor: f64) -> (f64, f64) {
+    let change = k_factor * (score - expected_score(first, second));
+    (first + change, second - change)
+}
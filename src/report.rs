@@ -0,0 +1,400 @@
+//! Output formats of the headless subcommands and the schemas of their JSON reports.
+//! Fields keep their names and types between releases; new fields are only ever added.
+
+use advent_wizard_rpg::{
+    playout::PlayoutStats,
+    solver::{Evaluation, LineStats, SolveResult, TreeAnalysis},
+    tournament::{Standing, TournamentOptions},
+    Battle, Spell,
+};
+use clap::{arg, builder::PossibleValue, Arg, ArgMatches};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+/// How a headless subcommand prints its results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Text for reading in a terminal
+    Human,
+    /// One JSON document
+    Json,
+    /// One JSON object per line, each with a `type`
+    Jsonl,
+    Csv,
+}
+
+/// `--format` argument taking `human`, `json`, `jsonl` and `extra` formats. `table`, the
+/// old name of `human`, is still accepted.
+pub fn format_arg(extra: &[&'static str]) -> Arg {
+    let mut formats = vec![
+        PossibleValue::new("human").alias("table"),
+        PossibleValue::new("json"),
+        PossibleValue::new("jsonl"),
+    ];
+    formats.extend(extra.iter().map(|format| PossibleValue::new(*format)));
+    arg!(--format <FORMAT> "Output format")
+        .value_parser(formats)
+        .default_value("human")
+}
+
+pub fn format_from_matches(matches: &ArgMatches) -> OutputFormat {
+    match matches.get_one::<String>("format").unwrap().as_str() {
+        "json" => OutputFormat::Json,
+        "jsonl" => OutputFormat::Jsonl,
+        "csv" => OutputFormat::Csv,
+        _ => OutputFormat::Human,
+    }
+}
+
+/// What a subcommand reports: a summary and, for some, a row per item
+#[derive(Debug, Clone)]
+pub struct Report<S, R = ()> {
+    /// Type of the summary in JSONL
+    kind: &'static str,
+    summary: S,
+    /// Key of the rows in JSON, and their type in JSONL
+    rows_key: &'static str,
+    row_kind: &'static str,
+    rows: Vec<R>,
+}
+
+impl<S: Serialize> Report<S> {
+    pub fn new(kind: &'static str, summary: S) -> Self {
+        Self {
+            kind,
+            summary,
+            rows_key: "",
+            row_kind: "",
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn with_rows<R: Serialize>(
+        self,
+        rows_key: &'static str,
+        row_kind: &'static str,
+        rows: Vec<R>,
+    ) -> Report<S, R> {
+        Report {
+            kind: self.kind,
+            summary: self.summary,
+            rows_key,
+            row_kind,
+            rows,
+        }
+    }
+}
+
+impl<S: Serialize, R: Serialize> Report<S, R> {
+    /// Print as one JSON document with the rows in an array, or as JSONL with the summary
+    /// on the first line and a line per row. Human and CSV output are up to the subcommand.
+    pub fn print(self, format: OutputFormat) {
+        match format {
+            OutputFormat::Json => {
+                let document = Document {
+                    summary: &self.summary,
+                    rows: (!self.rows_key.is_empty()).then_some(NamedRows {
+                        key: self.rows_key,
+                        rows: &self.rows,
+                    }),
+                };
+                println!("{}", to_json(&document));
+            }
+            OutputFormat::Jsonl => {
+                println!("{}", to_json(&Tagged::new(self.kind, &self.summary)));
+                for row in &self.rows {
+                    println!("{}", to_json(&Tagged::new(self.row_kind, row)));
+                }
+            }
+            OutputFormat::Human | OutputFormat::Csv => (),
+        }
+    }
+}
+
+/// `value` as compact JSON. Reports only hold strings, numbers and structs of them, which
+/// always serialize.
+fn to_json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).expect("reports serialize to JSON")
+}
+
+/// A JSON report: the summary's fields, then its rows if it has any
+#[derive(Serialize)]
+struct Document<'a, S, R> {
+    #[serde(flatten)]
+    summary: &'a S,
+    #[serde(flatten)]
+    rows: Option<NamedRows<'a, R>>,
+}
+
+/// Rows of a report under their key
+struct NamedRows<'a, R> {
+    key: &'static str,
+    rows: &'a [R],
+}
+
+impl<R: Serialize> Serialize for NamedRows<'_, R> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.key, self.rows)?;
+        map.end()
+    }
+}
+
+/// A line of JSONL: `fields` with a `type` field first
+#[derive(Serialize)]
+struct Tagged<'a, T> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(flatten)]
+    fields: &'a T,
+}
+
+impl<'a, T> Tagged<'a, T> {
+    fn new(kind: &'static str, fields: &'a T) -> Self {
+        Self { kind, fields }
+    }
+}
+
+/// `value` rounded to `decimals` digits after the point, the precision of its field
+fn round(value: f64, decimals: i32) -> f64 {
+    let scale = 10f64.powi(decimals);
+    (value * scale).round() / scale
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BossSummary {
+    pub hitpoints: i32,
+    pub damage: i32,
+}
+
+impl BossSummary {
+    fn new(battle: &Battle) -> Self {
+        Self {
+            hitpoints: battle.get_boss().get_hitpoints(),
+            damage: battle.get_boss().get_damage(),
+        }
+    }
+}
+
+/// Display names of the spells of a line
+fn line_names(line: &[Spell]) -> Vec<&'static str> {
+    line.iter().map(|spell| spell.get_display_name()).collect()
+}
+
+/// A winning line and the mana it uses
+#[derive(Debug, Clone, Serialize)]
+pub struct WinSummary {
+    pub mana: i32,
+    pub spells: Vec<&'static str>,
+}
+
+pub fn evaluation_name(evaluation: Evaluation) -> &'static str {
+    match evaluation {
+        Evaluation::Won => "Won",
+        Evaluation::Lost => "Lost",
+        Evaluation::Unknown => "Unknown",
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LineStatsSummary {
+    /// `won`, `lost` or `unknown`
+    pub evaluation: String,
+    pub winning_lines: u64,
+    pub losing_lines: u64,
+    pub unfinished_lines: u64,
+    pub shortest_win: Option<Vec<&'static str>>,
+    pub cheapest_win: Option<WinSummary>,
+}
+
+impl LineStatsSummary {
+    fn new(stats: &LineStats) -> Self {
+        Self {
+            evaluation: evaluation_name(stats.get_evaluation()).to_lowercase(),
+            winning_lines: stats.winning_lines,
+            losing_lines: stats.losing_lines,
+            unfinished_lines: stats.unfinished_lines,
+            shortest_win: stats.shortest_win.as_deref().map(line_names),
+            cheapest_win: stats.cheapest_win.as_ref().map(|(mana, line)| WinSummary {
+                mana: *mana,
+                spells: line_names(line),
+            }),
+        }
+    }
+}
+
+/// `analyze` summary: the lines of the whole tree
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisSummary {
+    pub boss: BossSummary,
+    pub hard_mode: bool,
+    pub depth: usize,
+    #[serde(flatten)]
+    pub total: LineStatsSummary,
+}
+
+/// `analyze` row: the lines starting with a spell
+#[derive(Debug, Clone, Serialize)]
+pub struct FirstMoveRow {
+    pub spell: &'static str,
+    #[serde(flatten)]
+    pub stats: LineStatsSummary,
+}
+
+/// `analyze`: the lines of the whole tree, and a row per first move
+pub fn analysis_report(
+    battle: &Battle,
+    depth: usize,
+    analysis: &TreeAnalysis,
+) -> Report<AnalysisSummary, FirstMoveRow> {
+    let summary = AnalysisSummary {
+        boss: BossSummary::new(battle),
+        hard_mode: battle.get_hard_mode(),
+        depth,
+        total: LineStatsSummary::new(&analysis.total),
+    };
+    let first_moves = analysis
+        .first_moves
+        .iter()
+        .map(|(spell, stats)| FirstMoveRow {
+            spell: spell.get_display_name(),
+            stats: LineStatsSummary::new(stats),
+        })
+        .collect();
+    Report::new("analysis", summary).with_rows("first_moves", "first_move", first_moves)
+}
+
+/// `solve` summary: the cheapest win, null if none was found
+#[derive(Debug, Clone, Serialize)]
+pub struct SolveSummary {
+    pub boss: BossSummary,
+    pub hard_mode: bool,
+    pub algorithm: String,
+    pub depth: usize,
+    pub solution: Option<WinSummary>,
+    pub nodes_expanded: u64,
+    pub elapsed_seconds: f64,
+}
+
+/// `solve`: the cheapest win, null if none was found
+pub fn solve_report(
+    battle: &Battle,
+    algorithm: &str,
+    depth: usize,
+    result: &SolveResult,
+    elapsed_seconds: f64,
+) -> Report<SolveSummary> {
+    Report::new(
+        "solve",
+        SolveSummary {
+            boss: BossSummary::new(battle),
+            hard_mode: battle.get_hard_mode(),
+            algorithm: algorithm.to_string(),
+            depth,
+            solution: result.solution.as_ref().map(|solution| WinSummary {
+                mana: solution.mana,
+                spells: line_names(&solution.spells),
+            }),
+            nodes_expanded: result.nodes_expanded,
+            elapsed_seconds: round(elapsed_seconds, 3),
+        },
+    )
+}
+
+/// `simulate` summary: the playouts finished, fewer than asked for if cancelled
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationSummary {
+    pub boss: BossSummary,
+    pub hard_mode: bool,
+    pub seed: u64,
+    pub requested_playouts: u64,
+    pub playouts: u64,
+    pub wins: u64,
+    pub win_probability: f64,
+    /// Null if no playout was won
+    pub average_mana: Option<f64>,
+    pub threads: usize,
+    pub elapsed_seconds: f64,
+}
+
+/// `simulate`: the playouts finished, fewer than asked for if cancelled
+pub fn simulation_report(
+    battle: &Battle,
+    seed: u64,
+    playouts: u64,
+    stats: &PlayoutStats,
+    threads: usize,
+    elapsed_seconds: f64,
+) -> Report<SimulationSummary> {
+    let average_mana = (stats.wins > 0).then(|| stats.mana_used_in_wins as f64 / stats.wins as f64);
+    Report::new(
+        "simulation",
+        SimulationSummary {
+            boss: BossSummary::new(battle),
+            hard_mode: battle.get_hard_mode(),
+            seed,
+            requested_playouts: playouts,
+            playouts: stats.playouts,
+            wins: stats.wins,
+            win_probability: round(stats.get_win_probability(), 6),
+            average_mana: average_mana.map(|mana| round(mana, 1)),
+            threads,
+            elapsed_seconds: round(elapsed_seconds, 3),
+        },
+    )
+}
+
+/// `tournament` summary: the settings
+#[derive(Debug, Clone, Serialize)]
+pub struct TournamentSummary {
+    pub bosses: usize,
+    pub hard_mode: bool,
+    pub scouting: bool,
+    pub games: u64,
+}
+
+/// `tournament` row: a strategy's results
+#[derive(Debug, Clone, Serialize)]
+pub struct StandingRow {
+    pub rank: usize,
+    pub strategy: &'static str,
+    pub battles: u64,
+    pub battle_wins: u64,
+    /// Null if no battle was won
+    pub average_mana: Option<f64>,
+    pub duels: u64,
+    pub duel_wins: u64,
+    pub overall_win_rate: f64,
+    pub rating: f64,
+}
+
+/// `tournament`: the settings, and a row per strategy, best first
+pub fn tournament_report(
+    options: &TournamentOptions,
+    standings: &[Standing],
+) -> Report<TournamentSummary, StandingRow> {
+    let rows = standings
+        .iter()
+        .enumerate()
+        .map(|(i, standing)| StandingRow {
+            rank: i + 1,
+            strategy: standing.strategy,
+            battles: standing.battles.playouts,
+            battle_wins: standing.battles.wins,
+            average_mana: standing.get_average_mana().map(|mana| round(mana, 1)),
+            duels: standing.duels,
+            duel_wins: standing.duel_wins,
+            overall_win_rate: round(standing.get_overall_win_rate(), 4),
+            rating: round(standing.rating, 1),
+        })
+        .collect();
+    Report::new(
+        "tournament",
+        TournamentSummary {
+            bosses: options.bosses.len(),
+            hard_mode: options.hard_mode,
+            scouting: options.scouting,
+            games: options.games,
+        },
+    )
+    .with_rows("standings", "standing", rows)
+}
@@ -0,0 +1,187 @@
+use crate::Battle;
+use rusqlite::{params, Connection};
+use rustc_hash::FxHasher;
+use std::{hash::Hasher, io, path::Path};
+
+/// Table of the games in a results database
+const CREATE_GAMES_TABLE: &str = "CREATE TABLE IF NOT EXISTS games (
+    id INTEGER PRIMARY KEY,
+    source TEXT NOT NULL,
+    config TEXT NOT NULL,
+    seed INTEGER NOT NULL,
+    strategy TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    mana INTEGER NOT NULL,
+    turns INTEGER NOT NULL
+)";
+
+/// How a recorded game ended for the strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOutcome {
+    Won,
+    Lost,
+}
+
+impl GameOutcome {
+    pub fn get_key(self) -> &'static str {
+        match self {
+            Self::Won => "won",
+            Self::Lost => "lost",
+        }
+    }
+}
+
+/// One game of an experiment, a row of the `games` table of a results database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    /// Subcommand that played the game
+    pub source: String,
+    /// Hash of the battle the game started from, see `config_hash`
+    pub config: u64,
+    pub seed: u64,
+    pub strategy: String,
+    pub outcome: GameOutcome,
+    pub mana: i32,
+    pub turns: u32,
+}
+
+/// Hash of everything about `battle` that decides how games from it go: the boss, the
+/// wizard's stats, mana limit and items, the difficulty, blood magic, action points, the
+/// spellbook and what the wizard knows. The same on every run, so games from different runs
+/// and subcommands can be grouped by it.
+pub fn config_hash(battle: &Battle) -> u64 {
+    let wizard = battle.get_wizard();
+    let description = format!(
+        "{:?} {} {} {:?} {:?} {} {:?} {} {} {:?} {:?}",
+        battle.get_boss(),
+        wizard.get_hitpoints(),
+        wizard.get_mana(),
+        wizard.get_max_mana(),
+        wizard.get_equipment(),
+        battle.get_hard_mode(),
+        battle.get_rules_version(),
+        battle.get_blood_magic(),
+        battle.get_action_points().is_some(),
+        battle.get_spellbook(),
+        battle.get_knowledge()
+    );
+    let mut hasher = FxHasher::default();
+    hasher.write(description.as_bytes());
+    hasher.finish()
+}
+
+fn to_io_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+/// Open the SQLite results database at `path`, creating it and its table if needed
+fn open_results(path: &Path) -> io::Result<Connection> {
+    let connection = Connection::open(path).map_err(to_io_error)?;
+    connection
+        .execute(CREATE_GAMES_TABLE, ())
+        .map_err(to_io_error)?;
+    Ok(connection)
+}
+
+/// Add `records` to the results database at `path`, creating it if needed. The rows are
+/// added in one transaction, so a run is either recorded whole or not at all.
+pub fn append_results(path: &Path, records: &[GameRecord]) -> io::Result<()> {
+    let mut connection = open_results(path)?;
+    let transaction = connection.transaction().map_err(to_io_error)?;
+    {
+        let mut insert = transaction
+            .prepare(
+                "INSERT INTO games (source, config, seed, strategy, outcome, mana, turns)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )
+            .map_err(to_io_error)?;
+        for record in records {
+            insert
+                .execute(params![
+                    record.source,
+                    format!("{:016x}", record.config),
+                    // SQLite integers are signed, so seeds keep their bits rather than value
+                    record.seed as i64,
+                    record.strategy,
+                    record.outcome.get_key(),
+                    record.mana,
+                    record.turns,
+                ])
+                .map_err(to_io_error)?;
+        }
+    }
+    transaction.commit().map_err(to_io_error)
+}
+
+/// Games of one source, config and strategy in a results database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultsGroup {
+    pub source: String,
+    pub config: u64,
+    pub strategy: String,
+    pub games: u64,
+    pub wins: u64,
+    /// Total mana used in won games
+    pub mana_used_in_wins: u64,
+    /// Total turns of all games
+    pub turns: u64,
+}
+
+impl ResultsGroup {
+    pub fn get_win_rate(&self) -> f64 {
+        self.wins as f64 / self.games as f64
+    }
+
+    /// Average mana used in won games, None if no game was won
+    pub fn get_average_mana(&self) -> Option<f64> {
+        (self.wins > 0).then(|| self.mana_used_in_wins as f64 / self.wins as f64)
+    }
+
+    pub fn get_average_turns(&self) -> f64 {
+        self.turns as f64 / self.games as f64
+    }
+}
+
+/// Games in the results database at `path` grouped by source, config and strategy, in the
+/// order each group was first recorded. Only games of `source` and `strategy` are counted
+/// if given.
+pub fn summarize_results(
+    path: &Path,
+    source: Option<&str>,
+    strategy: Option<&str>,
+) -> io::Result<Vec<ResultsGroup>> {
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no results database",
+        ));
+    }
+    let connection = open_results(path)?;
+    let mut query = connection
+        .prepare(
+            "SELECT source, config, strategy, COUNT(*),
+                 SUM(outcome = 'won'),
+                 SUM(CASE WHEN outcome = 'won' THEN mana ELSE 0 END),
+                 SUM(turns)
+             FROM games
+             WHERE (?1 IS NULL OR source = ?1) AND (?2 IS NULL OR strategy = ?2)
+             GROUP BY source, config, strategy
+             ORDER BY MIN(id)",
+        )
+        .map_err(to_io_error)?;
+    let groups = query
+        .query_map(params![source, strategy], |row| {
+            let config: String = row.get(1)?;
+            Ok(ResultsGroup {
+                source: row.get(0)?,
+                config: u64::from_str_radix(&config, 16).unwrap_or_default(),
+                strategy: row.get(2)?,
+                games: row.get(3)?,
+                wins: row.get(4)?,
+                mana_used_in_wins: row.get(5)?,
+                turns: row.get(6)?,
+            })
+        })
+        .map_err(to_io_error)?;
+    groups.collect::<Result<_, _>>().map_err(to_io_error)
+}
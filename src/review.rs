@@ -0,0 +1,119 @@
+use crate::{
+    solver::{solve_cheapest_win, SolveOptions},
+    Battle, Spell,
+};
+
+/// Mana a cast may lose against the best move and still be graded fine
+pub const FINE_MANA_MARGIN: i32 = 60;
+/// Mana a cast may lose against the best move before it is a blunder
+pub const INACCURACY_MANA_MARGIN: i32 = 200;
+
+/// How a cast compares with the cheapest win from its position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastGrade {
+    /// Keeps to a cheapest win
+    Best,
+    /// Wins for up to `FINE_MANA_MARGIN` more mana
+    Fine,
+    /// Wins for up to `INACCURACY_MANA_MARGIN` more mana
+    Inaccuracy,
+    /// Wins only for more mana than that, or gives up a won position
+    Blunder,
+}
+
+impl CastGrade {
+    pub fn get_display_name(self) -> &'static str {
+        match self {
+            Self::Best => "Best",
+            Self::Fine => "Fine",
+            Self::Inaccuracy => "Inaccuracy",
+            Self::Blunder => "Blunder",
+        }
+    }
+
+    fn from_mana_lost(mana_lost: i32) -> Self {
+        if mana_lost <= 0 {
+            Self::Best
+        } else if mana_lost <= FINE_MANA_MARGIN {
+            Self::Fine
+        } else if mana_lost <= INACCURACY_MANA_MARGIN {
+            Self::Inaccuracy
+        } else {
+            Self::Blunder
+        }
+    }
+}
+
+/// A cast of a battle graded against the solver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CastReview {
+    /// Round of the cast, from 1
+    pub round: usize,
+    pub spell: Spell,
+    /// None if the solver found no win from the position, because it was already lost or
+    /// the win is beyond the search
+    pub grade: Option<CastGrade>,
+    /// Mana the cheapest win needs after the cast, beyond what it needed before. None if
+    /// the cast isn't graded or gave up the win.
+    pub mana_lost: Option<i32>,
+    /// First spell of the cheapest win from the position, if one was found
+    pub best: Option<Spell>,
+}
+
+/// Grade each of `spells`, cast in order from `start`, which should be at the start of the
+/// wizard's first turn. The position before every cast is replayed and solved with
+/// `options`. Casts that can't be replayed, such as after sandbox changes, end the review.
+pub fn review_casts(start: &Battle, spells: &[Spell], options: SolveOptions) -> Vec<CastReview> {
+    // Snapshot of the battle at the start of each of the wizard's turns
+    let mut positions = vec![start.clone()];
+    for spell in spells {
+        let mut battle = positions.last().unwrap().clone();
+        if battle.get_outcome().is_some() || battle.play_round(spell).is_err() {
+            break;
+        }
+        positions.push(battle);
+    }
+    let wins: Vec<Option<(i32, Option<Spell>)>> = positions
+        .iter()
+        .map(|battle| match battle.get_outcome() {
+            Some(true) => Some((0, None)),
+            Some(false) => None,
+            None => solve_cheapest_win(battle, options)
+                .solution
+                .map(|solution| (solution.mana, solution.spells.first().cloned())),
+        })
+        .collect();
+
+    spells
+        .iter()
+        .zip(wins.windows(2))
+        .enumerate()
+        .map(|(i, (spell, wins))| {
+            let (grade, mana_lost) = match (&wins[0], &wins[1]) {
+                (Some((before, _)), Some((after, _))) => {
+                    let mana_lost = start.get_spellbook().get_mana(spell) + after - before;
+                    (Some(CastGrade::from_mana_lost(mana_lost)), Some(mana_lost))
+                }
+                (Some(_), None) => (Some(CastGrade::Blunder), None),
+                (None, _) => (None, None),
+            };
+            CastReview {
+                round: i + 1,
+                spell: spell.clone(),
+                grade,
+                mana_lost,
+                best: wins[0].as_ref().and_then(|(_, best)| best.clone()),
+            }
+        })
+        .collect()
+}
+
+/// Share of the graded casts that were best or fine, None if no cast was graded
+pub fn get_accuracy(reviews: &[CastReview]) -> Option<f64> {
+    let graded: Vec<CastGrade> = reviews.iter().filter_map(|review| review.grade).collect();
+    let accurate = graded
+        .iter()
+        .filter(|grade| matches!(grade, CastGrade::Best | CastGrade::Fine))
+        .count();
+    (!graded.is_empty()).then(|| accurate as f64 / graded.len() as f64)
+}
@@ -0,0 +1,31 @@
+/// Small seeded random number generator (SplitMix64), so runs can be reproduced from a seed
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Generator for stream `stream` of `seed`, so parallel jobs each draw their own
+    /// numbers and still reproduce from the one seed
+    pub fn from_stream(seed: u64, stream: u64) -> Self {
+        let mut mixer = Self::new(seed ^ Self::new(stream).next_u64());
+        Self::new(mixer.next_u64())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly random number in `0..n`
+    pub fn below(&mut self, n: u32) -> u32 {
+        (((self.next_u64() >> 32) * n as u64) >> 32) as u32
+    }
+}
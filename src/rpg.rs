@@ -1,44 +1,131 @@
+use crate::{
+    ability::{
+        BossAbility, BurnStacks, HEAL_AMOUNT, HEAL_THRESHOLD_PERCENT, LIFESTEAL_AMOUNT,
+        MAX_BURN_STACKS, SILENCE_DURATION,
+    },
+    boss_ai::BossAiMode,
+    history::BattleHistory,
+    item::{Equipment, StatModifier},
+    knowledge::{BossView, KnowledgeMask, Known},
+    spellbook::get_blood_price,
+    trace::trace_battle,
+    Spellbook, ACTION_POINTS_PER_TURN,
+};
 use rustc_hash::FxHashSet;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr, sync::Arc};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Boss {
     hitpoints: i32,
+    /// Hitpoints the boss started with, which healing can't go past
+    max_hitpoints: i32,
     damage: i32,
     poisoned: Option<i32>,
+    ability: Option<BossAbility>,
+    /// Attacks until the ability is next used
+    ability_countdown: u8,
+    /// Damage the wizard takes from the boss's spiky skin for each spell that hits it
+    spiky_skin: i32,
+    /// How the boss decides whether to use its ability when it comes up
+    ai: BossAiMode,
 }
 
 impl Default for Boss {
     fn default() -> Self {
-        Self {
-            hitpoints: 55,
-            damage: 8,
-            poisoned: None,
-        }
+        Self::new(55, 8)
     }
 }
 
 impl Boss {
-    #[allow(dead_code)]
-    fn new(hitpoints: i32, damage: i32) -> Self {
+    pub fn new(hitpoints: i32, damage: i32) -> Self {
         Self {
             hitpoints,
+            max_hitpoints: hitpoints,
             damage,
             poisoned: None,
+            ability: None,
+            ability_countdown: 0,
+            spiky_skin: 0,
+            ai: BossAiMode::default(),
         }
     }
 
-    fn attack(&mut self, enemy: &mut Wizard) {
-        let adjusted_damage = if self.damage - enemy.armor <= 0 {
-            1
+    /// The boss using `ability` besides attacking
+    pub fn with_ability(self, ability: BossAbility) -> Self {
+        Self {
+            ability: Some(ability),
+            ability_countdown: ability.get_interval(),
+            ..self
+        }
+    }
+
+    /// The boss with spiky skin, pricking the wizard for `damage` whenever a spell hits it
+    pub fn with_spiky_skin(self, damage: i32) -> Self {
+        Self {
+            spiky_skin: damage,
+            ..self
+        }
+    }
+
+    /// The boss deciding on its ability with `ai`
+    pub fn with_ai(self, ai: BossAiMode) -> Self {
+        Self { ai, ..self }
+    }
+
+    /// Attack `enemy`, dealing at least `minimum_damage` through their armor
+    fn attack(&mut self, enemy: &mut Wizard, minimum_damage: i32) {
+        let armor = enemy.get_armor();
+        let adjusted_damage = if self.damage - armor <= minimum_damage {
+            minimum_damage
         } else {
-            self.damage - enemy.armor
+            self.damage - armor
         };
         enemy.hitpoints -= adjusted_damage;
     }
 
-    fn apply_effect(&mut self) {
+    /// Count down to the boss's ability with an attack, using it on `enemy` when it comes
+    /// up unless `hold`, which keeps it ready for the next attack. Returns the ability if
+    /// it was used.
+    fn use_ability(&mut self, enemy: &mut Wizard, hold: bool) -> Option<BossAbility> {
+        let ability = self.ability?;
+        self.ability_countdown -= 1;
+        if self.ability_countdown > 0 {
+            return None;
+        }
+        if hold {
+            self.ability_countdown = 1;
+            return None;
+        }
+        self.ability_countdown = ability.get_interval();
+        match ability {
+            BossAbility::Silence => enemy.silenced = Some(SILENCE_DURATION),
+            BossAbility::Burn => enemy.burns.add(),
+            BossAbility::Dispel => enemy.dispel(),
+            BossAbility::Heal => self.heal(HEAL_AMOUNT),
+            BossAbility::Lifesteal => {
+                enemy.hitpoints -= LIFESTEAL_AMOUNT;
+                self.heal(LIFESTEAL_AMOUNT);
+            }
+        }
+        Some(ability)
+    }
+
+    /// Whether the boss is below `HEAL_THRESHOLD_PERCENT` of its starting hitpoints
+    pub fn is_badly_wounded(&self) -> bool {
+        self.hitpoints * 100 < self.max_hitpoints * HEAL_THRESHOLD_PERCENT
+    }
+
+    /// Heal by `amount`, up to the starting hitpoints
+    fn heal(&mut self, amount: i32) {
+        self.hitpoints = (self.hitpoints + amount).min(self.max_hitpoints.max(self.hitpoints));
+    }
+
+    fn apply_effect(&mut self, spellbook: &Spellbook) {
         if let Some(poison_timer) = self.poisoned.as_mut() {
-            self.hitpoints -= 3;
+            self.hitpoints -= spellbook.get(&Spell::Poison).damage;
             *poison_timer -= 1;
             if *poison_timer == 0 {
                 self.poisoned = None;
@@ -50,6 +137,11 @@ impl Boss {
         self.hitpoints
     }
 
+    /// Hitpoints the boss started with, the most healing can bring it back to
+    pub fn get_max_hitpoints(&self) -> i32 {
+        self.max_hitpoints
+    }
+
     pub fn get_damage(&self) -> i32 {
         self.damage
     }
@@ -57,18 +149,62 @@ impl Boss {
     pub fn get_poisoned(&self) -> Option<i32> {
         self.poisoned
     }
+
+    pub fn get_ability(&self) -> Option<BossAbility> {
+        self.ability
+    }
+
+    /// Attacks until the ability is next used, None if the boss has no ability
+    pub fn get_ability_countdown(&self) -> Option<u8> {
+        self.ability.map(|_| self.ability_countdown)
+    }
+
+    /// Whether the boss's next attack brings its ability up
+    pub fn is_ability_ready(&self) -> bool {
+        self.get_ability_countdown() == Some(1)
+    }
+
+    pub fn get_ai(&self) -> BossAiMode {
+        self.ai
+    }
+
+    /// Damage the wizard takes for each spell that hits the boss, 0 without spiky skin
+    pub fn get_spiky_skin(&self) -> i32 {
+        self.spiky_skin
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
 pub enum Spell {
     MagicMissile,
     Drain,
     Shield,
     Poison,
     Recharge,
+    /// Ends the silence, burns and poison on the caster. Sealed in the default spellbook.
+    Cleanse,
+    /// Reflects damage at the boss whenever it attacks. Sealed in the default spellbook.
+    Thorns,
+    /// Summons a familiar attacking the boss at the start of each wizard turn. Sealed in
+    /// the default spellbook.
+    Summon,
 }
 
 impl Spell {
+    /// Every spell: the spells of the original puzzle cheapest first, then the extra ones
+    pub const ALL: [Spell; 8] = [
+        Spell::MagicMissile,
+        Spell::Drain,
+        Spell::Shield,
+        Spell::Poison,
+        Spell::Recharge,
+        Spell::Cleanse,
+        Spell::Thorns,
+        Spell::Summon,
+    ];
+
     pub fn get_mana(&self) -> i32 {
         match self {
             Spell::MagicMissile => 53,
@@ -76,6 +212,9 @@ impl Spell {
             Spell::Shield => 113,
             Spell::Poison => 173,
             Spell::Recharge => 229,
+            Spell::Cleanse => 83,
+            Spell::Thorns => 131,
+            Spell::Summon => 61,
         }
     }
 
@@ -86,18 +225,95 @@ impl Spell {
             Spell::Shield => "Shield",
             Spell::Poison => "Poison",
             Spell::Recharge => "Recharge",
+            Spell::Cleanse => "Cleanse",
+            Spell::Thorns => "Thorns",
+            Spell::Summon => "Summon Familiar",
         }
     }
+
+    /// First letter of each word of the name, e.g. "mm" for Magic Missile
+    pub fn get_initials(&self) -> String {
+        self.get_display_name()
+            .split(' ')
+            .filter_map(|word| word.chars().next())
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    }
+}
+
+impl fmt::Display for Spell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.get_display_name())
+    }
+}
+
+/// Spell name that matches no spell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSpellError(pub String);
+
+impl fmt::Display for ParseSpellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = Spell::ALL
+            .iter()
+            .map(|spell| format!("{} ({})", spell.get_display_name(), spell.get_initials()))
+            .collect();
+        write!(
+            f,
+            "unknown spell `{}`, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseSpellError {}
+
+impl FromStr for Spell {
+    type Err = ParseSpellError;
+
+    /// Parse a spell from its name in any case, with words separated by spaces, `-`, `_`
+    /// or nothing, or from its initials: "Magic Missile", "magic-missile" or "mm"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalize = |name: &str| -> String {
+            name.chars()
+                .filter(|c| !matches!(c, ' ' | '-' | '_'))
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        };
+        let name = normalize(s.trim());
+        Spell::ALL
+            .iter()
+            .find(|spell| {
+                normalize(spell.get_display_name()) == name || spell.get_initials() == name
+            })
+            .cloned()
+            .ok_or_else(|| ParseSpellError(s.to_string()))
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Wizard {
     hitpoints: i32,
     armor: i32,
     mana: i32,
     shielded: Option<i32>,
     recharging: Option<i32>,
+    thorns: Option<i32>,
+    /// Wizard turns the familiar has left, counted only at the start of the wizard's turns
+    familiar: Option<i32>,
+    /// Only an opposing wizard can poison a wizard
+    poisoned: Option<i32>,
+    /// Turns left the wizard can't cast effect spells for
+    silenced: Option<i32>,
+    burns: BurnStacks,
+    /// Whether casts the wizard is short of mana for take the rest from their hitpoints
+    blood_magic: bool,
+    /// Most mana the wizard can hold, None if there is no limit as in classic play
+    max_mana: Option<i32>,
     possible_spells: FxHashSet<Spell>,
+    /// Items worn, modifying the stats above where they are used
+    equipment: Equipment,
 }
 
 impl Default for Wizard {
@@ -108,9 +324,17 @@ impl Default for Wizard {
             mana: 500,
             shielded: None,
             recharging: None,
+            thorns: None,
+            familiar: None,
+            poisoned: None,
+            silenced: None,
+            burns: BurnStacks::default(),
+            blood_magic: false,
+            max_mana: None,
             possible_spells: FxHashSet::default(),
+            equipment: Equipment::default(),
         };
-        wizard.update_possible_spells(&Boss::default());
+        wizard.update_possible_spells(&Boss::default(), &Spellbook::default());
         wizard
     }
 }
@@ -124,52 +348,150 @@ impl Wizard {
             mana,
             shielded: None,
             recharging: None,
+            thorns: None,
+            familiar: None,
+            poisoned: None,
+            silenced: None,
+            burns: BurnStacks::default(),
+            blood_magic: false,
+            max_mana: None,
             possible_spells: FxHashSet::default(),
+            equipment: Equipment::default(),
         };
-        wizard.update_possible_spells(&Boss::default());
+        wizard.update_possible_spells(&Boss::default(), &Spellbook::default());
         wizard
     }
 
-    fn magic_missile(&mut self, enemy: &mut Boss) {
-        self.mana -= Spell::MagicMissile.get_mana();
-        enemy.hitpoints -= 4;
+    /// Hitpoints a cast costing `mana` takes under blood magic, for the mana the wizard is
+    /// short of. 0 if the wizard has the mana.
+    fn get_blood_price(&self, mana: i32) -> i32 {
+        get_blood_price(mana - self.mana)
     }
 
-    fn drain(&mut self, enemy: &mut Boss) {
-        self.mana -= Spell::Drain.get_mana();
-        enemy.hitpoints -= 2;
-        self.hitpoints += 2;
+    /// Whether the wizard can pay for a cast costing `mana`: from their mana, or under
+    /// blood magic the rest from hitpoints, as long as they survive paying them
+    fn can_afford(&self, mana: i32) -> bool {
+        self.mana >= mana || self.blood_magic && self.hitpoints > self.get_blood_price(mana)
     }
 
-    fn shield(&mut self) {
-        self.mana -= Spell::Shield.get_mana();
+    /// Pay for a cast costing `mana`, from hitpoints for the mana the wizard is short of
+    fn pay(&mut self, mana: i32) {
+        self.hitpoints -= self.get_blood_price(mana);
+        self.mana = (self.mana - mana).max(0);
+    }
+
+    fn magic_missile(&mut self, enemy: &mut Boss, spellbook: &Spellbook) {
+        let stats = spellbook.get(&Spell::MagicMissile);
+        self.pay(stats.mana);
+        enemy.hitpoints -= stats.damage;
+    }
+
+    fn drain(&mut self, enemy: &mut Boss, spellbook: &Spellbook) {
+        let stats = spellbook.get(&Spell::Drain);
+        self.pay(stats.mana);
+        enemy.hitpoints -= stats.damage;
+        self.hitpoints += stats.heal;
+    }
+
+    fn shield(&mut self, spellbook: &Spellbook) {
+        let stats = spellbook.get(&Spell::Shield);
+        self.pay(stats.mana);
         if self.shielded.is_some() {
             panic!("Can not shield with existing shield");
         } else {
-            self.shielded = Some(6);
-            self.armor = 7;
+            self.shielded = Some(
+                self.equipment
+                    .modify_duration(&Spell::Shield, stats.duration),
+            );
+            self.armor = stats.armor;
         }
     }
 
-    fn poison(&mut self, enemy: &mut Boss) {
-        self.mana -= Spell::Poison.get_mana();
+    fn poison(&mut self, enemy: &mut Boss, spellbook: &Spellbook) {
+        let stats = spellbook.get(&Spell::Poison);
+        self.pay(stats.mana);
         if enemy.poisoned.is_some() {
             panic!("Cannot poison with existing poison");
         } else {
-            enemy.poisoned = Some(6);
+            enemy.poisoned = Some(
+                self.equipment
+                    .modify_duration(&Spell::Poison, stats.duration),
+            );
         }
     }
 
-    fn recharge(&mut self) {
-        self.mana -= Spell::Recharge.get_mana();
+    fn recharge(&mut self, spellbook: &Spellbook) {
+        let stats = spellbook.get(&Spell::Recharge);
+        self.pay(stats.mana);
         if self.recharging.is_some() {
             panic!("Can not recharge with existing recharging");
         } else {
-            self.recharging = Some(5);
+            self.recharging = Some(
+                self.equipment
+                    .modify_duration(&Spell::Recharge, stats.duration),
+            );
         }
     }
 
-    fn apply_effect(&mut self) {
+    fn thorns(&mut self, spellbook: &Spellbook) {
+        let stats = spellbook.get(&Spell::Thorns);
+        self.pay(stats.mana);
+        if self.thorns.is_some() {
+            panic!("Can not grow thorns with existing thorns");
+        } else {
+            self.thorns = Some(
+                self.equipment
+                    .modify_duration(&Spell::Thorns, stats.duration),
+            );
+        }
+    }
+
+    fn summon(&mut self, spellbook: &Spellbook) {
+        let stats = spellbook.get(&Spell::Summon);
+        self.pay(stats.mana);
+        if self.familiar.is_some() {
+            panic!("Can not summon a familiar with one already summoned");
+        } else {
+            self.familiar = Some(
+                self.equipment
+                    .modify_duration(&Spell::Summon, stats.duration),
+            );
+        }
+    }
+
+    /// End the silence, burns and poison on the wizard. Returns the event recording what
+    /// was ended.
+    fn cleanse(&mut self, spellbook: &Spellbook) -> BattleEvent {
+        self.pay(spellbook.get_mana(&Spell::Cleanse));
+        let event = BattleEvent::WizardCleansed {
+            silenced: self.silenced.is_some(),
+            burn_stacks: self.burns.get_stacks(),
+            poisoned: self.poisoned.is_some(),
+        };
+        self.silenced = None;
+        self.burns = BurnStacks::default();
+        self.poisoned = None;
+        event
+    }
+
+    /// End Shield and Recharge early
+    fn dispel(&mut self) {
+        self.shielded = None;
+        self.armor = 0;
+        self.recharging = None;
+    }
+
+    /// Whether the wizard has something for Cleanse to end on its next cast. Burns and
+    /// poison tick at the start of the wizard's turn, so ones on their last turn when the
+    /// boss has attacked are gone by then.
+    fn has_cleansable_effects(&self) -> bool {
+        self.silenced.is_some()
+            || self.burns.get_timers()[0] > 1
+            || self.poisoned.is_some_and(|timer| timer > 1)
+    }
+
+    pub(crate) fn apply_effect(&mut self, spellbook: &Spellbook) {
+        self.mana += self.equipment.modify_turn_mana(0);
         if let Some(shield_timer) = self.shielded.as_mut() {
             *shield_timer -= 1;
             if *shield_timer == 0 {
@@ -178,60 +500,144 @@ impl Wizard {
             }
         }
         if let Some(recharge_timer) = self.recharging.as_mut() {
-            self.mana += 101;
+            self.mana += spellbook.get(&Spell::Recharge).mana_gain;
             *recharge_timer -= 1;
             if *recharge_timer == 0 {
                 self.recharging = None;
             }
         }
+        if let Some(max_mana) = self.max_mana {
+            self.mana = self.mana.min(max_mana);
+        }
+        if let Some(thorns_timer) = self.thorns.as_mut() {
+            *thorns_timer -= 1;
+            if *thorns_timer == 0 {
+                self.thorns = None;
+            }
+        }
+        if let Some(poison_timer) = self.poisoned.as_mut() {
+            self.hitpoints -= spellbook.get(&Spell::Poison).damage;
+            *poison_timer -= 1;
+            if *poison_timer == 0 {
+                self.poisoned = None;
+            }
+        }
+    }
+
+    /// Cast `spell` at another wizard in a duel, where attacks are reduced by the enemy's
+    /// armor (to a minimum of 1). The caller checks that the spell can be cast.
+    pub(crate) fn cast_at_wizard(
+        &mut self,
+        spell: &Spell,
+        enemy: &mut Wizard,
+        spellbook: &Spellbook,
+    ) {
+        let stats = spellbook.get(spell);
+        self.mana -= stats.mana;
+        match spell {
+            Spell::MagicMissile => enemy.hitpoints -= (stats.damage - enemy.armor).max(1),
+            Spell::Drain => {
+                enemy.hitpoints -= (stats.damage - enemy.armor).max(1);
+                self.hitpoints += stats.heal;
+            }
+            Spell::Shield => {
+                self.shielded = Some(stats.duration);
+                self.armor = stats.armor;
+            }
+            Spell::Poison => enemy.poisoned = Some(stats.duration),
+            Spell::Recharge => self.recharging = Some(stats.duration),
+            Spell::Cleanse => self.poisoned = None,
+            Spell::Thorns | Spell::Summon => (),
+        }
     }
 
-    fn update_possible_spells(&mut self, enemy: &Boss) {
-        if self.mana >= Spell::MagicMissile.get_mana() {
+    fn update_possible_spells(&mut self, enemy: &Boss, spellbook: &Spellbook) {
+        if self.can_afford(spellbook.get_mana(&Spell::MagicMissile)) {
             self.possible_spells.insert(Spell::MagicMissile);
         } else {
             self.possible_spells.remove(&Spell::MagicMissile);
         }
-        if self.mana >= Spell::Drain.get_mana() {
+        if self.can_afford(spellbook.get_mana(&Spell::Drain)) {
             self.possible_spells.insert(Spell::Drain);
         } else {
             self.possible_spells.remove(&Spell::Drain);
         }
-        if self.mana >= Spell::Shield.get_mana()
+        if self.can_afford(spellbook.get_mana(&Spell::Shield))
             && (self.shielded.is_none() || self.shielded == Some(1))
         {
             self.possible_spells.insert(Spell::Shield);
         } else {
             self.possible_spells.remove(&Spell::Shield);
         }
-        if self.mana >= Spell::Poison.get_mana()
+        if self.can_afford(spellbook.get_mana(&Spell::Poison))
             && (enemy.poisoned.is_none() || enemy.poisoned == Some(1))
         {
             self.possible_spells.insert(Spell::Poison);
         } else {
             self.possible_spells.remove(&Spell::Poison);
         }
-        if self.mana >= Spell::Recharge.get_mana()
+        if self.can_afford(spellbook.get_mana(&Spell::Recharge))
             && (self.recharging.is_none() || self.recharging == Some(1))
         {
             self.possible_spells.insert(Spell::Recharge);
         } else {
             self.possible_spells.remove(&Spell::Recharge);
         }
+        if self.can_afford(spellbook.get_mana(&Spell::Thorns))
+            && (self.thorns.is_none() || self.thorns == Some(1))
+        {
+            self.possible_spells.insert(Spell::Thorns);
+        } else {
+            self.possible_spells.remove(&Spell::Thorns);
+        }
+        if self.can_afford(spellbook.get_mana(&Spell::Summon))
+            && (self.familiar.is_none() || self.familiar == Some(1))
+        {
+            self.possible_spells.insert(Spell::Summon);
+        } else {
+            self.possible_spells.remove(&Spell::Summon);
+        }
+        if self.can_afford(spellbook.get_mana(&Spell::Cleanse)) && self.has_cleansable_effects() {
+            self.possible_spells.insert(Spell::Cleanse);
+        } else {
+            self.possible_spells.remove(&Spell::Cleanse);
+        }
+        let silenced = self.silenced.is_some();
+        self.possible_spells
+            .retain(|spell| !(spellbook.is_sealed(spell) || silenced && spell.is_effect()));
+    }
+
+    /// Count a turn of silence off, ending it once none are left
+    fn wear_off_silence(&mut self) {
+        if let Some(silence_timer) = self.silenced.as_mut() {
+            *silence_timer -= 1;
+            if *silence_timer == 0 {
+                self.silenced = None;
+            }
+        }
     }
 
     pub fn get_hitpoints(&self) -> i32 {
         self.hitpoints
     }
 
+    /// Armor from Shield and the items worn
     pub fn get_armor(&self) -> i32 {
-        self.armor
+        self.equipment.modify_armor(self.armor)
+    }
+
+    pub fn get_equipment(&self) -> &Equipment {
+        &self.equipment
     }
 
     pub fn get_mana(&self) -> i32 {
         self.mana
     }
 
+    pub fn get_max_mana(&self) -> Option<i32> {
+        self.max_mana
+    }
+
     pub fn get_shielded(&self) -> Option<i32> {
         self.shielded
     }
@@ -240,123 +646,1085 @@ impl Wizard {
         self.recharging
     }
 
+    pub fn get_thorns(&self) -> Option<i32> {
+        self.thorns
+    }
+
+    /// Wizard turns the familiar has left, None without one
+    pub fn get_familiar(&self) -> Option<i32> {
+        self.familiar
+    }
+
+    pub fn get_poisoned(&self) -> Option<i32> {
+        self.poisoned
+    }
+
+    pub fn get_silenced(&self) -> Option<i32> {
+        self.silenced
+    }
+
+    pub fn get_burns(&self) -> &BurnStacks {
+        &self.burns
+    }
+
     pub fn get_possible_spells(&self) -> &FxHashSet<Spell> {
         &self.possible_spells
     }
 }
 
-#[derive(Debug)]
+/// What dealt damage to a combatant
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum DamageSource {
+    MagicMissile,
+    Drain,
+    Poison,
+    BossAttack,
+    /// Hitpoint lost at the start of each wizard turn on hard mode
+    HardMode,
+    /// Hitpoints paid for a cast the wizard is short of mana for, under blood magic
+    BloodMagic,
+    /// The stacks of burn from the boss's ability
+    Burn,
+    /// Damage Thorns reflects at the attacking boss
+    Thorns,
+    /// Damage the boss's spiky skin deals to the wizard for a spell that hits it
+    SpikySkin,
+    /// The familiar's attack at the start of each wizard turn
+    Familiar,
+    /// Hitpoints the boss's ability takes from the wizard to heal itself
+    Lifesteal,
+}
+
+impl DamageSource {
+    pub fn get_display_name(&self) -> &'static str {
+        match self {
+            DamageSource::MagicMissile => "Magic Missile",
+            DamageSource::Drain => "Drain",
+            DamageSource::Poison => "Poison",
+            DamageSource::BossAttack => "Boss Attack",
+            DamageSource::HardMode => "Hard Mode",
+            DamageSource::BloodMagic => "Blood Magic",
+            DamageSource::Burn => "Burn",
+            DamageSource::Thorns => "Thorns",
+            DamageSource::SpikySkin => "Spiky Skin",
+            DamageSource::Familiar => "Familiar",
+            DamageSource::Lifesteal => "Lifesteal",
+        }
+    }
+}
+
+/// Something that happened during the battle, recorded in order
+#[derive(Debug, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum BattleEvent {
+    SpellCast(Spell),
+    BossDamaged {
+        source: DamageSource,
+        amount: i32,
+    },
+    WizardDamaged {
+        source: DamageSource,
+        amount: i32,
+    },
+    /// The boss kept the wizard from casting effect spells for `turns` turns
+    WizardSilenced {
+        turns: i32,
+    },
+    /// The boss added a stack of burn, leaving the wizard with `stacks`
+    WizardBurned {
+        stacks: usize,
+    },
+    /// Cleanse ended the wizard's silence, `burn_stacks` stacks of burn and poison
+    WizardCleansed {
+        silenced: bool,
+        burn_stacks: usize,
+        poisoned: bool,
+    },
+    /// The boss ended the wizard's `spells` effects early
+    EffectsDispelled {
+        spells: Vec<Spell>,
+    },
+    /// The boss's ability restored `amount` of its hitpoints
+    BossHealed {
+        amount: i32,
+    },
+}
+
+/// Mutators that change a battle outside its rules, for testing content. They don't end
+/// the battle, and battles changed with them can't be replayed from their spells.
+#[cfg(feature = "sandbox")]
+impl Battle {
+    pub fn set_wizard_hitpoints(&mut self, hitpoints: i32) {
+        self.wizard.hitpoints = hitpoints;
+    }
+
+    pub fn set_wizard_mana(&mut self, mana: i32) {
+        self.wizard.mana = mana;
+        self.wizard
+            .update_possible_spells(&self.boss, &self.spellbook);
+    }
+
+    pub fn set_boss_hitpoints(&mut self, hitpoints: i32) {
+        self.boss.hitpoints = hitpoints;
+    }
+
+    /// Set the turns left of `spell`'s effect, ending it if `turns` is 0. Returns false
+    /// if the spell has no effect.
+    pub fn set_effect(&mut self, spell: &Spell, turns: i32) -> bool {
+        let timer = (turns > 0).then_some(turns);
+        match spell {
+            Spell::Shield => {
+                self.wizard.shielded = timer;
+                self.wizard.armor = match timer {
+                    Some(_) => self.spellbook.get(&Spell::Shield).armor,
+                    None => 0,
+                };
+            }
+            Spell::Recharge => self.wizard.recharging = timer,
+            Spell::Thorns => self.wizard.thorns = timer,
+            Spell::Summon => self.wizard.familiar = timer,
+            Spell::Poison => self.boss.poisoned = timer,
+            _ => return false,
+        }
+        self.wizard
+            .update_possible_spells(&self.boss, &self.spellbook);
+        true
+    }
+}
+
+/// Compact copy of everything that affects how the rest of a battle plays out
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BattleState {
+    pub wizard_hitpoints: i32,
+    pub wizard_mana: i32,
+    /// Turns of shield left, 0 if not shielded
+    pub shield_timer: u8,
+    /// Turns of recharge left, 0 if not recharging
+    pub recharge_timer: u8,
+    pub boss_hitpoints: i32,
+    /// Turns of poison left, 0 if not poisoned
+    pub poison_timer: u8,
+    /// Spells the wizard can cast, one bit per spell in the order of `Spell::ALL`
+    pub possible_spells: u8,
+    /// Turns of silence left, 0 if not silenced
+    pub silence_timer: u8,
+    /// Attacks until the boss's ability is next used, 0 if it has none
+    pub ability_countdown: u8,
+    /// Turns left of each stack of burn, longest first
+    pub burn_timers: [u8; MAX_BURN_STACKS],
+    /// Turns of thorns left, 0 without thorns
+    pub thorns_timer: u8,
+    /// Wizard turns the familiar has left, 0 without one
+    pub familiar_timer: u8,
+}
+
+impl BattleState {
+    /// How the stats shown to the player changed from this state to the later `other`
+    pub fn diff(&self, other: &BattleState) -> StateDiff {
+        StateDiff {
+            wizard_hitpoints: other.wizard_hitpoints - self.wizard_hitpoints,
+            wizard_mana: other.wizard_mana - self.wizard_mana,
+            boss_hitpoints: other.boss_hitpoints - self.boss_hitpoints,
+        }
+    }
+}
+
+/// Change of the wizard's and boss's stats between two states of a battle, see
+/// `BattleState::diff`
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default)]
+pub struct StateDiff {
+    pub wizard_hitpoints: i32,
+    pub wizard_mana: i32,
+    pub boss_hitpoints: i32,
+}
+
+impl StateDiff {
+    /// Whether none of the stats changed
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Version of the battle rules. Saves record the version they were played under, so they
+/// keep replaying the same after the rules are refined.
+///
+/// A rule change adds a version and makes it the latest, and `Battle` matches on its
+/// version only where the changed rule is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum RulesVersion {
+    /// The rules of Advent of Code 2015 day 22
+    #[default]
+    V1,
+}
+
+impl RulesVersion {
+    /// Version new battles are played under
+    pub const LATEST: RulesVersion = RulesVersion::V1;
+    pub const ALL: [RulesVersion; 1] = [RulesVersion::V1];
+
+    /// Number stored in files
+    pub fn get_number(&self) -> i64 {
+        match self {
+            Self::V1 => 1,
+        }
+    }
+
+    /// Version stored as `number`, None if this build doesn't know it
+    pub fn from_number(number: i64) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|version| version.get_number() == number)
+    }
+}
+
+impl fmt::Display for RulesVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.get_number())
+    }
+}
+
+/// Callback given each event of a battle as it happens
+pub type EventObserver = Box<dyn FnMut(&BattleEvent) + Send>;
+
+/// Observers subscribed to a battle. Clones of a battle, such as the ones searches make,
+/// start without observers so they don't report events that never happened.
+#[derive(Default)]
+struct Observers(Vec<EventObserver>);
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Battle {
     wizard: Wizard,
     boss: Boss,
     hard_mode: bool,
+    rules_version: RulesVersion,
+    spellbook: Arc<Spellbook>,
     mana_used: i32,
     spells_used: Vec<Spell>,
+    events: Vec<BattleEvent>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    observers: Observers,
     /// Did the the wizard win
     outcome: Option<bool>,
+    /// What the wizard knows of the boss's stats
+    knowledge: KnowledgeMask,
+    /// Action points left this turn, None under the one cast a turn rules
+    action_points: Option<i32>,
+    /// Snapshots of the rounds played, None unless asked for with `with_history`
+    history: Option<BattleHistory>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EffectOngoingError();
 
+/// Whether a spell can be cast now, and why not if it can't
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SpellAvailability {
+    Available,
+    /// The wizard is short of mana and pays `hitpoints` for the rest under blood magic
+    AvailableAtCost {
+        hitpoints: i32,
+    },
+    /// The spell was sealed away, such as by the loadout
+    Sealed,
+    /// The boss silenced the wizard, so effect spells can't be cast
+    Silenced,
+    NotEnoughMana,
+    /// The spell's effect is still running
+    EffectRunning,
+    /// Not enough action points are left this turn
+    NoActionPoints,
+    /// Cleanse has nothing to end
+    NothingToCleanse,
+}
+
+impl SpellAvailability {
+    pub fn is_available(&self) -> bool {
+        matches!(
+            self,
+            SpellAvailability::Available | SpellAvailability::AvailableAtCost { .. }
+        )
+    }
+
+    /// Why the spell can't be cast, None if it can
+    pub fn get_reason(&self) -> Option<&'static str> {
+        match self {
+            SpellAvailability::Available | SpellAvailability::AvailableAtCost { .. } => None,
+            SpellAvailability::Sealed => Some("sealed"),
+            SpellAvailability::Silenced => Some("silenced"),
+            SpellAvailability::NotEnoughMana => Some("not enough mana"),
+            SpellAvailability::EffectRunning => Some("effect running"),
+            SpellAvailability::NoActionPoints => Some("no action points"),
+            SpellAvailability::NothingToCleanse => Some("nothing to cleanse"),
+        }
+    }
+}
+
+/// Why casting a spell now would be strictly wasteful
+#[derive(Debug, Eq, PartialEq, Clone)]
+#[non_exhaustive]
+pub enum WastedCast {
+    /// The boss dies to poison at the start of its turn whatever is cast
+    BossDiesToPoison,
+    /// A cheaper spell defeats the boss this turn
+    CheaperSpellWins(Spell),
+    /// Recharge at the most mana the wizard can hold only wins back what it costs
+    ManaFull,
+}
+
 impl Battle {
     pub fn new(hard_mode: bool) -> Self {
         Self {
             wizard: Wizard::default(),
             boss: Boss::default(),
             hard_mode,
+            rules_version: RulesVersion::LATEST,
+            spellbook: Arc::new(Spellbook::default()),
             mana_used: 0,
             spells_used: Vec::new(),
+            events: Vec::new(),
+            observers: Observers::default(),
             outcome: None,
+            knowledge: KnowledgeMask::FULL,
+            action_points: None,
+            history: None,
+        }
+    }
+
+    /// A battle against a boss with custom stats
+    pub fn with_boss(hard_mode: bool, boss: Boss) -> Self {
+        Self {
+            boss,
+            ..Self::new(hard_mode)
+        }
+    }
+
+    /// A battle against a boss with custom stats, casting spells from a custom spellbook
+    pub fn with_spellbook(hard_mode: bool, boss: Boss, spellbook: Spellbook) -> Self {
+        let mut battle = Self {
+            spellbook: Arc::new(spellbook),
+            ..Self::with_boss(hard_mode, boss)
+        };
+        battle
+            .wizard
+            .update_possible_spells(&battle.boss, &battle.spellbook);
+        battle
+    }
+
+    /// A copy of the battle for a search to play out, leaving out the history of rounds,
+    /// which searches don't read and would otherwise copy at every position
+    pub fn for_search(&self) -> Self {
+        Self {
+            wizard: self.wizard.clone(),
+            boss: self.boss.clone(),
+            hard_mode: self.hard_mode,
+            rules_version: self.rules_version,
+            spellbook: self.spellbook.clone(),
+            mana_used: self.mana_used,
+            spells_used: self.spells_used.clone(),
+            events: self.events.clone(),
+            observers: Observers::default(),
+            outcome: self.outcome,
+            knowledge: self.knowledge,
+            action_points: self.action_points,
+            history: None,
         }
     }
 
+    /// The battle played under older rules, to replay a battle recorded under them
+    pub fn with_rules_version(self, rules_version: RulesVersion) -> Self {
+        Self {
+            rules_version,
+            ..self
+        }
+    }
+
+    /// The battle with the wizard starting with extra hitpoints and mana, such as from
+    /// resting before it
+    pub fn with_wizard_bonus(mut self, hitpoints: i32, mana: i32) -> Self {
+        self.wizard.hitpoints += hitpoints;
+        self.wizard.mana += mana;
+        self.wizard
+            .update_possible_spells(&self.boss, &self.spellbook);
+        self
+    }
+
+    /// The battle with the wizard wearing `equipment`
+    pub fn with_equipment(mut self, equipment: Equipment) -> Self {
+        self.wizard.equipment = equipment;
+        self
+    }
+
+    /// The battle with the wizard knowing only the boss's stats in `knowledge`
+    pub fn with_knowledge(self, knowledge: KnowledgeMask) -> Self {
+        Self { knowledge, ..self }
+    }
+
+    /// The battle played under the action point economy: each turn gives
+    /// `ACTION_POINTS_PER_TURN` and each cast takes some, so cheap spells can be cast twice
+    /// in a turn
+    pub fn with_action_points(self) -> Self {
+        Self {
+            action_points: Some(ACTION_POINTS_PER_TURN),
+            ..self
+        }
+    }
+
+    /// The battle played under blood magic: the wizard can cast spells they are short of
+    /// mana for, paying a hitpoint for every `BLOOD_MAGIC_MANA_PER_HITPOINT` mana short
+    pub fn with_blood_magic(mut self) -> Self {
+        self.wizard.blood_magic = true;
+        self.wizard
+            .update_possible_spells(&self.boss, &self.spellbook);
+        self
+    }
+
+    /// The battle with the wizard holding at most `max_mana` mana, what Recharge and items
+    /// give beyond it being lost
+    pub fn with_max_mana(mut self, max_mana: i32) -> Self {
+        self.wizard.max_mana = Some(max_mana);
+        self.wizard.mana = self.wizard.mana.min(max_mana);
+        self.wizard
+            .update_possible_spells(&self.boss, &self.spellbook);
+        self
+    }
+
+    /// The battle keeping a snapshot of the state at the start of each of the wizard's
+    /// turns, for the last `capacity` rounds
+    pub fn with_history(self, capacity: usize) -> Self {
+        Self {
+            history: Some(BattleHistory::new(capacity)),
+            ..self
+        }
+    }
+
+    /// Let the wizard learn the boss's stats in `knowledge`, such as from intel
+    pub fn reveal(&mut self, knowledge: KnowledgeMask) {
+        self.knowledge = self.knowledge.union(knowledge);
+    }
+
+    /// Call `observer` with each event from now on, as it happens
+    pub fn subscribe(&mut self, observer: EventObserver) {
+        self.observers.0.push(observer);
+    }
+
+    fn push_event(&mut self, event: BattleEvent) {
+        for observer in &mut self.observers.0 {
+            observer(&event);
+        }
+        self.events.push(event);
+    }
+
     /// Returns Some(true) if the wizard won, Some(false) if the boss won or None
     /// if neither has won.
+    #[cfg_attr(
+        feature = "logging",
+        tracing::instrument(level = "trace", target = "advent_wizard_rpg::battle", skip_all)
+    )]
     pub fn wizard_turn_apply_effects(&mut self) -> Option<bool> {
         if self.hard_mode {
-            self.wizard.hitpoints -= 1;
+            let drain = match self.rules_version {
+                RulesVersion::V1 => 1,
+            };
+            self.wizard.hitpoints -= drain;
+            self.push_event(BattleEvent::WizardDamaged {
+                source: DamageSource::HardMode,
+                amount: drain,
+            });
             // Check wizard lost
             if self.wizard.hitpoints <= 0 {
+                trace_battle!(
+                    "round {}: boss won by hard mode",
+                    self.spells_used.len() + 1
+                );
                 self.outcome = Some(false);
                 return Some(false);
             }
         }
-        self.wizard.apply_effect();
-        self.boss.apply_effect();
+        self.wizard.apply_effect(&self.spellbook);
+        self.wizard_apply_burn();
+        self.boss_apply_effect();
+        self.familiar_attack();
+        if let Some(action_points) = self.action_points.as_mut() {
+            *action_points = ACTION_POINTS_PER_TURN;
+        }
+        trace_battle!(
+            "round {}: wizard turn effects applied, {:?}",
+            self.spells_used.len() + 1,
+            self.get_state()
+        );
         // Check boss lost
         if self.boss.hitpoints <= 0 {
+            trace_battle!("round {}: wizard won by poison", self.spells_used.len() + 1);
             self.outcome = Some(true);
             return Some(true);
         }
+        // Check wizard lost
+        if self.wizard.hitpoints <= 0 {
+            trace_battle!("round {}: boss won by burn", self.spells_used.len() + 1);
+            self.outcome = Some(false);
+            return Some(false);
+        }
+        if self.history.is_some() {
+            let state = self.get_state();
+            if let Some(history) = self.history.as_mut() {
+                history.push(state);
+            }
+        }
         None
     }
 
     /// Returns Some(true) if the wizard won, Some(false) if the boss won or None
     /// if neither has won.
+    #[cfg_attr(
+        feature = "logging",
+        tracing::instrument(level = "trace", target = "advent_wizard_rpg::battle", skip_all)
+    )]
     pub fn wizard_turn_cast_spell(
         &mut self,
         spell: &Spell,
     ) -> Result<Option<bool>, EffectOngoingError> {
         // Check chosen spell is possible
         if !self.wizard.possible_spells.contains(spell) {
+            trace_battle!("{} can't be cast", spell.get_display_name());
             return Err(EffectOngoingError());
         }
 
+        let boss_hitpoints_old = self.boss.hitpoints;
+        let blood_price = self.get_blood_price(spell);
+        let mut cleansed = None;
         match spell {
-            Spell::MagicMissile => self.wizard.magic_missile(&mut self.boss),
-            Spell::Drain => self.wizard.drain(&mut self.boss),
-            Spell::Shield => self.wizard.shield(),
-            Spell::Poison => self.wizard.poison(&mut self.boss),
-            Spell::Recharge => self.wizard.recharge(),
+            Spell::MagicMissile => self.wizard.magic_missile(&mut self.boss, &self.spellbook),
+            Spell::Drain => self.wizard.drain(&mut self.boss, &self.spellbook),
+            Spell::Shield => self.wizard.shield(&self.spellbook),
+            Spell::Poison => self.wizard.poison(&mut self.boss, &self.spellbook),
+            Spell::Recharge => self.wizard.recharge(&self.spellbook),
+            Spell::Cleanse => cleansed = Some(self.wizard.cleanse(&self.spellbook)),
+            Spell::Thorns => self.wizard.thorns(&self.spellbook),
+            Spell::Summon => self.wizard.summon(&self.spellbook),
         }
-        self.mana_used += spell.get_mana();
+        self.mana_used += self.spellbook.get_mana(spell);
         self.spells_used.push(spell.clone());
+        self.push_event(BattleEvent::SpellCast(spell.clone()));
+        if blood_price > 0 {
+            self.push_event(BattleEvent::WizardDamaged {
+                source: DamageSource::BloodMagic,
+                amount: blood_price,
+            });
+        }
+        if let Some(event) = cleansed {
+            self.push_event(event);
+        }
+        let source = match spell {
+            Spell::MagicMissile => Some(DamageSource::MagicMissile),
+            Spell::Drain => Some(DamageSource::Drain),
+            _ => None,
+        };
+        if let Some(source) = source {
+            self.push_event(BattleEvent::BossDamaged {
+                source,
+                amount: boss_hitpoints_old - self.boss.hitpoints,
+            });
+        }
+        trace_battle!(
+            "round {}: wizard cast {}, {:?}",
+            self.spells_used.len(),
+            spell.get_display_name(),
+            self.get_state()
+        );
 
         // Check boss lost
         if self.boss.hitpoints <= 0 {
+            trace_battle!(
+                "round {}: wizard won by {}",
+                self.spells_used.len(),
+                spell.get_display_name()
+            );
             self.outcome = Some(true);
             return Ok(Some(true));
         }
+        // The boss's spiky skin pricks the wizard for a spell that hit it but didn't kill it
+        if source.is_some() && self.boss.spiky_skin > 0 {
+            self.wizard.hitpoints -= self.boss.spiky_skin;
+            self.push_event(BattleEvent::WizardDamaged {
+                source: DamageSource::SpikySkin,
+                amount: self.boss.spiky_skin,
+            });
+            // Check wizard lost
+            if self.wizard.hitpoints <= 0 {
+                trace_battle!("round {}: boss won by spiky skin", self.spells_used.len());
+                self.outcome = Some(false);
+                return Ok(Some(false));
+            }
+        }
+        if let Some(action_points) = self.action_points.as_mut() {
+            *action_points -= self.spellbook.get_action_points(spell);
+            self.update_possible_follow_up_spells();
+        }
         Ok(None)
     }
 
+    /// Spells that can still be cast this turn after a cast under the action point
+    /// economy. Effects still running can't be cast again, and neither can spells costing
+    /// more action points than are left.
+    fn update_possible_follow_up_spells(&mut self) {
+        let action_points = self.action_points.unwrap_or(0);
+        self.wizard
+            .update_possible_spells(&self.boss, &self.spellbook);
+        let affordable: Vec<Spell> = Spell::ALL
+            .into_iter()
+            .filter(|spell| {
+                self.get_effect_timer(spell).is_none()
+                    && self.spellbook.get_action_points(spell) <= action_points
+            })
+            .collect();
+        self.wizard
+            .possible_spells
+            .retain(|spell| affordable.contains(spell));
+    }
+
+    /// Whether `spell` can be cast now, and why not if it can't
+    pub fn get_spell_availability(&self, spell: &Spell) -> SpellAvailability {
+        if self.wizard.possible_spells.contains(spell) {
+            match self.get_blood_price(spell) {
+                0 => SpellAvailability::Available,
+                hitpoints => SpellAvailability::AvailableAtCost { hitpoints },
+            }
+        } else if self.spellbook.is_sealed(spell) {
+            SpellAvailability::Sealed
+        } else if self.wizard.silenced.is_some() && spell.is_effect() {
+            SpellAvailability::Silenced
+        } else if !self.wizard.can_afford(self.spellbook.get_mana(spell)) {
+            SpellAvailability::NotEnoughMana
+        } else if spell.is_effect() && self.get_effect_timer(spell).is_some() {
+            SpellAvailability::EffectRunning
+        } else if *spell == Spell::Cleanse && !self.wizard.has_cleansable_effects() {
+            SpellAvailability::NothingToCleanse
+        } else {
+            SpellAvailability::NoActionPoints
+        }
+    }
+
+    /// Turns left of `spell`'s effect, None if it isn't running or the spell has no effect
+    fn get_effect_timer(&self, spell: &Spell) -> Option<i32> {
+        match spell {
+            Spell::Shield => self.wizard.shielded,
+            Spell::Poison => self.boss.poisoned,
+            Spell::Recharge => self.wizard.recharging,
+            Spell::Thorns => self.wizard.thorns,
+            Spell::Summon => self.wizard.familiar,
+            Spell::MagicMissile | Spell::Drain | Spell::Cleanse => None,
+        }
+    }
+
+    /// Have the boss decide on its ability with `ai` from now on
+    pub(crate) fn set_boss_ai(&mut self, ai: BossAiMode) {
+        self.boss.ai = ai;
+    }
+
+    /// Hitpoints casting `spell` now takes under blood magic, 0 if the wizard has the mana
+    pub fn get_blood_price(&self, spell: &Spell) -> i32 {
+        self.wizard.get_blood_price(self.spellbook.get_mana(spell))
+    }
+
+    /// Action points left this turn, None unless the battle uses the action point economy
+    pub fn get_action_points(&self) -> Option<i32> {
+        self.action_points
+    }
+
+    /// Whether the wizard has cast this turn and can cast again under the action point
+    /// economy, so the turn only ends when they choose to
+    pub fn can_cast_again(&self) -> bool {
+        self.outcome.is_none()
+            && self
+                .action_points
+                .is_some_and(|action_points| action_points < ACTION_POINTS_PER_TURN)
+            && !self.wizard.possible_spells.is_empty()
+    }
+
+    /// Whether the wizard has cast this turn under the action point economy, so they may
+    /// end the turn with action points left
+    pub fn can_end_turn(&self) -> bool {
+        self.outcome.is_none()
+            && self
+                .action_points
+                .is_some_and(|action_points| action_points < ACTION_POINTS_PER_TURN)
+    }
+
     /// Returns Some(true) if the wizard won, Some(false) if the boss won or None
     /// if neither has won.
+    #[cfg_attr(
+        feature = "logging",
+        tracing::instrument(level = "trace", target = "advent_wizard_rpg::battle", skip_all)
+    )]
     pub fn boss_turn_apply_effects(&mut self) -> Option<bool> {
-        self.wizard.apply_effect();
-        self.boss.apply_effect();
+        self.wizard.apply_effect(&self.spellbook);
+        self.wizard_apply_burn();
+        self.boss_apply_effect();
+        trace_battle!(
+            "round {}: boss turn effects applied, {:?}",
+            self.spells_used.len(),
+            self.get_state()
+        );
         // Check boss lost
         if self.boss.hitpoints <= 0 {
+            trace_battle!("round {}: wizard won by poison", self.spells_used.len());
             self.outcome = Some(true);
             return Some(true);
         }
+        // Check wizard lost
+        if self.wizard.hitpoints <= 0 {
+            trace_battle!("round {}: boss won by burn", self.spells_used.len());
+            self.outcome = Some(false);
+            return Some(false);
+        }
         None
     }
 
+    #[cfg_attr(
+        feature = "logging",
+        tracing::instrument(level = "trace", target = "advent_wizard_rpg::battle", skip_all)
+    )]
     pub fn boss_turn_attack(&mut self) -> Option<bool> {
-        self.boss.attack(&mut self.wizard);
+        let wizard_hitpoints_old = self.wizard.hitpoints;
+        self.wizard.wear_off_silence();
+        let minimum_damage = match self.rules_version {
+            RulesVersion::V1 => 1,
+        };
+        self.boss.attack(&mut self.wizard, minimum_damage);
+        // The wizard feels how hard the boss hits
+        self.knowledge.damage = true;
+        self.push_event(BattleEvent::WizardDamaged {
+            source: DamageSource::BossAttack,
+            amount: wizard_hitpoints_old - self.wizard.hitpoints,
+        });
+        trace_battle!(
+            "round {}: boss attacked for {}, {:?}",
+            self.spells_used.len(),
+            wizard_hitpoints_old - self.wizard.hitpoints,
+            self.get_state()
+        );
+        // Check wizard lost
+        if self.wizard.hitpoints <= 0 {
+            trace_battle!("round {}: boss won by attack", self.spells_used.len());
+            self.outcome = Some(false);
+            return Some(false);
+        }
+        // Thorns strike back once the wizard has survived the hit. The damage reflected is
+        // the spell's, whatever armor took off the attack.
+        if self.wizard.thorns.is_some() {
+            let damage = self.spellbook.get(&Spell::Thorns).damage;
+            self.boss.hitpoints -= damage;
+            self.push_event(BattleEvent::BossDamaged {
+                source: DamageSource::Thorns,
+                amount: damage,
+            });
+            // Check boss lost
+            if self.boss.hitpoints <= 0 {
+                trace_battle!("round {}: wizard won by thorns", self.spells_used.len());
+                self.outcome = Some(true);
+                return Some(true);
+            }
+        }
+        let use_ability =
+            !self.boss.is_ability_ready() || self.boss.ai.get_ai(self.hard_mode).use_ability(self);
+        self.boss_turn_ability(use_ability)
+    }
+
+    /// The rest of the boss's attack once the wizard and the boss have survived it: the
+    /// countdown to the boss's ability, using it if it comes up and `use_ability`.
+    /// Returns Some(false) if the ability defeated the wizard, otherwise None.
+    pub(crate) fn boss_turn_ability(&mut self, use_ability: bool) -> Option<bool> {
+        let dispellable: Vec<Spell> = [Spell::Shield, Spell::Recharge]
+            .into_iter()
+            .filter(|spell| self.get_effect_timer(spell).is_some())
+            .collect();
+        let wizard_hitpoints_old = self.wizard.hitpoints;
+        let boss_hitpoints_old = self.boss.hitpoints;
+        match self.boss.use_ability(&mut self.wizard, !use_ability) {
+            Some(BossAbility::Silence) => self.push_event(BattleEvent::WizardSilenced {
+                turns: SILENCE_DURATION,
+            }),
+            Some(BossAbility::Burn) => self.push_event(BattleEvent::WizardBurned {
+                stacks: self.wizard.burns.get_stacks(),
+            }),
+            Some(BossAbility::Dispel) if !dispellable.is_empty() => {
+                self.push_event(BattleEvent::EffectsDispelled {
+                    spells: dispellable,
+                })
+            }
+            Some(BossAbility::Lifesteal) => self.push_event(BattleEvent::WizardDamaged {
+                source: DamageSource::Lifesteal,
+                amount: wizard_hitpoints_old - self.wizard.hitpoints,
+            }),
+            _ => (),
+        }
+        if self.boss.hitpoints > boss_hitpoints_old {
+            self.push_event(BattleEvent::BossHealed {
+                amount: self.boss.hitpoints - boss_hitpoints_old,
+            });
+        }
         // Check wizard lost
         if self.wizard.hitpoints <= 0 {
+            trace_battle!("round {}: boss won by lifesteal", self.spells_used.len());
             self.outcome = Some(false);
             return Some(false);
         }
 
-        self.wizard.update_possible_spells(&self.boss);
+        // After the ability, so spells it dispelled or cleansing what it did can be cast
+        self.wizard
+            .update_possible_spells(&self.boss, &self.spellbook);
         None
     }
 
+    /// Play a whole round: the wizard casts `spell`, then the boss' turn, then the effects
+    /// at the start of the wizard's next turn.
+    /// Returns Some(true) if the wizard won, Some(false) if the boss won or None
+    /// if neither has won.
+    #[cfg_attr(
+        feature = "logging",
+        tracing::instrument(
+            level = "trace",
+            target = "advent_wizard_rpg::battle",
+            skip_all,
+            fields(round = self.spells_used.len() + 1, spell = spell.get_display_name())
+        )
+    )]
+    pub fn play_round(&mut self, spell: &Spell) -> Result<Option<bool>, EffectOngoingError> {
+        if let Some(won) = self.wizard_turn_cast_spell(spell)? {
+            return Ok(Some(won));
+        }
+        Ok(self.end_turn())
+    }
+
+    /// Play a whole turn under the action point economy: the wizard casts each of `spells`,
+    /// then the boss' turn, then the effects at the start of the wizard's next turn.
+    /// Returns Some(true) if the wizard won, Some(false) if the boss won or None
+    /// if neither has won.
+    pub fn play_turn(&mut self, spells: &[Spell]) -> Result<Option<bool>, EffectOngoingError> {
+        for spell in spells {
+            if let Some(won) = self.wizard_turn_cast_spell(spell)? {
+                return Ok(Some(won));
+            }
+        }
+        Ok(self.end_turn())
+    }
+
+    /// The rest of the round after the wizard's casts: the boss' turn, then the effects at
+    /// the start of the wizard's next turn.
+    /// Returns Some(true) if the wizard won, Some(false) if the boss won or None
+    /// if neither has won.
+    pub fn end_turn(&mut self) -> Option<bool> {
+        if let Some(won) = self.boss_turn_apply_effects() {
+            return Some(won);
+        }
+        if let Some(won) = self.boss_turn_attack() {
+            return Some(won);
+        }
+        self.wizard_turn_apply_effects()
+    }
+
+    /// Whether casting `spell` now loses the battle before the wizard's next turn, found by
+    /// playing the rest of the round on a clone. False if the spell can't be cast, or if
+    /// the wizard can cast again after it under the action point economy, and while any of
+    /// the boss's stats are hidden.
+    pub fn is_cast_fatal(&self, spell: &Spell) -> bool {
+        if !self.wizard.possible_spells.contains(spell)
+            || self.outcome.is_some()
+            || !self.knowledge.is_full()
+        {
+            return false;
+        }
+        let mut battle = self.for_search();
+        if !matches!(battle.wizard_turn_cast_spell(spell), Ok(None)) || battle.can_cast_again() {
+            return false;
+        }
+        battle.end_turn() == Some(false)
+    }
+
+    /// Heuristic check for whether casting `spell` now would be strictly wasteful.
+    /// Returns None if the cast is reasonable or the spell can't be cast. What depends on
+    /// the boss's hitpoints isn't checked while they are hidden.
+    pub fn evaluate_cast(&self, spell: &Spell) -> Option<WastedCast> {
+        if !self.wizard.possible_spells.contains(spell) {
+            return None;
+        }
+        if *spell == Spell::Recharge
+            && self
+                .wizard
+                .max_mana
+                .is_some_and(|max_mana| self.wizard.mana >= max_mana)
+        {
+            return Some(WastedCast::ManaFull);
+        }
+        if !self.knowledge.hitpoints {
+            return None;
+        }
+        // The rest flag a cast only when a cheaper spell would have done as well
+        let cheaper: Vec<Spell> = self
+            .spellbook
+            .get_spells_by_cost()
+            .into_iter()
+            .filter(|other| {
+                self.spellbook.get_mana(other) < self.spellbook.get_mana(spell)
+                    && self.wizard.possible_spells.contains(other)
+            })
+            .collect();
+        if cheaper.is_empty() {
+            return None;
+        }
+
+        // Poison ticks at the start of the boss' turn, before it can attack
+        if self.boss.hitpoints <= self.projected_poison_damage(1) {
+            return Some(WastedCast::BossDiesToPoison);
+        }
+        cheaper
+            .into_iter()
+            .find(|other| {
+                !other.is_effect() && self.boss.hitpoints <= self.spellbook.get(other).damage
+            })
+            .map(WastedCast::CheaperSpellWins)
+    }
+
+    fn wizard_apply_burn(&mut self) {
+        let damage = self.wizard.burns.tick();
+        if damage > 0 {
+            self.wizard.hitpoints -= damage;
+            self.push_event(BattleEvent::WizardDamaged {
+                source: DamageSource::Burn,
+                amount: damage,
+            });
+        }
+    }
+
+    /// The familiar attacks the boss, at the start of the wizard's turn
+    fn familiar_attack(&mut self) {
+        let Some(familiar_timer) = self.wizard.familiar.as_mut() else {
+            return;
+        };
+        *familiar_timer -= 1;
+        if *familiar_timer == 0 {
+            self.wizard.familiar = None;
+        }
+        let damage = self.spellbook.get(&Spell::Summon).damage;
+        self.boss.hitpoints -= damage;
+        self.push_event(BattleEvent::BossDamaged {
+            source: DamageSource::Familiar,
+            amount: damage,
+        });
+    }
+
+    fn boss_apply_effect(&mut self) {
+        let boss_hitpoints_old = self.boss.hitpoints;
+        self.boss.apply_effect(&self.spellbook);
+        if self.boss.hitpoints < boss_hitpoints_old {
+            self.push_event(BattleEvent::BossDamaged {
+                source: DamageSource::Poison,
+                amount: boss_hitpoints_old - self.boss.hitpoints,
+            });
+        }
+    }
+
+    pub fn get_state(&self) -> BattleState {
+        BattleState {
+            wizard_hitpoints: self.wizard.hitpoints,
+            wizard_mana: self.wizard.mana,
+            shield_timer: self.wizard.shielded.unwrap_or(0) as u8,
+            recharge_timer: self.wizard.recharging.unwrap_or(0) as u8,
+            boss_hitpoints: self.boss.hitpoints,
+            poison_timer: self.boss.poisoned.unwrap_or(0) as u8,
+            possible_spells: Spell::ALL
+                .iter()
+                .enumerate()
+                .filter(|(_, spell)| self.wizard.possible_spells.contains(spell))
+                .fold(0, |mask, (i, _)| mask | 1 << i),
+            silence_timer: self.wizard.silenced.unwrap_or(0) as u8,
+            ability_countdown: self.boss.get_ability_countdown().unwrap_or(0),
+            burn_timers: self.wizard.burns.get_timers(),
+            thorns_timer: self.wizard.thorns.unwrap_or(0) as u8,
+            familiar_timer: self.wizard.familiar.unwrap_or(0) as u8,
+        }
+    }
+
     pub fn get_wizard(&self) -> &Wizard {
         &self.wizard
     }
 
+    /// Damage the poison on the boss deals over the next `turns` turns, until it wears
+    /// off. It ticks at the start of every turn, the boss's and the wizard's alike, so from
+    /// the wizard's turn the first tick lands before the boss attacks.
+    pub fn projected_poison_damage(&self, turns: i32) -> i32 {
+        let ticks = self.boss.poisoned.unwrap_or(0).min(turns).max(0);
+        ticks * self.spellbook.get(&Spell::Poison).damage
+    }
+
+    /// Damage the familiar deals over the next `turns` wizard turns, until it leaves. It
+    /// only attacks at the start of the wizard's turns.
+    pub fn projected_familiar_damage(&self, turns: i32) -> i32 {
+        let attacks = self.wizard.familiar.unwrap_or(0).min(turns).max(0);
+        attacks * self.spellbook.get(&Spell::Summon).damage
+    }
+
+    /// Mana Recharge gives the wizard over the next `turns` turns, until it wears off,
+    /// ticking like `projected_poison_damage`
+    pub fn projected_recharge_mana(&self, turns: i32) -> i32 {
+        let ticks = self.wizard.recharging.unwrap_or(0).min(turns).max(0);
+        ticks * self.spellbook.get(&Spell::Recharge).mana_gain
+    }
+
     pub fn get_boss(&self) -> &Boss {
         &self.boss
     }
 
+    pub fn get_knowledge(&self) -> KnowledgeMask {
+        self.knowledge
+    }
+
+    /// The boss as far as the wizard knows it. UIs and bots should show and use this rather
+    /// than `get_boss`, which has the real stats.
+    pub fn get_boss_view(&self) -> BossView {
+        BossView {
+            hitpoints: Known::hitpoints(self.boss.hitpoints, self.knowledge.hitpoints),
+            damage: Known::exact_or_unknown(self.boss.damage, self.knowledge.damage),
+            poisoned: self.boss.poisoned,
+        }
+    }
+
     pub fn get_hard_mode(&self) -> bool {
         self.hard_mode
     }
 
+    pub fn get_blood_magic(&self) -> bool {
+        self.wizard.blood_magic
+    }
+
+    pub fn get_rules_version(&self) -> RulesVersion {
+        self.rules_version
+    }
+
+    pub fn get_spellbook(&self) -> &Spellbook {
+        &self.spellbook
+    }
+
     pub fn get_mana_used(&self) -> i32 {
         self.mana_used
     }
@@ -365,7 +1733,221 @@ impl Battle {
         &self.spells_used
     }
 
+    /// Spell cast in the last round, None before the first cast
+    pub fn get_last_spell_cast(&self) -> Option<&Spell> {
+        self.spells_used.last()
+    }
+
+    pub fn get_events(&self) -> &Vec<BattleEvent> {
+        &self.events
+    }
+
     pub fn get_outcome(&self) -> Option<bool> {
         self.outcome
     }
+
+    /// Snapshots of the rounds played, None unless the battle was made `with_history`
+    pub fn history(&self) -> Option<&BattleHistory> {
+        self.history.as_ref()
+    }
+
+    /// The state at the start of round `round`, from 1, if the history still has it
+    pub fn state_at_round(&self, round: usize) -> Option<BattleState> {
+        self.history
+            .as_ref()
+            .and_then(|history| history.state_at_round(round))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::SILENCE_INTERVAL;
+
+    #[test]
+    fn recharge_at_full_mana_is_wasted() {
+        let battle = Battle::new(false).with_max_mana(500);
+        assert_eq!(
+            battle.evaluate_cast(&Spell::Recharge),
+            Some(WastedCast::ManaFull)
+        );
+        assert_eq!(battle.evaluate_cast(&Spell::Shield), None);
+    }
+
+    #[test]
+    fn recharge_without_mana_limit_is_not_wasted() {
+        let battle = Battle::new(false);
+        assert_eq!(battle.evaluate_cast(&Spell::Recharge), None);
+        let battle = Battle::new(false).with_max_mana(1000);
+        assert_eq!(battle.evaluate_cast(&Spell::Recharge), None);
+    }
+
+    #[test]
+    fn cheapest_spell_can_be_wasted() {
+        let mut spellbook = Spellbook::default();
+        spellbook.get_mut(&Spell::Recharge).mana = 10;
+        let battle = Battle::with_spellbook(false, Boss::default(), spellbook).with_max_mana(500);
+        assert_eq!(
+            battle.evaluate_cast(&Spell::Recharge),
+            Some(WastedCast::ManaFull)
+        );
+    }
+
+    #[test]
+    fn cheaper_finishing_spell_is_flagged() {
+        let battle = Battle::with_boss(false, Boss::new(4, 8));
+        assert_eq!(
+            battle.evaluate_cast(&Spell::Drain),
+            Some(WastedCast::CheaperSpellWins(Spell::MagicMissile))
+        );
+        assert_eq!(battle.evaluate_cast(&Spell::MagicMissile), None);
+    }
+
+    #[test]
+    fn ability_counts_down_with_each_attack() {
+        let boss = Boss::new(100, 1).with_ability(BossAbility::Silence);
+        let mut battle = Battle::with_boss(false, boss);
+        assert_eq!(
+            battle.get_boss().get_ability_countdown(),
+            Some(SILENCE_INTERVAL)
+        );
+        for countdown in (1..SILENCE_INTERVAL).rev() {
+            battle.play_round(&Spell::MagicMissile).unwrap();
+            assert_eq!(battle.get_boss().get_ability_countdown(), Some(countdown));
+            assert_eq!(battle.get_wizard().get_silenced(), None);
+        }
+    }
+
+    #[test]
+    fn silence_triggers_and_wears_off() {
+        let boss = Boss::new(100, 1).with_ability(BossAbility::Silence);
+        let mut battle = Battle::with_boss(false, boss);
+        for _ in 0..SILENCE_INTERVAL {
+            battle.play_round(&Spell::MagicMissile).unwrap();
+        }
+        // The countdown starts over once the ability is used
+        assert_eq!(
+            battle.get_boss().get_ability_countdown(),
+            Some(SILENCE_INTERVAL)
+        );
+        assert_eq!(battle.get_wizard().get_silenced(), Some(SILENCE_DURATION));
+        assert_eq!(
+            battle.get_spell_availability(&Spell::Poison),
+            SpellAvailability::Silenced
+        );
+        assert!(battle
+            .get_wizard()
+            .get_possible_spells()
+            .contains(&Spell::MagicMissile));
+        for _ in 0..SILENCE_DURATION {
+            battle.play_round(&Spell::MagicMissile).unwrap();
+        }
+        assert_eq!(battle.get_wizard().get_silenced(), None);
+        assert_eq!(
+            battle.get_spell_availability(&Spell::Poison),
+            SpellAvailability::Available
+        );
+    }
+
+    #[test]
+    fn bosses_without_ability_have_no_countdown() {
+        let mut battle = Battle::with_boss(false, Boss::new(100, 1));
+        battle.play_round(&Spell::MagicMissile).unwrap();
+        assert_eq!(battle.get_boss().get_ability_countdown(), None);
+        assert_eq!(battle.get_wizard().get_silenced(), None);
+    }
+
+    #[test]
+    fn casts_spend_action_points() {
+        let mut battle = Battle::with_boss(false, Boss::new(100, 1)).with_action_points();
+        assert_eq!(battle.get_action_points(), Some(ACTION_POINTS_PER_TURN));
+        assert!(!battle.can_end_turn());
+
+        // Magic Missile is cheap and takes one point, so another cast fits in the turn
+        battle.wizard_turn_cast_spell(&Spell::MagicMissile).unwrap();
+        assert_eq!(battle.get_action_points(), Some(ACTION_POINTS_PER_TURN - 1));
+        assert!(battle.can_end_turn());
+        assert!(battle.can_cast_again());
+        assert_eq!(
+            battle.get_spell_availability(&Spell::Poison),
+            SpellAvailability::NoActionPoints
+        );
+        assert!(battle.wizard_turn_cast_spell(&Spell::Poison).is_err());
+
+        battle.wizard_turn_cast_spell(&Spell::MagicMissile).unwrap();
+        assert_eq!(battle.get_action_points(), Some(0));
+        assert!(!battle.can_cast_again());
+        assert!(battle.can_end_turn());
+
+        battle.end_turn();
+        assert_eq!(battle.get_action_points(), Some(ACTION_POINTS_PER_TURN));
+        assert!(!battle.can_end_turn());
+    }
+
+    #[test]
+    fn dear_spells_take_the_whole_turn() {
+        let mut battle = Battle::with_boss(false, Boss::new(100, 1)).with_action_points();
+        battle.wizard_turn_cast_spell(&Spell::Poison).unwrap();
+        assert_eq!(battle.get_action_points(), Some(0));
+        assert!(!battle.can_cast_again());
+        assert!(battle.can_end_turn());
+    }
+
+    #[test]
+    fn turns_end_only_under_action_points() {
+        let mut battle = Battle::with_boss(false, Boss::new(100, 1));
+        battle.wizard_turn_cast_spell(&Spell::MagicMissile).unwrap();
+        assert_eq!(battle.get_action_points(), None);
+        assert!(!battle.can_end_turn());
+        assert!(!battle.can_cast_again());
+    }
+
+    #[test]
+    fn blood_magic_pays_the_shortfall_in_hitpoints() {
+        let mut battle = Battle::with_boss(false, Boss::new(100, 1))
+            .with_wizard_bonus(0, -450)
+            .with_blood_magic();
+        // Drain costs 73, 23 more than the wizard has, which rounds up to 12 hitpoints
+        assert_eq!(battle.get_blood_price(&Spell::Drain), 12);
+        assert_eq!(
+            battle.get_spell_availability(&Spell::Drain),
+            SpellAvailability::AvailableAtCost { hitpoints: 12 }
+        );
+        assert_eq!(battle.get_blood_price(&Spell::MagicMissile), 2);
+
+        battle.wizard_turn_cast_spell(&Spell::Drain).unwrap();
+        let heal = battle.get_spellbook().get(&Spell::Drain).heal;
+        assert_eq!(battle.get_wizard().get_hitpoints(), 50 - 12 + heal);
+        assert_eq!(battle.get_wizard().get_mana(), 0);
+        // The whole cost counts as mana used
+        assert_eq!(battle.get_mana_used(), 73);
+    }
+
+    #[test]
+    fn blood_magic_never_takes_the_last_hitpoint() {
+        let battle = Battle::with_boss(false, Boss::new(100, 1))
+            .with_wizard_bonus(0, -450)
+            .with_blood_magic();
+        // Recharge is 179 mana short, which would take 90 of the wizard's 50 hitpoints
+        assert_eq!(battle.get_blood_price(&Spell::Recharge), 90);
+        assert_eq!(
+            battle.get_spell_availability(&Spell::Recharge),
+            SpellAvailability::NotEnoughMana
+        );
+    }
+
+    #[test]
+    fn spells_cost_no_hitpoints_with_enough_mana_or_without_blood_magic() {
+        let battle = Battle::with_boss(false, Boss::new(100, 1)).with_blood_magic();
+        assert_eq!(battle.get_blood_price(&Spell::Recharge), 0);
+        assert_eq!(
+            battle.get_spell_availability(&Spell::Recharge),
+            SpellAvailability::Available
+        );
+        let battle = Battle::with_boss(false, Boss::new(100, 1)).with_wizard_bonus(0, -450);
+        assert_eq!(
+            battle.get_spell_availability(&Spell::Drain),
+            SpellAvailability::NotEnoughMana
+        );
+    }
 }
@@ -0,0 +1,125 @@
+//! Boss rush: every boss of a bestiary back to back, without healing, against the clock.
+
+use crate::{crash_report, create_app, stopwatch::Stopwatch, ui, ui::tui};
+use advent_wizard_rpg::{
+    bestiary::Bestiary,
+    boss_rush::{format_run_time, next_boss_rush_battle, BossRushRun},
+    challenge::Challenge,
+    solver::SearchBudget,
+};
+use clap::{arg, value_parser, Arg, ArgMatches};
+use std::{fs, io, path::PathBuf, process};
+
+pub fn args() -> [Arg; 2] {
+    [
+        arg!(--"boss-rush" <BESTIARY> "Fight every boss of a bestiary back to back against the clock, the built-in bosses if BESTIARY is left out")
+            .value_parser(value_parser!(PathBuf))
+            .num_args(0..=1)
+            .conflicts_with_all(["challenge", "aoc-session", "random", "preset", "campaign", "export-csv"]),
+        arg!(--"rush-splits" <FILE> "Write the splits of the boss rush to a Markdown file")
+            .value_parser(value_parser!(PathBuf))
+            .requires("boss-rush"),
+    ]
+}
+
+/// Bestiary given by `--boss-rush`, the built-in one if no file was given. Exits if it
+/// can't be loaded.
+pub fn bestiary_from_matches(matches: &ArgMatches) -> Option<Bestiary> {
+    if !matches.contains_id("boss-rush") {
+        return None;
+    }
+    let Some(path) = matches.get_one::<PathBuf>("boss-rush") else {
+        return Some(Bestiary::builtin());
+    };
+    match Bestiary::load(path) {
+        Ok(bestiary) if !bestiary.get_entries().is_empty() => Some(bestiary),
+        Ok(_) => {
+            eprintln!("Bestiary {} has no bosses", path.display());
+            process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("Could not load bestiary {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Fight the bosses of `bestiary` in order, the wizard carrying their hitpoints and mana
+/// from one battle to the next, until all are defeated, the wizard loses or the player
+/// quits. The timer runs from the start of the first battle, and a split is taken as each
+/// boss falls. Boss rush battles aren't autosaved, since a save can't hold the run.
+pub fn play(
+    terminal: &mut tui::Tui,
+    matches: &ArgMatches,
+    bestiary: &Bestiary,
+    blunder_check: Option<SearchBudget>,
+) -> io::Result<()> {
+    let bosses = bestiary.get_entries();
+    let mut run = BossRushRun::default();
+    let mut run_timer = Stopwatch::start();
+    let mut battle = Challenge {
+        hard_mode: matches.get_flag("hard"),
+        ..Challenge::new(bosses[0].clone())
+    }
+    .create_battle();
+    for (i, boss) in bosses.iter().enumerate() {
+        let challenge = Challenge {
+            hard_mode: battle.get_hard_mode(),
+            ..Challenge::new(boss.clone())
+        };
+        crash_report::set_challenge(&challenge);
+        let mut app = create_app(
+            matches,
+            battle,
+            Some(format!("{} ({}/{})", boss.name, i + 1, bosses.len())),
+            &challenge,
+            blunder_check,
+            None,
+        );
+        app.exit_on_win = true;
+        app.run_timer = Some(run_timer);
+        // The clock is running, so there is no time for an intro
+        app.intro_banner = None;
+        app.run(terminal, &[])?;
+        // Time the battle spent paused carries on to the rest of the run
+        run_timer = app.run_timer.unwrap_or(run_timer);
+        if app.game.get_outcome() != Some(true) {
+            break;
+        }
+        run.push_split(
+            &boss.name,
+            app.get_run_time().unwrap(),
+            &app.game,
+            app.stats.get_total_think_time(),
+        );
+        battle = match bosses.get(i + 1) {
+            Some(next) => next_boss_rush_battle(&app.game, next),
+            None => app.game,
+        };
+    }
+
+    let completed = run.splits.len() == bosses.len();
+    if let Some(path) = matches.get_one::<PathBuf>("rush-splits") {
+        fs::write(path, run.to_markdown(completed))?;
+    }
+    let mut text = String::new();
+    for (i, split) in run.splits.iter().enumerate() {
+        text.push_str(&format!(
+            "{:<20} {:>8} {:>8}\n",
+            split.boss,
+            format_run_time(run.get_segment_time(i)),
+            format_run_time(split.time)
+        ));
+    }
+    text.push_str(&if completed {
+        format!("\nFinal time: {}", format_run_time(run.get_total_time()))
+    } else {
+        format!(
+            "\nThe rush ended after {} of {} bosses.",
+            run.splits.len(),
+            bosses.len()
+        )
+    });
+    ui::tell_story(terminal, "Boss Rush", &text, &[])?;
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! Sandbox console: commands that change the battle outside its rules, for testing
+//! content and setting up screenshots.
+
+use advent_wizard_rpg::{Battle, Spell};
+use clap::{arg, Arg};
+
+pub fn arg() -> Arg {
+    arg!(--sandbox "Enable the console (:) for changing the battle, which turns autosaving off")
+}
+
+/// Run a console command, such as `set boss.hp 5`, `give mana 200` or `apply poison 6`.
+/// Returns a description of the change, or why the command failed.
+pub fn run_command(battle: &mut Battle, command: &str) -> Result<String, String> {
+    let words: Vec<&str> = command
+        .trim()
+        .trim_start_matches(':')
+        .split_whitespace()
+        .collect();
+    let (name, target, amount) = match words[..] {
+        [name, target, amount] => (name, target, amount),
+        _ => {
+            return Err(format!(
+                "expected `<command> <target> <amount>`, got `{}`",
+                command.trim()
+            ))
+        }
+    };
+    let amount: i32 = amount
+        .parse()
+        .map_err(|_| format!("`{}` is not a number", amount))?;
+    let wizard = battle.get_wizard();
+    match (name, target) {
+        ("set", "wizard.hp") => battle.set_wizard_hitpoints(amount),
+        ("set", "wizard.mana") => battle.set_wizard_mana(amount),
+        ("set", "boss.hp") => battle.set_boss_hitpoints(amount),
+        ("give", "hp") => battle.set_wizard_hitpoints(wizard.get_hitpoints() + amount),
+        ("give", "mana") => battle.set_wizard_mana(wizard.get_mana() + amount),
+        ("apply", effect) => {
+            let spell: Spell = effect.parse().map_err(|err| format!("{}", err))?;
+            if !battle.set_effect(&spell, amount) {
+                return Err(format!("{} has no effect to apply", spell));
+            }
+        }
+        ("set" | "give", target) => return Err(format!("unknown target `{}`", target)),
+        (name, _) => {
+            return Err(format!(
+                "unknown command `{}`, expected set, give or apply",
+                name
+            ))
+        }
+    }
+    Ok(format!("{} {} {}", name, target, amount))
+}
@@ -0,0 +1,96 @@
+use crate::{
+    challenge::Challenge,
+    file_format::{write_atomic, SAVE_FORMAT},
+    toml::{self, ParseError, Table, Value},
+    Battle, Spell,
+};
+use std::{fs, io, path::Path};
+
+/// Ending of save file names
+pub const SAVE_FILE_SUFFIX: &str = ".save.toml";
+
+/// A battle in progress, stored as the `SAVE_FORMAT` header, the challenge it is played
+/// under, then a `[[round]]` table naming the spell cast in each round so far. Battles
+/// have no randomness, so replaying the spells restores the battle exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveGame {
+    pub challenge: Challenge,
+    pub spells: Vec<Spell>,
+}
+
+impl SaveGame {
+    pub fn new(challenge: Challenge, battle: &Battle) -> Self {
+        Self {
+            challenge,
+            spells: battle.get_spells_used().clone(),
+        }
+    }
+
+    /// Replay the saved rounds, ending at the start of the wizard's next turn.
+    /// Returns None if a spell can't be cast or the battle ended before the last round.
+    pub fn restore(&self) -> Option<Battle> {
+        let mut battle = self.challenge.create_battle();
+        battle.wizard_turn_apply_effects();
+        for spell in &self.spells {
+            if battle.get_outcome().is_some() {
+                return None;
+            }
+            battle.play_round(spell).ok()?;
+        }
+        Some(battle)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        SAVE_FORMAT.read_version(text)?;
+        let (rounds, tables): (Vec<Table>, Vec<Table>) = toml::parse(text)?
+            .into_iter()
+            .partition(|table| table.name == "round");
+        let mut spells = Vec::new();
+        for round in rounds {
+            let name = round
+                .get_string("spell")?
+                .ok_or_else(|| round.error("round is missing `spell`".to_string()))?;
+            let spell = name
+                .parse::<Spell>()
+                .map_err(|err| round.error(err.to_string()))?;
+            spells.push(spell);
+        }
+        let save = Self {
+            challenge: Challenge::from_tables(tables)?,
+            spells,
+        };
+        if save.restore().is_none() {
+            return Err(ParseError {
+                line: 0,
+                message: "the saved rounds can't be replayed".to_string(),
+            });
+        }
+        Ok(save)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let rounds = self.spells.iter().map(|spell| {
+            let mut table = Table::new("round");
+            table.push("spell", Value::String(spell.get_display_name().to_string()));
+            table
+        });
+        let tables: Vec<Table> = self
+            .challenge
+            .to_tables()
+            .into_iter()
+            .chain(rounds)
+            .collect();
+        SAVE_FORMAT.with_header(&toml::write(&tables))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Write the save so that `path` always holds either the old or the new save, even if
+    /// the game crashes part way through
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, self.to_toml())
+    }
+}
@@ -0,0 +1,518 @@
+//! Named save slots the player saves battles to and resumes them from. A slot is a save
+//! file named after the slot, next to a text file holding the screen as it was saved.
+
+use crate::{
+    data_dir,
+    ui::{self, theme, tui},
+};
+use advent_wizard_rpg::{
+    challenge::Challenge,
+    file_format::write_atomic,
+    save::{SaveGame, SAVE_FILE_SUFFIX},
+    Battle,
+};
+use clap::{arg, value_parser, Arg, ArgMatches};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Alignment, Constraint, Layout},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListState, Paragraph},
+    Frame,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Ending of the names of the files holding the screen of a slot
+const PREVIEW_FILE_SUFFIX: &str = ".preview.txt";
+/// Longest slot name, in chars
+const MAX_NAME_LENGTH: usize = 32;
+
+pub fn args() -> [Arg; 2] {
+    [
+        arg!(--load <SLOT> "Resume the battle saved in a slot, or pick the slot if SLOT is left out")
+            .num_args(0..=1)
+            .conflicts_with_all([
+                "challenge",
+                "aoc-session",
+                "random",
+                "preset",
+                "replay",
+                "campaign",
+                "boss-rush",
+                "survival",
+            ]),
+        arg!(--"saves-dir" <DIR> "Directory of the save slots [default: ~/.local/state/advent-wizard-rpg/saves]")
+            .value_parser(value_parser!(PathBuf)),
+    ]
+}
+
+/// Save slot directory used when none is given
+pub fn default_dir() -> PathBuf {
+    data_dir::saves_dir()
+}
+
+/// Save slot directory given by `args`
+pub fn dir_from_matches(matches: &ArgMatches) -> PathBuf {
+    matches
+        .get_one::<PathBuf>("saves-dir")
+        .cloned()
+        .unwrap_or_else(default_dir)
+}
+
+/// A battle saved under a name
+#[derive(Debug)]
+pub struct SaveSlot {
+    pub name: String,
+    /// When the slot was last written, None if the file system doesn't say
+    pub saved_at: Option<SystemTime>,
+    /// The saved battle, None if the file can't be read
+    pub save: Option<SaveGame>,
+    /// The screen as the battle was saved, empty if there is none
+    pub preview: String,
+}
+
+impl SaveSlot {
+    /// Date, boss, round and difficulty of the battle, one per line
+    pub fn describe(&self) -> String {
+        let saved = self.saved_at.map_or("unknown".to_string(), format_saved_at);
+        let Some(save) = &self.save else {
+            return format!("Saved: {}\n\nNot a valid save", saved);
+        };
+        format!(
+            "Saved: {}\nBoss: {}\nRound: {}\nDifficulty: {}",
+            saved,
+            save.challenge.name,
+            save.spells.len() + 1,
+            if save.challenge.hard_mode {
+                "hard"
+            } else {
+                "normal"
+            }
+        )
+    }
+}
+
+/// Whether `name` can name a slot: letters, digits, spaces, `-` and `_`, not starting or
+/// ending with a space
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && name.trim() == name
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+}
+
+fn save_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}{}", name, SAVE_FILE_SUFFIX))
+}
+
+fn preview_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}{}", name, PREVIEW_FILE_SUFFIX))
+}
+
+/// The slots in `dir`, most recently saved first. A directory that doesn't exist yet has
+/// no slots.
+pub fn list(dir: &Path) -> io::Result<Vec<SaveSlot>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut slots = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(SAVE_FILE_SUFFIX));
+        let Some(name) = name else {
+            continue;
+        };
+        slots.push(SaveSlot {
+            name: name.to_string(),
+            saved_at: fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            save: SaveGame::load(&path).ok(),
+            preview: fs::read_to_string(preview_path(dir, name)).unwrap_or_default(),
+        });
+    }
+    slots.sort_by(|a, b| b.saved_at.cmp(&a.saved_at).then(a.name.cmp(&b.name)));
+    Ok(slots)
+}
+
+/// The battle saved in slot `name` of `dir`
+pub fn load(dir: &Path, name: &str) -> io::Result<SaveGame> {
+    SaveGame::load(&save_path(dir, name))
+}
+
+/// Write `save` to slot `name` of `dir`, with `preview` of the screen, replacing what was
+/// in the slot
+pub fn write(dir: &Path, name: &str, save: &SaveGame, preview: &str) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    save.save(&save_path(dir, name))?;
+    write_atomic(&preview_path(dir, name), preview)
+}
+
+/// Rename slot `from` of `dir` to `to`, which mustn't be taken
+pub fn rename(dir: &Path, from: &str, to: &str) -> io::Result<()> {
+    if save_path(dir, to).exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("there is already a slot named {}", to),
+        ));
+    }
+    fs::rename(save_path(dir, from), save_path(dir, to))?;
+    ignore_not_found(fs::rename(preview_path(dir, from), preview_path(dir, to)))
+}
+
+/// Delete slot `name` of `dir`
+pub fn delete(dir: &Path, name: &str) -> io::Result<()> {
+    fs::remove_file(save_path(dir, name))?;
+    ignore_not_found(fs::remove_file(preview_path(dir, name)))
+}
+
+/// Slots saved before previews were kept have none
+fn ignore_not_found(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// `time` as a UTC date and time to the minute, such as 2024-12-22 18:05 UTC
+fn format_saved_at(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+    // Civil date from days since the epoch, counting in 400 year eras from March 0000
+    let days = days as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60
+    )
+}
+
+/// Saves the battle it's played with to a slot the player picks
+#[derive(Debug)]
+pub struct SlotSaver {
+    dir: PathBuf,
+    /// Rules of the battle being saved
+    challenge: Challenge,
+}
+
+impl SlotSaver {
+    pub fn new(dir: PathBuf, challenge: Challenge) -> Self {
+        Self { dir, challenge }
+    }
+
+    /// Let the player pick the slot to save `battle` to, with `preview` of the screen.
+    /// Returns the name of the slot, None if they cancelled.
+    pub fn save(
+        &self,
+        terminal: &mut tui::Tui,
+        battle: &Battle,
+        preview: &str,
+    ) -> io::Result<Option<String>> {
+        let Some(name) = manage(terminal, &self.dir, SlotMode::Save)? else {
+            return Ok(None);
+        };
+        let save = SaveGame::new(self.challenge.clone(), battle);
+        write(&self.dir, &name, &save, preview)?;
+        Ok(Some(name))
+    }
+}
+
+/// Let the player pick a slot of `dir` to resume. Returns None if they cancelled.
+pub fn pick_to_load(terminal: &mut tui::Tui, dir: &Path) -> io::Result<Option<SaveGame>> {
+    match manage(terminal, dir, SlotMode::Load)? {
+        Some(name) => load(dir, &name).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// What a slot is picked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotMode {
+    /// Saving to an existing slot or a new one
+    Save,
+    /// Resuming a battle
+    Load,
+}
+
+/// Slot list of the management screen, where slots are also renamed and deleted
+#[derive(Debug)]
+struct SlotScreen {
+    dir: PathBuf,
+    mode: SlotMode,
+    slots: Vec<SaveSlot>,
+    /// Selected row. When saving, the first row is a new slot and the slots follow.
+    list_state: ListState,
+    /// Name being typed for a new slot or a renamed one, None if none is
+    name_input: Option<ui::TextInput>,
+    /// Result of the last change, or why it failed
+    status: String,
+}
+
+impl SlotScreen {
+    fn new(dir: &Path, mode: SlotMode) -> io::Result<Self> {
+        let mut screen = Self {
+            dir: dir.to_path_buf(),
+            mode,
+            slots: Vec::new(),
+            list_state: ListState::default(),
+            name_input: None,
+            status: String::new(),
+        };
+        screen.reload()?;
+        Ok(screen)
+    }
+
+    fn reload(&mut self) -> io::Result<()> {
+        self.slots = list(&self.dir)?;
+        let rows = self.slots.len() + (self.mode == SlotMode::Save) as usize;
+        let selected = self.list_state.selected().unwrap_or(0);
+        self.list_state
+            .select((rows > 0).then(|| selected.min(rows - 1)));
+        Ok(())
+    }
+
+    /// The selected slot, None if none is or the new slot row is
+    fn get_selected(&self) -> Option<&SaveSlot> {
+        let offset = (self.mode == SlotMode::Save) as usize;
+        self.list_state
+            .selected()
+            .and_then(|selected| selected.checked_sub(offset))
+            .and_then(|index| self.slots.get(index))
+    }
+
+    fn is_new_slot_selected(&self) -> bool {
+        self.mode == SlotMode::Save && self.list_state.selected() == Some(0)
+    }
+
+    /// Act on a key, asking before overwriting or deleting a slot. Returns the name of the
+    /// slot picked, if one was.
+    fn handle_key_event(
+        &mut self,
+        terminal: &mut tui::Tui,
+        key: KeyCode,
+    ) -> io::Result<Option<String>> {
+        if let Some(input) = &mut self.name_input {
+            match key {
+                KeyCode::Esc => self.name_input = None,
+                KeyCode::Enter => return self.submit_name(),
+                key => {
+                    input.handle_key_event(key);
+                }
+            }
+            return Ok(None);
+        }
+        match key {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Enter if self.is_new_slot_selected() => {
+                self.start_naming(String::new());
+            }
+            KeyCode::Enter => {
+                let Some(slot) = self.get_selected() else {
+                    return Ok(None);
+                };
+                let name = slot.name.clone();
+                match self.mode {
+                    SlotMode::Load if slot.save.is_none() => {
+                        self.status = format!("{} can't be resumed", name);
+                    }
+                    SlotMode::Load => return Ok(Some(name)),
+                    SlotMode::Save => {
+                        let question = format!("Save over {}?", name);
+                        if ui::confirm(terminal, "Overwrite Slot", &question)? {
+                            return Ok(Some(name));
+                        }
+                    }
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some(name) = self.get_selected().map(|slot| slot.name.clone()) {
+                    self.start_naming(name);
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some(name) = self.get_selected().map(|slot| slot.name.clone()) {
+                    let question = format!("Delete {}? It can't be undone.", name);
+                    if ui::confirm(terminal, "Delete Slot", &question)? {
+                        self.status = match delete(&self.dir, &name) {
+                            Ok(()) => format!("Deleted {}", name),
+                            Err(err) => format!("Could not delete {}: {}", name, err),
+                        };
+                        self.reload()?;
+                    }
+                }
+            }
+            _ => (),
+        }
+        Ok(None)
+    }
+
+    fn start_naming(&mut self, name: String) {
+        let mut input = ui::TextInput::new(name);
+        input.set_focused(true);
+        self.name_input = Some(input);
+        self.status.clear();
+    }
+
+    /// Name the new slot, returning it to save to, or rename the selected slot
+    fn submit_name(&mut self) -> io::Result<Option<String>> {
+        let Some(input) = &self.name_input else {
+            return Ok(None);
+        };
+        let name = input.get_value().to_string();
+        if !is_valid_name(&name) {
+            self.status = format!(
+                "Names are 1 to {} letters, digits, spaces, - and _",
+                MAX_NAME_LENGTH
+            );
+            return Ok(None);
+        }
+        if self.is_new_slot_selected() {
+            if self.slots.iter().any(|slot| slot.name == name) {
+                self.status = format!("There is already a slot named {}", name);
+                return Ok(None);
+            }
+            return Ok(Some(name));
+        }
+        self.name_input = None;
+        if let Some(from) = self.get_selected().map(|slot| slot.name.clone()) {
+            self.status = match rename(&self.dir, &from, &name) {
+                Ok(()) => format!("Renamed {} to {}", from, name),
+                Err(err) => format!("Could not rename {}: {}", from, err),
+            };
+            self.reload()?;
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [title, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [slots, preview] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(body);
+
+        let title_text = match self.mode {
+            SlotMode::Save => "Save Battle",
+            SlotMode::Load => "Resume Battle",
+        };
+        frame.render_widget(
+            Block::new()
+                .title_alignment(Alignment::Center)
+                .title(title_text.bold()),
+            title,
+        );
+
+        let new_slot = (self.mode == SlotMode::Save).then(|| "+ New slot".to_string());
+        let rows: Vec<String> = new_slot
+            .into_iter()
+            .chain(self.slots.iter().map(|slot| slot.name.clone()))
+            .collect();
+        let list = List::new(rows)
+            .gray()
+            .highlight_style(Style::default().fg(theme::get().accent).bold())
+            .highlight_symbol("> ")
+            .block(
+                Block::bordered()
+                    .border_style(Style::default().fg(theme::get().wizard))
+                    .title("Slots".bold().gray())
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_stateful_widget(list, slots, &mut self.list_state);
+
+        let text = match self.get_selected() {
+            Some(slot) => format!("{}\n\n{}", slot.describe(), slot.preview),
+            None if self.slots.is_empty() && self.mode == SlotMode::Load => {
+                format!("No saved battles in {}", self.dir.display())
+            }
+            None => String::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(text).gray().block(
+                Block::bordered()
+                    .gray()
+                    .title("Preview".bold())
+                    .title_alignment(Alignment::Center),
+            ),
+            preview,
+        );
+
+        match &self.name_input {
+            Some(input) => {
+                let label = "Name (Enter: done, Esc: cancel): ";
+                let [label_area, input_area] = Layout::horizontal([
+                    Constraint::Length(label.len() as u16),
+                    Constraint::Min(0),
+                ])
+                .areas(footer);
+                frame.render_widget(Line::from(label).gray(), label_area);
+                frame.render_widget(input, input_area);
+            }
+            None if !self.status.is_empty() => {
+                frame.render_widget(Line::from(self.status.as_str()).gray(), footer);
+            }
+            None => {
+                let help = match self.mode {
+                    SlotMode::Save => "j/k: select  Enter: save  r: rename  d: delete  q: cancel",
+                    SlotMode::Load => "j/k: select  Enter: resume  r: rename  d: delete  q: cancel",
+                };
+                frame.render_widget(Line::from(help).dark_gray(), footer);
+            }
+        }
+    }
+}
+
+/// Show the slots of `dir` for the player to rename and delete, until they pick one for
+/// `mode`. Returns None if they quit without picking one.
+fn manage(terminal: &mut tui::Tui, dir: &Path, mode: SlotMode) -> io::Result<Option<String>> {
+    let mut screen = SlotScreen::new(dir, mode)?;
+    loop {
+        terminal.draw(|frame| screen.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc if screen.name_input.is_none() => {
+                    return Ok(None)
+                }
+                code => {
+                    if let Some(name) = screen.handle_key_event(terminal, code)? {
+                        return Ok(Some(name));
+                    }
+                }
+            }
+        }
+    }
+}
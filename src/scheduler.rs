@@ -0,0 +1,163 @@
+use advent_wizard_rpg::Spell;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// A step of a battle round
+#[derive(Debug, Clone)]
+pub enum Phase {
+    WizardCastSpell(Spell),
+    BossApplyEffects,
+    BossAttack,
+    WizardApplyEffects,
+}
+
+/// Playback speeds, slowest first
+const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+/// Index of normal speed in `SPEEDS`
+const NORMAL_SPEED_INDEX: usize = 2;
+
+/// Runs queued phases one at a time, waiting a delay between each
+#[derive(Debug)]
+pub struct PhaseScheduler {
+    phases: VecDeque<Phase>,
+    delay: Duration,
+    /// When the next phase may run
+    next_phase_at: Instant,
+    /// Index into `SPEEDS` of the playback speed
+    speed_index: usize,
+    /// When playback was paused, None if it isn't
+    paused_at: Option<Instant>,
+}
+
+impl PhaseScheduler {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            phases: VecDeque::new(),
+            delay,
+            next_phase_at: Instant::now(),
+            speed_index: NORMAL_SPEED_INDEX,
+            paused_at: None,
+        }
+    }
+
+    /// Multiple of the normal speed phases run at
+    pub fn get_speed(&self) -> f64 {
+        SPEEDS[self.speed_index]
+    }
+
+    pub fn speed_up(&mut self) {
+        self.speed_index = (self.speed_index + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slow_down(&mut self) {
+        self.speed_index = self.speed_index.saturating_sub(1);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Hold the phases until resumed. Does nothing if they're already paused.
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+        }
+    }
+
+    /// Run the phases again. Time spent paused doesn't count towards the delay.
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.next_phase_at += paused_at.elapsed();
+        }
+    }
+
+    /// Pause or resume the phases
+    pub fn toggle_pause(&mut self) {
+        if self.is_paused() {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    /// Queue the phases of a round where the wizard casts `spell`. The first phase is
+    /// due immediately.
+    pub fn schedule_round(&mut self, spell: Spell) {
+        if self.phases.is_empty() {
+            self.next_phase_at = Instant::now();
+        }
+        self.phases.extend(Self::round_phases(spell));
+    }
+
+    /// Queue only the wizard casting `spell`, for a turn that may go on with more casts.
+    /// The phase is due immediately.
+    pub fn schedule_cast(&mut self, spell: Spell) {
+        if self.phases.is_empty() {
+            self.next_phase_at = Instant::now();
+        }
+        self.phases.push_back(Phase::WizardCastSpell(spell));
+    }
+
+    /// Queue the rest of a round after the wizard's casts. The first phase is due a delay
+    /// after the last phase ran.
+    pub fn schedule_turn_end(&mut self) {
+        self.phases.extend([
+            Phase::BossApplyEffects,
+            Phase::BossAttack,
+            Phase::WizardApplyEffects,
+        ]);
+    }
+
+    /// The phases of a round where the wizard casts `spell`, in order
+    pub fn round_phases(spell: Spell) -> [Phase; 4] {
+        [
+            Phase::WizardCastSpell(spell),
+            Phase::BossApplyEffects,
+            Phase::BossAttack,
+            Phase::WizardApplyEffects,
+        ]
+    }
+
+    /// Time between phases at the current speed
+    pub fn get_phase_delay(&self) -> Duration {
+        self.delay.div_f64(self.get_speed())
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.phases.is_empty()
+    }
+
+    /// Time until the next phase is due, if any phase is queued and playback isn't paused
+    pub fn time_until_next_phase(&self) -> Option<Duration> {
+        if self.phases.is_empty() || self.is_paused() {
+            None
+        } else {
+            Some(self.next_phase_at.saturating_duration_since(Instant::now()))
+        }
+    }
+
+    /// Phases waiting to run, next first
+    pub fn get_queued_phases(&self) -> &VecDeque<Phase> {
+        &self.phases
+    }
+
+    /// Take the next phase now, even if it isn't due or playback is paused
+    pub fn step(&mut self) -> Option<Phase> {
+        let phase = self.phases.pop_front()?;
+        self.next_phase_at = Instant::now() + self.delay.div_f64(self.get_speed());
+        Some(phase)
+    }
+
+    /// Take the next phase if it is due
+    pub fn next_due_phase(&mut self) -> Option<Phase> {
+        let now = Instant::now();
+        if now < self.next_phase_at || self.is_paused() {
+            return None;
+        }
+        let phase = self.phases.pop_front()?;
+        self.next_phase_at = now + self.delay.div_f64(self.get_speed());
+        Some(phase)
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{Battle, BattleEvent, BattleState, EffectOngoingError, Spell};
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// Input to a `BattleSession`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SessionAction {
+    /// Play a round, casting the spell
+    Cast(Spell),
+    /// Stop the session
+    Quit,
+}
+
+/// What a `BattleSession` sends to its subscribers
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SessionUpdate {
+    /// An event, as it happens
+    Event(BattleEvent),
+    /// State at the start of the wizard's turn, or when the battle ended. Sent when
+    /// subscribing and after every round.
+    State {
+        state: BattleState,
+        outcome: Option<bool>,
+    },
+    /// A cast that couldn't be played, because the spell's effect is ongoing or the
+    /// battle is over
+    Rejected(Spell),
+}
+
+type Subscribers = Arc<Mutex<Vec<Sender<SessionUpdate>>>>;
+
+/// A battle owned by its own thread, played by actions sent from any thread, that sends
+/// its events and state to every subscriber. Lets several frontends, such as a TUI and a
+/// spectator, follow one battle. Needs no async runtime; the `tokio` feature adds
+/// `AsyncBattleSession` for frontends that have one.
+#[derive(Debug)]
+pub struct BattleSession {
+    actions: Sender<SessionAction>,
+    subscribers: Subscribers,
+    /// Latest state, for new subscribers
+    state: Arc<Mutex<(BattleState, Option<bool>)>>,
+    thread: Option<JoinHandle<Battle>>,
+}
+
+impl BattleSession {
+    /// Start a session of `battle`, which should be at the start of the wizard's turn
+    pub fn spawn(mut battle: Battle) -> Self {
+        let (actions, receiver) = mpsc::channel();
+        let subscribers = Subscribers::default();
+        let state = Arc::new(Mutex::new((battle.get_state(), battle.get_outcome())));
+
+        let event_subscribers = subscribers.clone();
+        battle.subscribe(Box::new(move |event| {
+            broadcast(&event_subscribers, SessionUpdate::Event(event.clone()))
+        }));
+        let thread_subscribers = subscribers.clone();
+        let thread_state = state.clone();
+        let thread = thread::spawn(move || {
+            play(&mut battle, receiver, &thread_subscribers, &thread_state);
+            // Disconnect the subscribers, although the battle keeps its observer
+            thread_subscribers.lock().unwrap().clear();
+            battle
+        });
+        Self {
+            actions,
+            subscribers,
+            state,
+            thread: Some(thread),
+        }
+    }
+
+    /// Sender of actions to the session, which can be cloned to other threads
+    pub fn get_sender(&self) -> Sender<SessionAction> {
+        self.actions.clone()
+    }
+
+    /// Receive the updates of the session from now on, starting with the current state.
+    /// Dropping the receiver unsubscribes.
+    pub fn subscribe(&self) -> Receiver<SessionUpdate> {
+        let (sender, receiver) = mpsc::channel();
+        // Hold the state while subscribing so no round is missed in between
+        let current = self.state.lock().unwrap();
+        let (state, outcome) = *current;
+        let _ = sender.send(SessionUpdate::State { state, outcome });
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Stop the session once the actions already sent are played, returning the battle
+    pub fn finish(mut self) -> Battle {
+        let _ = self.actions.send(SessionAction::Quit);
+        self.thread
+            .take()
+            .unwrap()
+            .join()
+            .expect("battle session panicked")
+    }
+}
+
+impl Drop for BattleSession {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = self.actions.send(SessionAction::Quit);
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Play actions until told to quit or every sender is dropped
+fn play(
+    battle: &mut Battle,
+    actions: Receiver<SessionAction>,
+    subscribers: &Subscribers,
+    state: &Mutex<(BattleState, Option<bool>)>,
+) {
+    for action in actions {
+        let spell = match action {
+            SessionAction::Cast(spell) => spell,
+            SessionAction::Quit => return,
+        };
+        let played = match battle.get_outcome() {
+            Some(_) => Err(EffectOngoingError()),
+            None => battle.play_round(&spell),
+        };
+        if played.is_err() {
+            broadcast(subscribers, SessionUpdate::Rejected(spell));
+            continue;
+        }
+        let mut current = state.lock().unwrap();
+        *current = (battle.get_state(), battle.get_outcome());
+        broadcast(
+            subscribers,
+            SessionUpdate::State {
+                state: current.0,
+                outcome: current.1,
+            },
+        );
+    }
+}
+
+/// Send `update` to every subscriber, dropping the ones that unsubscribed
+fn broadcast(subscribers: &Subscribers, update: SessionUpdate) {
+    subscribers
+        .lock()
+        .unwrap()
+        .retain(|subscriber| subscriber.send(update.clone()).is_ok());
+}
@@ -0,0 +1,75 @@
+//! Totals of the battles played since the game was started, shown on the game over screen
+//! and printed on quitting. They only last as long as the process, so back-to-back games
+//! give feedback without anything being saved.
+
+use advent_wizard_rpg::boss_rush::format_run_time;
+use std::{fmt, sync::Mutex, time::Duration};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    pub games: usize,
+    pub wins: usize,
+    /// Mana spent over all the games
+    pub mana: i32,
+    /// Time of the fastest win, from the start of the battle to the killing blow
+    pub best_time: Option<Duration>,
+}
+
+impl SessionStats {
+    pub fn get_losses(&self) -> usize {
+        self.games - self.wins
+    }
+}
+
+impl fmt::Display for SessionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "This session: {} {}, {} won, {} lost, {} mana spent",
+            self.games,
+            if self.games == 1 { "game" } else { "games" },
+            self.wins,
+            self.get_losses(),
+            self.mana
+        )?;
+        if let Some(best_time) = self.best_time {
+            write!(f, ", fastest win {}", format_run_time(best_time))?;
+        }
+        Ok(())
+    }
+}
+
+static SESSION: Mutex<SessionStats> = Mutex::new(SessionStats {
+    games: 0,
+    wins: 0,
+    mana: 0,
+    best_time: None,
+});
+
+/// Whether the totals have been printed, so quitting prints them once
+static PRINTED: Mutex<bool> = Mutex::new(false);
+
+/// Add a finished battle that took `time` to the totals
+pub fn record_battle(won: bool, mana: i32, time: Duration) {
+    let mut session = SESSION.lock().unwrap();
+    session.games += 1;
+    session.mana += mana;
+    if won {
+        session.wins += 1;
+        session.best_time = Some(session.best_time.map_or(time, |best| best.min(time)));
+    }
+}
+
+pub fn get() -> SessionStats {
+    *SESSION.lock().unwrap()
+}
+
+/// Print the totals once the terminal is restored, if any battle was finished
+pub fn print_summary() {
+    let session = get();
+    let mut printed = PRINTED.lock().unwrap();
+    if session.games > 0 && !*printed {
+        println!("{}", session);
+        *printed = true;
+    }
+}
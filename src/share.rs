@@ -0,0 +1,62 @@
+use crate::{
+    notation::{format_line, format_numbered_line},
+    Battle, Spell,
+};
+
+/// Result of a finished battle, as shared with other players
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BattleSummary {
+    pub boss: String,
+    pub hard_mode: bool,
+    pub won: bool,
+    pub rounds: usize,
+    pub mana: i32,
+    pub spells: Vec<Spell>,
+}
+
+impl BattleSummary {
+    /// Summary of `battle` against the boss named `boss`, None if the battle isn't over
+    pub fn from_battle(boss: &str, battle: &Battle) -> Option<Self> {
+        Some(Self {
+            boss: boss.to_string(),
+            hard_mode: battle.get_hard_mode(),
+            won: battle.get_outcome()?,
+            rounds: battle.get_spells_used().len(),
+            mana: battle.get_mana_used(),
+            spells: battle.get_spells_used().clone(),
+        })
+    }
+
+    fn get_title(&self) -> String {
+        format!(
+            "{} against {}{}",
+            if self.won { "Victory" } else { "Defeat" },
+            self.boss,
+            if self.hard_mode { " (hard)" } else { "" }
+        )
+    }
+
+    /// Two lines to paste in a chat: the outcome, rounds and mana, then the spells in
+    /// notation
+    pub fn to_share_string(&self) -> String {
+        format!(
+            "Wizard RPG: {} in {} rounds, {} mana\n{}",
+            self.get_title(),
+            self.rounds,
+            self.mana,
+            format_line(&self.spells)
+        )
+    }
+
+    /// Markdown report with a table of the rounds and mana, and the spells with their
+    /// rounds
+    pub fn to_markdown(&self) -> String {
+        format!(
+            "### {}\n\n| Rounds | Mana |\n| ---: | ---: |\n| {} | {} |\n\n`{}`\n",
+            self.get_title(),
+            self.rounds,
+            self.mana,
+            format_numbered_line(&self.spells, 1)
+        )
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{
+    bestiary::BestiaryEntry,
+    item::{Equipment, Item},
+    Battle,
+};
+
+/// Gold for any victory, before the bonuses for speed and mana
+const VICTORY_GOLD: u32 = 30;
+/// Rounds a battle can last before the speed bonus runs out
+const PAR_ROUNDS: u32 = 15;
+/// Mana a battle can use before the mana bonus runs out
+const PAR_MANA: i32 = 1500;
+
+/// Gold won by defeating a boss: a base amount, 2 gold for every round under 15 and 1
+/// gold for every 50 mana under 1500
+pub fn get_victory_gold(battle: &Battle) -> u32 {
+    let rounds = battle.get_spells_used().len() as u32;
+    let speed_bonus = PAR_ROUNDS.saturating_sub(rounds) * 2;
+    let mana_bonus = (PAR_MANA - battle.get_mana_used()).max(0) as u32 / 50;
+    VICTORY_GOLD + speed_bonus + mana_bonus
+}
+
+/// What the wizard carries from one campaign battle to the next
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CampaignProgress {
+    pub gold: u32,
+    pub equipment: Equipment,
+    /// Hitpoints and mana from potions, added at the start of the next battle
+    pub hitpoints: i32,
+    pub mana: i32,
+    /// Whether the stats of the next boss have been bought
+    pub intel: bool,
+}
+
+impl CampaignProgress {
+    /// Pay for `offer` and take it. Returns false if there isn't enough gold.
+    pub fn buy(&mut self, offer: ShopOffer) -> bool {
+        if self.gold < offer.get_price() {
+            return false;
+        }
+        self.gold -= offer.get_price();
+        match offer {
+            ShopOffer::HealthPotion => self.hitpoints += 10,
+            ShopOffer::ManaPotion => self.mana += 100,
+            ShopOffer::Equipment(item) => {
+                self.equipment.equip(item);
+            }
+            ShopOffer::Intel => self.intel = true,
+        }
+        true
+    }
+
+    /// Use up the potions and intel bought for the battle that is starting
+    pub fn start_battle(&mut self) {
+        self.hitpoints = 0;
+        self.mana = 0;
+        self.intel = false;
+    }
+}
+
+/// What the shop between campaign battles sells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShopOffer {
+    /// 10 hitpoints for the next battle
+    HealthPotion,
+    /// 100 mana for the next battle
+    ManaPotion,
+    Equipment(Item),
+    /// The hitpoints and damage of the next boss
+    Intel,
+}
+
+impl ShopOffer {
+    /// Offers for `progress`: potions, items not worn yet, and intel unless it was bought
+    /// already
+    pub fn get_offers(progress: &CampaignProgress) -> Vec<Self> {
+        let mut offers = vec![Self::HealthPotion, Self::ManaPotion];
+        offers.extend(
+            Item::ALL
+                .into_iter()
+                .filter(|item| !progress.equipment.get_items().any(|worn| worn == *item))
+                .map(Self::Equipment),
+        );
+        if !progress.intel {
+            offers.push(Self::Intel);
+        }
+        offers
+    }
+
+    pub fn get_price(self) -> u32 {
+        match self {
+            Self::HealthPotion => 20,
+            Self::ManaPotion => 25,
+            Self::Equipment(_) => 60,
+            Self::Intel => 15,
+        }
+    }
+
+    pub fn get_label(self) -> String {
+        let name = match self {
+            Self::HealthPotion => "Health potion: +10 HP next battle".to_string(),
+            Self::ManaPotion => "Mana potion: +100 mana next battle".to_string(),
+            Self::Equipment(item) => {
+                format!("{}: {}", item.get_display_name(), item.get_description())
+            }
+            Self::Intel => "Boss intel: the next boss's stats".to_string(),
+        };
+        format!("{} ({} gold)", name, self.get_price())
+    }
+}
+
+/// What boss intel reveals about `boss`
+pub fn describe_intel(boss: &BestiaryEntry) -> String {
+    format!(
+        "Intel: {} has {} hitpoints and deals {} damage.",
+        boss.name, boss.hitpoints, boss.damage
+    )
+}
@@ -0,0 +1,115 @@
+//! `simulate` subcommand: estimate the chance of winning by casting random spells.
+
+#[cfg(feature = "results-db")]
+use crate::tally;
+use crate::{
+    cli::{
+        battle_args, battle_from_challenge, battle_from_matches, challenge_from_matches,
+        threads_arg, threads_from_matches,
+    },
+    report::{self, OutputFormat},
+    ui,
+};
+use advent_wizard_rpg::{
+    parallel::Progress,
+    playout::{estimate_win_probability_parallel, record_playouts_parallel, LANES},
+};
+use clap::{arg, parser::ValueSource, value_parser, ArgMatches, Command};
+use std::time::Instant;
+
+pub fn command() -> Command {
+    let command = Command::new("simulate")
+        .about("Estimate the win probability of casting random possible spells")
+        .args(battle_args())
+        .arg(
+            arg!(--playouts <COUNT> "Number of random battles to play")
+                .value_parser(value_parser!(u64))
+                .default_value("1000000"),
+        )
+        .arg(
+            arg!(--seed <SEED> "Seed for the random spell choices, overriding the challenge's")
+                .value_parser(value_parser!(u64))
+                .default_value("0"),
+        )
+        .arg(threads_arg())
+        .arg(report::format_arg(&[]));
+    #[cfg(feature = "results-db")]
+    let command = command.arg(tally::db_arg());
+    command
+}
+
+pub fn run(matches: &ArgMatches) {
+    let challenge = challenge_from_matches(matches);
+    let battle = challenge
+        .as_ref()
+        .map_or_else(|| battle_from_matches(matches), battle_from_challenge);
+    let playouts = *matches.get_one::<u64>("playouts").unwrap();
+    let seed = match challenge {
+        Some(challenge) if matches.value_source("seed") != Some(ValueSource::CommandLine) => {
+            challenge.seed
+        }
+        _ => *matches.get_one::<u64>("seed").unwrap(),
+    };
+
+    let threads = threads_from_matches(matches);
+    #[cfg(feature = "results-db")]
+    let db = tally::db_from_matches(matches);
+    #[cfg(feature = "results-db")]
+    let record = db.is_some();
+    #[cfg(not(feature = "results-db"))]
+    let record = false;
+
+    let progress = Progress::new(playouts);
+    let start = Instant::now();
+    // Only keep every playout when they are written to the results database
+    let results = ui::with_progress("playouts", &progress, || {
+        if record {
+            record_playouts_parallel(&battle, playouts, seed, threads, &progress)
+        } else {
+            let stats =
+                estimate_win_probability_parallel(&battle, playouts, seed, threads, &progress);
+            (stats, Vec::new())
+        }
+    });
+    let elapsed = start.elapsed();
+    let stats = results.0;
+    #[cfg(feature = "results-db")]
+    if let Some(path) = db {
+        tally::record_playouts(path, &battle, seed, &results.1);
+    }
+
+    let format = report::format_from_matches(matches);
+    if format != OutputFormat::Human {
+        report::simulation_report(
+            &battle,
+            seed,
+            playouts,
+            &stats,
+            threads,
+            elapsed.as_secs_f64(),
+        )
+        .print(format);
+        return;
+    }
+    if progress.is_cancelled() {
+        eprintln!("Stopped after {} of {} playouts", stats.playouts, playouts);
+    }
+    println!(
+        "Win probability: {:.2}% ({} of {} playouts)",
+        stats.get_win_probability() * 100.0,
+        stats.wins,
+        stats.playouts
+    );
+    if stats.wins > 0 {
+        println!(
+            "Average mana used in wins: {:.1}",
+            stats.mana_used_in_wins as f64 / stats.wins as f64
+        );
+    }
+    println!(
+        "Simulated {:.0} playouts per second, {} at a time on {} threads",
+        stats.playouts as f64 / elapsed.as_secs_f64(),
+        LANES,
+        threads
+    );
+}
@@ -0,0 +1,84 @@
+//! `solve` subcommand: find the cheapest win against a boss.
+
+use crate::{
+    cli::{battle_args, battle_from_matches, display_line},
+    report::{self, OutputFormat},
+};
+use advent_wizard_rpg::solver::{solve_cheapest_win, Algorithm, SolveOptions};
+use clap::{arg, value_parser, ArgAction, ArgMatches, Command};
+use std::time::Instant;
+
+pub fn command() -> Command {
+    Command::new("solve")
+        .about("Find the line of spells that wins using the least mana")
+        .args(battle_args())
+        .arg(
+            arg!(--algorithm <ALGORITHM> "Search strategy")
+                .value_parser(["dfs", "bfs", "dijkstra", "astar"])
+                .default_value("dfs"),
+        )
+        .arg(
+            arg!(--depth <ROUNDS> "Rounds to search")
+                .value_parser(value_parser!(usize))
+                .default_value("20"),
+        )
+        .arg(
+            arg!(--prune <BOOL> "Skip lines using more mana than the best win found")
+                .value_parser(value_parser!(bool))
+                .action(ArgAction::Set)
+                .default_value("true"),
+        )
+        .arg(
+            arg!(--"memo-size" <POSITIONS> "Transposition table capacity, 0 to disable")
+                .value_parser(value_parser!(usize))
+                .default_value("1000000"),
+        )
+        .arg(report::format_arg(&[]))
+}
+
+pub fn run(matches: &ArgMatches) {
+    let battle = battle_from_matches(matches);
+    let algorithm_name = matches.get_one::<String>("algorithm").unwrap();
+    let algorithm = match algorithm_name.as_str() {
+        "bfs" => Algorithm::BreadthFirst,
+        "dijkstra" => Algorithm::Dijkstra,
+        "astar" => Algorithm::AStar,
+        _ => Algorithm::DepthFirst,
+    };
+    let options = SolveOptions {
+        algorithm,
+        max_depth: *matches.get_one::<usize>("depth").unwrap(),
+        prune: *matches.get_one::<bool>("prune").unwrap(),
+        memo_size: *matches.get_one::<usize>("memo-size").unwrap(),
+    };
+
+    let start = Instant::now();
+    let result = solve_cheapest_win(&battle, options);
+    let elapsed = start.elapsed();
+
+    let format = report::format_from_matches(matches);
+    if format != OutputFormat::Human {
+        report::solve_report(
+            &battle,
+            algorithm_name,
+            options.max_depth,
+            &result,
+            elapsed.as_secs_f64(),
+        )
+        .print(format);
+        return;
+    }
+
+    match result.solution {
+        Some(solution) => {
+            println!("Cheapest win: {} mana", solution.mana);
+            println!("Spells: {}", display_line(&solution.spells));
+        }
+        None => println!("No win found within {} rounds", options.max_depth),
+    }
+    println!(
+        "Expanded {} positions in {:.3}s",
+        result.nodes_expanded,
+        elapsed.as_secs_f64()
+    );
+}
@@ -0,0 +1,588 @@
+use crate::{Battle, BattleState, Spell};
+use rustc_hash::FxHashMap;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    time::{Duration, Instant},
+};
+
+/// Limits on how much of the game tree a search may explore
+#[derive(Debug, Clone, Copy)]
+pub struct SearchBudget {
+    /// Maximum number of rounds to look ahead
+    pub max_depth: usize,
+    /// Give up after this long
+    pub time_limit: Option<Duration>,
+}
+
+/// Result of searching a position for the wizard
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Evaluation {
+    /// Some line of spells wins within the budget
+    Won,
+    /// Every line of spells loses within the budget
+    Lost,
+    /// The budget ran out before the position could be decided
+    Unknown,
+}
+
+/// Search the game tree from the start of the wizard's turn to decide whether the
+/// position is won or lost.
+pub fn evaluate_position(battle: &Battle, budget: SearchBudget) -> Evaluation {
+    let deadline = budget.time_limit.map(|limit| Instant::now() + limit);
+    evaluate_to_depth(battle, budget.max_depth, deadline)
+}
+
+fn evaluate_to_depth(battle: &Battle, depth: usize, deadline: Option<Instant>) -> Evaluation {
+    match battle.get_outcome() {
+        Some(true) => return Evaluation::Won,
+        Some(false) => return Evaluation::Lost,
+        None => (),
+    }
+    if depth == 0 || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        return Evaluation::Unknown;
+    }
+
+    // Without a spell to cast the wizard loses
+    let mut evaluation = Evaluation::Lost;
+    for spell in Spell::ALL
+        .iter()
+        .filter(|spell| battle.get_wizard().get_possible_spells().contains(spell))
+    {
+        let mut next = battle.for_search();
+        if next.play_round(spell).is_err() {
+            continue;
+        }
+        match evaluate_to_depth(&next, depth - 1, deadline) {
+            Evaluation::Won => return Evaluation::Won,
+            Evaluation::Unknown => evaluation = Evaluation::Unknown,
+            Evaluation::Lost => (),
+        }
+    }
+    evaluation
+}
+
+/// Statistics about the lines of spells explored from a position
+#[derive(Debug, Default, Clone)]
+pub struct LineStats {
+    pub winning_lines: u64,
+    pub losing_lines: u64,
+    /// Lines still undecided at the depth limit
+    pub unfinished_lines: u64,
+    /// Winning line with the fewest rounds
+    pub shortest_win: Option<Vec<Spell>>,
+    /// Winning line using the least mana, and the mana it uses
+    pub cheapest_win: Option<(i32, Vec<Spell>)>,
+}
+
+impl LineStats {
+    pub fn get_evaluation(&self) -> Evaluation {
+        if self.winning_lines > 0 {
+            Evaluation::Won
+        } else if self.unfinished_lines > 0 {
+            Evaluation::Unknown
+        } else {
+            Evaluation::Lost
+        }
+    }
+
+    fn merge(&mut self, other: LineStats) {
+        self.winning_lines += other.winning_lines;
+        self.losing_lines += other.losing_lines;
+        self.unfinished_lines += other.unfinished_lines;
+        if let Some(line) = other.shortest_win {
+            if self
+                .shortest_win
+                .as_ref()
+                .is_none_or(|shortest| line.len() < shortest.len())
+            {
+                self.shortest_win = Some(line);
+            }
+        }
+        if let Some((mana, line)) = other.cheapest_win {
+            if self
+                .cheapest_win
+                .as_ref()
+                .is_none_or(|(cheapest, _)| mana < *cheapest)
+            {
+                self.cheapest_win = Some((mana, line));
+            }
+        }
+    }
+}
+
+/// Exhaustive exploration of the game tree from a position
+#[derive(Debug, Default, Clone)]
+pub struct TreeAnalysis {
+    /// Lines across all first moves
+    pub total: LineStats,
+    /// Lines starting with each possible first spell
+    pub first_moves: Vec<(Spell, LineStats)>,
+}
+
+/// Explore every line of spells from the start of the wizard's turn, up to `max_depth`
+/// rounds.
+pub fn analyze_tree(battle: &Battle, max_depth: usize) -> TreeAnalysis {
+    let mut analysis = TreeAnalysis::default();
+    let mut line = Vec::with_capacity(max_depth);
+    for spell in Spell::ALL
+        .iter()
+        .filter(|spell| battle.get_wizard().get_possible_spells().contains(spell))
+    {
+        let mut stats = LineStats::default();
+        let mut next = battle.for_search();
+        if next.play_round(spell).is_ok() {
+            line.push(spell.clone());
+            explore(&next, max_depth.saturating_sub(1), &mut line, &mut stats);
+            line.pop();
+        }
+        analysis.total.merge(stats.clone());
+        analysis.first_moves.push((spell.clone(), stats));
+    }
+    if analysis.first_moves.is_empty() {
+        // Without a spell to cast the wizard loses
+        analysis.total.losing_lines = 1;
+    }
+    analysis
+}
+
+fn explore(battle: &Battle, depth: usize, line: &mut Vec<Spell>, stats: &mut LineStats) {
+    match battle.get_outcome() {
+        Some(true) => {
+            let mana = battle.get_mana_used();
+            stats.merge(LineStats {
+                winning_lines: 1,
+                shortest_win: Some(line.clone()),
+                cheapest_win: Some((mana, line.clone())),
+                ..LineStats::default()
+            });
+            return;
+        }
+        Some(false) => {
+            stats.losing_lines += 1;
+            return;
+        }
+        None => (),
+    }
+    if depth == 0 {
+        stats.unfinished_lines += 1;
+        return;
+    }
+
+    let mut any_spell = false;
+    for spell in Spell::ALL
+        .iter()
+        .filter(|spell| battle.get_wizard().get_possible_spells().contains(spell))
+    {
+        let mut next = battle.for_search();
+        if next.play_round(spell).is_err() {
+            continue;
+        }
+        any_spell = true;
+        line.push(spell.clone());
+        explore(&next, depth - 1, line, stats);
+        line.pop();
+    }
+    if !any_spell {
+        // Without a spell to cast the wizard loses
+        stats.losing_lines += 1;
+    }
+}
+
+/// Search strategy for finding the cheapest win
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Algorithm {
+    /// Depth-first branch and bound
+    DepthFirst,
+    /// Round by round, keeping the cheapest way found to reach each position
+    BreadthFirst,
+    /// Expand the position reached using the least mana first
+    Dijkstra,
+    /// Dijkstra guided by a lower bound on the mana still needed to win
+    AStar,
+}
+
+/// Options for finding the cheapest win
+#[derive(Debug, Clone, Copy)]
+pub struct SolveOptions {
+    pub algorithm: Algorithm,
+    /// Maximum number of rounds to look ahead
+    pub max_depth: usize,
+    /// Skip lines that already use more mana than the best win found so far
+    pub prune: bool,
+    /// Maximum number of positions kept in the transposition table, 0 to disable it
+    pub memo_size: usize,
+}
+
+/// Cheapest line of spells that wins
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub mana: i32,
+    pub spells: Vec<Spell>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SolveResult {
+    pub solution: Option<Solution>,
+    /// Number of positions whose spells were tried
+    pub nodes_expanded: u64,
+}
+
+struct Solver {
+    options: SolveOptions,
+    /// Least mana used so far and most rounds left when each position was reached
+    transpositions: FxHashMap<BattleState, (i32, usize)>,
+    best: Option<Solution>,
+    line: Vec<Spell>,
+    nodes_expanded: u64,
+}
+
+/// Find the line of spells that wins using the least mana, from the start of the wizard's
+/// turn.
+pub fn solve_cheapest_win(battle: &Battle, options: SolveOptions) -> SolveResult {
+    match options.algorithm {
+        Algorithm::DepthFirst => solve_depth_first(battle, options),
+        Algorithm::BreadthFirst => solve_breadth_first(battle, options),
+        Algorithm::Dijkstra => solve_best_first(battle, options, |_| 0),
+        Algorithm::AStar => solve_best_first(battle, options, minimum_mana_to_win),
+    }
+}
+
+/// Admissible estimate of the mana still needed to win: the remaining boss hitpoints
+/// dealt at the rate of the most mana-efficient spell, which is Poison (18 damage over 6
+/// turns) in the default spellbook. Damage from poison already ticking and a familiar
+/// already summoned is free. Bosses that heal only add to the damage still needed, so it
+/// stays a lower bound for them.
+pub fn minimum_mana_to_win(battle: &Battle) -> i32 {
+    let spellbook = battle.get_spellbook();
+    let boss = battle.get_boss();
+    let hitpoints_left = boss.get_hitpoints()
+        - spellbook.get(&Spell::Poison).damage * boss.get_poisoned().unwrap_or(0)
+        - battle.projected_familiar_damage(i32::MAX);
+    if hitpoints_left <= 0 {
+        return 0;
+    }
+    // Compare mana per damage as fractions to stay in integers
+    let (mana, damage) = Spell::ALL
+        .iter()
+        .map(|spell| (spellbook.get_mana(spell), spellbook.get_total_damage(spell)))
+        .filter(|&(_, damage)| damage > 0)
+        .min_by_key(|&(mana, damage)| Ratio(mana, damage))
+        .unwrap_or((0, 1));
+    hitpoints_left * mana / damage
+}
+
+/// Fraction compared by value, for positive denominators
+#[derive(PartialEq, Eq)]
+struct Ratio(i32, i32);
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ratio {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.0 as i64 * other.1 as i64).cmp(&(other.0 as i64 * self.1 as i64))
+    }
+}
+
+/// Spells cast since `root` and the mana they used
+fn solution_from(root: &Battle, battle: &Battle) -> Solution {
+    Solution {
+        mana: battle.get_mana_used() - root.get_mana_used(),
+        spells: battle.get_spells_used()[root.get_spells_used().len()..].to_vec(),
+    }
+}
+
+/// Next positions after casting each possible spell, skipping ones where the wizard lost
+fn successors(battle: &Battle) -> impl Iterator<Item = Battle> + '_ {
+    Spell::ALL
+        .iter()
+        .filter(|spell| battle.get_wizard().get_possible_spells().contains(spell))
+        .filter_map(|spell| {
+            let mut next = battle.for_search();
+            next.play_round(spell).ok()?;
+            (next.get_outcome() != Some(false)).then_some(next)
+        })
+}
+
+/// Whether `battle` improves on the cheapest known way to reach its position. Remembers
+/// it if so, as long as the table has room.
+fn improves_position(
+    seen: &mut FxHashMap<BattleState, i32>,
+    battle: &Battle,
+    options: &SolveOptions,
+) -> bool {
+    if options.memo_size == 0 {
+        return true;
+    }
+    let state = battle.get_state();
+    let mana = battle.get_mana_used();
+    match seen.get(&state) {
+        Some(&seen_mana) if seen_mana <= mana => false,
+        Some(_) => {
+            seen.insert(state, mana);
+            true
+        }
+        None => {
+            if seen.len() < options.memo_size {
+                seen.insert(state, mana);
+            }
+            true
+        }
+    }
+}
+
+fn solve_breadth_first(battle: &Battle, options: SolveOptions) -> SolveResult {
+    let mut seen = FxHashMap::default();
+    let mut best: Option<Battle> = None;
+    let mut nodes_expanded = 0;
+    let mut frontier = vec![battle.for_search()];
+
+    for _ in 0..options.max_depth {
+        let mut next_frontier = Vec::new();
+        for position in &frontier {
+            nodes_expanded += 1;
+            for next in successors(position) {
+                let mana = next.get_mana_used();
+                if best
+                    .as_ref()
+                    .is_some_and(|best| options.prune && best.get_mana_used() <= mana)
+                {
+                    continue;
+                }
+                if next.get_outcome() == Some(true) {
+                    if best.as_ref().is_none_or(|best| mana < best.get_mana_used()) {
+                        best = Some(next);
+                    }
+                } else if improves_position(&mut seen, &next, &options) {
+                    next_frontier.push(next);
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+
+    SolveResult {
+        solution: best.map(|best| solution_from(battle, &best)),
+        nodes_expanded,
+    }
+}
+
+fn solve_best_first(
+    battle: &Battle,
+    options: SolveOptions,
+    heuristic: fn(&Battle) -> i32,
+) -> SolveResult {
+    let mut seen = FxHashMap::default();
+    let mut nodes_expanded = 0;
+    // Positions and their depth, indexed by heap entries
+    let mut positions = vec![(battle.for_search(), 0)];
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((heuristic(battle), 0)));
+
+    while let Some(Reverse((_, index))) = open.pop() {
+        let (position, depth) = &positions[index];
+        if position.get_outcome() == Some(true) {
+            // With an admissible heuristic the first win reached is the cheapest
+            return SolveResult {
+                solution: Some(solution_from(battle, position)),
+                nodes_expanded,
+            };
+        }
+        if *depth >= options.max_depth {
+            continue;
+        }
+
+        let depth = *depth;
+        nodes_expanded += 1;
+        let next_positions: Vec<Battle> = successors(position)
+            .filter(|next| improves_position(&mut seen, next, &options))
+            .collect();
+        for next in next_positions {
+            let priority = next.get_mana_used() + heuristic(&next);
+            open.push(Reverse((priority, positions.len())));
+            positions.push((next, depth + 1));
+        }
+    }
+
+    SolveResult {
+        solution: None,
+        nodes_expanded,
+    }
+}
+
+fn solve_depth_first(battle: &Battle, options: SolveOptions) -> SolveResult {
+    let mut solver = Solver {
+        options,
+        transpositions: FxHashMap::default(),
+        best: None,
+        line: Vec::with_capacity(options.max_depth),
+        nodes_expanded: 0,
+    };
+    let spells = battle.get_spellbook().get_spells_by_cost();
+    solver.search(&spells, battle, 0, options.max_depth);
+    SolveResult {
+        solution: solver.best,
+        nodes_expanded: solver.nodes_expanded,
+    }
+}
+
+impl Solver {
+    /// Try `spells`, which are ordered cheapest first, from `battle`
+    fn search(&mut self, spells: &[Spell], battle: &Battle, mana: i32, depth: usize) {
+        match battle.get_outcome() {
+            Some(true) => {
+                if self.best.as_ref().is_none_or(|best| mana < best.mana) {
+                    self.best = Some(Solution {
+                        mana,
+                        spells: self.line.clone(),
+                    });
+                }
+                return;
+            }
+            Some(false) => return,
+            None => (),
+        }
+        if depth == 0 {
+            return;
+        }
+
+        // Skip positions already reached with no more mana and at least as many rounds left
+        if self.options.memo_size > 0 {
+            let state = battle.get_state();
+            match self.transpositions.get(&state) {
+                Some(&(seen_mana, seen_depth)) if seen_mana <= mana && seen_depth >= depth => {
+                    return
+                }
+                Some(_) => {
+                    self.transpositions.insert(state, (mana, depth));
+                }
+                None if self.transpositions.len() < self.options.memo_size => {
+                    self.transpositions.insert(state, (mana, depth));
+                }
+                None => (),
+            }
+        }
+
+        self.nodes_expanded += 1;
+        for spell in spells
+            .iter()
+            .filter(|spell| battle.get_wizard().get_possible_spells().contains(spell))
+        {
+            let next_mana = mana + battle.get_spellbook().get_mana(spell);
+            // Spells are ordered cheapest first, so the rest cost even more
+            if self.options.prune
+                && self
+                    .best
+                    .as_ref()
+                    .is_some_and(|best| next_mana >= best.mana)
+            {
+                break;
+            }
+            let mut next = battle.for_search();
+            if next.play_round(spell).is_err() {
+                continue;
+            }
+            self.line.push(spell.clone());
+            self.search(spells, &next, next_mana, depth - 1);
+            self.line.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Boss;
+
+    /// Bosses of Advent of Code 2015 day 22 puzzle inputs, with the least mana that wins
+    /// against each in normal and hard mode
+    const AOC_INPUTS: [(i32, i32, i32, i32); 4] = [
+        (51, 9, 900, 1216),
+        (55, 8, 953, 1289),
+        (58, 9, 1269, 1309),
+        (71, 10, 1824, 1937),
+    ];
+
+    fn options(algorithm: Algorithm, memo_size: usize) -> SolveOptions {
+        SolveOptions {
+            algorithm,
+            max_depth: 20,
+            prune: true,
+            memo_size,
+        }
+    }
+
+    /// Least mana that wins against the boss, from the start of the battle
+    fn cheapest_mana(hitpoints: i32, damage: i32, hard_mode: bool, options: SolveOptions) -> i32 {
+        let mut battle = Battle::with_boss(hard_mode, Boss::new(hitpoints, damage));
+        battle.wizard_turn_apply_effects();
+        solve_cheapest_win(&battle, options).solution.unwrap().mana
+    }
+
+    #[test]
+    fn memoized_search_finds_advent_of_code_answers() {
+        for (hitpoints, damage, normal, hard) in AOC_INPUTS {
+            let options = options(Algorithm::DepthFirst, 100_000);
+            assert_eq!(cheapest_mana(hitpoints, damage, false, options), normal);
+            assert_eq!(cheapest_mana(hitpoints, damage, true, options), hard);
+        }
+    }
+
+    #[test]
+    fn memo_keeps_the_cheapest_win() {
+        let (hitpoints, damage, _, _) = AOC_INPUTS[0];
+        for hard_mode in [false, true] {
+            assert_eq!(
+                cheapest_mana(
+                    hitpoints,
+                    damage,
+                    hard_mode,
+                    options(Algorithm::DepthFirst, 100_000)
+                ),
+                cheapest_mana(
+                    hitpoints,
+                    damage,
+                    hard_mode,
+                    options(Algorithm::DepthFirst, 0)
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn best_first_matches_depth_first() {
+        for (hitpoints, damage, _, _) in AOC_INPUTS {
+            for hard_mode in [false, true] {
+                let depth_first = cheapest_mana(
+                    hitpoints,
+                    damage,
+                    hard_mode,
+                    options(Algorithm::DepthFirst, 100_000),
+                );
+                for algorithm in [Algorithm::Dijkstra, Algorithm::AStar] {
+                    assert_eq!(
+                        cheapest_mana(hitpoints, damage, hard_mode, options(algorithm, 100_000)),
+                        depth_first,
+                        "{:?} against {} hitpoints and {} damage",
+                        algorithm,
+                        hitpoints,
+                        damage
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn heuristic_is_admissible() {
+        for (hitpoints, damage, normal, _) in AOC_INPUTS {
+            let mut battle = Battle::with_boss(false, Boss::new(hitpoints, damage));
+            battle.wizard_turn_apply_effects();
+            assert!(minimum_mana_to_win(&battle) <= normal);
+        }
+    }
+}
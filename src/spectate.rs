@@ -0,0 +1,164 @@
+//! `watch` subcommand: follow a battle played in another terminal through the autosave it
+//! writes after every round, in the battle UI with the input that plays it disabled.
+
+use crate::{autosave, create_app, ui::tui};
+use advent_wizard_rpg::{save::SaveGame, Spell};
+use clap::{arg, value_parser, ArgMatches, Command};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Constraint, Flex, Layout},
+    style::Stylize,
+    text::Line,
+    widgets::Paragraph,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// How often the followed save is read for new rounds
+pub const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub fn command() -> Command {
+    Command::new("watch")
+        .about("Follow a battle played elsewhere through its autosaves, without playing it")
+        .arg(
+            arg!([PATH] "Autosave directory or save file to follow [default: the autosave directory]")
+                .value_parser(value_parser!(PathBuf)),
+        )
+}
+
+/// Watch the battles saved to the path of `sub_matches` until the user quits. A battle
+/// that gives way to another, such as when the player starts a new game, is followed by
+/// the new one.
+pub fn run(matches: &ArgMatches, sub_matches: &ArgMatches) -> io::Result<()> {
+    let path = sub_matches
+        .get_one::<PathBuf>("PATH")
+        .cloned()
+        .unwrap_or_else(autosave::default_dir);
+    let mut terminal = tui::init()?;
+    let result = watch(&mut terminal, matches, &path);
+    tui::restore()?;
+    result
+}
+
+fn watch(terminal: &mut tui::Tui, matches: &ArgMatches, path: &Path) -> io::Result<()> {
+    loop {
+        let Some(follower) = wait_for_save(terminal, path)? else {
+            return Ok(());
+        };
+        let save = follower.save.clone();
+        let mut app = create_app(
+            matches,
+            save.challenge.create_battle(),
+            Some(save.challenge.name.clone()),
+            &save.challenge,
+            None,
+            None,
+        );
+        app.follower = Some(follower);
+        // The player posts the result of the battle, not everyone watching it
+        app.webhook = None;
+        app.intro_banner = None;
+        // Nor are watched battles part of the watcher's session
+        app.session_recorded = true;
+        app.run(terminal, &save.spells)?;
+        if !app.follower.is_some_and(|follower| follower.is_replaced()) {
+            return Ok(());
+        }
+    }
+}
+
+/// Follower of the first save found in `path`, waiting until there is one. Returns None
+/// if the user quit while waiting.
+fn wait_for_save(terminal: &mut tui::Tui, path: &Path) -> io::Result<Option<SaveFollower>> {
+    loop {
+        if let Some(follower) = SaveFollower::open(path) {
+            return Ok(Some(follower));
+        }
+        terminal.draw(|frame| {
+            let [area] = Layout::vertical([Constraint::Length(3)])
+                .flex(Flex::Center)
+                .areas(frame.area());
+            let text = vec![
+                Line::from(format!(
+                    "Waiting for a battle to be saved in {}",
+                    path.display()
+                )),
+                Line::default(),
+                Line::from("q: quit").dark_gray(),
+            ];
+            frame.render_widget(Paragraph::new(text).centered(), area);
+        })?;
+        if event::poll(WATCH_POLL_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+/// Newest save in `path`, a directory of autosaves or a save file
+fn load_latest(path: &Path) -> Option<SaveGame> {
+    let file = if path.is_dir() {
+        autosave::find_latest(path)?
+    } else {
+        path.to_path_buf()
+    };
+    SaveGame::load(&file).ok()
+}
+
+/// Reads the save of a battle played elsewhere for the rounds played since it was last
+/// read
+#[derive(Debug)]
+pub struct SaveFollower {
+    path: PathBuf,
+    /// Save as of the last read, whose rounds have all been handed out
+    save: SaveGame,
+    last_read: Instant,
+    /// Whether the save was replaced by one of another battle
+    replaced: bool,
+}
+
+impl SaveFollower {
+    fn open(path: &Path) -> Option<Self> {
+        Some(Self {
+            path: path.to_path_buf(),
+            save: load_latest(path)?,
+            last_read: Instant::now(),
+            replaced: false,
+        })
+    }
+
+    pub fn is_replaced(&self) -> bool {
+        self.replaced
+    }
+
+    /// Time until the save is due to be read again
+    pub fn time_until_next_read(&self) -> Duration {
+        WATCH_POLL_INTERVAL.saturating_sub(self.last_read.elapsed())
+    }
+
+    /// Spells cast in the rounds saved since the last read, if it is due. A save of
+    /// another battle marks the follower replaced instead.
+    pub fn read_new_rounds(&mut self) -> Vec<Spell> {
+        if self.replaced || self.last_read.elapsed() < WATCH_POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_read = Instant::now();
+        // A save being written may fail to parse, it is read again next time
+        let Some(save) = load_latest(&self.path) else {
+            return Vec::new();
+        };
+        if save.challenge != self.save.challenge || !save.spells.starts_with(&self.save.spells) {
+            self.replaced = true;
+            return Vec::new();
+        }
+        let spells = save.spells[self.save.spells.len()..].to_vec();
+        self.save = save;
+        spells
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{
+    file_format::write_atomic,
+    toml::{self, ParseError, Table, Value},
+    Spell,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+/// Action points the wizard gets each turn under the action point economy
+pub const ACTION_POINTS_PER_TURN: i32 = 2;
+/// Spells costing up to this much mana take a single action point, dearer ones take two
+pub const CHEAP_SPELL_MANA: i32 = 100;
+/// Mana each hitpoint pays for under blood magic
+pub const BLOOD_MAGIC_MANA_PER_HITPOINT: i32 = 2;
+
+/// Hitpoints blood magic takes for `mana_short` mana, rounding up
+pub(crate) fn get_blood_price(mana_short: i32) -> i32 {
+    (mana_short.max(0) + BLOOD_MAGIC_MANA_PER_HITPOINT - 1) / BLOOD_MAGIC_MANA_PER_HITPOINT
+}
+
+/// A number describing a spell
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum SpellStat {
+    Mana,
+    Damage,
+    Heal,
+    Armor,
+    ManaGain,
+    Duration,
+}
+
+impl SpellStat {
+    pub const ALL: [SpellStat; 6] = [
+        SpellStat::Mana,
+        SpellStat::Damage,
+        SpellStat::Heal,
+        SpellStat::Armor,
+        SpellStat::ManaGain,
+        SpellStat::Duration,
+    ];
+
+    /// Key of the stat in spellbook files
+    pub fn get_key(&self) -> &'static str {
+        match self {
+            SpellStat::Mana => "mana",
+            SpellStat::Damage => "damage",
+            SpellStat::Heal => "heal",
+            SpellStat::Armor => "armor",
+            SpellStat::ManaGain => "mana_gain",
+            SpellStat::Duration => "duration",
+        }
+    }
+
+    pub fn get_display_name(&self) -> &'static str {
+        match self {
+            SpellStat::Mana => "Cost",
+            SpellStat::Damage => "Damage",
+            SpellStat::Heal => "Heal",
+            SpellStat::Armor => "Armor",
+            SpellStat::ManaGain => "Mana gain",
+            SpellStat::Duration => "Duration",
+        }
+    }
+}
+
+impl Spell {
+    /// Whether the spell is one of the extra spells beyond the original puzzle's, which
+    /// the default spellbook seals
+    pub fn is_extra(&self) -> bool {
+        matches!(self, Spell::Cleanse | Spell::Thorns | Spell::Summon)
+    }
+
+    /// Whether casting the spell starts an effect that lasts several turns
+    pub fn is_effect(&self) -> bool {
+        matches!(
+            self,
+            Spell::Shield | Spell::Poison | Spell::Recharge | Spell::Thorns | Spell::Summon
+        )
+    }
+
+    /// Stats the engine reads for this spell. Instant spells deal damage and heal on cast,
+    /// Shield gives armor, Poison deals damage each turn and Recharge gives mana each turn.
+    pub fn get_stats(&self) -> &'static [SpellStat] {
+        match self {
+            Spell::MagicMissile => &[SpellStat::Mana, SpellStat::Damage],
+            Spell::Drain => &[SpellStat::Mana, SpellStat::Damage, SpellStat::Heal],
+            Spell::Shield => &[SpellStat::Mana, SpellStat::Armor, SpellStat::Duration],
+            Spell::Poison => &[SpellStat::Mana, SpellStat::Damage, SpellStat::Duration],
+            Spell::Recharge => &[SpellStat::Mana, SpellStat::ManaGain, SpellStat::Duration],
+            Spell::Cleanse => &[SpellStat::Mana],
+            Spell::Thorns => &[SpellStat::Mana, SpellStat::Damage, SpellStat::Duration],
+            Spell::Summon => &[SpellStat::Mana, SpellStat::Damage, SpellStat::Duration],
+        }
+    }
+}
+
+/// Definition of a spell in a spellbook
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpellStats {
+    pub mana: i32,
+    /// Damage dealt on cast, or each turn for Poison
+    pub damage: i32,
+    /// Hitpoints the caster regains on cast
+    pub heal: i32,
+    /// Armor while Shield is active
+    pub armor: i32,
+    /// Mana gained each turn while Recharge is active
+    pub mana_gain: i32,
+    /// Turns an effect lasts, 0 for instant spells
+    pub duration: i32,
+    pub description: String,
+}
+
+impl SpellStats {
+    pub fn get(&self, stat: SpellStat) -> i32 {
+        match stat {
+            SpellStat::Mana => self.mana,
+            SpellStat::Damage => self.damage,
+            SpellStat::Heal => self.heal,
+            SpellStat::Armor => self.armor,
+            SpellStat::ManaGain => self.mana_gain,
+            SpellStat::Duration => self.duration,
+        }
+    }
+
+    pub fn set(&mut self, stat: SpellStat, value: i32) {
+        match stat {
+            SpellStat::Mana => self.mana = value,
+            SpellStat::Damage => self.damage = value,
+            SpellStat::Heal => self.heal = value,
+            SpellStat::Armor => self.armor = value,
+            SpellStat::ManaGain => self.mana_gain = value,
+            SpellStat::Duration => self.duration = value,
+        }
+    }
+}
+
+/// The numbers behind every spell, stored as `[[spell]]` tables in a TOML file. Tables
+/// only need the keys they change: the rest keep their default values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Spellbook {
+    /// Stats of each spell, in the order of `Spell::ALL`
+    spells: [SpellStats; 8],
+    /// Whether each spell is left out of the wizard's loadout and can't be cast, in the
+    /// order of `Spell::ALL`
+    sealed: [bool; 8],
+}
+
+impl Default for Spellbook {
+    fn default() -> Self {
+        let spell =
+            |mana, damage, heal, armor, mana_gain, duration, description: &str| SpellStats {
+                mana,
+                damage,
+                heal,
+                armor,
+                mana_gain,
+                duration,
+                description: description.to_string(),
+            };
+        Self {
+            spells: [
+                spell(53, 4, 0, 0, 0, 0, "Deals damage"),
+                spell(73, 2, 2, 0, 0, 0, "Deals damage and heals the caster"),
+                spell(113, 0, 0, 7, 0, 6, "Increases armor while active"),
+                spell(173, 3, 0, 0, 0, 6, "Deals damage at the start of each turn"),
+                spell(229, 0, 0, 0, 101, 5, "Gives mana at the start of each turn"),
+                spell(
+                    83,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    "Ends silence, burns and poison on the caster",
+                ),
+                spell(
+                    131,
+                    2,
+                    0,
+                    0,
+                    0,
+                    6,
+                    "Deals damage to the boss whenever it attacks",
+                ),
+                spell(
+                    61,
+                    1,
+                    0,
+                    0,
+                    0,
+                    5,
+                    "Summons a familiar dealing damage at the start of each wizard turn",
+                ),
+            ],
+            sealed: Spell::ALL.map(|spell| spell.is_extra()),
+        }
+    }
+}
+
+impl Spellbook {
+    pub fn get(&self, spell: &Spell) -> &SpellStats {
+        &self.spells[Self::index(spell)]
+    }
+
+    pub fn get_mut(&mut self, spell: &Spell) -> &mut SpellStats {
+        &mut self.spells[Self::index(spell)]
+    }
+
+    /// Keep `spell` from being cast
+    pub fn seal(&mut self, spell: &Spell) {
+        self.sealed[Self::index(spell)] = true;
+    }
+
+    /// Let `spell` be cast again
+    pub fn unseal(&mut self, spell: &Spell) {
+        self.sealed[Self::index(spell)] = false;
+    }
+
+    /// Unseal the extra spells
+    pub fn with_extra_spells(mut self) -> Self {
+        for spell in Spell::ALL.iter().filter(|spell| spell.is_extra()) {
+            self.unseal(spell);
+        }
+        self
+    }
+
+    pub fn is_sealed(&self, spell: &Spell) -> bool {
+        self.sealed[Self::index(spell)]
+    }
+
+    pub fn get_mana(&self, spell: &Spell) -> i32 {
+        self.get(spell).mana
+    }
+
+    /// Action points casting `spell` takes under the action point economy
+    pub fn get_action_points(&self, spell: &Spell) -> i32 {
+        if self.get_mana(spell) <= CHEAP_SPELL_MANA {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Every spell, cheapest first
+    pub fn get_spells_by_cost(&self) -> Vec<Spell> {
+        let mut spells = Spell::ALL.to_vec();
+        spells.sort_by_key(|spell| self.get_mana(spell));
+        spells
+    }
+
+    /// Total damage one cast of `spell` deals, counting every turn of an effect. Thorns
+    /// only deals damage on the boss's turns, every other turn, while Summon's duration
+    /// only counts the wizard's turns, on each of which the familiar attacks.
+    pub fn get_total_damage(&self, spell: &Spell) -> i32 {
+        let stats = self.get(spell);
+        match spell {
+            Spell::Thorns => stats.damage * ((stats.duration + 1) / 2),
+            _ if spell.is_effect() => stats.damage * stats.duration,
+            _ => stats.damage,
+        }
+    }
+
+    /// Describe what is wrong with the spellbook, if anything
+    pub fn validate(&self) -> Result<(), String> {
+        for spell in Spell::ALL.iter() {
+            let stats = self.get(spell);
+            for stat in spell.get_stats() {
+                let value = stats.get(*stat);
+                let message = match stat {
+                    SpellStat::Mana if value <= 0 => "must be positive",
+                    SpellStat::Duration if !(1..=u8::MAX as i32).contains(&value) => {
+                        "must be between 1 and 255"
+                    }
+                    _ if value < 0 => "must not be negative",
+                    _ => continue,
+                };
+                return Err(format!(
+                    "{} {} {}",
+                    spell.get_display_name(),
+                    stat.get_display_name().to_lowercase(),
+                    message
+                ));
+            }
+        }
+        if Spell::ALL
+            .iter()
+            .all(|spell| self.get_total_damage(spell) <= 0)
+        {
+            return Err("no spell deals damage".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut spellbook = Self::default();
+        for table in toml::parse(text)? {
+            if table.name != "spell" {
+                return Err(table.error(format!("unknown table `[[{}]]`", table.name)));
+            }
+            spellbook.apply_table(&table)?;
+            spellbook
+                .validate()
+                .map_err(|message| table.error(message))?;
+        }
+        Ok(spellbook)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::write(&self.to_tables())
+    }
+
+    /// One `[[spell]]` table per spell, holding the stats the spell uses and whether it is
+    /// sealed if that differs from the default spellbook
+    pub fn to_tables(&self) -> Vec<Table> {
+        Spell::ALL
+            .iter()
+            .map(|spell| {
+                let stats = self.get(spell);
+                let mut table = Table::new("spell");
+                table.push("name", Value::String(spell.get_display_name().to_string()));
+                for stat in spell.get_stats() {
+                    table.push(stat.get_key(), Value::Integer(stats.get(*stat) as i64));
+                }
+                table.push("description", Value::String(stats.description.clone()));
+                if self.is_sealed(spell) != spell.is_extra() {
+                    table.push("sealed", Value::Boolean(self.is_sealed(spell)));
+                }
+                table
+            })
+            .collect()
+    }
+
+    /// Override the stats of the spell named by a `[[spell]]` table
+    pub fn apply_table(&mut self, table: &Table) -> Result<(), ParseError> {
+        let name = table
+            .get_string("name")?
+            .ok_or_else(|| table.error("spell is missing `name`".to_string()))?;
+        let spell = &name
+            .parse::<Spell>()
+            .map_err(|err| table.error(err.to_string()))?;
+        for (key, _) in &table.entries {
+            if key != "name"
+                && key != "description"
+                && key != "sealed"
+                && !spell.get_stats().iter().any(|stat| stat.get_key() == key)
+            {
+                return Err(table.error(format!("{} has no `{}`", name, key)));
+            }
+        }
+
+        let stats = self.get_mut(spell);
+        for stat in spell.get_stats() {
+            if let Some(value) = table.get_integer(stat.get_key())? {
+                let value = i32::try_from(value)
+                    .map_err(|_| table.error(format!("`{}` is out of range", stat.get_key())))?;
+                stats.set(*stat, value);
+            }
+        }
+        if let Some(description) = table.get_string("description")? {
+            stats.description = description;
+        }
+        match table.get_boolean("sealed")? {
+            Some(true) => self.seal(spell),
+            Some(false) => self.unseal(spell),
+            None => (),
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, self.to_toml())
+    }
+
+    fn index(spell: &Spell) -> usize {
+        match spell {
+            Spell::MagicMissile => 0,
+            Spell::Drain => 1,
+            Spell::Shield => 2,
+            Spell::Poison => 3,
+            Spell::Recharge => 4,
+            Spell::Cleanse => 5,
+            Spell::Thorns => 6,
+            Spell::Summon => 7,
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! `edit-spellbook` subcommand: modify spell definitions in a form-style editor.
+
+use crate::ui::{theme, tui, TextInput};
+use advent_wizard_rpg::{
+    balance::check_balance, bestiary::Bestiary, challenge::Challenge, Spell, Spellbook,
+};
+use clap::{arg, value_parser, ArgMatches, Command};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Alignment, Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListState, Paragraph},
+    Frame,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Rounds the balance check searches for a win
+const BALANCE_CHECK_DEPTH: usize = 20;
+
+pub fn command() -> Command {
+    Command::new("edit-spellbook")
+        .about("Modify spell definitions in a spellbook file")
+        .arg(
+            arg!(--file <FILE> "Spellbook file to edit, created on save if missing")
+                .value_parser(value_parser!(PathBuf))
+                .default_value("spellbook.toml"),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> io::Result<()> {
+    let path = matches.get_one::<PathBuf>("file").unwrap().clone();
+    let spellbook = if path.exists() {
+        Spellbook::load(&path)?
+    } else {
+        Spellbook::default()
+    };
+
+    let mut terminal = tui::init()?;
+    let result = SpellbookEditor::new(path, spellbook).run(&mut terminal);
+    tui::restore()?;
+    result
+}
+
+#[derive(Debug)]
+struct SpellbookEditor {
+    exit: bool,
+    path: PathBuf,
+    spellbook: Spellbook,
+    list_state: ListState,
+    /// Stats of the spell being edited followed by its description, None when browsing
+    /// the list
+    form: Option<Vec<TextInput>>,
+    field_selected: usize,
+    /// Result of the last action
+    status: Vec<String>,
+}
+
+impl SpellbookEditor {
+    fn new(path: PathBuf, spellbook: Spellbook) -> Self {
+        Self {
+            exit: false,
+            path,
+            spellbook,
+            list_state: ListState::default().with_selected(Some(0)),
+            form: None,
+            field_selected: 0,
+            status: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, terminal: &mut tui::Tui) -> io::Result<()> {
+        while !self.exit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if self.form.is_some() {
+                    self.handle_form_key_event(key.code);
+                } else {
+                    self.handle_list_key_event(key.code);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_list_key_event(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => self.exit = true,
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char('e') | KeyCode::Enter => self.open_form(),
+            KeyCode::Char('c') => self.check(),
+            KeyCode::Char('r') => {
+                let spell = self.selected_spell();
+                *self.spellbook.get_mut(&spell) = Spellbook::default().get(&spell).clone();
+                self.status = vec![format!("Reset {} (unsaved)", spell.get_display_name())];
+            }
+            KeyCode::Char('s') => self.save(),
+            KeyCode::Char('x') => self.export_challenge(),
+            _ => (),
+        }
+    }
+
+    fn handle_form_key_event(&mut self, key: KeyCode) {
+        let Some(form) = self.form.as_mut() else {
+            return;
+        };
+        let fields = form.len();
+        match key {
+            KeyCode::Esc => {
+                self.form = None;
+                self.status = vec!["Edit cancelled".to_string()];
+            }
+            KeyCode::Enter => self.apply_form(),
+            KeyCode::Tab | KeyCode::Down => {
+                self.field_selected = (self.field_selected + 1) % fields;
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                self.field_selected = (self.field_selected + fields - 1) % fields;
+            }
+            key => {
+                form[self.field_selected].handle_key_event(key);
+            }
+        }
+    }
+
+    fn selected_spell(&self) -> Spell {
+        let selected = self.list_state.selected().unwrap_or(0);
+        Spell::ALL[selected.min(Spell::ALL.len() - 1)].clone()
+    }
+
+    fn open_form(&mut self) {
+        let spell = self.selected_spell();
+        let stats = self.spellbook.get(&spell);
+        let mut form: Vec<TextInput> = spell
+            .get_stats()
+            .iter()
+            .map(|stat| TextInput::new(stats.get(*stat).to_string()))
+            .collect();
+        form.push(TextInput::new(stats.description.clone()));
+        self.form = Some(form);
+        self.field_selected = 0;
+        self.status.clear();
+    }
+
+    /// Validate the form and write it to the selected spell, then check the balance
+    fn apply_form(&mut self) {
+        let Some(form) = &self.form else {
+            return;
+        };
+        let spell = self.selected_spell();
+        let mut spellbook = self.spellbook.clone();
+        let stats = spellbook.get_mut(&spell);
+        for (stat, input) in spell.get_stats().iter().zip(form) {
+            let Ok(value) = input.get_value().trim().parse() else {
+                self.status = vec![format!(
+                    "{} must be a whole number",
+                    stat.get_display_name()
+                )];
+                return;
+            };
+            stats.set(*stat, value);
+        }
+        stats.description = form[form.len() - 1].get_value().trim().to_string();
+        if let Err(message) = spellbook.validate() {
+            self.status = vec![format!("Invalid spellbook: {}", message)];
+            return;
+        }
+
+        self.spellbook = spellbook;
+        self.form = None;
+        self.check();
+        self.status
+            .insert(0, format!("Updated {} (unsaved)", spell.get_display_name()));
+    }
+
+    /// Challenge against the default boss with the current spellbook
+    fn create_challenge(&self) -> Challenge {
+        let boss = Bestiary::builtin().get_entries()[0].clone();
+        Challenge {
+            name: format!(
+                "{} with {}",
+                boss.name,
+                self.path.file_stem().unwrap_or_default().to_string_lossy()
+            ),
+            spellbook: self.spellbook.clone(),
+            ..Challenge::new(boss)
+        }
+    }
+
+    /// Run the solver against the default boss with the current spellbook
+    fn check(&mut self) {
+        let mut battle = self.create_challenge().create_battle();
+        battle.wizard_turn_apply_effects();
+        let warnings = check_balance(&battle, BALANCE_CHECK_DEPTH);
+        self.status = if warnings.is_empty() {
+            vec!["Balance check passed against the default boss".to_string()]
+        } else {
+            warnings
+                .iter()
+                .map(|warning| format!("Warning: {}", warning))
+                .collect()
+        };
+    }
+
+    fn save(&mut self) {
+        self.status = vec![match self.spellbook.save(&self.path) {
+            Ok(()) => format!("Saved to {}", self.path.display()),
+            Err(err) => format!("Could not save to {}: {}", self.path.display(), err),
+        }];
+    }
+
+    /// Write a challenge against the default boss next to the spellbook
+    fn export_challenge(&mut self) {
+        let challenge = self.create_challenge();
+        let path = self
+            .path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(challenge.get_file_name());
+        self.status = vec![match challenge.save(&path) {
+            Ok(()) => format!("Exported challenge to {}", path.display()),
+            Err(err) => format!("Could not export to {}: {}", path.display(), err),
+        }];
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let chunks = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(4),
+            Constraint::Length(1),
+        ])
+        .split(frame.area());
+
+        let title = Block::new()
+            .title_alignment(Alignment::Center)
+            .title(format!("Spellbook Editor: {}", self.path.display()).bold());
+        frame.render_widget(title, chunks[0]);
+
+        let columns = Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+            .split(chunks[1]);
+
+        // Left: list of spells
+        let names: Vec<String> = Spell::ALL
+            .iter()
+            .map(|spell| {
+                format!(
+                    "{} ({} mana)",
+                    spell.get_display_name(),
+                    self.spellbook.get_mana(spell)
+                )
+            })
+            .collect();
+        let list = List::new(names)
+            .gray()
+            .highlight_style(Style::default().fg(theme::get().accent).bold())
+            .highlight_symbol("> ")
+            .block(
+                Block::bordered()
+                    .border_style(Style::default().fg(theme::get().wizard))
+                    .title("Spells".bold().gray())
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_stateful_widget(list, columns[0], &mut self.list_state);
+
+        // Right: form for the selected spell
+        let spell = self.selected_spell();
+        let form_block = Block::bordered()
+            .border_style(Style::default().fg(if self.form.is_some() {
+                theme::get().accent
+            } else {
+                Color::Gray
+            }))
+            .title(spell.get_display_name().bold().gray())
+            .title_alignment(Alignment::Center);
+        let form_area = form_block.inner(columns[1]);
+        frame.render_widget(form_block, columns[1]);
+
+        let stats = self.spellbook.get(&spell);
+        let labels: Vec<&str> = spell
+            .get_stats()
+            .iter()
+            .map(|stat| stat.get_display_name())
+            .chain(["Description"])
+            .collect();
+        let values: Vec<String> = spell
+            .get_stats()
+            .iter()
+            .map(|stat| stats.get(*stat).to_string())
+            .chain([stats.description.clone()])
+            .collect();
+        let rows = Layout::vertical(vec![Constraint::Length(2); labels.len()]).split(form_area);
+        for (i, row) in rows.iter().enumerate() {
+            let row = Layout::horizontal([Constraint::Length(13), Constraint::Min(0)]).split(*row);
+            frame.render_widget(Paragraph::new(format!("{}:", labels[i])).gray(), row[0]);
+            match self.form.as_mut() {
+                Some(form) => {
+                    form[i].set_focused(i == self.field_selected);
+                    frame.render_widget(&form[i], row[1]);
+                }
+                None => frame.render_widget(Paragraph::new(values[i].as_str()).gray(), row[1]),
+            }
+        }
+
+        let status: Vec<Line> = self
+            .status
+            .iter()
+            .map(|line| Line::from(line.as_str()))
+            .collect();
+        frame.render_widget(Paragraph::new(status).fg(theme::get().accent), chunks[2]);
+        let help = if self.form.is_some() {
+            "Tab/Up/Down: field  Enter: apply  Esc: cancel"
+        } else {
+            "j/k: select  e: edit  r: reset  c: check balance  s: save  x: export challenge  q: quit"
+        };
+        frame.render_widget(Line::from(help).dark_gray(), chunks[3]);
+    }
+}
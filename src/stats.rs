@@ -0,0 +1,196 @@
+use crate::{Battle, BattleEvent, DamageSource, Spell};
+use std::time::{Duration, Instant};
+
+/// Snapshot of the battle taken at the start of a round
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundSample {
+    pub wizard_hitpoints: i32,
+    pub boss_hitpoints: i32,
+    pub mana: i32,
+}
+
+/// Row of the battle journal, describing a round and the state of the battle at the start
+/// of the next one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundRecord {
+    /// Rounds played, 0 for the start of the battle
+    pub round: usize,
+    /// Time since the start of the battle was recorded
+    pub elapsed: Duration,
+    /// Spell cast in the round, None for the start of the battle
+    pub spell: Option<Spell>,
+    pub mana_spent: i32,
+    pub wizard_hitpoints: i32,
+    pub boss_hitpoints: i32,
+    pub armor: i32,
+    pub mana: i32,
+    /// Turns left of each effect
+    pub shield: Option<i32>,
+    pub recharge: Option<i32>,
+    pub poison: Option<i32>,
+    /// Time the player took to decide the spell, None for the start of the battle and
+    /// rounds the player didn't decide, such as replayed ones
+    pub think_time: Option<Duration>,
+}
+
+/// Column names of `BattleStats::journal_to_csv`
+pub const JOURNAL_CSV_HEADER: &str = "round,elapsed_ms,spell,mana_spent,wizard_hitpoints,boss_hitpoints,armor,mana,shield,recharge,poison,ironman,think_ms";
+
+#[derive(Debug, Default)]
+pub struct BattleStats {
+    samples: Vec<RoundSample>,
+    journal: Vec<RoundRecord>,
+    /// When the first round was recorded
+    started: Option<Instant>,
+    /// Whether the battle is played without assists, saves or recovery
+    ironman: bool,
+    /// Think time of the round being played, until the round is recorded
+    pending_think_time: Option<Duration>,
+}
+
+impl BattleStats {
+    /// Record the current state of the battle as a new round sample and journal row
+    pub fn record_round(&mut self, battle: &Battle) {
+        let wizard = battle.get_wizard();
+        self.samples.push(RoundSample {
+            wizard_hitpoints: wizard.get_hitpoints(),
+            boss_hitpoints: battle.get_boss().get_hitpoints(),
+            mana: wizard.get_mana(),
+        });
+
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let mana_used = self
+            .journal
+            .iter()
+            .map(|record| record.mana_spent)
+            .sum::<i32>();
+        self.journal.push(RoundRecord {
+            round: battle.get_spells_used().len(),
+            elapsed: started.elapsed(),
+            spell: battle.get_spells_used().last().cloned(),
+            mana_spent: battle.get_mana_used() - mana_used,
+            wizard_hitpoints: wizard.get_hitpoints(),
+            boss_hitpoints: battle.get_boss().get_hitpoints(),
+            armor: wizard.get_armor(),
+            mana: wizard.get_mana(),
+            shield: wizard.get_shielded(),
+            recharge: wizard.get_recharging(),
+            poison: battle.get_boss().get_poisoned(),
+            think_time: self.pending_think_time.take(),
+        });
+    }
+
+    /// Record how long the player took to decide the spell of the round being played
+    pub fn record_think_time(&mut self, think_time: Duration) {
+        self.pending_think_time = Some(think_time);
+    }
+
+    /// Think time of every round the player decided, in order
+    pub fn get_think_times(&self) -> Vec<Duration> {
+        self.journal
+            .iter()
+            .filter_map(|record| record.think_time)
+            .collect()
+    }
+
+    pub fn get_total_think_time(&self) -> Duration {
+        self.get_think_times().into_iter().sum()
+    }
+
+    /// Average think time, None if the player decided no round
+    pub fn get_average_think_time(&self) -> Option<Duration> {
+        let think_times = self.get_think_times();
+        (!think_times.is_empty())
+            .then(|| think_times.iter().sum::<Duration>() / think_times.len() as u32)
+    }
+
+    pub fn get_longest_think_time(&self) -> Option<Duration> {
+        self.get_think_times().into_iter().max()
+    }
+
+    pub fn set_ironman(&mut self, ironman: bool) {
+        self.ironman = ironman;
+    }
+
+    pub fn is_ironman(&self) -> bool {
+        self.ironman
+    }
+
+    pub fn get_samples(&self) -> &Vec<RoundSample> {
+        &self.samples
+    }
+
+    pub fn get_journal(&self) -> &Vec<RoundRecord> {
+        &self.journal
+    }
+
+    /// The journal as CSV, with a header row. Effects that aren't active and rounds without
+    /// a think time are left empty.
+    pub fn journal_to_csv(&self) -> String {
+        let optional = |value: Option<i32>| value.map_or(String::new(), |value| value.to_string());
+        let mut csv = format!("{}\n", JOURNAL_CSV_HEADER);
+        for record in &self.journal {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                record.round,
+                record.elapsed.as_millis(),
+                record
+                    .spell
+                    .as_ref()
+                    .map_or("", |spell| spell.get_display_name()),
+                record.mana_spent,
+                record.wizard_hitpoints,
+                record.boss_hitpoints,
+                record.armor,
+                record.mana,
+                optional(record.shield),
+                optional(record.recharge),
+                optional(record.poison),
+                self.ironman,
+                record
+                    .think_time
+                    .map_or(String::new(), |think_time| think_time
+                        .as_millis()
+                        .to_string()),
+            ));
+        }
+        csv
+    }
+
+    /// Per-round series of a sample field, clamped to 0 for plotting
+    pub fn series(&self, field: fn(&RoundSample) -> i32) -> Vec<u64> {
+        self.samples
+            .iter()
+            .map(|sample| field(sample).max(0) as u64)
+            .collect()
+    }
+}
+
+/// Total damage per source, in the order sources first appeared
+#[derive(Debug, Default)]
+pub struct DamageBreakdown {
+    pub dealt: Vec<(DamageSource, i32)>,
+    pub taken: Vec<(DamageSource, i32)>,
+}
+
+impl DamageBreakdown {
+    pub fn from_events(events: &[BattleEvent]) -> Self {
+        let mut breakdown = Self::default();
+        for event in events {
+            let (totals, source, amount) = match event {
+                BattleEvent::BossDamaged { source, amount } => {
+                    (&mut breakdown.dealt, source, amount)
+                }
+                BattleEvent::WizardDamaged { source, amount } => {
+                    (&mut breakdown.taken, source, amount)
+                }
+                _ => continue,
+            };
+            match totals.iter_mut().find(|(s, _)| s == source) {
+                Some((_, total)) => *total += amount,
+                None => totals.push((*source, *amount)),
+            }
+        }
+        breakdown
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+/// Measures the time since it was started, leaving out the time it was paused
+#[derive(Debug, Clone, Copy)]
+pub struct Stopwatch {
+    started: Instant,
+    /// Time spent paused before the current pause
+    paused_total: Duration,
+    /// When the current pause started, None if it isn't paused
+    paused_at: Option<Instant>,
+}
+
+impl Stopwatch {
+    /// A stopwatch running from now
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            paused_total: Duration::ZERO,
+            paused_at: None,
+        }
+    }
+
+    /// Time running since it was started
+    pub fn elapsed(&self) -> Duration {
+        let end = self.paused_at.unwrap_or_else(Instant::now);
+        end.saturating_duration_since(self.started)
+            .saturating_sub(self.paused_total)
+    }
+
+    /// Stop counting time until resumed. Does nothing if it's already paused.
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+        }
+    }
+
+    /// Count time again from now. Does nothing if it isn't paused.
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_total += paused_at.elapsed();
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{duel::Duel, knowledge::Known, rng::Rng, Battle, Spell, Spellbook};
+
+/// What a wizard sees when choosing a spell, whether facing a boss or another wizard. A
+/// boss's stats are only as exact as the battle's knowledge mask allows, so strategies
+/// play under scouting with what the wizard has learned.
+#[derive(Debug, Clone)]
+pub struct Observation<'a> {
+    pub hitpoints: i32,
+    pub armor: i32,
+    pub mana: i32,
+    /// Turns of shield left, 0 if not shielded
+    pub shield_timer: i32,
+    /// Turns of recharge left, 0 if not recharging
+    pub recharge_timer: i32,
+    /// Mana the recharge still gives before it wears off
+    pub recharge_mana: i32,
+    pub opponent_hitpoints: Known,
+    /// Damage of the opponent's attack, None if the opponent is a wizard
+    pub opponent_damage: Option<Known>,
+    /// Turns of poison left on the opponent, 0 if not poisoned
+    pub opponent_poison_timer: i32,
+    /// Damage the poison on the opponent still deals before it wears off
+    pub opponent_poison_damage: i32,
+    pub possible_spells: Vec<Spell>,
+    /// Costs and stats of the spells
+    pub spellbook: &'a Spellbook,
+}
+
+impl<'a> Observation<'a> {
+    /// The wizard's view of a battle against a boss
+    pub fn from_battle(battle: &'a Battle) -> Self {
+        let wizard = battle.get_wizard();
+        let boss = battle.get_boss_view();
+        Self {
+            hitpoints: wizard.get_hitpoints(),
+            armor: wizard.get_armor(),
+            mana: wizard.get_mana(),
+            shield_timer: wizard.get_shielded().unwrap_or(0),
+            recharge_timer: wizard.get_recharging().unwrap_or(0),
+            recharge_mana: battle.projected_recharge_mana(i32::MAX),
+            opponent_hitpoints: boss.hitpoints,
+            opponent_damage: Some(boss.damage),
+            opponent_poison_timer: boss.poisoned.unwrap_or(0),
+            opponent_poison_damage: battle.projected_poison_damage(i32::MAX),
+            possible_spells: Spell::ALL
+                .iter()
+                .filter(|spell| wizard.get_possible_spells().contains(spell))
+                .cloned()
+                .collect(),
+            spellbook: battle.get_spellbook(),
+        }
+    }
+
+    /// The view of the wizard on `side` of a duel
+    pub fn from_duel(duel: &'a Duel, side: usize) -> Self {
+        let wizard = duel.get_wizard(side);
+        let opponent = duel.get_wizard(1 - side);
+        Self {
+            hitpoints: wizard.get_hitpoints(),
+            armor: wizard.get_armor(),
+            mana: wizard.get_mana(),
+            shield_timer: wizard.get_shielded().unwrap_or(0),
+            recharge_timer: wizard.get_recharging().unwrap_or(0),
+            recharge_mana: duel.projected_recharge_mana(side, i32::MAX),
+            opponent_hitpoints: Known::Exact(opponent.get_hitpoints()),
+            opponent_damage: None,
+            opponent_poison_timer: opponent.get_poisoned().unwrap_or(0),
+            opponent_poison_damage: duel.projected_poison_damage(1 - side, i32::MAX),
+            possible_spells: duel.get_possible_spells(side),
+            spellbook: duel.get_spellbook(),
+        }
+    }
+
+    /// The cheapest possible spell whose hit is sure to finish the opponent, going by the
+    /// most hitpoints they can have left
+    pub fn get_finishing_spell(&self) -> Option<Spell> {
+        let hitpoints = self.opponent_hitpoints.get_upper_bound()?;
+        self.possible_spells
+            .iter()
+            .filter(|spell| !spell.is_effect() && self.spellbook.get(spell).damage >= hitpoints)
+            .min_by_key(|spell| self.spellbook.get_mana(spell))
+            .cloned()
+    }
+
+    /// Whether the opponent's next attack can kill the wizard, as far as its damage is
+    /// known. The shield only counts if it lasts through the opponent's turn.
+    pub fn is_attack_fatal(&self) -> bool {
+        let Some(damage) = self.opponent_damage.and_then(Known::get_upper_bound) else {
+            return false;
+        };
+        let armor = if self.shield_timer > 1 { self.armor } else { 0 };
+        self.hitpoints <= (damage - armor).max(1)
+    }
+}
+
+/// A bot that decides which spell the wizard casts
+pub trait Strategy {
+    fn get_name(&self) -> &'static str;
+
+    /// Choose one of the possible spells, or None if there are none
+    fn choose_spell(&mut self, observation: &Observation<'_>) -> Option<Spell>;
+}
+
+/// Casts uniformly random possible spells, unless one is sure to finish the opponent
+#[derive(Debug)]
+pub struct RandomStrategy {
+    rng: Rng,
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn get_name(&self) -> &'static str {
+        "random"
+    }
+
+    fn choose_spell(&mut self, observation: &Observation<'_>) -> Option<Spell> {
+        if observation.possible_spells.is_empty() {
+            return None;
+        }
+        if let Some(spell) = observation.get_finishing_spell() {
+            return Some(spell);
+        }
+        let index = self.rng.below(observation.possible_spells.len() as u32) as usize;
+        Some(observation.possible_spells[index].clone())
+    }
+}
+
+/// Finishes the opponent when it surely can and shields against an attack it knows is
+/// fatal. Otherwise keeps effects running when it can, and attacks with Magic Missile.
+#[derive(Debug, Default)]
+pub struct GreedyStrategy;
+
+impl Strategy for GreedyStrategy {
+    fn get_name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn choose_spell(&mut self, observation: &Observation<'_>) -> Option<Spell> {
+        if let Some(spell) = observation.get_finishing_spell() {
+            return Some(spell);
+        }
+        if observation.is_attack_fatal() && observation.possible_spells.contains(&Spell::Shield) {
+            return Some(Spell::Shield);
+        }
+        // Mana still to come from Recharge counts towards the next casts
+        let spellbook = observation.spellbook;
+        let low_on_mana = observation.mana + observation.recharge_mana
+            < spellbook.get_mana(&Spell::Recharge) + spellbook.get_mana(&Spell::Poison);
+        let preferences = if low_on_mana {
+            [
+                Spell::Recharge,
+                Spell::Poison,
+                Spell::Shield,
+                Spell::MagicMissile,
+            ]
+        } else {
+            [
+                Spell::Poison,
+                Spell::Shield,
+                Spell::Recharge,
+                Spell::MagicMissile,
+            ]
+        };
+        preferences
+            .into_iter()
+            .chain([Spell::Drain])
+            .find(|spell| observation.possible_spells.contains(spell))
+    }
+}
+
+/// Names of the built-in strategies, accepted by `create_strategy`
+pub const STRATEGY_NAMES: [&str; 2] = ["random", "greedy"];
+
+/// Create a built-in strategy by name
+pub fn create_strategy(name: &str, seed: u64) -> Option<Box<dyn Strategy>> {
+    match name {
+        "random" => Some(Box::new(RandomStrategy::new(seed))),
+        "greedy" => Some(Box::new(GreedyStrategy)),
+        _ => None,
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{
+    bestiary::{Bestiary, BestiaryEntry},
+    challenge::Challenge,
+    Battle,
+};
+
+/// Hitpoints the wizard heals between bosses in survival
+pub const SURVIVAL_HEAL: i32 = 10;
+/// Extra boss hitpoints each wave, in percent of the first boss's
+const HITPOINTS_PER_WAVE: i32 = 15;
+
+/// Boss of `wave` of survival, counting from 0: the first built-in boss, with 15% more
+/// hitpoints each wave and a point more damage every other wave
+pub fn get_survival_boss(wave: usize) -> BestiaryEntry {
+    let first = Bestiary::builtin().get_entries()[0].clone();
+    let wave = wave as i32;
+    BestiaryEntry::new(
+        &format!("Wave {}", wave + 1),
+        first.hitpoints * (100 + HITPOINTS_PER_WAVE * wave) / 100,
+        first.damage + wave / 2,
+    )
+}
+
+/// Battle against the boss of `wave`, the wizard keeping the hitpoints and mana they had
+/// at the end of `previous` and healing `SURVIVAL_HEAL` hitpoints. Effects don't carry
+/// over.
+pub fn next_survival_battle(previous: &Battle, wave: usize) -> Battle {
+    let battle = Challenge {
+        hard_mode: previous.get_hard_mode(),
+        ..Challenge::new(get_survival_boss(wave))
+    }
+    .create_battle();
+    let wizard = previous.get_wizard();
+    let hitpoints = wizard.get_hitpoints() + SURVIVAL_HEAL - battle.get_wizard().get_hitpoints();
+    let mana = wizard.get_mana() - battle.get_wizard().get_mana();
+    battle.with_wizard_bonus(hitpoints, mana)
+}
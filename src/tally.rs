@@ -0,0 +1,161 @@
+//! Results database: the `--db` sink of `simulate` and `tournament`, and the `stats`
+//! subcommand that aggregates it.
+
+use advent_wizard_rpg::{
+    playout::Playout,
+    results::{
+        append_results, config_hash, summarize_results, GameOutcome, GameRecord, ResultsGroup,
+    },
+    tournament::{TournamentBattle, TournamentOptions},
+    Battle,
+};
+use clap::{arg, value_parser, Arg, ArgMatches, Command};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Argument adding a row per game to a results database
+pub fn db_arg() -> Arg {
+    arg!(--db <FILE> "Add a row per game to a SQLite results database")
+        .value_parser(value_parser!(PathBuf))
+}
+
+pub fn db_from_matches(matches: &ArgMatches) -> Option<&PathBuf> {
+    matches.get_one::<PathBuf>("db")
+}
+
+/// Record the random playouts `simulate` played from `battle`
+pub fn record_playouts(path: &Path, battle: &Battle, seed: u64, playouts: &[Playout]) {
+    let config = config_hash(battle);
+    let records: Vec<GameRecord> = playouts
+        .iter()
+        .map(|playout| GameRecord {
+            source: "simulate".to_string(),
+            config,
+            seed,
+            strategy: "random".to_string(),
+            outcome: outcome(playout.won),
+            mana: playout.mana_used,
+            turns: playout.rounds,
+        })
+        .collect();
+    append_or_exit(path, &records);
+}
+
+/// Record the battles against bosses of a tournament
+pub fn record_tournament(path: &Path, options: &TournamentOptions, battles: &[TournamentBattle]) {
+    let configs: Vec<u64> = (0..options.bosses.len())
+        .map(|boss| {
+            let mut battle = options.create_battle(boss);
+            battle.wizard_turn_apply_effects();
+            config_hash(&battle)
+        })
+        .collect();
+    let records: Vec<GameRecord> = battles
+        .iter()
+        .map(|battle| GameRecord {
+            source: "tournament".to_string(),
+            config: configs[battle.boss],
+            seed: battle.seed,
+            strategy: battle.strategy.to_string(),
+            outcome: outcome(battle.won),
+            mana: battle.mana_used,
+            turns: battle.rounds,
+        })
+        .collect();
+    append_or_exit(path, &records);
+}
+
+fn outcome(won: bool) -> GameOutcome {
+    if won {
+        GameOutcome::Won
+    } else {
+        GameOutcome::Lost
+    }
+}
+
+fn append_or_exit(path: &Path, records: &[GameRecord]) {
+    if let Err(err) = append_results(path, records) {
+        eprintln!("Could not write results to {}: {}", path.display(), err);
+        process::exit(1);
+    }
+}
+
+pub fn command() -> Command {
+    Command::new("stats")
+        .about("Aggregate the games in a results database")
+        .arg(
+            arg!(--db <FILE> "SQLite results database written with `--db`")
+                .value_parser(value_parser!(PathBuf))
+                .required(true),
+        )
+        .arg(arg!(--source <SOURCE> "Only games played by this subcommand"))
+        .arg(arg!(--strategy <STRATEGY> "Only games played by this strategy"))
+        .arg(
+            arg!(--format <FORMAT> "Output format")
+                .value_parser(["table", "csv"])
+                .default_value("table"),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> io::Result<()> {
+    let path = matches.get_one::<PathBuf>("db").unwrap();
+    let source = matches.get_one::<String>("source").map(String::as_str);
+    let strategy = matches.get_one::<String>("strategy").map(String::as_str);
+    let groups = match summarize_results(path, source, strategy) {
+        Ok(groups) => groups,
+        Err(err) => {
+            eprintln!("Could not load results {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    };
+
+    if matches.get_one::<String>("format").unwrap() == "csv" {
+        println!("source,config,strategy,games,wins,win_rate,average_mana,average_turns");
+        for group in &groups {
+            println!(
+                "{},{:016x},{},{},{},{:.4},{},{:.2}",
+                group.source,
+                group.config,
+                group.strategy,
+                group.games,
+                group.wins,
+                group.get_win_rate(),
+                group
+                    .get_average_mana()
+                    .map_or(String::new(), |mana| format!("{:.1}", mana)),
+                group.get_average_turns()
+            );
+        }
+        return Ok(());
+    }
+
+    let games: u64 = groups.iter().map(|group| group.games).sum();
+    println!("{} games in {} groups", games, groups.len());
+    println!();
+    println!(
+        "{:<12}{:<18}{:<10}{:>10}{:>10}{:>12}{:>11}",
+        "Source", "Config", "Strategy", "Games", "Win rate", "Avg mana", "Avg turns"
+    );
+    for group in &groups {
+        print_table_row(group);
+    }
+    Ok(())
+}
+
+fn print_table_row(group: &ResultsGroup) {
+    println!(
+        "{:<12}{:<18}{:<10}{:>10}{:>9.1}%{:>12}{:>11.1}",
+        group.source,
+        format!("{:016x}", group.config),
+        group.strategy,
+        group.games,
+        group.get_win_rate() * 100.0,
+        group
+            .get_average_mana()
+            .map_or("-".to_string(), |mana| format!("{:.1}", mana)),
+        group.get_average_turns()
+    );
+}
@@ -0,0 +1,104 @@
+use crate::toml::{ParseError, Table, Value};
+
+/// When a boss taunts the wizard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TauntTrigger {
+    BattleStart,
+    /// The boss falls below half of its hitpoints
+    Wounded,
+    /// The boss defeats the wizard
+    KillingBlow,
+}
+
+impl TauntTrigger {
+    pub const ALL: [TauntTrigger; 3] = [Self::BattleStart, Self::Wounded, Self::KillingBlow];
+
+    /// Key of the trigger's lines in a `[[boss]]` table
+    pub fn get_key(self) -> &'static str {
+        match self {
+            Self::BattleStart => "taunt_start",
+            Self::Wounded => "taunt_wounded",
+            Self::KillingBlow => "taunt_killing_blow",
+        }
+    }
+
+    /// Lines of bosses that have none of their own for the trigger
+    pub fn get_default_lines(self) -> &'static [&'static str] {
+        match self {
+            Self::BattleStart => &[
+                "Another wizard? Your spells won't save you.",
+                "Come then, conjurer. Let's see what you've got.",
+            ],
+            Self::Wounded => &[
+                "A lucky spell. It won't happen twice.",
+                "You'll pay for that, wizard!",
+            ],
+            Self::KillingBlow => &["Your magic is spent.", "Next!"],
+        }
+    }
+}
+
+/// What a boss says on each trigger, from its bestiary entry. Triggers with no lines use
+/// the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Taunts {
+    pub battle_start: Vec<String>,
+    pub wounded: Vec<String>,
+    pub killing_blow: Vec<String>,
+}
+
+impl Taunts {
+    pub fn get_lines(&self, trigger: TauntTrigger) -> &Vec<String> {
+        match trigger {
+            TauntTrigger::BattleStart => &self.battle_start,
+            TauntTrigger::Wounded => &self.wounded,
+            TauntTrigger::KillingBlow => &self.killing_blow,
+        }
+    }
+
+    fn get_lines_mut(&mut self, trigger: TauntTrigger) -> &mut Vec<String> {
+        match trigger {
+            TauntTrigger::BattleStart => &mut self.battle_start,
+            TauntTrigger::Wounded => &mut self.wounded,
+            TauntTrigger::KillingBlow => &mut self.killing_blow,
+        }
+    }
+
+    /// One of the lines for `trigger`, picked by `round` so a battle says the same thing
+    /// each time it is replayed
+    pub fn choose(&self, trigger: TauntTrigger, round: usize) -> &str {
+        let lines = self.get_lines(trigger);
+        if lines.is_empty() {
+            let defaults = trigger.get_default_lines();
+            defaults[round % defaults.len()]
+        } else {
+            &lines[round % lines.len()]
+        }
+    }
+
+    /// Read the optional taunt keys of a `[[boss]]` table, one line per line of the string
+    pub(crate) fn from_table(table: &Table) -> Result<Self, ParseError> {
+        let mut taunts = Self::default();
+        for trigger in TauntTrigger::ALL {
+            if let Some(lines) = table.get_string(trigger.get_key())? {
+                *taunts.get_lines_mut(trigger) = lines
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+        }
+        Ok(taunts)
+    }
+
+    /// Add the triggers with lines to a `[[boss]]` table
+    pub(crate) fn push_to_table(&self, table: &mut Table) {
+        for trigger in TauntTrigger::ALL {
+            let lines = self.get_lines(trigger);
+            if !lines.is_empty() {
+                table.push(trigger.get_key(), Value::String(lines.join("\n")));
+            }
+        }
+    }
+}
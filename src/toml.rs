@@ -0,0 +1,206 @@
+//! Reader and writer for the small subset of TOML used by content files: arrays of tables
+//! (`[[boss]]`) holding `key = value` pairs, where values are strings, integers or booleans.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(string) => {
+                write!(f, "\"")?;
+                for c in string.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Value::Integer(integer) => write!(f, "{}", integer),
+            Value::Boolean(boolean) => write!(f, "{}", boolean),
+        }
+    }
+}
+
+/// One `[[name]]` table and its key/value pairs, in file order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub name: String,
+    pub entries: Vec<(String, Value)>,
+    /// Line the table header is on, for error messages
+    pub line: usize,
+}
+
+impl Table {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            entries: Vec::new(),
+            line: 0,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn push(&mut self, key: &str, value: Value) {
+        self.entries.push((key.to_string(), value));
+    }
+
+    pub fn get_string(&self, key: &str) -> Result<Option<String>, ParseError> {
+        match self.get(key) {
+            Some(Value::String(string)) => Ok(Some(string.clone())),
+            Some(_) => Err(self.error(format!("`{}` must be a string", key))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_integer(&self, key: &str) -> Result<Option<i64>, ParseError> {
+        match self.get(key) {
+            Some(Value::Integer(integer)) => Ok(Some(*integer)),
+            Some(_) => Err(self.error(format!("`{}` must be an integer", key))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_boolean(&self, key: &str) -> Result<Option<bool>, ParseError> {
+        match self.get(key) {
+            Some(Value::Boolean(boolean)) => Ok(Some(*boolean)),
+            Some(_) => Err(self.error(format!("`{}` must be true or false", key))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: self.line,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line the error is on, 0 if it is about the whole file
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            // Not about any one line, such as a missing table
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn parse(text: &str) -> Result<Vec<Table>, ParseError> {
+    let mut tables: Vec<Table> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let error = |message: &str| ParseError {
+            line: line_number,
+            message: message.to_string(),
+        };
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix("[[").and_then(|l| l.strip_suffix("]]")) {
+            tables.push(Table {
+                name: name.trim().to_string(),
+                entries: Vec::new(),
+                line: line_number,
+            });
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected `key = value` or `[[table]]`"))?;
+        let table = tables
+            .last_mut()
+            .ok_or_else(|| error("key outside of a `[[table]]`"))?;
+        let value = parse_value(value.trim()).ok_or_else(|| error("invalid value"))?;
+        table.entries.push((key.trim().to_string(), value));
+    }
+    Ok(tables)
+}
+
+pub fn write(tables: &[Table]) -> String {
+    let mut text = String::new();
+    for (i, table) in tables.iter().enumerate() {
+        if i != 0 {
+            text.push('\n');
+        }
+        text.push_str(&format!("[[{}]]\n", table.name));
+        for (key, value) in &table.entries {
+            text.push_str(&format!("{} = {}\n", key, value));
+        }
+    }
+    text
+}
+
+/// Remove a trailing `#` comment, ignoring `#` inside strings
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn parse_value(value: &str) -> Option<Value> {
+    if let Some(quoted) = value.strip_prefix('"') {
+        let mut string = String::new();
+        let mut chars = quoted.chars();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => string.push(match chars.next()? {
+                    'n' => '\n',
+                    c @ ('"' | '\\') => c,
+                    _ => return None,
+                }),
+                c => string.push(c),
+            }
+        }
+        // Nothing may follow the closing quote
+        return chars
+            .as_str()
+            .trim()
+            .is_empty()
+            .then_some(Value::String(string));
+    }
+    match value {
+        "true" => Some(Value::Boolean(true)),
+        "false" => Some(Value::Boolean(false)),
+        _ => value.replace('_', "").parse().ok().map(Value::Integer),
+    }
+}
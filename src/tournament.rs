@@ -0,0 +1,310 @@
+use crate::{
+    bestiary::BestiaryEntry,
+    duel::Duel,
+    knowledge::KnowledgeMask,
+    parallel::{run_jobs, Progress},
+    playout::PlayoutStats,
+    rating::{update_ratings, INITIAL_RATING},
+    rng::Rng,
+    strategy::{create_strategy, Observation, Strategy, STRATEGY_NAMES},
+    Battle,
+};
+
+/// Upper bound on duel turns, in case neither strategy can finish the other off
+pub const MAX_DUEL_TURNS: usize = 1000;
+
+/// What a tournament plays
+#[derive(Debug, Clone)]
+pub struct TournamentOptions {
+    /// Every strategy fights each of these bosses
+    pub bosses: Vec<BestiaryEntry>,
+    pub hard_mode: bool,
+    /// Whether the strategies only see the bosses' stats once they learn them
+    pub scouting: bool,
+    /// Games per strategy and boss, and per ordered pair of strategies in duels
+    pub games: u64,
+    pub seed: u64,
+    /// Most a rating changes in one duel
+    pub k_factor: f64,
+    /// Threads the games are played over
+    pub threads: usize,
+}
+
+impl TournamentOptions {
+    /// Battles and duels the tournament plays
+    pub fn get_game_count(&self) -> u64 {
+        let strategies = STRATEGY_NAMES.len() as u64;
+        let pairings = strategies * (strategies - 1);
+        (strategies * self.bosses.len() as u64 + pairings) * self.games
+    }
+
+    /// Battle against boss `boss` of `bosses`, before the wizard's first turn
+    pub fn create_battle(&self, boss: usize) -> Battle {
+        Battle::with_boss(self.hard_mode, self.bosses[boss].create_boss()).with_knowledge(
+            if self.scouting {
+                KnowledgeMask::NONE
+            } else {
+                KnowledgeMask::FULL
+            },
+        )
+    }
+}
+
+/// How one strategy did in a tournament
+#[derive(Debug, Clone)]
+pub struct Standing {
+    pub strategy: &'static str,
+    /// Battles against the bosses
+    pub battles: PlayoutStats,
+    pub duels: u64,
+    pub duel_wins: u64,
+    /// Elo rating from the duels
+    pub rating: f64,
+    /// Rating after each duel, in the order they were played
+    pub rating_history: Vec<f64>,
+}
+
+impl Standing {
+    fn new(strategy: &'static str) -> Self {
+        Self {
+            strategy,
+            battles: PlayoutStats::default(),
+            duels: 0,
+            duel_wins: 0,
+            rating: INITIAL_RATING,
+            rating_history: Vec::new(),
+        }
+    }
+
+    pub fn get_duel_win_rate(&self) -> f64 {
+        if self.duels == 0 {
+            0.0
+        } else {
+            self.duel_wins as f64 / self.duels as f64
+        }
+    }
+
+    /// Average mana used in won battles, None if no battle was won
+    pub fn get_average_mana(&self) -> Option<f64> {
+        (self.battles.wins > 0)
+            .then(|| self.battles.mana_used_in_wins as f64 / self.battles.wins as f64)
+    }
+
+    /// Share of all battles and duels won
+    pub fn get_overall_win_rate(&self) -> f64 {
+        let games = self.battles.playouts + self.duels;
+        if games == 0 {
+            0.0
+        } else {
+            (self.battles.wins + self.duel_wins) as f64 / games as f64
+        }
+    }
+}
+
+/// Play `battle`, from the start of the wizard's turn, to the end with `strategy` choosing
+/// the spells. The wizard loses if the strategy chooses no spell or one that can't be cast.
+/// Returns whether the wizard won.
+pub fn play_battle(battle: &mut Battle, strategy: &mut dyn Strategy) -> bool {
+    loop {
+        if let Some(won) = battle.get_outcome() {
+            return won;
+        }
+        let Some(spell) = strategy.choose_spell(&Observation::from_battle(battle)) else {
+            return false;
+        };
+        if battle.play_round(&spell).is_err() {
+            return false;
+        }
+    }
+}
+
+/// Battle of a strategy against a boss in a tournament
+#[derive(Debug, Clone)]
+pub struct TournamentBattle {
+    pub strategy: &'static str,
+    /// Index of the boss in `TournamentOptions::bosses`
+    pub boss: usize,
+    /// Seed of the strategy
+    pub seed: u64,
+    pub won: bool,
+    pub mana_used: i32,
+    /// Spells cast
+    pub rounds: u32,
+}
+
+/// Game of a tournament, with the seeds of the strategies playing it
+enum Game {
+    Battle {
+        standing: usize,
+        boss: usize,
+        start: Box<Battle>,
+        seed: u64,
+    },
+    Duel {
+        first: usize,
+        second: usize,
+        seeds: [u64; 2],
+    },
+}
+
+/// How a game of a tournament ended
+enum GameResult {
+    Battle(TournamentBattle),
+    /// Side that won, None for a draw
+    Duel(Option<usize>),
+}
+
+impl Game {
+    fn play(self) -> GameResult {
+        match self {
+            Self::Battle {
+                standing,
+                boss,
+                mut start,
+                seed,
+            } => {
+                let mut strategy = create_strategy(STRATEGY_NAMES[standing], seed).unwrap();
+                start.wizard_turn_apply_effects();
+                let won = play_battle(&mut start, strategy.as_mut());
+                GameResult::Battle(TournamentBattle {
+                    strategy: STRATEGY_NAMES[standing],
+                    boss,
+                    seed,
+                    won,
+                    mana_used: start.get_mana_used(),
+                    rounds: start.get_spells_used().len() as u32,
+                })
+            }
+            Self::Duel {
+                first,
+                second,
+                seeds,
+            } => {
+                let mut strategies = [
+                    create_strategy(STRATEGY_NAMES[first], seeds[0]).unwrap(),
+                    create_strategy(STRATEGY_NAMES[second], seeds[1]).unwrap(),
+                ];
+                let mut duel = Duel::new();
+                for _ in 0..MAX_DUEL_TURNS {
+                    let side = duel.get_turn();
+                    if duel.play_turn(strategies[side].as_mut()).1.is_some() {
+                        break;
+                    }
+                }
+                GameResult::Duel(duel.get_winner())
+            }
+        }
+    }
+}
+
+/// Pit every built-in strategy against every boss, and against each other in duels with
+/// both strategies taking the first turn, rating them by the duels. The games are played
+/// over `options.threads` threads and `progress` counts them; if it is cancelled the
+/// standings are from the games finished so far. Returns the standings, best first.
+pub fn run_tournament(options: &TournamentOptions, progress: &Progress) -> Vec<Standing> {
+    play_tournament(options, progress).0
+}
+
+/// Run a tournament like `run_tournament`, also returning each finished battle against a
+/// boss in the order they were played
+pub fn play_tournament(
+    options: &TournamentOptions,
+    progress: &Progress,
+) -> (Vec<Standing>, Vec<TournamentBattle>) {
+    // Seeds are drawn in the order the games are rated, so the standings don't depend on
+    // the number of threads
+    let mut rng = Rng::new(options.seed);
+    let mut games = Vec::new();
+    for standing in 0..STRATEGY_NAMES.len() {
+        for boss in 0..options.bosses.len() {
+            let start = options.create_battle(boss);
+            for _ in 0..options.games {
+                games.push(Game::Battle {
+                    standing,
+                    boss,
+                    start: Box::new(start.clone()),
+                    seed: rng.next_u64(),
+                });
+            }
+        }
+    }
+    for first in 0..STRATEGY_NAMES.len() {
+        for second in (0..STRATEGY_NAMES.len()).filter(|&second| second != first) {
+            for _ in 0..options.games {
+                games.push(Game::Duel {
+                    first,
+                    second,
+                    seeds: [rng.next_u64(), rng.next_u64()],
+                });
+            }
+        }
+    }
+    let pairings: Vec<(usize, usize)> = games
+        .iter()
+        .map(|game| match game {
+            Game::Battle { standing, .. } => (*standing, *standing),
+            Game::Duel { first, second, .. } => (*first, *second),
+        })
+        .collect();
+    let results = run_jobs(games, options.threads, progress, |game| {
+        let result = game.play();
+        progress.advance(1);
+        result
+    });
+
+    let mut standings: Vec<Standing> = STRATEGY_NAMES
+        .iter()
+        .map(|name| Standing::new(name))
+        .collect();
+    let mut battles = Vec::new();
+    for ((first, second), result) in pairings.into_iter().zip(results) {
+        match result {
+            None => (),
+            Some(GameResult::Battle(battle)) => {
+                let standing = &mut standings[first];
+                standing.battles.playouts += 1;
+                if battle.won {
+                    standing.battles.wins += 1;
+                    standing.battles.mana_used_in_wins += battle.mana_used as u64;
+                }
+                battles.push(battle);
+            }
+            Some(GameResult::Duel(winner)) => {
+                standings[first].duels += 1;
+                standings[second].duels += 1;
+                let score = match winner {
+                    Some(0) => {
+                        standings[first].duel_wins += 1;
+                        1.0
+                    }
+                    Some(_) => {
+                        standings[second].duel_wins += 1;
+                        0.0
+                    }
+                    None => 0.5,
+                };
+                let (first_rating, second_rating) = update_ratings(
+                    standings[first].rating,
+                    standings[second].rating,
+                    score,
+                    options.k_factor,
+                );
+                standings[first].rating = first_rating;
+                standings[first].rating_history.push(first_rating);
+                standings[second].rating = second_rating;
+                standings[second].rating_history.push(second_rating);
+            }
+        }
+    }
+
+    // Most wins first, then least mana
+    standings.sort_by(|a, b| {
+        b.get_overall_win_rate()
+            .total_cmp(&a.get_overall_win_rate())
+            .then_with(|| match (a.get_average_mana(), b.get_average_mana()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            })
+    });
+    (standings, battles)
+}
@@ -0,0 +1,11 @@
+//! Logging of battle state changes, compiled out unless the `logging` feature is on.
+
+/// Log a change to a battle at trace level
+macro_rules! trace_battle {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "logging")]
+        tracing::trace!(target: "advent_wizard_rpg::battle", $($arg)+);
+    };
+}
+
+pub(crate) use trace_battle;
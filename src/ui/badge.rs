@@ -0,0 +1,56 @@
+//! Short colored label for a status, such as an active effect.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+#[derive(Debug, Clone)]
+pub struct Badge {
+    label: String,
+    color: Color,
+}
+
+impl Badge {
+    pub fn new(label: String, color: Color) -> Self {
+        Self { label, color }
+    }
+
+    /// Columns taken by the label and its padding
+    pub fn get_width(&self) -> u16 {
+        self.label.chars().count() as u16 + 2
+    }
+}
+
+impl Widget for &Badge {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 1 {
+            return;
+        }
+        let style = Style::default().fg(Color::Black).bg(self.color);
+        buf.set_stringn(
+            area.x,
+            area.y,
+            format!(" {} ", self.label),
+            area.width as usize,
+            style,
+        );
+    }
+}
+
+/// Draw `badges` in a centered row, one column apart
+pub fn render_badges(badges: &[Badge], area: Rect, buf: &mut Buffer) {
+    let areas = Layout::horizontal(
+        badges
+            .iter()
+            .map(|badge| Constraint::Length(badge.get_width())),
+    )
+    .flex(Flex::Center)
+    .spacing(1)
+    .split(area);
+    for (badge, badge_area) in badges.iter().zip(areas.iter()) {
+        badge.render(*badge_area, buf);
+    }
+}
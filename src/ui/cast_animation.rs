@@ -0,0 +1,128 @@
+//! Brief ASCII animations of spells being cast, drawn over the bottom line of the event
+//! pane while the round waits for its next phase.
+
+use super::theme;
+use advent_wizard_rpg::Spell;
+use ratatui::{style::Stylize, text::Line};
+use std::time::Duration;
+
+/// How long a cast is animated when the phases of a round run without a delay
+pub const CAST_ANIMATION_DURATION: Duration = Duration::from_millis(600);
+
+/// Columns between the drops of Poison
+const POISON_DROP_SPACING: usize = 6;
+/// Characters a drop of Poison goes through as it falls
+const POISON_DROP_FRAMES: [char; 4] = ['\'', ':', '.', ' '];
+
+/// Animation of a spell, played once over `duration`
+#[derive(Debug, Clone)]
+pub struct CastAnimation {
+    spell: Spell,
+    duration: Duration,
+    elapsed: Duration,
+}
+
+impl CastAnimation {
+    pub fn new(spell: Spell, duration: Duration) -> Self {
+        Self {
+            spell,
+            duration,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.elapsed += elapsed;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Share of the animation played, between 0 and 1
+    fn get_progress(&self) -> f64 {
+        if self.duration.is_zero() {
+            1.0
+        } else {
+            (self.elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+        }
+    }
+
+    /// The current frame, `width` columns wide
+    pub fn render_line(&self, width: usize) -> Line<'static> {
+        let theme = theme::get();
+        let progress = self.get_progress();
+        // Column the head of a moving spell has reached
+        let head = (progress * width.saturating_sub(1) as f64).round() as usize;
+        match self.spell {
+            // A streak of stars flying at the boss
+            Spell::MagicMissile => {
+                let streak = ['·', '-', '=', '*'];
+                // The tail is cut off at the left edge
+                let tail = head.min(streak.len() - 1);
+                let line: String = " ".repeat(head - tail)
+                    + &streak[streak.len() - 1 - tail..].iter().collect::<String>();
+                Line::from(line).fg(theme.accent)
+            }
+            // Life flowing back from the boss to the wizard
+            Spell::Drain => {
+                let position = width.saturating_sub(1) - head;
+                Line::from(format!("{}<~~", " ".repeat(position))).fg(theme.wizard)
+            }
+            // A wall of Shield building up
+            Spell::Shield => {
+                let symbols = (head + 2) / 2;
+                let wall = vec![theme::SHIELD_SYMBOL.to_string(); symbols].join(" ");
+                Line::from(wall).fg(theme.shield)
+            }
+            // Drops falling along the pane
+            Spell::Poison => {
+                let frame = (progress * POISON_DROP_FRAMES.len() as f64) as usize;
+                let drops: String = (0..width)
+                    .map(|column| {
+                        // Neighbouring drops fall a frame apart
+                        let offset = column / POISON_DROP_SPACING % 2;
+                        if column % POISON_DROP_SPACING == 0 {
+                            POISON_DROP_FRAMES[(frame + offset).min(POISON_DROP_FRAMES.len() - 1)]
+                        } else {
+                            ' '
+                        }
+                    })
+                    .collect();
+                Line::from(drops).fg(theme.poison)
+            }
+            // A bar of mana charging up
+            Spell::Recharge => {
+                let inner_width = width.saturating_sub(4);
+                let filled = (progress * inner_width as f64).round() as usize;
+                Line::from(format!(
+                    "{} [{}{}]",
+                    theme::RECHARGE_SYMBOL,
+                    "█".repeat(filled),
+                    "░".repeat(inner_width - filled)
+                ))
+                .fg(theme.recharge)
+            }
+            // A wave washing back over the wizard, leaving sparkles behind
+            Spell::Cleanse => {
+                let position = width.saturating_sub(1) - head;
+                Line::from(format!("{}){}", " ".repeat(position), "·".repeat(head)))
+                    .fg(theme.accent)
+            }
+            // Thorns sprouting out from the wizard
+            Spell::Thorns => {
+                let symbols = (head + 2) / 2;
+                let thorns = vec![theme::THORNS_SYMBOL.to_string(); symbols].join(" ");
+                Line::from(thorns).fg(theme.wizard)
+            }
+            // The familiar flitting out towards the boss
+            Spell::Summon => Line::from(format!(
+                "{}~{}",
+                " ".repeat(head.saturating_sub(1)),
+                theme::FAMILIAR_SYMBOL
+            ))
+            .fg(theme.wizard),
+            _ => Line::default(),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! Custom widget for centered text.
 //! Source: https://github.com/fdehau/tui-rs/issues/396#issuecomment-1430447664
 
+use super::theme;
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -32,7 +33,10 @@ impl<'a> Widget for CenterPosition<'a> {
         }
 
         let style = if self.unavailable {
-            Style::default().bold().crossed_out().red()
+            Style::default()
+                .bold()
+                .crossed_out()
+                .fg(theme::get().unavailable)
         } else {
             Style::default().bold()
         };
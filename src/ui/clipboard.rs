@@ -0,0 +1,55 @@
+//! Copying text to the system clipboard. Without the `clipboard` feature, the text is
+//! printed once the terminal is restored, to copy by hand.
+
+#[cfg(feature = "clipboard")]
+use super::encode_base64;
+#[cfg(feature = "clipboard")]
+use arboard::Clipboard;
+#[cfg(feature = "clipboard")]
+use ratatui::crossterm::{execute, style::Print};
+#[cfg(feature = "clipboard")]
+use std::io::stdout;
+use std::{io, sync::Mutex};
+
+/// Text that couldn't be copied, printed when the terminal is restored
+static UNCOPIED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// The system clipboard, kept open while the game runs as on X11 the copied text is gone
+/// once it's closed
+#[cfg(feature = "clipboard")]
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+/// Copy `text` to the system clipboard, or with the OSC 52 escape sequence, which most
+/// terminals support, where there is none, such as over SSH. Returns false if clipboard
+/// support isn't built, in which case the text is printed when the game exits instead.
+pub fn copy_to_clipboard(text: &str) -> io::Result<bool> {
+    #[cfg(feature = "clipboard")]
+    {
+        let mut clipboard = CLIPBOARD.lock().unwrap();
+        if clipboard.is_none() {
+            *clipboard = Clipboard::new().ok();
+        }
+        let copied = clipboard
+            .as_mut()
+            .is_some_and(|clipboard| clipboard.set_text(text).is_ok());
+        if !copied {
+            execute!(
+                stdout(),
+                Print(format!("\x1b]52;c;{}\x07", encode_base64(text.as_bytes())))
+            )?;
+        }
+        Ok(true)
+    }
+    #[cfg(not(feature = "clipboard"))]
+    {
+        UNCOPIED.lock().unwrap().push(text.to_string());
+        Ok(false)
+    }
+}
+
+/// Print the text that couldn't be copied, for the user to copy by hand
+pub fn print_uncopied() {
+    for text in UNCOPIED.lock().unwrap().drain(..) {
+        println!("{}\n", text);
+    }
+}
@@ -0,0 +1,94 @@
+//! Transient effects on big moments of a battle: shaking the screen and flashing a panel
+//! for a few ticks, applied while drawing.
+
+use super::theme;
+use ratatui::layout::Rect;
+use std::time::Duration;
+
+/// Length of a tick of an effect
+pub const EFFECT_TICK: Duration = Duration::from_millis(50);
+/// Ticks the screen shakes for on a big hit
+const SHAKE_TICKS: u32 = 6;
+/// Ticks the boss panel flashes for on the killing blow
+const FLASH_TICKS: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransientEffect {
+    /// Offset the whole layout by a cell, back and forth
+    Shake,
+    /// Invert the colors of the boss panel
+    BossFlash,
+}
+
+impl TransientEffect {
+    /// Whether the effect is turned on, see `theme::set_effects`
+    fn is_enabled(self) -> bool {
+        let flags = theme::get_effects();
+        match self {
+            Self::Shake => flags.shake,
+            Self::BossFlash => flags.flash,
+        }
+    }
+
+    fn get_ticks(self) -> u32 {
+        match self {
+            Self::Shake => SHAKE_TICKS,
+            Self::BossFlash => FLASH_TICKS,
+        }
+    }
+}
+
+/// Effects being played, each with the time it has played for
+#[derive(Debug, Default)]
+pub struct TransientEffects {
+    active: Vec<(TransientEffect, Duration)>,
+}
+
+impl TransientEffects {
+    /// Play `effect` from the start, if it is turned on
+    pub fn trigger(&mut self, effect: TransientEffect) {
+        if !effect.is_enabled() {
+            return;
+        }
+        self.active.retain(|(active, _)| *active != effect);
+        self.active.push((effect, Duration::ZERO));
+    }
+
+    pub fn is_active(&self, effect: TransientEffect) -> bool {
+        self.active.iter().any(|(active, _)| *active == effect)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Play the effects for `elapsed`, ending those that have played all their ticks
+    pub fn advance(&mut self, elapsed: Duration) {
+        for (_, played) in &mut self.active {
+            *played += elapsed;
+        }
+        self.active
+            .retain(|(effect, played)| *played < EFFECT_TICK * effect.get_ticks());
+    }
+
+    /// `area` offset a cell to the right on even ticks of a shake and to the left on odd
+    /// ones, losing a column to make room
+    pub fn shake(&self, area: Rect) -> Rect {
+        let Some((_, played)) = self
+            .active
+            .iter()
+            .find(|(effect, _)| *effect == TransientEffect::Shake)
+        else {
+            return area;
+        };
+        if area.width < 2 {
+            return area;
+        }
+        let tick = played.as_millis() / EFFECT_TICK.as_millis();
+        Rect {
+            x: area.x + u16::from(tick.is_multiple_of(2)),
+            width: area.width - 1,
+            ..area
+        }
+    }
+}
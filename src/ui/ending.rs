@@ -0,0 +1,114 @@
+//! Sequences played when a battle ends, before the game over screen: confetti falling for
+//! a victory and the colors draining from the screen for a defeat. Both are applied over
+//! the drawn screen.
+
+use super::theme;
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+use std::time::Duration;
+
+/// How long an ending plays for
+const ENDING_DURATION: Duration = Duration::from_millis(1500);
+/// Time for confetti to fall a row at its slowest
+const CONFETTI_ROW_DURATION: Duration = Duration::from_millis(60);
+/// Shapes of the pieces of confetti
+const CONFETTI_SYMBOLS: [&str; 5] = ["*", "+", "•", "o", "~"];
+
+/// Ending of a battle, played once
+#[derive(Debug, Clone)]
+pub struct EndingCinematic {
+    won: bool,
+    elapsed: Duration,
+}
+
+impl EndingCinematic {
+    pub fn new(won: bool) -> Self {
+        Self {
+            won,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.elapsed += elapsed;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= ENDING_DURATION
+    }
+
+    /// Share of the ending played, between 0 and 1
+    fn get_progress(&self) -> f64 {
+        (self.elapsed.as_secs_f64() / ENDING_DURATION.as_secs_f64()).min(1.0)
+    }
+
+    /// Apply the current frame to what has been drawn in `area`
+    pub fn apply(&self, area: Rect, buf: &mut Buffer) {
+        if self.won {
+            self.drop_confetti(area, buf);
+        } else {
+            self.desaturate(area, buf);
+        }
+    }
+
+    /// Draw a piece of confetti in about every third column, each setting off at its own
+    /// time and speed
+    fn drop_confetti(&self, area: Rect, buf: &mut Buffer) {
+        let theme = theme::get();
+        let colors = [
+            theme.accent,
+            theme.wizard,
+            theme.shield,
+            theme.poison,
+            theme.recharge,
+        ];
+        let rows_fallen = (self.elapsed.as_millis() / CONFETTI_ROW_DURATION.as_millis()) as u64;
+        for column in 0..area.width {
+            // Scatter the pieces by hashing their column
+            let hash = u64::from(column).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32;
+            if !hash.is_multiple_of(3) {
+                continue;
+            }
+            let start = hash % u64::from(area.height / 2 + 1);
+            let speed = 1 + hash / 7 % 2;
+            let Some(row) = (rows_fallen * speed).checked_sub(start) else {
+                continue;
+            };
+            if row >= u64::from(area.height) {
+                continue;
+            }
+            let piece = (hash / 3) as usize;
+            buf[(area.x + column, area.y + row as u16)]
+                .set_symbol(CONFETTI_SYMBOLS[piece % CONFETTI_SYMBOLS.len()])
+                .set_fg(colors[piece / 5 % colors.len()]);
+        }
+    }
+
+    /// Fade every color towards gray as the ending plays
+    fn desaturate(&self, area: Rect, buf: &mut Buffer) {
+        let progress = self.get_progress();
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = &mut buf[(x, y)];
+                cell.fg = desaturate_color(cell.fg, progress);
+                cell.bg = desaturate_color(cell.bg, progress);
+            }
+        }
+    }
+}
+
+/// `color` a share `progress` of the way to the gray of the same lightness. Named colors
+/// have no channels to fade, so turn gray halfway through.
+fn desaturate_color(color: Color, progress: f64) -> Color {
+    match color {
+        Color::Reset | Color::Black => color,
+        Color::Rgb(r, g, b) => {
+            let gray = 0.3 * f64::from(r) + 0.59 * f64::from(g) + 0.11 * f64::from(b);
+            let fade = |channel: u8| {
+                (f64::from(channel) + (gray - f64::from(channel)) * progress).round() as u8
+            };
+            Color::Rgb(fade(r), fade(g), fade(b))
+        }
+        _ if progress >= 0.5 => Color::DarkGray,
+        _ => color,
+    }
+}
@@ -0,0 +1,186 @@
+//! Directory browser for choosing a file, with a preview of the selected file.
+
+use super::{theme, tui};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListState, Paragraph, Wrap},
+    Frame,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+pub struct FilePicker {
+    dir: PathBuf,
+    /// Only files whose name ends with this are listed
+    suffix: String,
+    /// Subdirectories then matching files, sorted by name. The first entry is the parent
+    /// directory if there is one.
+    entries: Vec<PathBuf>,
+    list_state: ListState,
+}
+
+impl FilePicker {
+    pub fn new(dir: &Path, suffix: &str) -> io::Result<Self> {
+        let mut picker = Self {
+            dir: PathBuf::new(),
+            suffix: suffix.to_string(),
+            entries: Vec::new(),
+            list_state: ListState::default(),
+        };
+        picker.open_dir(dir)?;
+        Ok(picker)
+    }
+
+    pub fn get_selected(&self) -> Option<&Path> {
+        self.list_state
+            .selected()
+            .and_then(|selected| self.entries.get(selected))
+            .map(PathBuf::as_path)
+    }
+
+    fn open_dir(&mut self, dir: &Path) -> io::Result<()> {
+        let dir = dir.canonicalize()?;
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().ends_with(&self.suffix))
+            {
+                files.push(path);
+            }
+        }
+        dirs.sort();
+        files.sort();
+
+        self.entries = dir.parent().map(Path::to_path_buf).into_iter().collect();
+        self.entries.extend(dirs);
+        self.entries.extend(files);
+        self.dir = dir;
+        self.list_state
+            .select((!self.entries.is_empty()).then_some(0));
+        Ok(())
+    }
+
+    /// Move through the listing, staying put if a directory can't be read. Returns the
+    /// chosen file once one is picked.
+    pub fn handle_key_event(&mut self, key: KeyCode) -> Option<PathBuf> {
+        match key {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Backspace => {
+                if let Some(parent) = self.dir.parent().map(Path::to_path_buf) {
+                    self.open_dir(&parent).ok();
+                }
+            }
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => {
+                if let Some(selected) = self.get_selected().map(Path::to_path_buf) {
+                    if selected.is_dir() {
+                        self.open_dir(&selected).ok();
+                    } else {
+                        return Some(selected);
+                    }
+                }
+            }
+            _ => (),
+        }
+        None
+    }
+
+    /// Draw the listing on the left of `area` and `preview` of the selected file on the
+    /// right
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect, preview: &dyn Fn(&Path) -> String) {
+        let columns = Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(area);
+
+        let has_parent = self.dir.parent().is_some();
+        let names: Vec<String> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let name = path
+                    .file_name()
+                    .map_or(String::new(), |name| name.to_string_lossy().to_string());
+                if i == 0 && has_parent {
+                    "../".to_string()
+                } else if path.is_dir() {
+                    format!("{}/", name)
+                } else {
+                    name
+                }
+            })
+            .collect();
+        let list = List::new(names)
+            .gray()
+            .highlight_style(Style::default().fg(theme::get().accent).bold())
+            .highlight_symbol("> ")
+            .block(
+                Block::bordered()
+                    .border_style(Style::default().fg(theme::get().wizard))
+                    .title(self.dir.display().to_string().bold().gray())
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_stateful_widget(list, columns[0], &mut self.list_state);
+
+        let text = match self.get_selected() {
+            Some(path) if path.is_file() => preview(path),
+            _ => String::new(),
+        };
+        let preview = Paragraph::new(text).gray().wrap(Wrap::default()).block(
+            Block::bordered()
+                .gray()
+                .title("Preview".bold())
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(preview, columns[1]);
+    }
+}
+
+/// Let the user browse from `dir` for a file ending with `suffix`. Returns None if they
+/// quit without picking one.
+pub fn pick_file(
+    terminal: &mut tui::Tui,
+    title: &str,
+    dir: &Path,
+    suffix: &str,
+    preview: &dyn Fn(&Path) -> String,
+) -> io::Result<Option<PathBuf>> {
+    let mut picker = FilePicker::new(dir, suffix)?;
+    loop {
+        terminal.draw(|frame| {
+            let chunks = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(frame.area());
+            let title = Block::new()
+                .title_alignment(Alignment::Center)
+                .title(title.bold());
+            frame.render_widget(title, chunks[0]);
+            picker.draw(frame, chunks[1], preview);
+            let help = "j/k: select  l/Enter: open  h/Backspace: up  q: cancel";
+            frame.render_widget(Line::from(help).dark_gray(), chunks[2]);
+        })?;
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                code => {
+                    if let Some(path) = picker.handle_key_event(code) {
+                        return Ok(Some(path));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Sprites shown as images on terminals with the kitty or iTerm2 graphics protocol.
+
+use super::{
+    encode_base64,
+    sprite::{Sprite, SPRITE_HEIGHT, SPRITE_WIDTH},
+    tui::{self, GraphicsProtocol},
+};
+use ratatui::{
+    crossterm::{cursor::MoveTo, queue},
+    layout::Rect,
+};
+use std::io::{self, Write};
+
+/// Image pixels per sprite pixel
+const IMAGE_SCALE: usize = 8;
+/// Largest piece of an image sent in one kitty escape sequence
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Sprites shown as images, so they are only sent again when they move
+#[derive(Debug)]
+pub struct SpriteImages {
+    protocol: GraphicsProtocol,
+    /// Base64 PNG of each sprite
+    images: Vec<String>,
+    /// Where each sprite is shown
+    shown: Vec<Option<Rect>>,
+}
+
+impl SpriteImages {
+    pub fn new(protocol: GraphicsProtocol, sprites: &[&Sprite]) -> Self {
+        Self {
+            protocol,
+            images: sprites
+                .iter()
+                .map(|sprite| encode_base64(&encode_png(sprite)))
+                .collect(),
+            shown: vec![None; sprites.len()],
+        }
+    }
+
+    /// Show each sprite in its area, or hide it if the area is None. Returns true if the
+    /// screen must be redrawn before the sprites can be shown.
+    pub fn update(&mut self, terminal: &mut tui::Tui, areas: &[Option<Rect>]) -> io::Result<bool> {
+        if self.shown == areas {
+            return Ok(false);
+        }
+        let moved = self
+            .shown
+            .iter()
+            .zip(areas)
+            .any(|(shown, area)| shown.is_some() && shown != area);
+        if moved {
+            match self.protocol {
+                GraphicsProtocol::Kitty => self.clear(terminal)?,
+                // Inline images are part of the cells, so redraw every cell
+                GraphicsProtocol::Iterm2 => {
+                    terminal.clear()?;
+                    self.shown.fill(None);
+                    return Ok(true);
+                }
+            }
+        }
+
+        let out = terminal.backend_mut();
+        for (i, area) in areas.iter().enumerate() {
+            let Some(area) = area.filter(|_| self.shown[i].is_none()) else {
+                continue;
+            };
+            queue!(out, MoveTo(area.x, area.y))?;
+            match self.protocol {
+                GraphicsProtocol::Kitty => write_kitty_image(out, i + 1, &self.images[i])?,
+                GraphicsProtocol::Iterm2 => write!(
+                    out,
+                    "\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=0:{}\x07",
+                    SPRITE_WIDTH, SPRITE_HEIGHT, self.images[i]
+                )?,
+            }
+        }
+        out.flush()?;
+        self.shown = areas.to_vec();
+        Ok(false)
+    }
+
+    /// Remove the kitty images. Inline images go away with the cells they are in.
+    pub fn clear(&mut self, terminal: &mut tui::Tui) -> io::Result<()> {
+        if self.protocol == GraphicsProtocol::Kitty {
+            let out = terminal.backend_mut();
+            write!(out, "\x1b_Ga=d,q=2\x1b\\")?;
+            out.flush()?;
+        }
+        self.shown.fill(None);
+        Ok(())
+    }
+}
+
+/// Send a PNG to kitty as image `id`, placed at the cursor
+fn write_kitty_image(out: &mut impl Write, id: usize, base64: &str) -> io::Result<()> {
+    let chunks: Vec<&[u8]> = base64.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            // Don't move the cursor, and don't reply
+            write!(
+                out,
+                "\x1b_Ga=T,f=100,i={},c={},r={},C=1,q=2,m={};",
+                id, SPRITE_WIDTH, SPRITE_HEIGHT, more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// RGBA PNG of `sprite`, scaled up by `IMAGE_SCALE`, compressed with stored deflate blocks
+fn encode_png(sprite: &Sprite) -> Vec<u8> {
+    let width = sprite.rows[0].len() * IMAGE_SCALE;
+    let height = sprite.rows.len() * IMAGE_SCALE;
+    let mut pixels = Vec::with_capacity((width * 4 + 1) * height);
+    for y in 0..height {
+        // Filter type: none
+        pixels.push(0);
+        for x in 0..width {
+            match sprite.get_pixel(x / IMAGE_SCALE, y / IMAGE_SCALE) {
+                Some([r, g, b]) => pixels.extend([r, g, b, 255]),
+                None => pixels.extend([0, 0, 0, 0]),
+            }
+        }
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = pixels.chunks(u16::MAX as usize).collect();
+    for (i, block) in blocks.iter().enumerate() {
+        let len = block.len() as u16;
+        zlib.push((i + 1 == blocks.len()) as u8);
+        zlib.extend(len.to_le_bytes());
+        zlib.extend((!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend(adler32(&pixels).to_be_bytes());
+
+    let mut header = Vec::new();
+    header.extend((width as u32).to_be_bytes());
+    header.extend((height as u32).to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, no filtering, not interlaced
+    header.extend([8, 6, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, data) in [(b"IHDR", header), (b"IDAT", zlib), (b"IEND", Vec::new())] {
+        png.extend((data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(kind);
+        png.extend(data);
+        let crc = crc32(&png[start..]);
+        png.extend(crc.to_be_bytes());
+    }
+    png
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0_u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1_u32, 0_u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
@@ -0,0 +1,128 @@
+//! Intro played when a battle starts: the boss's banner slides in, its stats are revealed
+//! a line at a time, then "FIGHT!".
+
+use super::theme;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Flex, Layout, Margin, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget},
+};
+use std::time::Duration;
+
+/// How long the banner takes to slide in
+const BANNER_SLIDE_DURATION: Duration = Duration::from_millis(500);
+/// Time between the stat lines being revealed
+const STAT_LINE_DURATION: Duration = Duration::from_millis(300);
+/// How long "FIGHT!" is shown before the battle starts
+const FIGHT_DURATION: Duration = Duration::from_millis(800);
+
+/// Intro of a battle, played once
+#[derive(Debug, Clone)]
+pub struct BattleIntro {
+    banner: Vec<String>,
+    stats: Vec<String>,
+    elapsed: Duration,
+}
+
+impl BattleIntro {
+    pub fn new(banner: Vec<String>, stats: Vec<String>) -> Self {
+        Self {
+            banner,
+            stats,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.elapsed += elapsed;
+    }
+
+    /// When the stats have all been revealed and "FIGHT!" is shown
+    fn get_fight_start(&self) -> Duration {
+        BANNER_SLIDE_DURATION + STAT_LINE_DURATION * self.stats.len() as u32
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.get_fight_start() + FIGHT_DURATION
+    }
+
+    /// Columns the banner has yet to slide in from the right, out of `width`
+    fn get_banner_offset(&self, width: u16) -> u16 {
+        let progress = (self.elapsed.as_secs_f64() / BANNER_SLIDE_DURATION.as_secs_f64()).min(1.0);
+        ((1.0 - progress) * f64::from(width)).round() as u16
+    }
+
+    fn get_stat_lines_shown(&self) -> usize {
+        let Some(revealing) = self.elapsed.checked_sub(BANNER_SLIDE_DURATION) else {
+            return 0;
+        };
+        // A line is revealed at the start of its interval
+        let shown = (revealing.as_millis() / STAT_LINE_DURATION.as_millis()) as usize + 1;
+        shown.min(self.stats.len())
+    }
+}
+
+impl Widget for &BattleIntro {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let theme = theme::get();
+        let block = Block::bordered()
+            .border_style(Style::default().fg(theme.boss))
+            .title_bottom(Line::from("Press any key to skip").gray().centered());
+        let inner_area = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let stats_height = self.stats.len() as u16;
+        let [banner_area, stats_area, fight_area] = Layout::vertical([
+            Constraint::Length(self.banner.len() as u16),
+            Constraint::Length(stats_height + 2),
+            Constraint::Length(1),
+        ])
+        .flex(Flex::Center)
+        .areas(inner_area);
+
+        // The banner is centered once it has slid in
+        let offset = self.get_banner_offset(banner_area.width);
+        let banner_area = Rect {
+            x: banner_area.x + offset,
+            width: banner_area.width - offset,
+            ..banner_area
+        };
+        let banner_width = self
+            .banner
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let left_padding =
+            " ".repeat(usize::from(inner_area.width).saturating_sub(banner_width) / 2);
+        let banner: Vec<Line> = self
+            .banner
+            .iter()
+            .map(|line| Line::from(format!("{}{}", left_padding, line)))
+            .collect();
+        Paragraph::new(banner)
+            .fg(theme.boss)
+            .bold()
+            .render(banner_area, buf);
+
+        let stats: Vec<Line> = self.stats[..self.get_stat_lines_shown()]
+            .iter()
+            .map(|line| Line::from(line.as_str()))
+            .collect();
+        Paragraph::new(stats)
+            .gray()
+            .alignment(Alignment::Center)
+            .render(stats_area.inner(Margin::new(0, 1)), buf);
+
+        if self.elapsed >= self.get_fight_start() {
+            Line::from("FIGHT!")
+                .fg(theme.accent)
+                .bold()
+                .centered()
+                .render(fight_area, buf);
+        }
+    }
+}
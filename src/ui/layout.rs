@@ -0,0 +1,234 @@
+//! Layouts of the battle screen. The battle screen asks the layout for the terminal's size
+//! where each of its parts goes, then draws the parts it was given room for.
+
+use super::SelectionGeometry;
+use advent_wizard_rpg::Spell;
+use ratatui::layout::{Constraint, Layout, Rect};
+
+/// Terminals narrower than this get the compact layout
+const COMPACT_WIDTH: u16 = 96;
+/// Terminals shorter than this get the compact layout
+const COMPACT_HEIGHT: u16 = 28;
+/// Indexes of the spells on each row of buttons of the grid. Recharge and Summon Familiar
+/// span their rows.
+const BUTTON_ROWS: [&[usize]; 5] = [&[0, 1], &[2, 3], &[4], &[5, 6], &[7]];
+
+/// How the spell buttons of the full layout are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpellArrangement {
+    /// Rows of two buttons under the game screen
+    #[default]
+    Grid,
+    /// A single row of short buttons, moved along with left and right
+    Bar,
+}
+
+impl SpellArrangement {
+    pub const ALL: [SpellArrangement; 2] = [SpellArrangement::Grid, SpellArrangement::Bar];
+
+    pub fn get_key(&self) -> &'static str {
+        match self {
+            SpellArrangement::Grid => "grid",
+            SpellArrangement::Bar => "bar",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|arrangement| arrangement.get_key().eq_ignore_ascii_case(key))
+    }
+}
+
+/// How a spell button is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonStyle {
+    /// Bordered, with the spell's name, mana and why it can't be cast
+    Full,
+    /// Bordered, with just the spell's key, initials and mana
+    Short,
+    /// A single line with just the spell's key, initials and mana, reversed if selected
+    Bare,
+}
+
+/// Where each part of the battle screen goes
+#[derive(Debug, Clone)]
+pub struct BattleAreas {
+    pub title: Rect,
+    /// Status strip of bars and badges
+    pub status: Rect,
+    pub events: Rect,
+    /// Hitpoint and mana graphs under the events, None if left out
+    pub stats: Option<Rect>,
+    /// Side panels of the wizard and the boss, None if left out
+    pub panels: Option<[Rect; 2]>,
+    /// Button of each spell shown, in reading order
+    pub spell_buttons: Vec<(Spell, Rect)>,
+    pub button_style: ButtonStyle,
+    /// How the selection moves between the buttons
+    pub selection: SelectionGeometry,
+    /// Area covered by popups, such as the intro and the game over screen
+    pub battle: Rect,
+    /// Area covered by overlays of the events, such as the forecast
+    pub overlays: Rect,
+}
+
+/// A way of laying out the battle screen
+pub trait BattleLayout {
+    /// Lay the battle screen out in `area`, with buttons for `spells`, which are the five
+    /// spells of the puzzle and the extra spells after them if they are shown
+    fn split(&self, area: Rect, spells: &[Spell]) -> BattleAreas;
+}
+
+/// Buttons for `spells` side by side in `bar`
+fn split_bar(bar: Rect, spells: &[Spell]) -> Vec<(Spell, Rect)> {
+    let columns = Layout::horizontal(vec![
+        Constraint::Ratio(1, spells.len().max(1) as u32);
+        spells.len()
+    ])
+    .split(bar);
+    spells
+        .iter()
+        .cloned()
+        .zip(columns.iter().copied())
+        .collect()
+}
+
+/// The wizard and boss panels either side of the events, over the spell buttons
+#[derive(Debug, Default)]
+pub struct FullLayout {
+    pub arrangement: SpellArrangement,
+}
+
+impl FullLayout {
+    /// Buttons for `spells` in the rows of `BUTTON_ROWS`, splitting `area` between the rows
+    /// with a button
+    fn split_grid(area: Rect, spells: &[Spell]) -> Vec<(Spell, Rect)> {
+        let rows: Vec<&[usize]> = BUTTON_ROWS
+            .into_iter()
+            .filter(|row| row.iter().any(|index| *index < spells.len()))
+            .collect();
+        let row_areas =
+            Layout::vertical(vec![Constraint::Ratio(1, rows.len() as u32); rows.len()]).split(area);
+        let mut spell_buttons = Vec::new();
+        for (row, indexes) in row_areas.iter().zip(rows) {
+            let columns = Layout::horizontal(vec![
+                Constraint::Ratio(1, indexes.len() as u32);
+                indexes.len()
+            ])
+            .split(*row);
+            for (index, column) in indexes.iter().zip(columns.iter()) {
+                if let Some(spell) = spells.get(*index) {
+                    spell_buttons.push((spell.clone(), *column));
+                }
+            }
+        }
+        spell_buttons
+    }
+}
+
+impl BattleLayout for FullLayout {
+    fn split(&self, area: Rect, spells: &[Spell]) -> BattleAreas {
+        // Extra spells get two rows of buttons, taken from the game screen. The bar only
+        // takes a row of buttons.
+        let (game_screen, buttons) = match self.arrangement {
+            SpellArrangement::Grid if spells.len() > 5 => {
+                (Constraint::Percentage(50), Constraint::Percentage(50))
+            }
+            SpellArrangement::Grid => (Constraint::Percentage(70), Constraint::Percentage(30)),
+            SpellArrangement::Bar => (Constraint::Min(0), Constraint::Length(3)),
+        };
+        let [title, status, game_screen, buttons] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            game_screen,
+            buttons,
+        ])
+        .areas(area);
+
+        // Layout for 3 game screens, the middle one split between events and stats
+        let [wizard_panel, middle, boss_panel] = Layout::horizontal([
+            Constraint::Percentage(25),
+            Constraint::Percentage(50),
+            Constraint::Percentage(25),
+        ])
+        .areas(game_screen);
+        let [events, stats] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(8)]).areas(middle);
+
+        let (spell_buttons, button_style, selection) = match self.arrangement {
+            SpellArrangement::Grid => (
+                Self::split_grid(buttons, spells),
+                ButtonStyle::Full,
+                SelectionGeometry::new(&BUTTON_ROWS, spells.len()),
+            ),
+            SpellArrangement::Bar => (
+                split_bar(buttons, spells),
+                ButtonStyle::Short,
+                SelectionGeometry::bar(spells.len()),
+            ),
+        };
+
+        BattleAreas {
+            title,
+            status,
+            events,
+            stats: Some(stats),
+            panels: Some([wizard_panel, boss_panel]),
+            spell_buttons,
+            button_style,
+            selection,
+            battle: game_screen,
+            overlays: middle,
+        }
+    }
+}
+
+/// A single column for tiny terminals and tmux splits: the status strip, the events and a
+/// bar of short spell buttons, leaving out the side panels and graphs
+#[derive(Debug, Default)]
+pub struct CompactLayout;
+
+impl BattleLayout for CompactLayout {
+    fn split(&self, area: Rect, spells: &[Spell]) -> BattleAreas {
+        let [title, status, events, bar] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        BattleAreas {
+            title,
+            status,
+            events,
+            stats: None,
+            panels: None,
+            spell_buttons: split_bar(bar, spells),
+            button_style: ButtonStyle::Bare,
+            selection: SelectionGeometry::bar(spells.len()),
+            battle: events,
+            overlays: events,
+        }
+    }
+}
+
+/// The layout for a terminal of `area`: compact below `COMPACT_WIDTH` by `COMPACT_HEIGHT`,
+/// and otherwise the full layout with the spell buttons in `arrangement`
+pub fn select_battle_layout(
+    area: Rect,
+    arrangement: SpellArrangement,
+) -> &'static dyn BattleLayout {
+    if area.width < COMPACT_WIDTH || area.height < COMPACT_HEIGHT {
+        &CompactLayout
+    } else {
+        match arrangement {
+            SpellArrangement::Grid => &FullLayout {
+                arrangement: SpellArrangement::Grid,
+            },
+            SpellArrangement::Bar => &FullLayout {
+                arrangement: SpellArrangement::Bar,
+            },
+        }
+    }
+}
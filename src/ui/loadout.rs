@@ -0,0 +1,124 @@
+//! Loadout screen shown before a battle, picking the spells and trait the wizard takes.
+
+use super::{theme, tui};
+use advent_wizard_rpg::{
+    loadout::{Loadout, PassiveTrait, LOADOUT_SPELLS},
+    Spell, Spellbook,
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+};
+use std::io;
+
+/// Let the player pick `LOADOUT_SPELLS` of the unsealed spells of `spellbook` and one
+/// trait. Space toggles the spell or picks the trait under the cursor, Enter confirms once
+/// enough spells are picked. Returns None if the player quit.
+pub fn pick_loadout(terminal: &mut tui::Tui, spellbook: &Spellbook) -> io::Result<Option<Loadout>> {
+    let spells: Vec<Spell> = Spell::ALL
+        .into_iter()
+        .filter(|spell| !spellbook.is_sealed(spell))
+        .collect();
+    let rows = spells.len() + PassiveTrait::ALL.len();
+    let mut picked: Vec<Spell> = spells[..LOADOUT_SPELLS].to_vec();
+    let mut passive = PassiveTrait::ALL[0];
+    let mut selected = 0;
+    loop {
+        terminal.draw(|frame| {
+            let theme = theme::get();
+            let [area] = Layout::vertical([Constraint::Length(rows as u16 + 6)])
+                .flex(Flex::Center)
+                .areas(frame.area());
+            let [area] = Layout::horizontal([Constraint::Length(48)])
+                .flex(Flex::Center)
+                .areas(area);
+            let mut lines =
+                vec![Line::from(format!("Spells ({} of {})", picked.len(), LOADOUT_SPELLS)).bold()];
+            for (i, spell) in spells.iter().enumerate() {
+                lines.push(option_line(
+                    i == selected,
+                    picked.contains(spell),
+                    format!(
+                        "{} ({} Mana)",
+                        spell.get_display_name(),
+                        spellbook.get_mana(spell)
+                    ),
+                ));
+            }
+            lines.push(Line::default());
+            lines.push(Line::from("Trait").bold());
+            for (i, option) in PassiveTrait::ALL.into_iter().enumerate() {
+                lines.push(option_line(
+                    spells.len() + i == selected,
+                    option == passive,
+                    format!(
+                        "{}: {}",
+                        option.get_display_name(),
+                        option.get_description()
+                    ),
+                ));
+            }
+            let hint = if picked.len() == LOADOUT_SPELLS {
+                "Space to pick, Enter to fight, q to quit"
+            } else {
+                "Space to pick, q to quit"
+            };
+            let popup = Paragraph::new(lines).gray().block(
+                Block::bordered()
+                    .border_style(Style::default().fg(theme.accent))
+                    .title("Loadout".bold().gray())
+                    .title_alignment(Alignment::Center)
+                    .title_bottom(Line::from(hint).dark_gray().centered()),
+            );
+            frame.render_widget(Clear, area);
+            frame.render_widget(popup, area);
+        })?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => selected = (selected + 1).min(rows - 1),
+            KeyCode::Char('k') | KeyCode::Up => selected = selected.saturating_sub(1),
+            KeyCode::Char(' ') => match spells.get(selected) {
+                Some(spell) => {
+                    if let Some(index) = picked.iter().position(|picked| picked == spell) {
+                        picked.remove(index);
+                    } else if picked.len() < LOADOUT_SPELLS {
+                        picked.push(spell.clone());
+                    }
+                }
+                None => passive = PassiveTrait::ALL[selected - spells.len()],
+            },
+            KeyCode::Enter if picked.len() == LOADOUT_SPELLS => {
+                // Keep the spells in the order of the spellbook
+                let spells = spells
+                    .iter()
+                    .filter(|spell| picked.contains(spell))
+                    .cloned()
+                    .collect();
+                return Ok(Some(Loadout { spells, passive }));
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+            _ => (),
+        }
+    }
+}
+
+/// Row of the loadout screen, with a mark if the option is picked
+fn option_line(is_selected: bool, is_picked: bool, text: String) -> Line<'static> {
+    let line = Line::from(format!(
+        "{} [{}] {}",
+        if is_selected { ">" } else { " " },
+        if is_picked { "x" } else { " " },
+        text
+    ));
+    if is_selected {
+        line.fg(theme::get().accent).bold()
+    } else {
+        line
+    }
+}
@@ -0,0 +1,129 @@
+//! Bar showing a value out of a maximum, filled to an eighth of a cell.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+/// Partly filled cells, by eighths filled
+const PARTIAL_BLOCKS: [&str; 8] = [" ", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+const FULL_BLOCK: &str = "█";
+/// Unfilled part of the bar
+const TRACK_COLOR: Color = Color::DarkGray;
+
+/// Label followed by a bar, such as `HP 50 ██████▍   `
+#[derive(Debug, Clone)]
+pub struct Meter {
+    label: String,
+    value: i32,
+    max: i32,
+    color: Color,
+}
+
+impl Meter {
+    pub fn new(label: String, value: i32, max: i32, color: Color) -> Self {
+        Self {
+            label,
+            value,
+            max,
+            color,
+        }
+    }
+
+    /// Eighths of a cell filled in a bar `width` cells wide, rounded to the nearest
+    /// eighth. Any value above 0 fills at least an eighth, and any value below the
+    /// maximum leaves at least an eighth empty, so the bar is never full or empty unless
+    /// the value is.
+    pub fn get_filled_eighths(&self, width: u16) -> u32 {
+        let eighths = width as u32 * 8;
+        if self.value <= 0 || self.max <= 0 || eighths == 0 {
+            return 0;
+        }
+        if self.value >= self.max {
+            return eighths;
+        }
+        let value = self.value as u64;
+        let max = self.max as u64;
+        let filled = ((value * eighths as u64 * 2 + max) / (max * 2)) as u32;
+        filled.clamp(1, eighths - 1)
+    }
+}
+
+impl Widget for &Meter {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 1 {
+            return;
+        }
+        let label = format!("{} ", self.label);
+        let (x, _) = buf.set_stringn(
+            area.x,
+            area.y,
+            &label,
+            area.width as usize,
+            Style::default().fg(Color::Gray),
+        );
+        let width = area.right().saturating_sub(x);
+        let filled = self.get_filled_eighths(width);
+        let style = Style::default().fg(self.color).bg(TRACK_COLOR);
+        for i in 0..width {
+            let cell_eighths = filled.saturating_sub(i as u32 * 8).min(8);
+            let symbol = match cell_eighths {
+                8 => FULL_BLOCK,
+                eighths => PARTIAL_BLOCKS[eighths as usize],
+            };
+            buf[(x + i, area.y)].set_symbol(symbol).set_style(style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(value: i32, max: i32, width: u16) -> u32 {
+        Meter::new(String::new(), value, max, Color::Red).get_filled_eighths(width)
+    }
+
+    #[test]
+    fn empty_at_zero() {
+        assert_eq!(filled(0, 50, 10), 0);
+        assert_eq!(filled(-5, 50, 10), 0);
+    }
+
+    #[test]
+    fn full_at_max() {
+        assert_eq!(filled(50, 50, 10), 80);
+        assert_eq!(filled(60, 50, 10), 80);
+    }
+
+    #[test]
+    fn empty_without_max() {
+        assert_eq!(filled(10, 0, 10), 0);
+        assert_eq!(filled(10, -1, 10), 0);
+    }
+
+    #[test]
+    fn empty_without_width() {
+        assert_eq!(filled(25, 50, 0), 0);
+        assert_eq!(filled(50, 50, 0), 0);
+    }
+
+    #[test]
+    fn tiny_value_fills_an_eighth() {
+        assert_eq!(filled(1, 1000, 10), 1);
+    }
+
+    #[test]
+    fn value_below_max_leaves_an_eighth() {
+        assert_eq!(filled(999, 1000, 10), 79);
+    }
+
+    #[test]
+    fn exact_half_rounds_up() {
+        // 3 of 16 is 1.5 eighths of a cell
+        assert_eq!(filled(3, 16, 1), 2);
+        assert_eq!(filled(25, 50, 1), 4);
+    }
+}
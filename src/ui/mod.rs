@@ -1,3 +1,54 @@
+mod badge;
+pub use badge::*;
+#[cfg(any(feature = "sprites", feature = "clipboard"))]
+mod base64;
+#[cfg(any(feature = "sprites", feature = "clipboard"))]
+pub use base64::*;
+mod cast_animation;
+pub use cast_animation::*;
 mod centre_position;
 pub use centre_position::*;
+mod clipboard;
+pub use clipboard::*;
+mod effects;
+pub use effects::*;
+mod ending;
+pub use ending::*;
+mod file_picker;
+pub use file_picker::*;
+#[cfg(feature = "sprites")]
+mod graphics;
+#[cfg(feature = "sprites")]
+pub use graphics::*;
+mod intro;
+pub use intro::*;
+mod layout;
+pub use layout::*;
+mod loadout;
+pub use loadout::*;
+mod meter;
+pub use meter::*;
+mod progress;
+pub use progress::*;
+mod prompt;
+pub use prompt::*;
+mod review;
+pub use review::*;
+mod screenshot;
+pub use screenshot::*;
+mod scroll_view;
+pub use scroll_view::*;
+mod selection;
+pub use selection::*;
+mod sprite;
+pub use sprite::*;
+mod stat_diff;
+pub use stat_diff::*;
+mod story;
+pub use story::*;
+mod text_input;
+pub use text_input::*;
+pub mod theme;
 pub mod tui;
+mod typewriter;
+pub use typewriter::*;
@@ -0,0 +1,88 @@
+//! Progress bar shown below the command line while a headless subcommand runs.
+
+use advent_wizard_rpg::parallel::Progress;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use ratatui::crossterm::{
+    event::{self, Event, KeyCode, KeyModifiers},
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+use std::{
+    io::{self, stderr, stdin, IsTerminal},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+/// How often the bar is updated
+const PROGRESS_REFRESH: Duration = Duration::from_millis(100);
+
+/// Run `work` while another thread shows how far `progress` is, counting `unit`. Ctrl-C,
+/// q or Esc cancel `progress`, and `work` is expected to return what it finished so far.
+/// Without a terminal `work` just runs.
+pub fn with_progress<R>(unit: &str, progress: &Progress, work: impl FnOnce() -> R) -> R {
+    if !stdin().is_terminal() || !stderr().is_terminal() {
+        return work();
+    }
+    let bar =
+        ProgressBar::with_draw_target(Some(progress.get_total()), ProgressDrawTarget::stderr());
+    bar.set_style(
+        ProgressStyle::with_template("{wide_bar:.cyan} {pos} of {len} {msg}")
+            .expect("progress template is valid")
+            .progress_chars("█▉▊▋▌▍▎▏ "),
+    );
+    bar.set_message(format!("{}, Ctrl-C to stop", unit));
+    let finished = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let watcher = scope.spawn(|| watch_until_finished(unit, progress, &bar, &finished));
+        let result = work();
+        finished.store(true, Ordering::Relaxed);
+        let watched = watcher.join().unwrap();
+        // Leave the line empty for whatever is printed next
+        bar.finish_and_clear();
+        if let Err(err) = watched {
+            eprintln!("Could not read keys: {}", err);
+        }
+        result
+    })
+}
+
+/// Raw mode while held, turning Ctrl-C into a key press instead of killing the process.
+/// Dropping it restores the terminal, even when reading keys fails.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        enable_raw_mode()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Move `bar` along with `progress` and cancel it on Ctrl-C, q or Esc
+fn watch_until_finished(
+    unit: &str,
+    progress: &Progress,
+    bar: &ProgressBar,
+    finished: &AtomicBool,
+) -> io::Result<()> {
+    let _raw_mode = RawMode::enable()?;
+    while !finished.load(Ordering::Relaxed) {
+        bar.set_position(progress.get_done());
+        if event::poll(PROGRESS_REFRESH)? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c =
+                    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    progress.cancel();
+                    bar.set_message(format!("{}, stopping", unit));
+                }
+            }
+        }
+    }
+    Ok(())
+}
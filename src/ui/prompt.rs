@@ -0,0 +1,49 @@
+//! Yes/no question shown before the game starts.
+
+use super::{theme, tui};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Wrap},
+};
+use std::io;
+
+/// Ask `question` in a popup until the user answers. Returns whether they answered yes.
+pub fn confirm(terminal: &mut tui::Tui, title: &str, question: &str) -> io::Result<bool> {
+    loop {
+        terminal.draw(|frame| {
+            let [area] = Layout::vertical([Constraint::Length(6)])
+                .flex(Flex::Center)
+                .areas(frame.area());
+            let [area] = Layout::horizontal([Constraint::Percentage(60)])
+                .flex(Flex::Center)
+                .areas(area);
+            let text = vec![
+                Line::from(question),
+                Line::default(),
+                Line::from("(y/n)").dark_gray(),
+            ];
+            let popup = Paragraph::new(text)
+                .gray()
+                .alignment(Alignment::Center)
+                .wrap(Wrap::default())
+                .block(
+                    Block::bordered()
+                        .border_style(Style::default().fg(theme::get().accent))
+                        .title(title.bold())
+                        .title_alignment(Alignment::Center),
+                );
+            frame.render_widget(Clear, area);
+            frame.render_widget(popup, area);
+        })?;
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => return Ok(true),
+                KeyCode::Char('n') | KeyCode::Esc | KeyCode::Char('q') => return Ok(false),
+                _ => (),
+            }
+        }
+    }
+}
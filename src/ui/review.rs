@@ -0,0 +1,196 @@
+//! Review screen grading each cast of a finished battle against the solver.
+
+use super::{theme, tui};
+use advent_wizard_rpg::{
+    history::BattleHistory,
+    notation::format_numbered_line,
+    review::{get_accuracy, review_casts, CastGrade, CastReview},
+    solver::{Algorithm, SolveOptions},
+    Battle, Spell,
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+};
+use std::io;
+
+/// Search for the cheapest win from each position of a review
+const REVIEW_SOLVE_OPTIONS: SolveOptions = SolveOptions {
+    algorithm: Algorithm::AStar,
+    max_depth: 20,
+    prune: true,
+    memo_size: 1_000_000,
+};
+
+/// Grade the casts of `spells` from `start`, showing a notice while the solver runs, then
+/// show the review until the player leaves it. The hitpoints before each cast are read from
+/// `history` where it still has the round.
+pub fn review_battle(
+    terminal: &mut tui::Tui,
+    start: &Battle,
+    spells: &[Spell],
+    history: Option<&BattleHistory>,
+) -> io::Result<()> {
+    terminal.draw(|frame| {
+        let [area] = Layout::vertical([Constraint::Length(1)])
+            .flex(Flex::Center)
+            .areas(frame.area());
+        frame.render_widget(Clear, frame.area());
+        frame.render_widget(
+            Paragraph::new("Reviewing the battle...")
+                .centered()
+                .fg(theme::get().accent),
+            area,
+        );
+    })?;
+    let reviews = review_casts(start, spells, REVIEW_SOLVE_OPTIONS);
+    show_review(terminal, &reviews, history)
+}
+
+fn grade_color(grade: CastGrade) -> Color {
+    let theme = theme::get();
+    match grade {
+        CastGrade::Best => theme.wizard,
+        CastGrade::Fine => theme.mana,
+        CastGrade::Inaccuracy => theme.accent,
+        CastGrade::Blunder => theme.boss,
+    }
+}
+
+/// Accuracy and the number of casts of each grade
+fn summary_line(reviews: &[CastReview]) -> Line<'static> {
+    let mut text = match get_accuracy(reviews) {
+        Some(accuracy) => format!("Accuracy {:.0}%", accuracy * 100.0),
+        None => "No cast could be graded".to_string(),
+    };
+    for grade in [
+        CastGrade::Best,
+        CastGrade::Fine,
+        CastGrade::Inaccuracy,
+        CastGrade::Blunder,
+    ] {
+        let count = reviews
+            .iter()
+            .filter(|review| review.grade == Some(grade))
+            .count();
+        text.push_str(&format!("  {}: {}", grade.get_display_name(), count));
+    }
+    Line::from(text).bold()
+}
+
+fn show_review(
+    terminal: &mut tui::Tui,
+    reviews: &[CastReview],
+    history: Option<&BattleHistory>,
+) -> io::Result<()> {
+    let mut table_state = TableState::default().with_selected(Some(0));
+    loop {
+        terminal.draw(|frame| {
+            let [area] = Layout::vertical([Constraint::Percentage(80)])
+                .flex(Flex::Center)
+                .areas(frame.area());
+            let [area] = Layout::horizontal([Constraint::Length(82)])
+                .flex(Flex::Center)
+                .areas(area);
+            let block = Block::bordered()
+                .border_style(Style::default().fg(theme::get().accent))
+                .title("Review".bold().gray())
+                .title_alignment(Alignment::Center)
+                .title_bottom(
+                    Line::from("j/k to scroll, q to leave")
+                        .dark_gray()
+                        .centered(),
+                );
+            let inner_area = block.inner(area);
+            frame.render_widget(Clear, area);
+            frame.render_widget(block, area);
+
+            let [summary_area, table_area] =
+                Layout::vertical([Constraint::Length(4), Constraint::Min(0)]).areas(inner_area);
+            let spells: Vec<Spell> = reviews.iter().map(|review| review.spell.clone()).collect();
+            let summary = vec![
+                summary_line(reviews),
+                Line::from(format!("Played: {}", format_numbered_line(&spells, 1))),
+            ];
+            frame.render_widget(
+                Paragraph::new(summary).gray().wrap(Wrap::default()),
+                summary_area,
+            );
+
+            let rows = reviews.iter().map(|review| {
+                let grade = match review.grade {
+                    Some(grade) => Cell::from(grade.get_display_name()).fg(grade_color(grade)),
+                    None => Cell::from("-").dark_gray(),
+                };
+                let mana_lost = match (review.grade, review.mana_lost) {
+                    (_, Some(mana_lost)) if mana_lost > 0 => format!("{} mana", mana_lost),
+                    (Some(CastGrade::Blunder), None) => "gave up the win".to_string(),
+                    _ => String::new(),
+                };
+                let best = match (&review.best, review.grade) {
+                    (Some(best), Some(grade)) if grade != CastGrade::Best => {
+                        best.get_display_name()
+                    }
+                    _ => "",
+                };
+                let state = history.and_then(|history| history.state_at_round(review.round));
+                let (wizard_hitpoints, boss_hitpoints) = match state {
+                    Some(state) => (
+                        state.wizard_hitpoints.to_string(),
+                        state.boss_hitpoints.to_string(),
+                    ),
+                    None => ("-".to_string(), "-".to_string()),
+                };
+                Row::new([
+                    Cell::from(review.round.to_string()),
+                    Cell::from(wizard_hitpoints).fg(theme::get().wizard),
+                    Cell::from(boss_hitpoints).fg(theme::get().boss),
+                    Cell::from(review.spell.get_display_name()),
+                    grade,
+                    Cell::from(mana_lost),
+                    Cell::from(best),
+                ])
+                .gray()
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(6),
+                    Constraint::Length(4),
+                    Constraint::Length(5),
+                    Constraint::Length(14),
+                    Constraint::Length(11),
+                    Constraint::Length(16),
+                    Constraint::Min(0),
+                ],
+            )
+            .header(
+                Row::new([
+                    "Round",
+                    "Wiz",
+                    "Boss",
+                    "Spell",
+                    "Grade",
+                    "Lost",
+                    "Best move",
+                ])
+                .bold(),
+            )
+            .highlight_style(Style::default().fg(theme::get().accent));
+            frame.render_stateful_widget(table, table_area, &mut table_state);
+        })?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter => return Ok(()),
+            KeyCode::Char('j') | KeyCode::Down => table_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => table_state.select_previous(),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Saving a rendered frame as plain text and as ANSI art.
+
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier, Style},
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// SGR codes of the modifiers that ANSI art can show
+const MODIFIER_CODES: [(Modifier, u8); 6] = [
+    (Modifier::BOLD, 1),
+    (Modifier::DIM, 2),
+    (Modifier::ITALIC, 3),
+    (Modifier::UNDERLINED, 4),
+    (Modifier::REVERSED, 7),
+    (Modifier::CROSSED_OUT, 9),
+];
+
+/// Write `buffer` to `dir` as a `.txt` file and an ANSI colored `.ans` file, named by the
+/// time. Returns the path of the text file.
+pub fn save_screenshot(buffer: &Buffer, dir: &Path) -> io::Result<PathBuf> {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis());
+    let path = dir.join(format!("advent-wizard-rpg-{}.txt", millis));
+    fs::write(&path, buffer_to_text(buffer))?;
+    fs::write(path.with_extension("ans"), buffer_to_ansi(buffer))?;
+    Ok(path)
+}
+
+/// The symbols of `buffer`, with trailing spaces trimmed from each line
+pub fn buffer_to_text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for y in buffer.area.top()..buffer.area.bottom() {
+        let line: String = (buffer.area.left()..buffer.area.right())
+            .map(|x| buffer[(x, y)].symbol())
+            .collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// The symbols of `buffer` with escape codes for their colors and modifiers, resetting
+/// the style at the end of each line
+pub fn buffer_to_ansi(buffer: &Buffer) -> String {
+    let mut ansi = String::new();
+    for y in buffer.area.top()..buffer.area.bottom() {
+        let mut current = None;
+        for x in buffer.area.left()..buffer.area.right() {
+            let cell = &buffer[(x, y)];
+            let style = cell.style();
+            if current != Some(style) {
+                ansi.push_str(&style_codes(style));
+                current = Some(style);
+            }
+            ansi.push_str(cell.symbol());
+        }
+        ansi.push_str("\x1b[0m\n");
+    }
+    ansi
+}
+
+/// Escape sequence setting exactly `style`
+fn style_codes(style: Style) -> String {
+    let mut codes = vec!["0".to_string()];
+    codes.extend(
+        MODIFIER_CODES
+            .iter()
+            .filter(|(modifier, _)| style.add_modifier.contains(*modifier))
+            .map(|(_, code)| code.to_string()),
+    );
+    if let Some(code) = style.fg.and_then(|color| color_code(color, false)) {
+        codes.push(code);
+    }
+    if let Some(code) = style.bg.and_then(|color| color_code(color, true)) {
+        codes.push(code);
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// SGR code of a color, None for the terminal's default
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let named = |code: u8| Some((code + offset).to_string());
+    match color {
+        Color::Reset => None,
+        Color::Black => named(30),
+        Color::Red => named(31),
+        Color::Green => named(32),
+        Color::Yellow => named(33),
+        Color::Blue => named(34),
+        Color::Magenta => named(35),
+        Color::Cyan => named(36),
+        Color::Gray => named(37),
+        Color::DarkGray => named(90),
+        Color::LightRed => named(91),
+        Color::LightGreen => named(92),
+        Color::LightYellow => named(93),
+        Color::LightBlue => named(94),
+        Color::LightMagenta => named(95),
+        Color::LightCyan => named(96),
+        Color::White => named(97),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", 38 + offset, r, g, b)),
+        Color::Indexed(index) => Some(format!("{};5;{}", 38 + offset, index)),
+    }
+}
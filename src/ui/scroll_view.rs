@@ -0,0 +1,64 @@
+//! Scroll position of a window of lines, kept in step with its scrollbar.
+
+use ratatui::widgets::ScrollbarState;
+
+/// Which lines of a scrollable window are in view
+#[derive(Debug, Default)]
+pub struct ScrollView {
+    /// First line in view
+    offset: usize,
+    scrollbar_state: ScrollbarState,
+    /// Lines in view at once
+    height: usize,
+}
+
+impl ScrollView {
+    /// View `height` lines at once
+    pub fn new(height: usize) -> Self {
+        Self {
+            height,
+            ..Self::default()
+        }
+    }
+
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// View `height` lines at once, as the window was last drawn
+    pub fn set_height(&mut self, height: usize) {
+        self.height = height;
+    }
+
+    /// Number of lines there are to scroll through
+    pub fn set_content_length(&mut self, length: usize) {
+        self.scrollbar_state = self.scrollbar_state.content_length(length);
+    }
+
+    pub fn get_scrollbar_state_mut(&mut self) -> &mut ScrollbarState {
+        &mut self.scrollbar_state
+    }
+
+    /// Put `line` at the top of the view
+    pub fn scroll_to(&mut self, line: usize) {
+        self.offset = line;
+        self.scrollbar_state = self.scrollbar_state.position(self.offset);
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.scroll_to(self.offset.saturating_add(1));
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll_to(self.offset.saturating_sub(1));
+    }
+
+    /// Put `line` in the middle of the view, or as near it as the top allows
+    pub fn center_on(&mut self, line: usize) {
+        self.scroll_to(line.saturating_sub(self.height / 2));
+    }
+}
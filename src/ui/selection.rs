@@ -0,0 +1,70 @@
+//! Moving the selection between spell buttons. The selection is the index of a button in
+//! reading order, and a geometry says which button is next to it in each direction, so
+//! the grid and the bar share the same selection.
+
+/// Direction the selection moves in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionStep {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Buttons laid out in rows, each row holding the indexes of its buttons from left to right
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionGeometry {
+    rows: Vec<Vec<usize>>,
+}
+
+impl SelectionGeometry {
+    /// Geometry of `rows` of button indexes, leaving out the indexes from `buttons` on
+    pub fn new(rows: &[&[usize]], buttons: usize) -> Self {
+        Self {
+            rows: rows
+                .iter()
+                .map(|row| row.iter().copied().filter(|i| *i < buttons).collect())
+                .filter(|row: &Vec<usize>| !row.is_empty())
+                .collect(),
+        }
+    }
+
+    /// `buttons` buttons in a single row
+    pub fn bar(buttons: usize) -> Self {
+        Self {
+            rows: vec![(0..buttons).collect()],
+        }
+    }
+
+    /// Row and position in it of `button`, None if it isn't laid out
+    fn find(&self, button: usize) -> Option<(usize, usize)> {
+        self.rows.iter().enumerate().find_map(|(row, buttons)| {
+            buttons
+                .iter()
+                .position(|i| *i == button)
+                .map(|position| (row, position))
+        })
+    }
+
+    /// Button next to `button` in the direction of `step`, `button` itself at the edge. Moving
+    /// between rows of different lengths keeps to the same share of the row's width.
+    pub fn step(&self, button: usize, step: SelectionStep) -> usize {
+        let Some((row, position)) = self.find(button) else {
+            return 0;
+        };
+        let buttons = &self.rows[row];
+        let target = match step {
+            SelectionStep::Left => return buttons[position.saturating_sub(1)],
+            SelectionStep::Right => return buttons[(position + 1).min(buttons.len() - 1)],
+            SelectionStep::Up => row.checked_sub(1),
+            SelectionStep::Down => Some(row + 1).filter(|row| *row < self.rows.len()),
+        };
+        match target {
+            Some(target) => {
+                let target = &self.rows[target];
+                target[(position * target.len() / buttons.len()).min(target.len() - 1)]
+            }
+            None => button,
+        }
+    }
+}
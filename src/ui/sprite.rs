@@ -0,0 +1,205 @@
+//! Pixel art of the combatants, drawn with half block characters.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+use std::time::Duration;
+
+/// Pixels changed in one tick of an idle animation, as `(x, y, char)`
+pub type IdleFrame = &'static [(usize, usize, char)];
+
+/// Picture made of one char per pixel, where a space is transparent
+#[derive(Debug)]
+pub struct Sprite {
+    pub rows: [&'static str; 12],
+    /// Color of each char used in `rows` and `idle_cycle`
+    pub palette: &'static [(char, [u8; 3])],
+    /// Changes to the picture in each tick of the animation played over and over while
+    /// nothing happens
+    pub idle_cycle: &'static [IdleFrame],
+}
+
+/// Columns a sprite takes
+pub const SPRITE_WIDTH: u16 = 12;
+/// Rows a sprite takes, two pixels per row
+pub const SPRITE_HEIGHT: u16 = 6;
+
+/// Ticks of an idle animation
+pub const IDLE_TICK: Duration = Duration::from_millis(200);
+
+const WIZARD_BLINK: IdleFrame = &[(5, 6, 's'), (7, 6, 's')];
+/// Light at the top of the staff
+const WIZARD_GLOW: IdleFrame = &[(11, 4, 'g')];
+const WIZARD_BRIGHT_GLOW: IdleFrame = &[(11, 4, 'g'), (10, 4, 'g'), (11, 3, 'g')];
+const BOSS_BLINK: IdleFrame = &[(3, 4, 'r'), (8, 4, 'r')];
+
+pub const WIZARD_SPRITE: Sprite = Sprite {
+    rows: [
+        "     pp     ",
+        "    pppp    ",
+        "   ppyppp   ",
+        "  pppppppp  ",
+        " pppppppppp ",
+        "    ssss   w",
+        "    s.s.   w",
+        "   bbbbbb  w",
+        "  bbbbbbbbbw",
+        "  bbbbbbb  w",
+        "  bbbbbbb  w",
+        "  bb   bb  w",
+    ],
+    palette: &[
+        ('p', [128, 64, 192]),
+        ('y', [240, 220, 80]),
+        ('s', [240, 200, 160]),
+        ('.', [20, 20, 20]),
+        ('b', [60, 100, 220]),
+        ('w', [140, 90, 40]),
+        ('g', [250, 240, 150]),
+    ],
+    idle_cycle: &[
+        &[],
+        &[],
+        &[],
+        WIZARD_GLOW,
+        WIZARD_BRIGHT_GLOW,
+        WIZARD_BRIGHT_GLOW,
+        WIZARD_GLOW,
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        WIZARD_BLINK,
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+    ],
+};
+
+pub const BOSS_SPRITE: Sprite = Sprite {
+    rows: [
+        " h        h ",
+        " hh      hh ",
+        "  rrrrrrrr  ",
+        " rrrrrrrrrr ",
+        " rryrrrryrr ",
+        " rrrrrrrrrr ",
+        " rrwwwwwwrr ",
+        "  rrrrrrrr  ",
+        " rrrrrrrrrr ",
+        "rr rrrrrr rr",
+        "r  rrrrrr  r",
+        "   rr  rr   ",
+    ],
+    palette: &[
+        ('h', [230, 220, 190]),
+        ('r', [200, 40, 40]),
+        ('y', [250, 230, 60]),
+        ('w', [250, 250, 250]),
+    ],
+    idle_cycle: &[
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        BOSS_BLINK,
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        BOSS_BLINK,
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+    ],
+};
+
+impl Sprite {
+    /// Color of the pixel at `x`, `y`, None if transparent
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<[u8; 3]> {
+        self.get_color(self.rows[y].chars().nth(x)?)
+    }
+
+    fn get_color(&self, c: char) -> Option<[u8; 3]> {
+        self.palette
+            .iter()
+            .find(|(key, _)| *key == c)
+            .map(|(_, rgb)| *rgb)
+    }
+
+    /// The sprite as drawn on `tick` of its idle animation
+    pub fn idle_at(&self, tick: u64) -> IdleSprite<'_> {
+        let frame = match self.idle_cycle.len() {
+            0 => &[],
+            len => self.idle_cycle[(tick % len as u64) as usize],
+        };
+        IdleSprite {
+            sprite: self,
+            frame,
+        }
+    }
+}
+
+/// A sprite as drawn on one tick of its idle animation
+#[derive(Debug)]
+pub struct IdleSprite<'a> {
+    sprite: &'a Sprite,
+    frame: IdleFrame,
+}
+
+impl IdleSprite<'_> {
+    /// Color of the pixel at `x`, `y` on this tick, None if transparent
+    fn get_pixel(&self, x: usize, y: usize) -> Option<[u8; 3]> {
+        match self.frame.iter().find(|(px, py, _)| (*px, *py) == (x, y)) {
+            Some((_, _, c)) => self.sprite.get_color(*c),
+            None => self.sprite.get_pixel(x, y),
+        }
+    }
+}
+
+impl Widget for &Sprite {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.idle_at(0).render(area, buf);
+    }
+}
+
+impl Widget for IdleSprite<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let color = |pixel: Option<[u8; 3]>| pixel.map(|[r, g, b]| Color::Rgb(r, g, b));
+        for row in 0..SPRITE_HEIGHT.min(area.height) {
+            for column in 0..SPRITE_WIDTH.min(area.width) {
+                let top = color(self.get_pixel(column as usize, row as usize * 2));
+                let bottom = color(self.get_pixel(column as usize, row as usize * 2 + 1));
+                // The upper half block takes the top pixel's color and its background the
+                // bottom pixel's
+                let (symbol, style) = match (top, bottom) {
+                    (None, None) => continue,
+                    (Some(top), None) => ("▀", Style::default().fg(top)),
+                    (None, Some(bottom)) => ("▄", Style::default().fg(bottom)),
+                    (Some(top), Some(bottom)) => ("▀", Style::default().fg(top).bg(bottom)),
+                };
+                buf[(area.x + column, area.y + row)]
+                    .set_symbol(symbol)
+                    .set_style(style);
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Recent changes of the wizard's and boss's stats, shown next to them in the side panels as
+//! arrows that fade a couple of seconds after the change.
+
+use super::theme;
+use advent_wizard_rpg::StateDiff;
+use ratatui::{
+    style::Stylize,
+    text::{Span, Text},
+};
+use std::time::Duration;
+
+/// Time a change is shown in full color
+const STAT_DIFF_BRIGHT: Duration = Duration::from_millis(1500);
+/// Time a change is shown for in all, dimmed after `STAT_DIFF_BRIGHT`
+const STAT_DIFF_SHOWN: Duration = Duration::from_millis(2500);
+
+/// Stat of a side panel that shows its recent change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatField {
+    WizardHitpoints,
+    WizardMana,
+    BossHitpoints,
+}
+
+impl StatField {
+    const ALL: [StatField; 3] = [
+        StatField::WizardHitpoints,
+        StatField::WizardMana,
+        StatField::BossHitpoints,
+    ];
+
+    fn get_change(self, diff: &StateDiff) -> i32 {
+        match self {
+            Self::WizardHitpoints => diff.wizard_hitpoints,
+            Self::WizardMana => diff.wizard_mana,
+            Self::BossHitpoints => diff.boss_hitpoints,
+        }
+    }
+}
+
+/// Changes being shown, each with its net size and the time since it last changed
+#[derive(Debug, Default)]
+pub struct StatDiffs {
+    active: Vec<(StatField, i32, Duration)>,
+}
+
+impl StatDiffs {
+    /// Show the changes of `diff`, adding to the changes still shown and restarting their fade
+    pub fn record(&mut self, diff: &StateDiff) {
+        for field in StatField::ALL {
+            let change = field.get_change(diff);
+            if change == 0 {
+                continue;
+            }
+            match self.active.iter_mut().find(|(active, ..)| *active == field) {
+                Some((_, total, shown)) => {
+                    *total += change;
+                    *shown = Duration::ZERO;
+                }
+                None => self.active.push((field, change, Duration::ZERO)),
+            }
+        }
+        self.active.retain(|(_, total, _)| *total != 0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Fade the changes for `elapsed`, ending those that have faded out
+    pub fn advance(&mut self, elapsed: Duration) {
+        for (_, _, shown) in &mut self.active {
+            *shown += elapsed;
+        }
+        self.active.retain(|(_, _, shown)| *shown < STAT_DIFF_SHOWN);
+    }
+
+    /// Arrow and size of the recent change of `field`, such as ▼8, red going down and green
+    /// going up, and dimmed as it fades. None if no change is shown.
+    pub fn get_span(&self, field: StatField) -> Option<Span<'static>> {
+        let (_, total, shown) = self.active.iter().find(|(active, ..)| *active == field)?;
+        let theme = theme::get();
+        let span = if *total < 0 {
+            Span::from(format!(" ▼{}", -total)).fg(theme.boss)
+        } else {
+            Span::from(format!(" ▲{}", total)).fg(theme.wizard)
+        };
+        Some(if *shown < STAT_DIFF_BRIGHT {
+            span.bold()
+        } else {
+            span.dark_gray()
+        })
+    }
+
+    /// `text` of a side panel with the recent change of each of `fields` after the line
+    /// starting with its label
+    pub fn annotate(&self, text: String, fields: &[(&str, StatField)]) -> Text<'static> {
+        let mut text = Text::from(text);
+        for line in &mut text.lines {
+            let label = line
+                .spans
+                .first()
+                .map(|span| span.content.to_string())
+                .unwrap_or_default();
+            let span = fields
+                .iter()
+                .find(|(prefix, _)| label.starts_with(prefix))
+                .and_then(|(_, field)| self.get_span(*field));
+            if let Some(span) = span {
+                line.spans.push(span);
+            }
+        }
+        text
+    }
+}
@@ -0,0 +1,116 @@
+//! Story screen shown between battles, typing its text out before offering choices.
+
+use super::{theme, tui};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Wrap},
+};
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+/// Speed the story is typed out at
+const STORY_CHARS_PER_SECOND: f64 = 40.0;
+/// Time between frames while typing
+const STORY_FRAME_DURATION: Duration = Duration::from_millis(50);
+
+/// Type out `text` under `title`, then ask for one of `choices`. Any key shows the rest of
+/// the text at once. Returns the index of the choice, 0 when there are no choices to
+/// make, or None if the player quit.
+pub fn tell_story(
+    terminal: &mut tui::Tui,
+    title: &str,
+    text: &str,
+    choices: &[&str],
+) -> io::Result<Option<usize>> {
+    let started = Instant::now();
+    let text_len = text.chars().count();
+    let mut revealed_all = false;
+    let mut selected = 0;
+    loop {
+        let revealed = if revealed_all {
+            text_len
+        } else {
+            (started.elapsed().as_secs_f64() * STORY_CHARS_PER_SECOND) as usize
+        };
+        let typing = revealed < text_len;
+        terminal.draw(|frame| {
+            let [area] = Layout::vertical([Constraint::Percentage(60)])
+                .flex(Flex::Center)
+                .areas(frame.area());
+            let [area] = Layout::horizontal([Constraint::Percentage(60)])
+                .flex(Flex::Center)
+                .areas(area);
+            let mut lines: Vec<Line> = text
+                .chars()
+                .take(revealed)
+                .collect::<String>()
+                .lines()
+                .map(|line| Line::from(line.to_string()))
+                .collect();
+            if !typing {
+                lines.push(Line::default());
+                for (i, choice) in choices.iter().enumerate() {
+                    let line = format!(
+                        "{} {}. {}",
+                        if i == selected { ">" } else { " " },
+                        i + 1,
+                        choice
+                    );
+                    lines.push(if i == selected {
+                        Line::from(line).fg(theme::get().accent).bold()
+                    } else {
+                        Line::from(line)
+                    });
+                }
+            }
+            let hint = if typing {
+                "any key to skip"
+            } else if choices.is_empty() {
+                "Enter to continue"
+            } else {
+                "j/k to choose, Enter to confirm, q to quit"
+            };
+            let popup = Paragraph::new(lines).gray().wrap(Wrap::default()).block(
+                Block::bordered()
+                    .border_style(Style::default().fg(theme::get().accent))
+                    .title(title.bold().gray())
+                    .title_alignment(Alignment::Center)
+                    .title_bottom(Line::from(hint).dark_gray().centered()),
+            );
+            frame.render_widget(Clear, area);
+            frame.render_widget(popup, area);
+        })?;
+
+        // Keep typing while waiting for a key
+        if typing && !event::poll(STORY_FRAME_DURATION)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if typing {
+            revealed_all = true;
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                selected = (selected + 1).min(choices.len().saturating_sub(1))
+            }
+            KeyCode::Char('k') | KeyCode::Up => selected = selected.saturating_sub(1),
+            KeyCode::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if index < choices.len() {
+                    return Ok(Some(index));
+                }
+            }
+            KeyCode::Enter => return Ok(Some(selected)),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+            _ => (),
+        }
+    }
+}
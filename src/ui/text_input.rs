@@ -0,0 +1,88 @@
+//! Single line text input for forms.
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::KeyCode,
+    layout::Rect,
+    style::{Style, Stylize},
+    widgets::Widget,
+};
+
+#[derive(Debug, Default, Clone)]
+pub struct TextInput {
+    value: String,
+    /// Cursor position, in chars
+    cursor: usize,
+    focused: bool,
+}
+
+impl TextInput {
+    pub fn new(value: String) -> Self {
+        let cursor = value.chars().count();
+        Self {
+            value,
+            cursor,
+            focused: false,
+        }
+    }
+
+    pub fn get_value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Edit the text. Returns whether the key was used.
+    pub fn handle_key_event(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Char(c) => {
+                let byte_index = self.byte_index();
+                self.value.insert(byte_index, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let byte_index = self.byte_index();
+                self.value.remove(byte_index);
+            }
+            KeyCode::Delete if self.cursor < self.value.chars().count() => {
+                let byte_index = self.byte_index();
+                self.value.remove(byte_index);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.value.chars().count()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.value.chars().count(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn byte_index(&self) -> usize {
+        self.value
+            .char_indices()
+            .nth(self.cursor)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+}
+
+impl Widget for &TextInput {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 1 {
+            return;
+        }
+        let style = if self.focused {
+            Style::default().underlined()
+        } else {
+            Style::default()
+        };
+        buf.set_stringn(area.x, area.y, &self.value, area.width as usize, style);
+        if self.focused && (self.cursor as u16) < area.width {
+            // Draw the cursor as a reversed cell
+            let cell = &mut buf[(area.x + self.cursor as u16, area.y)];
+            cell.set_style(Style::default().reversed());
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Colors shared between screens, and the palettes they come from.
+
+use ratatui::style::Color;
+use std::sync::OnceLock;
+
+/// Names accepted by `--palette`, in the order of [`Palette`]'s variants
+pub const PALETTE_NAMES: [&str; 3] = ["default", "deuteranopia", "protanopia"];
+
+/// Colors for each role on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    Default,
+    /// Avoids telling red from green, using blue against orange instead
+    Deuteranopia,
+    /// Like deuteranopia, with reds brightened since they appear dark
+    Protanopia,
+}
+
+impl Palette {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::Default),
+            "deuteranopia" => Some(Self::Deuteranopia),
+            "protanopia" => Some(Self::Protanopia),
+            _ => None,
+        }
+    }
+
+    pub fn get_theme(self) -> Theme {
+        match self {
+            Self::Default => Theme {
+                wizard: Color::LightBlue,
+                boss: Color::LightRed,
+                mana: Color::Magenta,
+                accent: Color::Magenta,
+                shield: Color::LightBlue,
+                recharge: Color::Magenta,
+                poison: Color::Green,
+                hard_mode: Color::LightRed,
+                playback: Color::Yellow,
+                hint: Color::Gray,
+                plan: Color::Cyan,
+                unavailable: Color::Red,
+            },
+            // Okabe-Ito colors
+            Self::Deuteranopia => Theme {
+                wizard: Color::Rgb(86, 180, 233),
+                boss: Color::Rgb(230, 159, 0),
+                mana: Color::Rgb(204, 121, 167),
+                accent: Color::Rgb(240, 228, 66),
+                shield: Color::Rgb(86, 180, 233),
+                recharge: Color::Rgb(204, 121, 167),
+                poison: Color::Rgb(0, 158, 115),
+                hard_mode: Color::Rgb(213, 94, 0),
+                playback: Color::Rgb(240, 228, 66),
+                hint: Color::Gray,
+                plan: Color::Rgb(0, 114, 178),
+                unavailable: Color::Rgb(213, 94, 0),
+            },
+            Self::Protanopia => Theme {
+                wizard: Color::Rgb(86, 180, 233),
+                boss: Color::Rgb(255, 176, 0),
+                mana: Color::Rgb(220, 140, 190),
+                accent: Color::Rgb(240, 228, 66),
+                shield: Color::Rgb(86, 180, 233),
+                recharge: Color::Rgb(220, 140, 190),
+                poison: Color::Rgb(0, 158, 115),
+                hard_mode: Color::Rgb(255, 176, 0),
+                playback: Color::Rgb(240, 228, 66),
+                hint: Color::Gray,
+                plan: Color::Rgb(0, 114, 178),
+                unavailable: Color::Rgb(255, 176, 0),
+            },
+        }
+    }
+}
+
+/// Colors for each role on screen
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Wizard and their hitpoints, and wins
+    pub wizard: Color,
+    /// Boss and its hitpoints, and losses
+    pub boss: Color,
+    pub mana: Color,
+    /// Selected items and popups
+    pub accent: Color,
+    /// Shield and armor
+    pub shield: Color,
+    pub recharge: Color,
+    /// Poison damage over time
+    pub poison: Color,
+    /// Hard mode's hitpoint drain
+    pub hard_mode: Color,
+    /// Playback speed and pause
+    pub playback: Color,
+    /// Reminders, such as the last spell cast
+    pub hint: Color,
+    /// Planned spells
+    pub plan: Color,
+    /// Spells that can't be cast
+    pub unavailable: Color,
+}
+
+/// Shown next to the shield's color, for telling effects apart without color
+pub const SHIELD_SYMBOL: char = '◆';
+/// Shown next to recharge's color
+pub const RECHARGE_SYMBOL: char = '↻';
+/// Shown next to poison's color
+pub const POISON_SYMBOL: char = '☠';
+/// Shown next to the wizard's color while thorns grow around them
+pub const THORNS_SYMBOL: char = '✶';
+/// Shown next to the wizard's color while their familiar fights alongside them
+pub const FAMILIAR_SYMBOL: char = '✦';
+/// Shown next to the boss's color while it silences the wizard
+pub const SILENCE_SYMBOL: char = '⊘';
+/// Shown next to the boss's color while the wizard burns
+pub const BURN_SYMBOL: char = '♨';
+
+/// Which transient effects are played, like the palette set once for the whole program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectFlags {
+    /// Shake the screen on big hits
+    pub shake: bool,
+    /// Flash the boss panel on the killing blow
+    pub flash: bool,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+static EFFECTS: OnceLock<EffectFlags> = OnceLock::new();
+
+/// Use `palette` for the rest of the program. Only the first call has an effect.
+pub fn set_palette(palette: Palette) {
+    THEME.get_or_init(|| palette.get_theme());
+}
+
+/// The theme set by [`set_palette`], or the default palette's if it wasn't called
+pub fn get() -> &'static Theme {
+    THEME.get_or_init(|| Palette::Default.get_theme())
+}
+
+/// Play the effects of `flags` for the rest of the program. Only the first call has an
+/// effect.
+pub fn set_effects(flags: EffectFlags) {
+    EFFECTS.get_or_init(|| flags);
+}
+
+/// The effects set by [`set_effects`], or all of them if it wasn't called
+pub fn get_effects() -> &'static EffectFlags {
+    EFFECTS.get_or_init(|| EffectFlags {
+        shake: true,
+        flash: true,
+    })
+}
@@ -1,8 +1,11 @@
+#[cfg(feature = "sprites")]
+use std::env;
 use std::io::{self, stdout, Stdout};
 
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
+        event::{DisableFocusChange, EnableFocusChange},
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
@@ -12,16 +15,48 @@ use ratatui::{
 /// A type alias for the terminal type used in this application
 pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
-/// Initialize the terminal
+/// Initialize the terminal, which reports gaining and losing focus if it supports it
 pub fn init() -> io::Result<Tui> {
-    execute!(stdout(), EnterAlternateScreen)?;
+    execute!(stdout(), EnterAlternateScreen, EnableFocusChange)?;
     enable_raw_mode()?;
     Terminal::new(CrosstermBackend::new(stdout()))
 }
 
 /// Restore the terminal to its original state
 pub fn restore() -> io::Result<()> {
-    execute!(stdout(), LeaveAlternateScreen)?;
+    execute!(stdout(), DisableFocusChange, LeaveAlternateScreen)?;
     disable_raw_mode()?;
+    super::print_uncopied();
+    crate::session_stats::print_summary();
     Ok(())
 }
+
+/// Protocol a terminal uses to show images
+#[cfg(feature = "sprites")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    /// iTerm2's inline images
+    Iterm2,
+}
+
+/// Guess from the environment how the terminal shows images, None if it can't. Images
+/// aren't passed through terminal multiplexers.
+#[cfg(feature = "sprites")]
+pub fn detect_graphics_protocol() -> Option<GraphicsProtocol> {
+    let var = |name: &str| env::var(name).unwrap_or_default();
+    if env::var_os("TMUX").is_some() || var("TERM").starts_with("screen") {
+        None
+    } else if var("TERM") == "xterm-kitty"
+        || env::var_os("KITTY_WINDOW_ID").is_some()
+        || var("TERM_PROGRAM") == "ghostty"
+    {
+        Some(GraphicsProtocol::Kitty)
+    } else if matches!(var("TERM_PROGRAM").as_str(), "iTerm.app" | "WezTerm")
+        || var("LC_TERMINAL") == "iTerm2"
+    {
+        Some(GraphicsProtocol::Iterm2)
+    } else {
+        None
+    }
+}
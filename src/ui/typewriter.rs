@@ -0,0 +1,87 @@
+//! Text typed out a character at a time, at a steady rate whatever the frame rate.
+
+use ratatui::text::Line;
+use std::time::Duration;
+
+/// How far lines of text have been typed out
+#[derive(Debug, Default)]
+pub struct Typewriter {
+    /// Line being typed, the number of lines if all of them are
+    line_index: usize,
+    /// Characters of the line typed so far
+    char_index: usize,
+    /// Characters owed from elapsed time, carried between frames
+    char_budget: f64,
+}
+
+impl Typewriter {
+    /// Line being typed, or the number of lines typed once all of them are
+    pub fn get_line_index(&self) -> usize {
+        self.line_index
+    }
+
+    /// Whether some of `lines` are still to be typed
+    pub fn is_typing(&self, lines: &[Line]) -> bool {
+        self.line_index < lines.len()
+    }
+
+    /// Type `lines` out for `elapsed` at `chars_per_second`. Returns the number of lines
+    /// finished, and whether anything more was typed.
+    pub fn advance(
+        &mut self,
+        elapsed: Duration,
+        chars_per_second: f64,
+        lines: &[Line],
+    ) -> (usize, bool) {
+        let line_index_old = self.line_index;
+        let char_index_old = self.char_index;
+        self.char_budget += elapsed.as_secs_f64() * chars_per_second;
+
+        while self.line_index < lines.len() {
+            let line_len = lines[self.line_index].to_string().chars().count();
+            let chars_remaining = line_len.saturating_sub(self.char_index);
+            if self.char_budget < chars_remaining as f64 {
+                // Partially type the line
+                let chars = self.char_budget.floor();
+                self.char_index += chars as usize;
+                self.char_budget -= chars;
+                break;
+            }
+            // Whole line typed, move to the next line
+            self.char_budget -= chars_remaining as f64;
+            self.line_index += 1;
+            self.char_index = 0;
+        }
+        if !self.is_typing(lines) {
+            // Don't bank time while there is nothing to type
+            self.char_budget = 0.0;
+        }
+
+        (
+            self.line_index - line_index_old,
+            line_index_old != self.line_index || char_index_old != self.char_index,
+        )
+    }
+
+    /// Type all of `lines` at once
+    pub fn finish(&mut self, lines: &[Line]) {
+        self.line_index = lines.len();
+        self.char_index = 0;
+    }
+
+    /// The lines typed so far, the last one partially
+    pub fn get_typed_lines(&self, lines: &[Line<'static>]) -> Vec<Line<'static>> {
+        let line_index = self.line_index.min(lines.len());
+        let mut typed = lines[0..line_index].to_owned();
+        if let Some(current_line) = lines.get(line_index) {
+            typed.push(Line::from(
+                current_line
+                    .to_string()
+                    .chars()
+                    .take(self.char_index)
+                    .collect::<String>(),
+            ));
+        }
+        typed
+    }
+}
@@ -0,0 +1,47 @@
+//! Posting the result of each finished battle to a chat webhook, such as a Discord
+//! channel's, so a group playing the same challenge can share results as they play.
+
+use advent_wizard_rpg::share::BattleSummary;
+use clap::{arg, Arg, ArgMatches};
+use std::{env, thread, time::Duration};
+use ureq::Agent;
+
+/// Environment variable holding the webhook URL when `--webhook` isn't given
+const WEBHOOK_ENV_VAR: &str = "WIZARD_RPG_WEBHOOK";
+/// Longest a post may take, connecting included
+const POST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn arg() -> Arg {
+    arg!(--webhook <URL> "Post the result of each finished battle to this Discord-style webhook [env: WIZARD_RPG_WEBHOOK]")
+}
+
+/// Webhook URL given by `arg` or the environment, None if results aren't posted
+pub fn url_from_matches(matches: &ArgMatches) -> Option<String> {
+    matches
+        .get_one::<String>("webhook")
+        .cloned()
+        .or_else(|| env::var(WEBHOOK_ENV_VAR).ok())
+        .filter(|url| !url.is_empty())
+}
+
+/// Post `summary` to the webhook at `url` as a message with the share string, from a
+/// thread of its own. The game doesn't wait for the post, which is dropped if the game
+/// exits first, and failures are only logged.
+pub fn post_result(url: &str, summary: &BattleSummary) {
+    let body = serde_json::json!({ "content": summary.to_share_string() }).to_string();
+    let url = url.to_string();
+    thread::spawn(move || {
+        let agent: Agent = Agent::config_builder()
+            .timeout_global(Some(POST_TIMEOUT))
+            .build()
+            .into();
+        let _posted = agent
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .send(&body);
+        #[cfg(feature = "logging")]
+        if let Err(err) = _posted {
+            tracing::warn!("posting the result failed: {}", err);
+        }
+    });
+}